@@ -0,0 +1,96 @@
+// Precipitation shader: every rain drop and snow flake is a quad of one mesh,
+// placed here from the global time and the camera position.
+// POSITION holds the quad corner (sideways, up) in blocks, PARTICLE the spot in
+// the particle box (xyz, 0..1) and the rank (w), PARTICLE_KIND 0 for rain and
+// 1 for snow. See `material::precipitation_material` and `app::weather`.
+
+#import bevy_pbr::mesh_view_bindings::{view, globals}
+
+@group(2) @binding(0) var<uniform> rain_color: vec4<f32>;
+@group(2) @binding(1) var<uniform> snow_color: vec4<f32>;
+// Half width of the particle box, its height, rain and snow fall speeds
+@group(2) @binding(2) var<uniform> fall: vec4<f32>;
+// Rain and snow intensity (0..1), snow sway (blocks)
+@group(2) @binding(3) var<uniform> intensity: vec4<f32>;
+// Column (x, z) of the heightmap's first texel, columns along each side (0
+// while there is no heightmap)
+@group(2) @binding(4) var<uniform> heightmap_area: vec4<f32>;
+// Highest block of each column
+@group(2) @binding(5) var heightmap: texture_2d<f32>;
+
+const TAU: f32 = 6.28318530718;
+
+struct Vertex {
+    @location(0) corner: vec3<f32>,
+    @location(1) particle: vec4<f32>,
+    @location(2) kind: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+// `value` wrapped into the range of `size` starting at `start`
+fn wrap(value: f32, start: f32, size: f32) -> f32 {
+    let x = value - start;
+    return start + x - size * floor(x / size);
+}
+
+// Whether a block above `position` keeps precipitation off it. Columns off the
+// map are open
+fn is_sheltered(position: vec3<f32>) -> bool {
+    let texel = vec2<i32>(floor(position.xz - heightmap_area.xy));
+    if any(texel < vec2<i32>(0)) || any(texel >= vec2<i32>(i32(heightmap_area.z))) {
+        return false;
+    }
+    return textureLoad(heightmap, texel, 0).r + 1.0 > position.y;
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let snow = vertex.kind > 0.5;
+    let radius = fall.x;
+    let height = fall.y;
+    let size = radius * 2.0;
+    let t = globals.time;
+
+    // Each particle has a fixed spot in a box around the camera that wraps in
+    // world space, so the rain does not slide along with the player
+    let camera = view.world_position;
+    let start = camera - vec3<f32>(radius, height * 0.5, radius);
+    let offset = vertex.particle.xyz;
+    let speed = select(fall.z, fall.w, snow);
+    var position = vec3<f32>(
+        wrap(offset.x * size, start.x, size),
+        wrap(offset.y * height - t * speed, start.y, height),
+        wrap(offset.z * size, start.z, size),
+    );
+    if snow {
+        position.x += sin(t * 0.8 + offset.y * TAU) * intensity.z;
+    }
+
+    if vertex.particle.w >= select(intensity.x, intensity.y, snow) || is_sheltered(position) {
+        // A degenerate quad draws nothing
+        out.clip_position = vec4<f32>(0.0);
+        return out;
+    }
+
+    // Flakes face the camera; streaks only turn about the vertical
+    var right = view.world_from_view[0].xyz;
+    var up = view.world_from_view[1].xyz;
+    if !snow {
+        right = normalize(vec3<f32>(right.x, 0.0, right.z));
+        up = vec3<f32>(0.0, 1.0, 0.0);
+    }
+    let world_position = position + right * vertex.corner.x + up * vertex.corner.y;
+    out.clip_position = view.clip_from_world * vec4<f32>(world_position, 1.0);
+    out.color = select(rain_color, snow_color, snow);
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
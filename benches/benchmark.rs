@@ -252,12 +252,12 @@ fn bench_player_physics_sim(c: &mut Criterion) {
     c.bench_function("player_physics_many_steps", |b| {
         b.iter(|| {
             let mut tf = bevy::prelude::Transform::from_xyz(0.0, 30.0, 0.0);
-            let mut player = Player { velocity: bevy::prelude::Vec3::ZERO, on_ground: false, flying: false };
+            let mut player = Player::default();
             let dt = 1.0f32 / 60.0f32;
-            let kb = Default::default();
+            let actions = Default::default();
 
             for _ in 0..5_000 {
-                player_physics_mod::physics_step(&mut tf, &mut player, &world, dt, &kb, bevy::prelude::KeyCode::Tab, bevy::prelude::KeyCode::Space);
+                player_physics_mod::physics_step(&mut tf, &mut player, &world, &registry, dt, &actions);
             }
 
             black_box((tf, player));
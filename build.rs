@@ -0,0 +1,27 @@
+//! Build script: records the git commit the binary is built from in the
+//! `STRATUM_GIT_COMMIT` environment variable (read with `option_env!` by
+//! `chunk::profiler`), so exported profiles name their commit without
+//! running git at runtime.
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok());
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=STRATUM_GIT_COMMIT={}", commit.trim());
+    }
+
+    // Rebuild when a commit is made or another branch is checked out
+    for path in [".git/HEAD", ".git/refs", ".git/packed-refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={path}");
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
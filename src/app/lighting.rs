@@ -2,7 +2,8 @@
 //!
 //! This module handles the day/night cycle, updates directional and ambient
 //! lighting, and writes the ambient tint into the shared voxel material so
-//! rendered chunks receive consistent lighting across the scene.
+//! rendered chunks receive consistent lighting across the scene. The light
+//! and sky are dimmed by the active weather (see `world::weather`).
 //!
 //! The main exported system is `daylight_cycle` and a small helper `smoothstep`.
 use bevy::prelude::*;
@@ -12,10 +13,16 @@ use bevy::pbr::StandardMaterial;
 use stratum::voxel_material::VoxelMaterial;
 use stratum::chunk::VoxelMaterialHandle;
 use crate::CycleTimer;
-use crate::GameTicks;
+use stratum::world::time::WorldTime;
+use stratum::world::weather::ActiveWeather;
 use stratum::debug::SystemThreadLog;
 use stratum::settings::Settings;
 
+/// `Nishita` sun intensity and Rayleigh coefficient under a clear sky
+/// (the atmosphere crate's defaults).
+const CLEAR_SUN_INTENSITY: f32 = 22.0;
+const CLEAR_RAYLEIGH: Vec3 = Vec3::new(5.5e-6, 13.0e-6, 22.4e-6);
+
 // Small cached previous-daylight state to avoid noisy GPU/material updates
 #[derive(Resource, Default)]
 pub struct DaylightPrev {
@@ -33,7 +40,6 @@ pub struct DaylightPrev {
 // parameters into a `SystemParam` to reduce function-argument count
 type CelestialQuerySet<'w, 's> = ParamSet<'w, 's, (
     Query<'w, 's, (&'static mut Transform, &'static mut DirectionalLight), With<crate::Sun>>,
-    Query<'w, 's, &'static mut Transform, With<crate::Moon>>,
     Query<'w, 's, (&'static mut Transform, &'static mut DirectionalLight), With<crate::Skylight>>,
 )>;
 
@@ -50,19 +56,22 @@ pub struct DaylightCtx<'w, 's> {
     pub player_light: Query<'w, 's, &'static mut PointLight, With<crate::PlayerFillLight>>,
     pub settings: Res<'w, Settings>,
     pub prev: ResMut<'w, DaylightPrev>,
+    pub weather: Res<'w, ActiveWeather>,
 }
 
-/// Update sun/moon/skylight and the shared ambient tint each frame.
+/// Update sun/skylight and the shared ambient tint each frame (the moon is
+/// handled by `night_sky`).
 ///
-/// This system advances the in-game time, computes a smooth day/night
+/// This system reads the `WorldTime` clock, computes a smooth day/night
 /// interpolation and updates:
 /// - the directional `Sun` light transform, color and illuminance,
 /// - the `Skylight` directional light parameters,
 /// - the global ambient light color/brightness,
-/// - the `ambient_tint` field of the shared `VoxelMaterial` (if present).
+/// - the `ambient_tint` field of the shared `VoxelMaterial` (if present),
+/// - the sky's brightness and colour under cloud cover.
 pub fn daylight_cycle(
     mut ctx: DaylightCtx<'_, '_>, 
-    ticks: Res<GameTicks>, 
+    world_time: Res<WorldTime>,
     sys_log: Option<ResMut<SystemThreadLog>>
 ) {
     if let Some(mut l) = sys_log {
@@ -72,18 +81,16 @@ pub fn daylight_cycle(
     ctx.timer.0.tick(ctx.time.delta());
 
     if ctx.timer.0.finished() {
-        let ticks_per_day = (crate::FULL_DAY_SECONDS * crate::GAME_TICK_RATE) as u64;
-        let tick_idx = ticks.count % ticks_per_day;
-        let frac = (tick_idx as f32) / (ticks_per_day as f32);
-        let t = frac * std::f32::consts::TAU;
+        let t = world_time.sun_angle();
 
         let sun_height = t.sin();
         let is_night_global = sun_height < -0.05;
 
         ctx.atmosphere.sun_position = Vec3::new(0., t.sin(), t.cos());
-
-        let sun_y = t.sin() * 400.0 + 100.0;
-        let sun_z = t.cos() * 400.0;
+        // Overcast skies are duller and greyer
+        let cloud_cover = ctx.weather.effects.cloud_cover;
+        ctx.atmosphere.sun_intensity = CLEAR_SUN_INTENSITY * (1.0 - cloud_cover * 0.75);
+        ctx.atmosphere.rayleigh_coefficient = CLEAR_RAYLEIGH * (1.0 - cloud_cover * 0.5);
 
         let mut pending_sk_update: Option<(Quat, Vec3, f32)> = None;
 
@@ -92,7 +99,8 @@ pub fn daylight_cycle(
             light_trans.rotation = Quat::from_rotation_x(-t);
 
             // Compute daylight info (fast) but only write heavy state when it meaningfully changes
-            let info = stratum::lighting::compute_daylight(sun_height, ctx.startup.startup_complete);
+            let mut info = stratum::lighting::compute_daylight(sun_height, ctx.startup.startup_complete);
+            ctx.weather.effects.apply(&mut info);
 
             // tolerances to avoid noisy updates that force GPU/material work
             const COLOR_EPS: f32 = 0.01;
@@ -156,12 +164,8 @@ pub fn daylight_cycle(
             ctx.prev.skylight_illuminance = info.skylight_illuminance;
         }
 
-        if let Ok(mut moon_transform) = ctx.celestial.p1().get_single_mut() {
-            moon_transform.translation = Vec3::new(0.0, -sun_y, -sun_z);
-        }
-
         if let Some((rot, sk_color, sk_ill)) = pending_sk_update
-            && let Ok((mut sk_trans, mut sk_dir)) = ctx.celestial.p2().get_single_mut() {
+            && let Ok((mut sk_trans, mut sk_dir)) = ctx.celestial.p1().get_single_mut() {
                 sk_trans.rotation = rot;
                 sk_dir.color = Color::srgb(sk_color.x, sk_color.y, sk_color.z);
                 sk_dir.illuminance = sk_ill;
@@ -5,11 +5,17 @@ pub mod player;
 pub mod atmosphere;
 pub mod streaming;
 pub mod display;
+pub mod simulation;
+pub mod night_sky;
+pub mod weather;
 
 pub use assets::ensure_atlas_sampler;
-pub use setup::{setup_texture_array, setup_voxel_material, setup};
+pub use setup::{setup_texture_array, setup_item_icons, setup_voxel_material, setup};
 pub use lighting::daylight_cycle;
-pub use player::update_player_fill_light;
+pub use player::{respawn_player, update_player_fill_light};
 pub use atmosphere::sync_atmosphere_settings;
-pub use streaming::sync_streaming_settings;
+pub use streaming::{sync_streaming_budget, sync_streaming_settings};
 pub use display::sync_vsync_settings;
+pub use simulation::sync_simulation_rate;
+pub use night_sky::{spawn_night_sky, update_night_sky};
+pub use weather::{spawn_precipitation, update_precipitation, update_weather_fog};
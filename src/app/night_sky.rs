@@ -0,0 +1,223 @@
+//! Night sky: the moon, the star field and the moonlight.
+//!
+//! `spawn_night_sky` creates a camera-facing moon quad with one material per
+//! phase (images drawn by `lighting::sky::moon_phase_image`), a single mesh
+//! holding every star and a faint directional `MoonLight`. Each frame
+//! `update_night_sky` places the moon on the sun's circle offset by its
+//! phase (see `lighting::sky`), keeps both at `SKY_DISTANCE` around the
+//! camera, turns the stars with the sky, fades them in with
+//! `DaylightInfo::night_factor` and sets the moonlight from the phase and
+//! the moon's height. Cloud cover from the active weather hides the stars,
+//! the moon and its light.
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use stratum::chunk::WORLD_SEED;
+use stratum::lighting::compute_daylight;
+use stratum::lighting::sky::{
+    moon_angle, moon_phase, moon_phase_image, moon_phase_index, moonlight_illuminance, sky_direction,
+    star_alpha, star_field, MOON_LIGHT_COLOR, MOON_PHASES, STAR_COUNT,
+};
+use stratum::player::Player;
+use stratum::world::time::WorldTime;
+use stratum::world::weather::ActiveWeather;
+
+/// Distance from the camera at which the moon and stars are drawn (inside
+/// the camera's far plane, beyond the terrain).
+pub const SKY_DISTANCE: f32 = 700.0;
+
+/// Edge length of the moon quad at `SKY_DISTANCE`.
+const MOON_SIZE: f32 = 42.0;
+
+/// Width and height of a moon phase image in pixels.
+const MOON_IMAGE_SIZE: u32 = 64;
+
+/// Change in star opacity or moonlight illuminance below which the
+/// material/light is left untouched.
+const SKY_EPS: f32 = 0.01;
+
+/// Moon materials, one per phase (index 0 is new moon).
+#[derive(Resource)]
+pub struct MoonPhaseMaterials(pub Vec<Handle<StandardMaterial>>);
+
+/// Material of the star field, faded in at night.
+#[derive(Resource)]
+pub struct StarFieldMaterial(pub Handle<StandardMaterial>);
+
+/// Build the star field mesh: one quad per star on a sphere of
+/// `SKY_DISTANCE` around the origin, brightness in the vertex colour alpha.
+#[allow(clippy::cast_possible_truncation)]
+fn star_field_mesh() -> Mesh {
+    let stars = star_field(WORLD_SEED, STAR_COUNT);
+    let mut positions = Vec::with_capacity(stars.len() * 4);
+    let mut normals = Vec::with_capacity(stars.len() * 4);
+    let mut colors = Vec::with_capacity(stars.len() * 4);
+    let mut indices = Vec::with_capacity(stars.len() * 6);
+    for star in &stars {
+        let centre = star.direction * SKY_DISTANCE;
+        let half = star.size * SKY_DISTANCE;
+        let u = star.direction.any_orthonormal_vector() * half;
+        let v = star.direction.cross(u);
+        let base = positions.len() as u32;
+        for corner in [centre - u - v, centre + u - v, centre + u + v, centre - u + v] {
+            positions.push(corner.to_array());
+            normals.push((-star.direction).to_array());
+            colors.push([1.0, 1.0, 1.0, star.brightness]);
+        }
+        indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Spawn the moon, the star field and the moonlight.
+///
+/// # Arguments
+/// - `commands`: Commands used to spawn entities and insert resources.
+/// - `meshes`: Asset storage for the moon quad and star mesh.
+/// - `materials`: Asset storage for the moon phase and star materials.
+/// - `images`: Asset storage for the moon phase images.
+#[allow(clippy::cast_precision_loss)]
+pub fn spawn_night_sky(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let sky_material = |texture: Option<Handle<Image>>, base_color: Color| StandardMaterial {
+        base_color,
+        base_color_texture: texture,
+        unlit: true,
+        fog_enabled: false,
+        alpha_mode: AlphaMode::Blend,
+        cull_mode: None,
+        ..default()
+    };
+
+    let phases: Vec<Handle<StandardMaterial>> = (0..MOON_PHASES)
+        .map(|i| {
+            let image = Image::new(
+                Extent3d { width: MOON_IMAGE_SIZE, height: MOON_IMAGE_SIZE, depth_or_array_layers: 1 },
+                TextureDimension::D2,
+                moon_phase_image(i as f32 / MOON_PHASES as f32, MOON_IMAGE_SIZE),
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::RENDER_WORLD,
+            );
+            materials.add(sky_material(Some(images.add(image)), Color::WHITE))
+        })
+        .collect();
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Rectangle::new(MOON_SIZE, MOON_SIZE)),
+            material: phases[0].clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NotShadowCaster,
+        crate::Moon,
+    ));
+
+    let stars = materials.add(sky_material(None, Color::srgba(1.0, 1.0, 1.0, 0.0)));
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(star_field_mesh()),
+            material: stars.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NotShadowCaster,
+        crate::StarField,
+    ));
+
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                illuminance: 0.0,
+                color: Color::srgb(MOON_LIGHT_COLOR.x, MOON_LIGHT_COLOR.y, MOON_LIGHT_COLOR.z),
+                shadows_enabled: false,
+                ..default()
+            },
+            ..default()
+        },
+        crate::MoonLight,
+    ));
+
+    commands.insert_resource(MoonPhaseMaterials(phases));
+    commands.insert_resource(StarFieldMaterial(stars));
+}
+
+// Group the night sky queries into a `SystemParam` to keep the system
+// signature small
+type NightSkyQuerySet<'w, 's> = ParamSet<'w, 's, (
+    Query<'w, 's, (&'static mut Transform, &'static mut Handle<StandardMaterial>, &'static mut Visibility), With<crate::Moon>>,
+    Query<'w, 's, (&'static mut Transform, &'static mut Visibility), With<crate::StarField>>,
+    Query<'w, 's, (&'static mut Transform, &'static mut DirectionalLight), With<crate::MoonLight>>,
+    Query<'w, 's, &'static Transform, With<Player>>,
+)>;
+
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct NightSkyCtx<'w, 's> {
+    pub world_time: Res<'w, WorldTime>,
+    pub weather: Res<'w, ActiveWeather>,
+    pub phases: Res<'w, MoonPhaseMaterials>,
+    pub star_material: Res<'w, StarFieldMaterial>,
+    pub materials: ResMut<'w, Assets<StandardMaterial>>,
+    pub sky: NightSkyQuerySet<'w, 's>,
+}
+
+/// Move the moon, stars and moonlight for the current world time.
+///
+/// Runs in `PostUpdate` after the camera is interpolated, so the sky stays
+/// centred on the drawn camera position.
+///
+/// # Arguments
+/// - `ctx`: A `NightSkyCtx` with the world time, sky assets and entities.
+pub fn update_night_sky(mut ctx: NightSkyCtx<'_, '_>) {
+    let Ok(camera) = ctx.sky.p3().get_single().map(|tf| tf.translation) else { return };
+
+    let sun_angle = ctx.world_time.sun_angle();
+    let night_factor = compute_daylight(sun_angle.sin(), true).night_factor;
+    let phase = moon_phase(ctx.world_time.day, ctx.world_time.time_of_day);
+    let moon_angle = moon_angle(sun_angle, phase);
+    let moon_dir = sky_direction(moon_angle);
+    let clear_sky = 1.0 - ctx.weather.effects.cloud_cover;
+
+    if let Ok((mut tf, mut material, mut visibility)) = ctx.sky.p0().get_single_mut() {
+        // The moon circles in the y/z plane, so x is never parallel to it
+        *tf = Transform::from_translation(camera + moon_dir * SKY_DISTANCE).looking_to(moon_dir, Vec3::X);
+        let wanted = &ctx.phases.0[moon_phase_index(phase)];
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+        let shown = moon_dir.y > -0.1 && clear_sky > 0.4;
+        visibility.set_if_neq(if shown { Visibility::Inherited } else { Visibility::Hidden });
+    }
+
+    let alpha = star_alpha(night_factor) * clear_sky;
+    if let Ok((mut tf, mut visibility)) = ctx.sky.p1().get_single_mut() {
+        tf.translation = camera;
+        tf.rotation = Quat::from_rotation_x(-sun_angle);
+        visibility.set_if_neq(if alpha > 0.0 { Visibility::Inherited } else { Visibility::Hidden });
+    }
+    // Only touch the material when the fade moved, to avoid re-uploading it every frame
+    let current = ctx.materials.get(&ctx.star_material.0).map(|m| m.base_color.alpha());
+    if current.is_some_and(|a| (a - alpha).abs() > SKY_EPS || (alpha == 0.0) != (a == 0.0))
+        && let Some(material) = ctx.materials.get_mut(&ctx.star_material.0)
+    {
+        material.base_color.set_alpha(alpha);
+    }
+
+    let illuminance = moonlight_illuminance(phase, moon_dir.y, night_factor) * clear_sky;
+    if let Ok((mut tf, mut light)) = ctx.sky.p2().get_single_mut() {
+        tf.rotation = Quat::from_rotation_x(-moon_angle);
+        if (light.illuminance - illuminance).abs() > SKY_EPS {
+            light.illuminance = illuminance;
+        }
+    }
+}
@@ -25,3 +25,85 @@ pub fn update_player_fill_light(
         }
     }
 }
+
+/// Seconds between dying and respawning.
+pub const RESPAWN_DELAY: f32 = 2.0;
+
+/// Respawn the player at the safe spawn point (see `setup::safe_spawn_y`)
+/// `RESPAWN_DELAY` seconds after dying, with full health. The spawn chunk
+/// is generated synchronously if it has been unloaded, like at startup.
+///
+/// # Arguments
+/// - `time`: Time resource used for the respawn delay.
+/// - `since_death`: Seconds since the player died.
+/// - `world`: World searched for the spawn height.
+/// - `block_registry`: Registry used to generate the spawn chunk.
+/// - `player`: The player's transform, state and health.
+#[allow(clippy::needless_pass_by_value)]
+pub fn respawn_player(
+    time: Res<Time>,
+    mut since_death: Local<f32>,
+    mut world: ResMut<stratum::world::World>,
+    block_registry: Res<stratum::block::BlockRegistry>,
+    mut player: Query<(&mut Transform, &mut stratum::player::Player, &mut stratum::player::Health)>,
+) {
+    let Ok((mut tf, mut player, mut health)) = player.get_single_mut() else { return };
+    if !health.dead {
+        *since_death = 0.0;
+        return;
+    }
+    *since_death += time.delta_seconds();
+    if *since_death < RESPAWN_DELAY {
+        return;
+    }
+
+    world.chunks.entry((0, 0)).or_insert_with(|| {
+        let mut c = stratum::chunk::Chunk::new();
+        c.generate(0, 0, &block_registry);
+        c
+    });
+    tf.translation = Vec3::new(0.0, crate::app::setup::safe_spawn_y(&world), 0.0);
+    *player = stratum::player::Player::default();
+    *health = stratum::player::Health { current: health.max, max: health.max, dead: false };
+    info!("Player respawned");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use stratum::player::{Health, Player};
+
+    #[test]
+    fn dead_player_respawns_with_full_health_after_the_delay() {
+        let mut world = stratum::world::World::new();
+        let mut chunk = stratum::chunk::Chunk::new();
+        chunk.set(0, 4, 0, 1);
+        world.chunks.insert((0, 0), chunk);
+
+        let mut app = App::new();
+        app.insert_resource(world)
+            .init_resource::<stratum::block::BlockRegistry>()
+            .init_resource::<Time>()
+            .add_systems(Update, respawn_player);
+        let dead = Health { current: 0.0, dead: true, ..Health::default() };
+        let player = Player { velocity: Vec3::new(0.0, -20.0, 0.0), ..Player::default() };
+        let entity = app.world_mut().spawn((Transform::from_xyz(40.0, -30.0, 40.0), player, dead)).id();
+
+        let step = |app: &mut App, seconds: f32| {
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(seconds));
+            app.update();
+        };
+        step(&mut app, RESPAWN_DELAY * 0.5);
+        step(&mut app, RESPAWN_DELAY * 0.25);
+        let health = app.world().get::<Health>(entity).unwrap();
+        assert!(health.dead);
+
+        step(&mut app, RESPAWN_DELAY * 0.5);
+        let health = app.world().get::<Health>(entity).unwrap();
+        assert!(!health.dead);
+        assert!((health.current - health.max).abs() < f32::EPSILON);
+        assert_eq!(app.world().get::<Transform>(entity).unwrap().translation, Vec3::new(0.0, 7.0, 0.0));
+        assert_eq!(app.world().get::<Player>(entity).unwrap().velocity, Vec3::ZERO);
+    }
+}
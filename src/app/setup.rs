@@ -8,6 +8,7 @@ use bevy::asset::AssetServer;
 use bevy::prelude::*;
 use stratum::atlas_builder::{AtlasBuilder, AtlasUVMap, AtlasTextureHandle};
 use stratum::block::BlockRegistry;
+use stratum::item::{ItemIconAtlas, ItemRegistry};
 use stratum::chunk::{ChunkEntities, MeshGenerationStats, PendingLodBuilds, LodStability};
 use stratum::settings::Settings;
 use std::sync::Arc;
@@ -74,6 +75,35 @@ use stratum::chunk::VoxelMaterialHandle;
     }
 }
 
+/// Pack item icons into the item atlas and insert the `ItemIconAtlas` resource.
+///
+/// Failing to build the atlas is not fatal; the resource is simply not
+/// inserted and UI that draws item icons should skip them.
+///
+/// # Arguments
+/// - `commands`: Commands for inserting the `ItemIconAtlas` resource.
+/// - `asset_server`: Used to load the produced atlas image into Bevy.
+/// - `item_registry`: Registry of items whose icons are packed.
+#[allow(clippy::needless_pass_by_value)]
+pub fn setup_item_icons(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    item_registry: Res<ItemRegistry>,
+    mut asset_paths: ResMut<stratum::debug::AssetPathRegistry>,
+) {
+    match stratum::item::icons::build_item_icon_atlas(&item_registry) {
+        Ok((uvs, fallback)) => {
+            let path = stratum::item::icons::ITEM_ATLAS_PATH;
+            let image: Handle<Image> = asset_server.load(path);
+            asset_paths.0.insert(format!("{:?}", image.clone()), path.to_string());
+            commands.insert_resource(ItemIconAtlas { image, uvs, fallback });
+        }
+        Err(e) => {
+            eprintln!("Failed to build item icon atlas: {e}");
+        }
+    }
+}
+
 /// Create the shared voxel material once the atlas texture is ready. 
 /// Waits for the `AtlasTextureHandle` resource is avaiable before setting it as 
 /// the `StandardMaterial`.
@@ -124,23 +154,42 @@ pub fn setup_voxel_material(
     ready.0 = true;
 }
 
+/// Camera height for a safe spawn at the world origin: a few blocks above
+/// the highest solid block of the spawn column, or a fixed height if the
+/// spawn chunk is not loaded.
+///
+/// # Arguments
+/// - `world`: World to search.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn safe_spawn_y(world: &stratum::world::World) -> f32 {
+    let mut spawn_y = 25.0f32;
+    if let Some(center_chunk) = world.chunks.get(&(0, 0)) {
+        let max_h = i32::try_from(stratum::world::MAX_HEIGHT).expect("MAX_HEIGHT fits in i32");
+        for y in (0..max_h).rev() {
+            let b = center_chunk.get(0usize, usize::try_from(y).expect("y non-negative"), 0usize);
+            if b != stratum::block::blocks::AIR {
+                spawn_y = (y as f32) + 3.0;
+                break;
+            }
+        }
+    }
+    spawn_y
+}
+
 /// Perform initial synchronous world generation and spawn core entities.
 ///
 /// This startup system generates a small local world (used for safe spawn
 /// placement), inserts the generated `World` resource, spawns directional
-/// lights for sun and skylight, the player camera, a player-local fill
-/// light, and a non-emissive moon mesh.
+/// lights for sun and skylight, the player camera and a player-local fill
+/// light. The moon and stars are spawned by `night_sky::spawn_night_sky`.
 ///
 /// # Arguments
 /// - `commands`: Commands used to spawn entities and insert resources.
-/// - `meshes`: Asset storage for creating meshes (moon sphere).
-/// - `materials`: Asset storage for standard materials.
 /// - `block_registry`: Registry used by terrain generation.
 #[allow(clippy::needless_pass_by_value, clippy::cast_precision_loss)]
 pub fn setup(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     block_registry: Res<BlockRegistry>,
 ) {
     let mut initial_world = stratum::world::World::new();
@@ -153,17 +202,7 @@ pub fn setup(
         }
     }
 
-    let mut spawn_y = 25.0f32;
-    if let Some(center_chunk) = initial_world.chunks.get(&(0, 0)) {
-        let max_h = i32::try_from(stratum::world::MAX_HEIGHT).expect("MAX_HEIGHT fits in i32");
-        for y in (0..max_h).rev() {
-            let b = center_chunk.get(0usize, usize::try_from(y).expect("y non-negative"), 0usize);
-            if b != stratum::block::blocks::AIR {
-                spawn_y = (y as f32) + 3.0;
-                break;
-            }
-        }
-    }
+    let spawn_y = safe_spawn_y(&initial_world);
 
     commands.insert_resource(initial_world);
 
@@ -198,13 +237,16 @@ pub fn setup(
                 transform: Transform::from_xyz(0.0, spawn_y, 0.0),
                 ..default()
             },
-            stratum::player::Player {
-                velocity: Vec3::ZERO,
-                on_ground: false,
-                flying: false,
-            },
+            stratum::player::Player::default(),
+            stratum::player::Health::default(),
             bevy_atmosphere::prelude::AtmosphereCamera::default(),
             stratum::player::PlayerLook::default(),
+            stratum::player::SimulatedPosition::default(),
+            // Clear until the weather brings fog in (see `app::weather`)
+            bevy::pbr::FogSettings {
+                falloff: bevy::pbr::FogFalloff::Exponential { density: 0.0 },
+                ..default()
+            },
         ))
         .id();
 
@@ -229,19 +271,4 @@ pub fn setup(
         color: Color::WHITE,
         brightness: 0.7,
     });
-
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Sphere { radius: 3.0 }.mesh().uv(8, 8)),
-            material: materials.add(StandardMaterial {
-                base_color: Color::srgb(0.9, 0.9, 1.0),
-                emissive: LinearRgba::rgb(0.0, 0.0, 0.0),
-                unlit: false,
-                ..default()
-            }),
-            transform: Transform::from_xyz(0.0, -300.0, 0.0),
-            ..default()
-        },
-        crate::Moon,
-    ));
 }
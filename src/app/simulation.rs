@@ -0,0 +1,33 @@
+//! Simulation rate sync: applies `Settings.gameplay.simulation_rate` to the
+//! fixed timestep that `FixedUpdate` (physics, block ticks, entities) runs at.
+use bevy::prelude::*;
+use stratum::settings::Settings;
+
+/// Lowest and highest accepted simulation rates (steps per second).
+const SIMULATION_RATE_RANGE: (f32, f32) = (10.0, 1000.0);
+
+/// Sync `Settings.gameplay.simulation_rate` into the `Time<Fixed>` timestep.
+/// Allows the rate to change at runtime without restarting.
+///
+/// # Arguments
+/// - `settings`: The current settings resource, from which the rate is read.
+/// - `time`: Fixed time whose timestep is updated.
+/// - `last`: A local cache of the last applied rate to avoid redundant updates.
+///
+/// # Example
+/// ```
+/// app.add_systems(First, crate::app::sync_simulation_rate);
+/// ```
+#[allow(clippy::needless_pass_by_value)]
+pub fn sync_simulation_rate(
+    settings: Res<Settings>,
+    mut time: ResMut<Time<Fixed>>,
+    mut last: Local<Option<f32>>,
+) {
+    let desired = settings.gameplay.simulation_rate.clamp(SIMULATION_RATE_RANGE.0, SIMULATION_RATE_RANGE.1);
+    if desired.is_nan() || *last == Some(desired) { return; }
+
+    time.set_timestep_hz(f64::from(desired));
+    info!("Simulation rate: {desired} steps per second");
+    *last = Some(desired);
+}
@@ -1,6 +1,9 @@
 //! Systems related to chunk streaming and render distance management.
-//! This module includes a system to sync the render distance from the main `Settings` resource.
+//! This module includes systems to sync the render distance and the streaming
+//! budget from the main `Settings` resource.
 use bevy::prelude::*;
+use stratum::chunk::governor::StreamingGovernor;
+use stratum::chunk::lod::StreamingBudget;
 use stratum::chunk::ChunkStreamingConfig;
 use stratum::settings::Settings;
 
@@ -29,4 +32,28 @@ pub fn sync_streaming_settings(
     cfg.unload_distance = load + 2;
 
     *last = Some(r);
+}
+
+/// Rebuild the `StreamingBudget` from `Settings.performance` (preset,
+/// overrides, background meshing, the per-frame mesh limit and the adaptive
+/// governor's target) whenever the settings change, including on hot reload
+/// and from the settings menu. The governor keeps its current scale.
+///
+/// # Arguments
+/// - `settings`: The current settings resource, from which the performance settings are read.
+/// - `governor`: The frame-time governor, which holds the unscaled budget.
+/// - `budget`: The streaming budget read by `stream_chunks`.
+pub fn sync_streaming_budget(
+    settings: Res<Settings>,
+    mut governor: ResMut<StreamingGovernor>,
+    mut budget: ResMut<StreamingBudget>,
+) {
+    if !settings.is_changed() { return; }
+
+    governor.apply_settings(&settings.performance);
+    let new = governor.budget();
+    if *budget != new {
+        info!("Streaming budget ({:?} preset, scale {:.2}): {:?}", settings.performance.preset, governor.scale, new);
+        *budget = new;
+    }
 }
\ No newline at end of file
@@ -0,0 +1,250 @@
+//! Weather rendering: fog and rain/snow.
+//!
+//! The weather the player sees is `ActiveWeather` (see `world::weather`).
+//! `update_weather_fog` sets the camera's exponential fog from it, darkened
+//! at night; `daylight_cycle` dims the sun, sky and ambient light and
+//! `update_night_sky` hides the stars and moon behind the clouds.
+//!
+//! Precipitation is a single mesh spawned by `spawn_precipitation` with a
+//! quad for each of `RAIN_DROPS` streaks and `SNOW_FLAKES` flakes, drawn
+//! with a `PrecipitationMaterial` in one call. It is deliberately not an
+//! instanced quad: a `Material` gets no per-instance data, and instancing
+//! would need a custom render pipeline for the same result of one draw
+//! call with nothing uploaded per frame. The shader animates every
+//! particle from the global time: each one has a fixed spot in a box of
+//! `PRECIPITATION_RADIUS` around the camera that wraps in world space, so
+//! the rain does not slide along with the player, and particles above the
+//! intensity or under a block are collapsed. `update_precipitation` only
+//! writes the material's uniforms when the weather or light changes and
+//! uploads `PrecipitationHeightmap` as a texture when it changes, and hides
+//! the mesh while nothing falls.
+use bevy::pbr::{FogFalloff, FogSettings, NotShadowCaster};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::view::NoFrustumCulling;
+use stratum::chunk::WORLD_SEED;
+use stratum::lighting::compute_daylight;
+use stratum::material::precipitation_material::{PrecipitationMaterial, ATTRIBUTE_PARTICLE, ATTRIBUTE_PARTICLE_KIND};
+use stratum::player::Player;
+use stratum::world::time::WorldTime;
+use stratum::world::weather::{ActiveWeather, PrecipitationHeightmap};
+
+/// Rain streaks in the precipitation mesh.
+pub const RAIN_DROPS: usize = 1500;
+
+/// Snow flakes in the precipitation mesh.
+pub const SNOW_FLAKES: usize = 1200;
+
+/// Half the width of the box of particles around the camera (blocks).
+pub const PRECIPITATION_RADIUS: f32 = 16.0;
+
+/// Height of the box of particles, centred on the camera (blocks).
+const PRECIPITATION_HEIGHT: f32 = 24.0;
+
+/// Fall speeds (blocks per second).
+const RAIN_SPEED: f32 = 18.0;
+const SNOW_SPEED: f32 = 2.0;
+
+/// Sideways sway of falling snow (blocks).
+const SNOW_DRIFT: f32 = 0.4;
+
+/// Size of a rain streak and a snow flake (width, height in blocks).
+const RAIN_SIZE: Vec2 = Vec2::new(0.03, 0.7);
+const SNOW_SIZE: Vec2 = Vec2::new(0.09, 0.09);
+
+/// Change in fog or particle colour below which it is left untouched.
+const WEATHER_EPS: f32 = 0.002;
+
+/// Marks the precipitation mesh.
+#[derive(Component)]
+pub struct Precipitation;
+
+/// The precipitation material and its heightmap texture.
+#[derive(Resource)]
+pub struct PrecipitationHandles {
+    pub material: Handle<PrecipitationMaterial>,
+    pub heightmap: Handle<Image>,
+}
+
+/// Base colours of rain and snow in full daylight.
+const RAIN_COLOR: Vec4 = Vec4::new(0.72, 0.78, 0.88, 0.35);
+const SNOW_COLOR: Vec4 = Vec4::new(1.0, 1.0, 1.0, 0.85);
+
+/// Brightness of unlit weather (fog, particles) for the time of day.
+fn weather_brightness(world_time: &WorldTime) -> f32 {
+    1.0 - compute_daylight(world_time.sun_angle().sin(), true).night_factor * 0.85
+}
+
+/// Linear colour of sRGB `color` darkened to `brightness`, alpha kept.
+fn precipitation_color(color: Vec4, brightness: f32) -> Vec4 {
+    let linear = Color::srgba(color.x * brightness, color.y * brightness, color.z * brightness, color.w).to_linear();
+    Vec4::new(linear.red, linear.green, linear.blue, linear.alpha)
+}
+
+/// Build the precipitation mesh: a quad per particle, with the particle's
+/// spot, rank and kind on each of its corners.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn precipitation_mesh() -> Mesh {
+    let mut state = u64::from(WORLD_SEED) ^ 0x0AA1_4000_0000_0000;
+    let mut next = move || {
+        // splitmix64
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        ((z ^ (z >> 31)) >> 40) as f32 / (1u64 << 24) as f32
+    };
+
+    let particles = RAIN_DROPS + SNOW_FLAKES;
+    let mut corners = Vec::with_capacity(particles * 4);
+    let mut data = Vec::with_capacity(particles * 4);
+    let mut kinds = Vec::with_capacity(particles * 4);
+    let mut indices = Vec::with_capacity(particles * 6);
+    for (count, kind, size) in [(RAIN_DROPS, 0.0, RAIN_SIZE), (SNOW_FLAKES, 1.0, SNOW_SIZE)] {
+        for i in 0..count {
+            let particle = [next(), next(), next(), (i as f32 + 0.5) / count as f32];
+            let first = corners.len() as u32;
+            for (x, y) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)] {
+                corners.push([x * size.x, y * size.y, 0.0]);
+                data.push(particle);
+                kinds.push(kind);
+            }
+            indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+    }
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, corners)
+        .with_inserted_attribute(ATTRIBUTE_PARTICLE, data)
+        .with_inserted_attribute(ATTRIBUTE_PARTICLE_KIND, kinds)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Spawn the precipitation mesh, hidden.
+///
+/// # Arguments
+/// - `commands`: Commands used to spawn the mesh and insert resources.
+/// - `meshes`: Asset storage for the precipitation mesh.
+/// - `materials`: Asset storage for the precipitation material.
+/// - `images`: Asset storage for the heightmap texture.
+#[allow(clippy::cast_sign_loss)]
+pub fn spawn_precipitation(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PrecipitationMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let width = PrecipitationHeightmap::WIDTH as u32;
+    let heightmap = images.add(Image::new_fill(
+        Extent3d { width, height: width, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &(-1.0f32).to_ne_bytes(),
+        TextureFormat::R32Float,
+        RenderAssetUsages::default(),
+    ));
+    let material = materials.add(PrecipitationMaterial {
+        fall: Vec4::new(PRECIPITATION_RADIUS, PRECIPITATION_HEIGHT, RAIN_SPEED, SNOW_SPEED),
+        intensity: Vec4::new(0.0, 0.0, SNOW_DRIFT, 0.0),
+        heightmap: heightmap.clone(),
+        ..default()
+    });
+
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: meshes.add(precipitation_mesh()),
+            material: material.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NotShadowCaster,
+        // The shader moves the quads around the camera, so the mesh bounds
+        // say nothing about where they are drawn
+        NoFrustumCulling,
+        Precipitation,
+    ));
+    commands.insert_resource(PrecipitationHandles { material, heightmap });
+}
+
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct PrecipitationCtx<'w, 's> {
+    pub weather: Res<'w, ActiveWeather>,
+    pub world_time: Res<'w, WorldTime>,
+    pub heightmap: Res<'w, PrecipitationHeightmap>,
+    pub handles: Res<'w, PrecipitationHandles>,
+    pub materials: ResMut<'w, Assets<PrecipitationMaterial>>,
+    pub images: ResMut<'w, Assets<Image>>,
+    pub precipitation: Query<'w, 's, &'static mut Visibility, With<Precipitation>>,
+}
+
+/// Feed the precipitation shader: upload the heightmap when it changes,
+/// write the intensities and night-darkened colours when they change, and
+/// hide the mesh while nothing falls.
+///
+/// # Arguments
+/// - `ctx`: A `PrecipitationCtx` with the weather, heightmap and handles.
+#[allow(clippy::cast_precision_loss)]
+pub fn update_precipitation(mut ctx: PrecipitationCtx<'_, '_>) {
+    let effects = ctx.weather.effects;
+    let falling = effects.rain > 0.0 || effects.snow > 0.0;
+    for mut visibility in &mut ctx.precipitation {
+        visibility.set_if_neq(if falling { Visibility::Inherited } else { Visibility::Hidden });
+    }
+
+    let heightmap_changed = ctx.heightmap.is_changed();
+    if heightmap_changed && let Some(image) = ctx.images.get_mut(&ctx.handles.heightmap) {
+        image.data = ctx.heightmap.heights().iter().flat_map(|h| (*h as f32).to_ne_bytes()).collect();
+    }
+    if !falling && !heightmap_changed {
+        return;
+    }
+
+    let brightness = weather_brightness(&ctx.world_time);
+    let rain_color = precipitation_color(RAIN_COLOR, brightness);
+    let snow_color = precipitation_color(SNOW_COLOR, brightness);
+    let Some(material) = ctx.materials.get(&ctx.handles.material) else { return };
+    let intensity = Vec4::new(effects.rain, effects.snow, material.intensity.z, 0.0);
+    let moved = (intensity - material.intensity).abs().max_element() > WEATHER_EPS
+        || (rain_color - material.rain_color).abs().max_element() > WEATHER_EPS
+        || (snow_color - material.snow_color).abs().max_element() > WEATHER_EPS;
+    // Writing the material rebuilds its bind group, which also picks up the
+    // new heightmap texture
+    if (heightmap_changed || moved)
+        && let Some(material) = ctx.materials.get_mut(&ctx.handles.material)
+    {
+        material.rain_color = rain_color;
+        material.snow_color = snow_color;
+        material.intensity = intensity;
+        material.heightmap_area = ctx.heightmap.origin().map_or(Vec4::ZERO, |origin| {
+            Vec4::new(origin.x as f32, origin.y as f32, PrecipitationHeightmap::WIDTH as f32, 0.0)
+        });
+    }
+}
+
+/// Set the camera fog from the active weather, darkened at night.
+///
+/// # Arguments
+/// - `weather`: The weather the player sees.
+/// - `world_time`: The world clock, for the night darkening.
+/// - `fog`: The player camera's fog settings.
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_weather_fog(
+    weather: Res<ActiveWeather>,
+    world_time: Res<WorldTime>,
+    mut fog: Query<&mut FogSettings, With<Player>>,
+) {
+    let Ok(mut fog) = fog.get_single_mut() else { return };
+    let effects = weather.effects;
+    let color = effects.fog_color * weather_brightness(&world_time);
+    let current = fog.color.to_srgba();
+    let density_changed = match fog.falloff {
+        FogFalloff::Exponential { density } => (density - effects.fog_density).abs() > WEATHER_EPS * 0.01,
+        _ => true,
+    };
+    let color_changed = (current.red - color.x).abs().max((current.green - color.y).abs()).max((current.blue - color.z).abs())
+        > WEATHER_EPS;
+    if density_changed || color_changed {
+        fog.color = Color::srgb(color.x, color.y, color.z);
+        fog.falloff = FogFalloff::Exponential { density: effects.fog_density };
+    }
+}
@@ -64,6 +64,44 @@ impl AtlasBuilder {
         Ok(info)
     }
 
+    /// Build an atlas from an explicit list of named PNG files instead of a
+    /// directory.
+    ///
+    /// Each tile gets the name it is listed with, so files that share a stem
+    /// in different directories do not collide. Files that are missing or
+    /// cannot be decoded are skipped with a warning; a repeated name keeps
+    /// its first file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` when none of the files could be loaded or writing the
+    /// atlas image fails.
+    ///
+    /// # Arguments
+    /// * `files` - Tile names and the paths of their PNG files.
+    /// * `output_path` - Destination path for the generated atlas image (PNG).
+    pub fn build_atlas_from_files(
+        files: &[(String, std::path::PathBuf)],
+        output_path: &Path,
+    ) -> Result<crate::atlas::AtlasInfo, Box<dyn std::error::Error>> {
+        let mut textures: Vec<(String, RgbaImage)> = Vec::new();
+        for (name, path) in files {
+            if textures.iter().any(|(n, _)| n == name) {
+                continue;
+            }
+            match image::open(path) {
+                Ok(img) => textures.push((name.clone(), img.to_rgba8())),
+                Err(e) => eprintln!("Skipping atlas tile {}: {e}", path.display()),
+            }
+        }
+
+        if textures.is_empty() {
+            return Err("No textures could be loaded for atlas".into());
+        }
+
+        Self::build_from_textures(&textures, output_path)
+    }
+
     // --- helper methods extracted to reduce function length ---
 
     fn collect_textures(texture_dir: &Path) -> Result<Vec<(String, RgbaImage)>, Box<dyn std::error::Error>> {
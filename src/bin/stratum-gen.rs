@@ -0,0 +1,142 @@
+//! `stratum-gen`: headless terrain generation.
+//!
+//! Loads the block and biome definitions, generates a square of chunks with
+//! a given seed and writes region files and/or top-down PNG maps, without
+//! opening a window. Handy for comparing generator changes:
+//!
+//! ```text
+//! cargo run --release --bin stratum-gen -- --seed 42 --radius 8 --height-map height.png --biome-map biome.png
+//! cargo run --release --bin stratum-gen -- --seed 42 --save saves/generated
+//! ```
+
+use stratum::biome::climate::Climate;
+use stratum::biome::loader::load_biomes_from_dir;
+use stratum::block::loader::load_blocks_from_dir;
+use stratum::chunk::WORLD_SEED;
+use stratum::world::generate::{biome_map, generate_area, height_map, ChunkArea};
+use stratum::world::save::write_regions;
+use std::process::ExitCode;
+use std::time::Instant;
+
+const USAGE: &str = "\
+usage: stratum-gen [options] [--height-map FILE] [--biome-map FILE] [--save DIR]
+
+Generates the chunks within RADIUS of CENTER and writes at least one of:
+  --height-map FILE   top-down PNG height map (water tinted blue)
+  --biome-map FILE    top-down PNG biome map (red = warm, blue = cold, green = humid)
+  --save DIR          region files in DIR/region
+
+options:
+  --seed N            terrain seed (default: the game's seed)
+  --center X,Z        centre chunk (default: 0,0)
+  --radius R          radius in chunks (default: 4)
+  --blocks DIR        block definitions (default: data/blocks)
+  --biomes DIR        biome definitions (default: data/biomes)
+  --help              show this message";
+
+/// Parsed command line.
+struct Options {
+    seed: u32,
+    center: (i32, i32),
+    radius: i32,
+    blocks: String,
+    biomes: String,
+    height_map: Option<String>,
+    biome_map: Option<String>,
+    save: Option<String>,
+}
+
+/// Parse the command line (without the program name).
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        seed: WORLD_SEED,
+        center: (0, 0),
+        radius: 4,
+        blocks: "data/blocks".to_string(),
+        biomes: "data/biomes".to_string(),
+        height_map: None,
+        biome_map: None,
+        save: None,
+    };
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            return Err(String::new());
+        }
+        let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+        match flag.as_str() {
+            "--seed" => options.seed = value.parse().map_err(|_| format!("'{value}' is not a seed"))?,
+            "--center" => {
+                let (x, z) = value.split_once(',').ok_or("--center expects X,Z")?;
+                let parse = |v: &str| v.trim().parse::<i32>().map_err(|_| format!("'{v}' is not a chunk coordinate"));
+                options.center = (parse(x)?, parse(z)?);
+            }
+            "--radius" => {
+                options.radius = value.parse().ok().filter(|r| *r >= 0).ok_or_else(|| format!("'{value}' is not a radius"))?;
+            }
+            "--blocks" => options.blocks = value,
+            "--biomes" => options.biomes = value,
+            "--height-map" => options.height_map = Some(value),
+            "--biome-map" => options.biome_map = Some(value),
+            "--save" => options.save = Some(value),
+            _ => return Err(format!("unknown option {flag}")),
+        }
+    }
+    if options.height_map.is_none() && options.biome_map.is_none() && options.save.is_none() {
+        return Err("nothing to write: pass --height-map, --biome-map and/or --save".to_string());
+    }
+    Ok(options)
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let blocks = load_blocks_from_dir(&options.blocks);
+    let mut biomes = load_biomes_from_dir(&options.biomes);
+    biomes.climate = Climate::new(options.seed);
+    let area = ChunkArea::around(options.center, options.radius);
+
+    let started = Instant::now();
+    let chunks = generate_area(area, options.seed, &blocks);
+    println!(
+        "Generated {} chunks around ({}, {}) with seed {} in {:.2?}",
+        chunks.len(),
+        options.center.0,
+        options.center.1,
+        options.seed,
+        started.elapsed()
+    );
+
+    if let Some(path) = &options.height_map {
+        let (image, (low, high)) = height_map(&chunks, area, &blocks);
+        image.save(path).map_err(|e| format!("cannot write {path}: {e}"))?;
+        println!("Wrote height map {path} (surface y {low}..={high})");
+    }
+    if let Some(path) = &options.biome_map {
+        let (image, names) = biome_map(&biomes, area);
+        image.save(path).map_err(|e| format!("cannot write {path}: {e}"))?;
+        println!("Wrote biome map {path} ({})", names.join(", "));
+    }
+    if let Some(dir) = &options.save {
+        let files = write_regions(dir, &chunks)?;
+        println!("Wrote {} region file(s) to {dir}", files.len());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("error: {e}\n");
+            }
+            eprintln!("{USAGE}");
+            return if e.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+        }
+    };
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
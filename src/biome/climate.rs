@@ -0,0 +1,125 @@
+//! Climate noise fields used to pick biomes.
+//!
+//! Every block column has a climate: a temperature (°C), a humidity
+//! (`0..=1`) and a continentalness (`-1..=1`, low near the coast, high deep
+//! inland where the terrain generator raises mountains). A column gets the
+//! biome whose declared climate point is nearest to its own, so adding a
+//! biome RON file is enough to make it appear in the world.
+//!
+//! Continentalness uses the same noise as the generator's mountain factor
+//! (see `Chunk::generate_with_seed`), so biomes with a high continentalness
+//! line up with mountainous terrain when both use the same seed.
+
+use super::Biome;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin, Simplex};
+
+/// Temperature difference (°C) that counts as much as the full humidity
+/// range when comparing climates.
+pub const TEMPERATURE_SPAN: f32 = 40.0;
+
+/// Mean temperature of the world (°C).
+const MEAN_TEMPERATURE: f64 = 14.0;
+
+/// How far temperatures stray from the mean (°C per unit of noise).
+const TEMPERATURE_RANGE: f64 = 30.0;
+
+/// A point in climate space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClimatePoint {
+    pub temperature: f32,
+    pub humidity: f32,
+    pub continentalness: f32,
+}
+
+impl ClimatePoint {
+    /// The climate a biome declares in its RON file.
+    #[must_use]
+    pub fn of(biome: &Biome) -> Self {
+        Self { temperature: biome.temperature, humidity: biome.humidity, continentalness: biome.continentalness }
+    }
+
+    /// Distance between two climates, with temperature scaled down by
+    /// `TEMPERATURE_SPAN` so all three axes weigh about the same.
+    #[must_use]
+    pub fn distance(&self, other: &Self) -> f32 {
+        let t = (self.temperature - other.temperature) / TEMPERATURE_SPAN;
+        let h = self.humidity - other.humidity;
+        let c = self.continentalness - other.continentalness;
+        (t * t + h * h + c * c).sqrt()
+    }
+}
+
+/// Seeded climate noise fields.
+#[derive(Debug, Clone)]
+pub struct Climate {
+    seed: u32,
+    temperature: Fbm<Perlin>,
+    humidity: Fbm<Perlin>,
+    continentalness: Simplex,
+}
+
+impl Climate {
+    /// Climate fields for a terrain seed.
+    #[must_use]
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            temperature: Fbm::new(seed.wrapping_add(100)).set_octaves(3).set_frequency(0.0008).set_persistence(0.5),
+            humidity: Fbm::new(seed.wrapping_add(101)).set_octaves(3).set_frequency(0.0011).set_persistence(0.5),
+            // Same noise and frequency as the generator's mountain factor
+            continentalness: Simplex::new(seed.wrapping_add(2)),
+        }
+    }
+
+    /// The seed the fields were created with.
+    #[must_use]
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Climate of the block column at world `(x, z)`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn sample(&self, x: i32, z: i32) -> ClimatePoint {
+        let (x, z) = (f64::from(x), f64::from(z));
+        ClimatePoint {
+            temperature: (MEAN_TEMPERATURE + self.temperature.get([x, z]) * TEMPERATURE_RANGE) as f32,
+            humidity: (0.5 + self.humidity.get([x, z]) * 0.8).clamp(0.0, 1.0) as f32,
+            continentalness: self.continentalness.get([x * 0.002, z * 0.002]).clamp(-1.0, 1.0) as f32,
+        }
+    }
+}
+
+impl Default for Climate {
+    fn default() -> Self {
+        Self::new(crate::chunk::WORLD_SEED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome::BiomeRegistry;
+
+    #[test]
+    fn nearest_climate_point_wins_and_weights_sum_to_one() {
+        let mut registry = BiomeRegistry::default();
+        for (name, temperature, humidity) in [("cold", -10.0, 0.3), ("mild", 14.0, 0.5), ("hot", 35.0, 0.1)] {
+            registry.biomes.insert(name.to_string(), Biome { name: name.to_string(), temperature, humidity, ..Biome::default() });
+        }
+        let at = |temperature, humidity| ClimatePoint { temperature, humidity, continentalness: 0.0 };
+        assert_eq!(registry.biome_for_climate(&at(-5.0, 0.4)).map(|b| b.name.as_str()), Some("cold"));
+        assert_eq!(registry.biome_for_climate(&at(30.0, 0.0)).map(|b| b.name.as_str()), Some("hot"));
+
+        // A new biome takes over the climate it declares
+        registry.biomes.insert("swamp".to_string(), Biome { name: "swamp".to_string(), humidity: 0.95, ..Biome::default() });
+        assert_eq!(registry.biome_for_climate(&at(15.0, 0.9)).map(|b| b.name.as_str()), Some("swamp"));
+
+        for (x, z) in [(0, 0), (1000, -250), (-4000, 7000)] {
+            let weights = registry.biome_weights(x, z);
+            let total: f32 = weights.iter().map(|(_, w)| w).sum();
+            assert!((total - 1.0).abs() < 1e-4);
+            assert_eq!(weights[0].0.name, registry.biome_at_block(x, z).expect("biomes exist").name);
+        }
+    }
+}
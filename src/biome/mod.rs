@@ -5,7 +5,9 @@
 //! files in the `data/biomes` directory and can be hot-reloaded during runtime.
 //! The `BiomeRegistry` is exposed as a resource-like structure providing access
 //! to biome definitions and helper utilities for sampling weather and picking
-//! structures.
+//! structures. Which biome a column belongs to comes from climate noise
+//! (temperature, humidity, continentalness) matched against each biome's
+//! declared climate (see `climate`).
 //!
 //! # Examples
 //!
@@ -28,6 +30,7 @@
 
 
 use bevy::prelude::Resource;
+use climate::{Climate, ClimatePoint};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -92,6 +95,8 @@ pub struct Biome {
     #[serde(default)]
     pub temperature: f32, // Simple numeric temperature in Celsius, used for weather and biome classification
     pub humidity: f32, // Simple numeric humidity in 0..=1 range, used for weather and biome classification
+    #[serde(default)]
+    pub continentalness: f32, // -1..=1, low near the coast and high deep inland (mountains), used for biome classification
 
     /// Block refs
     #[serde(default)]
@@ -142,6 +147,7 @@ impl Default for Biome {
             noise_lacunarity: 2.0,
             temperature: 15.0,
             humidity: 0.5,
+            continentalness: 0.0,
             surface_block: None,
             soil_block: None,
             rock_block: None,
@@ -166,11 +172,20 @@ impl Default for Biome {
 }
 
 /// Registry for biomes, providing lookup and sampling utilities
+///
+/// # Fields
+/// * `biomes` - biome definitions by name
+/// * `climate` - climate noise fields biomes are picked with (see `climate`)
 #[derive(Resource, Default)]
 pub struct BiomeRegistry {
     pub biomes: HashMap<String, Biome>,
+    pub climate: Climate,
 }
 
+/// Biomes within this climate distance of the nearest one blend into it
+/// (see `BiomeRegistry::biome_weights`).
+pub const BIOME_BLEND_WIDTH: f32 = 0.04;
+
 /// Helper methods for biome lookup and sampling
 /// Provides utilities to get biomes by name, sample weather based on biome
 /// definitions, and pick structures to spawn based on biome configuration.
@@ -180,23 +195,85 @@ impl BiomeRegistry {
         self.biomes.get(name)
     }
 
+    /// Every biome with its distance to `climate`, nearest first. Ties are
+    /// broken by name so the order does not depend on the map's.
+    fn by_distance(&self, climate: &ClimatePoint) -> Vec<(&Biome, f32)> {
+        let mut biomes: Vec<(&Biome, f32)> =
+            self.biomes.values().map(|b| (b, ClimatePoint::of(b).distance(climate))).collect();
+        biomes.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        biomes
+    }
+
+    /// The biome whose climate point is nearest to `climate`.
     #[must_use]
-    pub fn get_biome_at(&self, x: i32, z: i32) -> Option<&Biome> {
-        use noise::{NoiseFn, Perlin};
+    pub fn biome_for_climate(&self, climate: &ClimatePoint) -> Option<&Biome> {
+        self.by_distance(climate).first().map(|(b, _)| *b)
+    }
 
-        let perlin = Perlin::new(1337);
-        let biome_noise = perlin.get([(f64::from(x)) * 0.01, (f64::from(z)) * 0.01]);
+    /// The biome of the block column at world `(x, z)`.
+    #[must_use]
+    pub fn biome_at_block(&self, x: i32, z: i32) -> Option<&Biome> {
+        self.biome_for_climate(&self.climate.sample(x, z))
+    }
 
-        let biome_name = match biome_noise {
-            n if n < -0.4 => "tundra",
-            n if n < -0.1 => "forest",
-            n if n < 0.2 => "plains",
-            n if n < 0.5 => "desert",
-            _ => "jungle",
-        };
+    /// The biome at the centre of chunk `(x, z)`.
+    #[must_use]
+    pub fn get_biome_at(&self, x: i32, z: i32) -> Option<&Biome> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        const HALF_CHUNK: i32 = crate::chunk::CHUNK_SIZE as i32 / 2;
+        self.biome_at_block(x * HALF_CHUNK * 2 + HALF_CHUNK, z * HALF_CHUNK * 2 + HALF_CHUNK)
+    }
+
+    /// Blend weights of the biomes around the block column at world
+    /// `(x, z)`, largest first, summing to 1.
+    ///
+    /// Each biome within `BIOME_BLEND_WIDTH` of the nearest climate distance
+    /// gets a weight that falls off linearly with its extra distance, so
+    /// weights change smoothly across biome borders and deep inside a biome
+    /// it is the only one returned.
+    #[must_use]
+    pub fn biome_weights(&self, x: i32, z: i32) -> Vec<(&Biome, f32)> {
+        let biomes = self.by_distance(&self.climate.sample(x, z));
+        let Some(&(_, nearest)) = biomes.first() else { return Vec::new() };
+        let mut weights: Vec<(&Biome, f32)> = biomes
+            .into_iter()
+            .map(|(b, d)| (b, nearest + BIOME_BLEND_WIDTH - d))
+            .filter(|(_, w)| *w > 0.0)
+            .collect();
+        let total: f32 = weights.iter().map(|(_, w)| w).sum();
+        for (_, w) in &mut weights {
+            *w /= total;
+        }
+        weights
+    }
 
-        self.biomes.get(biome_name)
+    /// Pick a weather kind from `biome.weather_chance` with `value` in
+    /// 0..=1: each chance is a relative weight and the kinds are taken in
+    /// name order, so the same value always gives the same weather.
+    ///
+    /// # Return
+    /// * `Option<&str>` - the weather name, or `None` if the biome declares
+    ///   no weather with a positive chance
+    #[must_use]
+    pub fn sample_weather_by_value<'a>(&self, biome: &'a Biome, value: f32) -> Option<&'a str> {
+        let mut kinds: Vec<(&str, f32)> = biome
+            .weather_chance
+            .iter()
+            .filter(|(_, chance)| **chance > 0.0)
+            .map(|(name, chance)| (name.as_str(), *chance))
+            .collect();
+        kinds.sort_by(|a, b| a.0.cmp(b.0));
+        let total: f32 = kinds.iter().map(|(_, chance)| chance).sum();
+        let mut pick = value.clamp(0.0, 1.0) * total;
+        for &(name, chance) in &kinds {
+            if pick < chance {
+                return Some(name);
+            }
+            pick -= chance;
+        }
+        kinds.last().map(|(name, _)| *name)
     }
 }
 
+pub mod climate;
 pub mod loader;
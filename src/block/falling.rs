@@ -0,0 +1,301 @@
+//! Falling blocks (sand, gravel, ...).
+//!
+//! Blocks with `affected_by_gravity` are checked whenever a `BlockChanged`
+//! event reports a change at or beneath them. An unsupported block is removed
+//! from the world and replaced by a `FallingBlock` entity that drops with the
+//! player's `GRAVITY`. When it lands on a full block it solidifies again; if
+//! it lands on a non-replaceable partial block (or its cell got filled while
+//! falling) it pops into its drop item instead.
+//!
+//! Removing a block queues a check of the block above it, so a sand column
+//! collapses as a chain. At most `MAX_FALLING_STARTS_PER_FRAME` blocks
+//! are converted per frame and the rest stay queued, and chunk remeshing goes
+//! through `DirtyChunks` so each touched chunk is rebuilt once per frame.
+
+use super::{blocks, Block, BlockId, BlockRegistry, DirtyChunks};
+use crate::atlas_builder::AtlasUVMap;
+use crate::chunk::{Chunk, VoxelMaterialHandle};
+use crate::item::{spawn_item_drop, ItemRegistry, ItemStack};
+use crate::player::{GRAVITY, TERMINAL_VELOCITY};
+use crate::world::{BlockChanged, World};
+use bevy::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const CHUNK_SIZE_I32: i32 = crate::chunk::CHUNK_SIZE as i32;
+
+/// Upper bound on blocks turned into falling entities in a single frame.
+pub const MAX_FALLING_STARTS_PER_FRAME: usize = 32;
+
+/// A block currently falling. The entity's translation is the block's
+/// minimum corner, like a block at `(x, y, z)` occupying `x..x+1`.
+#[derive(Component, Debug)]
+pub struct FallingBlock {
+    pub block: BlockId,
+    pub velocity: f32, // Vertical velocity in blocks per second (negative = down)
+}
+
+/// Positions waiting to be checked for unsupported gravity blocks.
+#[derive(Resource, Default)]
+pub struct FallingBlockQueue(pub VecDeque<IVec3>);
+
+/// Unit cube meshes (spanning `0..1`) for single blocks, keyed by block id.
+/// Used to draw falling blocks and dropped items with the voxel material.
+#[derive(Resource, Default)]
+pub struct BlockMeshCache {
+    meshes: HashMap<BlockId, Handle<Mesh>>,
+}
+
+impl BlockMeshCache {
+    /// Return the cached mesh for `block`, meshing it on first use.
+    pub fn get_or_build(
+        &mut self,
+        block: BlockId,
+        registry: &BlockRegistry,
+        layer_map: &AtlasUVMap,
+        meshes: &mut Assets<Mesh>,
+    ) -> Handle<Mesh> {
+        self.meshes
+            .entry(block)
+            .or_insert_with(|| {
+                // The mesher culls faces on the chunk border, so mesh the
+                // block one cell in and shift it back to the origin.
+                let mut chunk = Chunk::new();
+                chunk.set(1, 1, 1, block);
+                let (mut mesh, _) = chunk.build_mesh(registry, layer_map, 0, (0, 0), None);
+                mesh.translate_by(Vec3::NEG_ONE);
+                meshes.add(mesh)
+            })
+            .clone()
+    }
+
+    /// Forget every cached mesh (after block or atlas reloads).
+    pub fn clear(&mut self) {
+        self.meshes.clear();
+    }
+}
+
+/// Whether a falling block may move into a cell holding `id`.
+fn can_fall_into(registry: &BlockRegistry, id: BlockId) -> bool {
+    id == blocks::AIR || registry.get_by_id(id).is_some_and(|b| b.replaceable)
+}
+
+/// Remove the gravity block at `pos` if nothing holds it up.
+///
+/// # Return
+/// * `Option<BlockId>` - the block that started falling, or `None` if the
+///   cell holds no unsupported gravity block
+fn start_fall(world: &mut World, registry: &BlockRegistry, pos: IVec3) -> Option<BlockId> {
+    let id = world.get_block(pos.x, pos.y, pos.z);
+    let falls = registry.get_by_id(id).is_some_and(|b| b.affected_by_gravity)
+        && can_fall_into(registry, world.get_block(pos.x, pos.y - 1, pos.z));
+    if !falls {
+        return None;
+    }
+    world.set_block(pos.x, pos.y, pos.z, blocks::AIR, registry).map(|_| id)
+}
+
+/// Outcome of moving a falling block for one frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FallStep {
+    /// Nothing in the way; the block is now at this height.
+    Falling(f32),
+    /// The block hit something and comes to rest in cell `y`.
+    Landed(i32),
+    /// The block dropped below the bottom of the world.
+    OutOfWorld,
+}
+
+/// Move a falling block in column `(x, z)` from height `y` to `new_y`,
+/// walking every cell entered so fast blocks cannot tunnel.
+#[allow(clippy::cast_possible_truncation)]
+fn fall_step(world: &World, registry: &BlockRegistry, x: i32, z: i32, y: f32, new_y: f32) -> FallStep {
+    let current = y.floor() as i32;
+    let target = new_y.floor() as i32;
+    let rest = (target..current).rev().find(|y| !can_fall_into(registry, world.get_block(x, *y, z)));
+    match rest {
+        Some(y) => FallStep::Landed(y + 1),
+        None if new_y < 0.0 => FallStep::OutOfWorld,
+        None => FallStep::Falling(new_y),
+    }
+}
+
+/// Place a landed `block` at `pos` if it rests on a full block and its cell
+/// is still free.
+///
+/// # Return
+/// * `bool` - whether the block was placed; if not, it should pop into its drop
+fn settle(world: &mut World, registry: &BlockRegistry, block: BlockId, pos: IVec3) -> bool {
+    let below = world.get_block(pos.x, pos.y - 1, pos.z);
+    let supported = registry.get_by_id(below).is_some_and(Block::is_full_cube);
+    let cell_free = can_fall_into(registry, world.get_block(pos.x, pos.y, pos.z));
+    supported && cell_free && world.set_block(pos.x, pos.y, pos.z, block, registry).is_some()
+}
+
+/// System parameters shared by the falling block systems.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct FallingBlockCtx<'w, 's> {
+    pub commands: Commands<'w, 's>,
+    pub world: ResMut<'w, World>,
+    pub block_registry: Res<'w, BlockRegistry>,
+    pub item_registry: Res<'w, ItemRegistry>,
+    pub queue: ResMut<'w, FallingBlockQueue>,
+    pub dirty: ResMut<'w, DirtyChunks>,
+    pub mesh_cache: ResMut<'w, BlockMeshCache>,
+    pub meshes: ResMut<'w, Assets<Mesh>>,
+    pub layer_map: Option<Res<'w, AtlasUVMap>>,
+    pub material_handle: Option<Res<'w, VoxelMaterialHandle>>,
+    pub time: Res<'w, Time>,
+}
+
+/// Turn unsupported gravity blocks next to recent block updates into
+/// `FallingBlock` entities.
+///
+/// # Arguments
+/// * `ctx` - A `FallingBlockCtx` with the world, registries and render handles.
+/// * `changes` - block changes made through `World::set_block`
+pub fn start_falling_blocks(mut ctx: FallingBlockCtx, mut changes: EventReader<BlockChanged>) {
+    ctx.queue.0.extend(changes.read().flat_map(|BlockChanged(p)| [*p, *p + IVec3::Y]));
+    if ctx.queue.0.is_empty() {
+        return;
+    }
+
+    let (Some(layer_map), Some(material)) = (ctx.layer_map.as_ref(), ctx.material_handle.as_ref()) else {
+        return; // Keep the queue until blocks can be drawn
+    };
+    if ctx.block_registry.is_changed() || layer_map.is_changed() {
+        ctx.mesh_cache.clear();
+    }
+
+    let mut started = 0;
+    while started < MAX_FALLING_STARTS_PER_FRAME {
+        let Some(pos) = ctx.queue.0.pop_front() else { break };
+
+        let Some(id) = start_fall(&mut ctx.world, &ctx.block_registry, pos) else { continue };
+        // Removing this block may leave the one above unsupported
+        ctx.queue.0.push_back(pos + IVec3::Y);
+        ctx.dirty.mark_block(pos);
+
+        let mesh = ctx.mesh_cache.get_or_build(id, &ctx.block_registry, layer_map, &mut ctx.meshes);
+        ctx.commands.spawn((
+            MaterialMeshBundle {
+                mesh,
+                material: material.0.clone(),
+                transform: Transform::from_translation(pos.as_vec3()),
+                ..default()
+            },
+            FallingBlock { block: id, velocity: 0.0 },
+        ));
+        started += 1;
+    }
+}
+
+/// Move falling blocks and settle the ones that hit something.
+///
+/// # Arguments
+/// * `ctx` - A `FallingBlockCtx` with the world, registries and render handles.
+/// * `falling` - falling block entities
+#[allow(clippy::cast_possible_truncation)]
+pub fn update_falling_blocks(
+    mut ctx: FallingBlockCtx,
+    mut falling: Query<(Entity, &mut Transform, &mut FallingBlock)>,
+) {
+    let dt = ctx.time.delta_seconds();
+
+    for (entity, mut tf, mut block) in &mut falling {
+        let x = tf.translation.x.floor() as i32;
+        let z = tf.translation.z.floor() as i32;
+        let chunk = (x.div_euclid(CHUNK_SIZE_I32), z.div_euclid(CHUNK_SIZE_I32));
+        if !ctx.world.chunks.contains_key(&chunk) {
+            continue; // Wait for the column to stream back in
+        }
+
+        block.velocity = (block.velocity + GRAVITY * dt).max(TERMINAL_VELOCITY);
+        let new_y = tf.translation.y + block.velocity * dt;
+
+        match fall_step(&ctx.world, &ctx.block_registry, x, z, tf.translation.y, new_y) {
+            FallStep::Falling(y) => tf.translation.y = y,
+            FallStep::OutOfWorld => ctx.commands.entity(entity).despawn(),
+            FallStep::Landed(rest_y) => {
+                ctx.commands.entity(entity).despawn();
+                let pos = IVec3::new(x, rest_y, z);
+                if settle(&mut ctx.world, &ctx.block_registry, block.block, pos) {
+                    ctx.dirty.mark_block(pos);
+                } else {
+                    pop_into_drop(&mut ctx, block.block, pos);
+                }
+            }
+        }
+    }
+}
+
+/// Spawn the drop item of `block` in the cell at `pos`.
+fn pop_into_drop(ctx: &mut FallingBlockCtx, block: BlockId, pos: IVec3) {
+    let (Some(layer_map), Some(material)) = (ctx.layer_map.as_ref(), ctx.material_handle.as_ref()) else {
+        return;
+    };
+    let Some(def) = ctx.block_registry.get_by_id(block) else { return };
+    if def.drop_count == 0 || ctx.item_registry.get(&def.drop_item).is_none() {
+        return;
+    }
+
+    let mesh = ctx.mesh_cache.get_or_build(block, &ctx.block_registry, layer_map, &mut ctx.meshes);
+    spawn_item_drop(
+        &mut ctx.commands,
+        pos.as_vec3() + Vec3::splat(0.5),
+        ItemStack { item: def.drop_item.clone(), count: def.drop_count },
+        mesh,
+        material,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> BlockRegistry {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: 1, name: "stone".to_string(), ..Block::default() });
+        registry.register(Block { id: 2, name: "sand".to_string(), affected_by_gravity: true, ..Block::default() });
+        registry
+    }
+
+    #[test]
+    fn shipped_sand_and_gravel_fall() {
+        let registry = crate::block::loader::load_blocks_from_dir("data/blocks");
+        for name in ["sand", "gravel"] {
+            assert!(registry.get(name).is_some_and(|b| b.affected_by_gravity), "{name}");
+        }
+        assert!(registry.get("dirt").is_some_and(|b| !b.affected_by_gravity));
+    }
+
+    #[test]
+    fn unsupported_sand_falls_and_lands_on_stone() {
+        let registry = registry();
+        let mut world = World::new();
+        world.chunks.insert((0, 0), Chunk::new());
+        world.set_block(4, 10, 4, 1, &registry);
+        world.set_block(4, 20, 4, 2, &registry);
+        world.set_block(4, 21, 4, 2, &registry);
+
+        assert_eq!(start_fall(&mut world, &registry, IVec3::new(4, 10, 4)), None, "stone does not fall");
+        assert_eq!(start_fall(&mut world, &registry, IVec3::new(4, 20, 4)), Some(2));
+        assert_eq!(world.get_block(4, 20, 4), blocks::AIR);
+
+        let (mut y, mut velocity, dt) = (20.0, 0.0, 1.0 / 60.0);
+        let rest_y = loop {
+            velocity = (velocity + GRAVITY * dt).max(TERMINAL_VELOCITY);
+            match fall_step(&world, &registry, 4, 4, y, y + velocity * dt) {
+                FallStep::Falling(new_y) => y = new_y,
+                FallStep::Landed(rest_y) => break rest_y,
+                FallStep::OutOfWorld => panic!("sand fell through the stone"),
+            }
+        };
+        assert_eq!(rest_y, 11);
+        assert!(settle(&mut world, &registry, 2, IVec3::new(4, rest_y, 4)));
+        assert_eq!(world.get_block(4, 11, 4), 2);
+
+        // The block above lost its support and follows
+        assert_eq!(start_fall(&mut world, &registry, IVec3::new(4, 21, 4)), Some(2));
+    }
+}
@@ -0,0 +1,177 @@
+//! Fire.
+//!
+//! `fire` is an ordinary block with `ticks: true`, so it is driven by the
+//! block tick scheduler (`crate::world::tick`) at its `tick_rate`. The fire's
+//! block state counts the ticks it has been burning. Every tick a fire:
+//!
+//! * goes out when nothing holds it up (no full block below and no
+//!   flammable neighbour), or after `BURN_OUT_SECONDS` without fuel;
+//! * consumes each flammable neighbour once it has burnt for that block's
+//!   `burn_time`; the block turns into fire with its `fire_spread_chance`,
+//!   otherwise into air;
+//! * spreads to air within one block that touches a flammable block, with
+//!   that block's `fire_spread_chance`.
+//!
+//! With `gameplay.fire_spread` turned off (copied into `WorldRules`), fire
+//! neither spreads nor consumes blocks; it only goes out when unsupported.
+
+use super::{blocks, BlockRegistry};
+use crate::world::tick::{TickCtx, TickKind};
+use bevy::prelude::*;
+
+/// Seconds a fire without flammable neighbours keeps burning.
+pub const BURN_OUT_SECONDS: f32 = 3.0;
+
+const NEIGHBOURS: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+/// Chance (0..1) that fire appears at an air block at `pos`: the highest
+/// `fire_spread_chance` of its flammable neighbours.
+fn ignite_chance(ctx: &TickCtx, registry: &BlockRegistry, pos: IVec3) -> f32 {
+    NEIGHBOURS
+        .iter()
+        .filter_map(|d| registry.get_by_id(ctx.get_block(pos + *d)))
+        .filter(|b| b.flammable)
+        .map(|b| b.fire_spread_chance)
+        .fold(0.0, f32::max)
+}
+
+/// Scheduled tick handler for the `fire` block.
+#[allow(clippy::cast_precision_loss)]
+pub fn fire_tick(ctx: &mut TickCtx, pos: IVec3, _kind: TickKind) {
+    let registry = ctx.blocks;
+    let fire = ctx.get_block(pos);
+    let Some(fire_def) = registry.get_by_id(fire) else { return };
+
+    let fuel: Vec<IVec3> = NEIGHBOURS
+        .iter()
+        .map(|d| pos + *d)
+        .filter(|p| registry.get_by_id(ctx.get_block(*p)).is_some_and(|b| b.flammable))
+        .collect();
+    let supported = registry.get_by_id(ctx.get_block(pos + IVec3::NEG_Y)).is_some_and(super::Block::is_full_cube);
+    if fuel.is_empty() && !supported {
+        ctx.set_block(pos, blocks::AIR);
+        return;
+    }
+    if !ctx.rules.fire_spread {
+        return;
+    }
+
+    let age = ctx.get_state(pos).saturating_add(1);
+    let burnt_seconds = f32::from(age) / fire_def.tick_rate.max(f32::EPSILON);
+    if fuel.is_empty() && burnt_seconds >= BURN_OUT_SECONDS {
+        ctx.set_block(pos, blocks::AIR);
+        return;
+    }
+    // State-only write: the age is not a block change and must not schedule another tick
+    ctx.set_state(pos, age);
+
+    // Consume fuel that has burnt long enough
+    for p in &fuel {
+        let Some(def) = registry.get_by_id(ctx.get_block(*p)) else { continue };
+        if burnt_seconds < def.burn_time {
+            continue;
+        }
+        let becomes = if ctx.scheduler.random_f32() < def.fire_spread_chance { fire } else { blocks::AIR };
+        ctx.set_block(*p, becomes);
+    }
+
+    // Spread to nearby air touching flammable blocks
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                let target = pos + IVec3::new(dx, dy, dz);
+                if target == pos || ctx.get_block(target) != blocks::AIR || !ctx.world.is_loaded(target.x, target.z) {
+                    continue;
+                }
+                let chance = ignite_chance(ctx, registry, target);
+                if chance > 0.0 && ctx.scheduler.random_f32() < chance {
+                    ctx.set_block(target, fire);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{Block, DirtyChunks};
+    use crate::chunk::Chunk;
+    use crate::world::WorldRules;
+    use crate::world::tick::BlockTickScheduler;
+    use crate::world::World;
+
+    const STONE: u8 = 1;
+    const PLANKS: u8 = 2;
+    const FIRE: u8 = 3;
+
+    fn registry() -> BlockRegistry {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: STONE, name: "stone".to_string(), ..Block::default() });
+        registry.register(Block {
+            id: PLANKS,
+            name: "planks".to_string(),
+            flammable: true,
+            burn_time: 0.5,
+            fire_spread_chance: 1.0,
+            ..Block::default()
+        });
+        registry.register(Block { id: FIRE, name: "fire".to_string(), solid: false, ticks: true, tick_rate: 4.0, ..Block::default() });
+        registry
+    }
+
+    /// Run `ticks` fire ticks at `pos` and return the block left there.
+    fn burn(world: &mut World, registry: &BlockRegistry, rules: WorldRules, pos: IVec3, ticks: u64) -> u8 {
+        let mut scheduler = BlockTickScheduler::default();
+        let mut dirty = DirtyChunks::default();
+        for tick in 0..ticks {
+            let mut ctx = TickCtx { world, blocks: registry, scheduler: &mut scheduler, dirty: &mut dirty, rules: &rules, tick };
+            if ctx.get_block(pos) == FIRE {
+                fire_tick(&mut ctx, pos, TickKind::Scheduled);
+            }
+        }
+        world.get_block(pos.x, pos.y, pos.z)
+    }
+
+    fn world_with(blocks: &[(IVec3, u8)], registry: &BlockRegistry) -> World {
+        let mut world = World::new();
+        world.chunks.insert((0, 0), Chunk::new());
+        for (pos, id) in blocks {
+            world.set_block(pos.x, pos.y, pos.z, *id, registry);
+        }
+        world
+    }
+
+    #[test]
+    fn fire_ignites_next_to_fuel_and_consumes_it() {
+        let registry = registry();
+        let fire = IVec3::new(4, 11, 4);
+        let planks = IVec3::new(4, 10, 4);
+        let mut world = world_with(&[(planks, PLANKS), (fire, FIRE)], &registry);
+
+        burn(&mut world, &registry, WorldRules::default(), fire, 1);
+        assert_eq!(world.get_block(5, 10, 4), FIRE, "air touching the planks caught fire");
+
+        burn(&mut world, &registry, WorldRules::default(), fire, 1);
+        assert_eq!(world.get_block(planks.x, planks.y, planks.z), FIRE, "planks burnt after their burn_time");
+    }
+
+    #[test]
+    fn fire_without_fuel_burns_out_unless_spread_is_off() {
+        let registry = registry();
+        let fire = IVec3::new(4, 11, 4);
+        let blocks = [(IVec3::new(4, 10, 4), STONE), (fire, FIRE)];
+        let burn_out_ticks = 12; // BURN_OUT_SECONDS at 4 ticks per second
+
+        let mut world = world_with(&blocks, &registry);
+        assert_eq!(burn(&mut world, &registry, WorldRules::default(), fire, burn_out_ticks - 1), FIRE);
+        assert_eq!(burn(&mut world, &registry, WorldRules::default(), fire, 1), blocks::AIR);
+
+        let mut world = world_with(&blocks, &registry);
+        let rules = WorldRules { fire_spread: false };
+        assert_eq!(burn(&mut world, &registry, rules, fire, burn_out_ticks * 2), FIRE);
+
+        let mut world = world_with(&[(fire, FIRE)], &registry);
+        assert_eq!(burn(&mut world, &registry, rules, fire, 1), blocks::AIR, "unsupported fire goes out");
+    }
+}
@@ -0,0 +1,418 @@
+//! Flowing fluids (water, lava).
+//!
+//! A block with a `fluid` section is a fluid. Its flow level lives in the
+//! block state (see `Chunk::get_state`):
+//!
+//! * `0` is a source block;
+//! * `1..=flow_distance` is flowing fluid, one level per block away from the
+//!   nearest source;
+//! * `FLUID_FALLING` marks fluid falling down a column. It spreads sideways
+//!   like a source once it lands.
+//!
+//! Fluids are updated by scheduled block ticks (`crate::world::tick`).
+//! `schedule_fluid_ticks` schedules a tick for every fluid block at or next
+//! to a changed block, `tick_rate` game ticks per second apart, and
+//! `fluid_tick` then:
+//!
+//! 1. re-evaluates flowing fluid from its neighbours, draining it when its
+//!    source is gone and turning it into a source when `infinite_source`
+//!    fluid has two source neighbours over solid ground or a source;
+//! 2. flows down if it can, otherwise spreads sideways, preferring the
+//!    directions with the shortest path to a drop within `DROP_SEARCH_DISTANCE`.
+//!
+//! Every write goes through `TickCtx`, so only the chunks touched by the
+//! change (see `affected_chunks`) are remeshed. Fluids never flow into
+//! unloaded chunks.
+
+use super::{blocks, BlockId, BlockRegistry, FluidProperties};
+use crate::world::tick::{tick_delay, BlockTickHandlers, BlockTickScheduler, GameTicks, TickCtx, TickKind};
+use crate::world::{BlockChanged, World};
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+/// Block state flag for fluid falling down a column.
+pub const FLUID_FALLING: u8 = 0x80;
+
+/// Block state bits holding a fluid's flow level.
+pub const FLUID_LEVEL_MASK: u8 = 0x7F;
+
+/// How far (in blocks) spreading fluid looks for a drop to flow towards.
+pub const DROP_SEARCH_DISTANCE: u32 = 4;
+
+const HORIZONTAL: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+/// Surface height (0..1) of a fluid block with the given state.
+#[must_use]
+pub fn fluid_height(state: u8, flow_distance: u8) -> f32 {
+    if state & FLUID_FALLING != 0 {
+        return 1.0;
+    }
+    let level = f32::from((state & FLUID_LEVEL_MASK).min(flow_distance));
+    let max = f32::from(flow_distance);
+    (max + 1.0 - level) / (max + 2.0)
+}
+
+/// Register `fluid_tick` for every fluid block. Runs whenever the block
+/// registry changes so fluids added by a hot reload flow too (`fluid_tick`
+/// ignores blocks that stopped being fluids).
+///
+/// # Arguments
+/// * `blocks` - block registry
+/// * `handlers` - tick handlers to update
+#[allow(clippy::needless_pass_by_value)]
+pub fn sync_fluid_handlers(blocks: Res<BlockRegistry>, mut handlers: ResMut<BlockTickHandlers>) {
+    if !blocks.is_changed() {
+        return;
+    }
+    for block in blocks.blocks.values().filter(|b| b.is_fluid()) {
+        handlers.register(&block.name, fluid_tick);
+    }
+}
+
+/// Schedule a tick for every fluid block at or next to a changed block.
+///
+/// # Arguments
+/// * `changes` - block changes made through `World::set_block`
+/// * `world` - world used to look up the neighbouring blocks
+/// * `blocks` - block registry
+/// * `scheduler` - scheduled tick queue
+/// * `ticks` - current game tick
+#[allow(clippy::needless_pass_by_value)]
+pub fn schedule_fluid_ticks(
+    mut changes: EventReader<BlockChanged>,
+    world: Res<World>,
+    blocks: Res<BlockRegistry>,
+    mut scheduler: ResMut<BlockTickScheduler>,
+    ticks: Res<GameTicks>,
+) {
+    let mut seen = HashSet::new();
+    for BlockChanged(pos) in changes.read() {
+        for offset in [IVec3::ZERO, IVec3::Y, IVec3::NEG_Y, IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z] {
+            let p = *pos + offset;
+            if !seen.insert(p) {
+                continue;
+            }
+            let Some(block) = blocks.get_by_id(world.get_block(p.x, p.y, p.z)) else { continue };
+            if block.is_fluid() {
+                scheduler.schedule(p, ticks.count + tick_delay(block.tick_rate));
+            }
+        }
+    }
+}
+
+/// Scheduled tick handler shared by all fluid blocks.
+pub fn fluid_tick(ctx: &mut TickCtx, pos: IVec3, _kind: TickKind) {
+    let fluid = ctx.get_block(pos);
+    let Some(props) = ctx.blocks.get_by_id(fluid).and_then(|b| b.fluid.clone()) else { return };
+    let state = ctx.get_state(pos);
+
+    if state != 0 {
+        let expected = expected_state(ctx, pos, fluid, &props);
+        if expected != Some(state) {
+            match expected {
+                Some(s) => ctx.set_block_state(pos, fluid, s),
+                None => ctx.set_block(pos, blocks::AIR),
+            };
+            return; // The change reschedules this block and its neighbours
+        }
+    }
+
+    spread(ctx, pos, fluid, state, &props);
+}
+
+/// The state flowing fluid at `pos` should have given its neighbours, or
+/// `None` if it should drain away.
+fn expected_state(ctx: &TickCtx, pos: IVec3, fluid: BlockId, props: &FluidProperties) -> Option<u8> {
+    let mut sources = 0;
+    let mut min_level = None;
+    for dir in HORIZONTAL {
+        let n = pos + dir;
+        if ctx.get_block(n) != fluid {
+            continue;
+        }
+        let state = ctx.get_state(n);
+        if state == 0 {
+            sources += 1;
+        }
+        // Falling fluid feeds its landing spot like a source
+        let level = if state & FLUID_FALLING == 0 { state & FLUID_LEVEL_MASK } else { 0 };
+        min_level = Some(min_level.map_or(level, |m: u8| m.min(level)));
+    }
+
+    if props.infinite_source && sources >= 2 {
+        let below = pos + IVec3::NEG_Y;
+        let below_id = ctx.get_block(below);
+        let supported = if below_id == fluid {
+            ctx.get_state(below) == 0
+        } else {
+            ctx.blocks.get_by_id(below_id).is_some_and(super::Block::is_full_cube)
+        };
+        if supported {
+            return Some(0);
+        }
+    }
+
+    if ctx.get_block(pos + IVec3::Y) == fluid {
+        return Some(FLUID_FALLING);
+    }
+    min_level.map(|l| l + 1).filter(|l| *l <= props.flow_distance)
+}
+
+/// Whether fluid may move into `pos` with the given state: air, replaceable
+/// non-fluid blocks, or weaker fluid of the same kind. Positions in unloaded
+/// chunks are never flowed into.
+fn can_flow_into(ctx: &TickCtx, pos: IVec3, fluid: BlockId, state: u8) -> bool {
+    if !ctx.world.is_loaded(pos.x, pos.z) || pos.y < 0 {
+        return false;
+    }
+    let id = ctx.get_block(pos);
+    if id == fluid {
+        let current = ctx.get_state(pos);
+        if current == 0 || current & FLUID_FALLING != 0 {
+            return false; // Sources and falling columns are never overwritten
+        }
+        return state & FLUID_FALLING != 0 || state & FLUID_LEVEL_MASK < current;
+    }
+    id == blocks::AIR || ctx.blocks.get_by_id(id).is_some_and(|b| b.replaceable && !b.is_fluid())
+}
+
+/// Flow down if possible, otherwise spread sideways.
+fn spread(ctx: &mut TickCtx, pos: IVec3, fluid: BlockId, state: u8, props: &FluidProperties) {
+    let below = pos + IVec3::NEG_Y;
+    if can_flow_into(ctx, below, fluid, FLUID_FALLING) {
+        ctx.set_block_state(below, fluid, FLUID_FALLING);
+        return;
+    }
+    if ctx.get_block(below) == fluid && ctx.get_state(below) & FLUID_FALLING != 0 {
+        return; // Already pouring down
+    }
+
+    let next = if state & FLUID_FALLING == 0 { (state & FLUID_LEVEL_MASK) + 1 } else { 1 };
+    if next > props.flow_distance {
+        return;
+    }
+
+    let open: Vec<IVec3> = HORIZONTAL.into_iter().filter(|d| can_flow_into(ctx, pos + *d, fluid, next)).collect();
+    let distances: Vec<Option<u32>> = open.iter().map(|d| drop_distance(ctx, pos + *d, -*d, fluid, 0)).collect();
+    let nearest = distances.iter().flatten().min().copied();
+
+    for (dir, distance) in open.into_iter().zip(distances) {
+        if nearest.is_none() || distance == nearest {
+            ctx.set_block_state(pos + dir, fluid, next);
+        }
+    }
+}
+
+/// Horizontal distance from `pos` to the nearest cell fluid could fall from,
+/// searching at most `DROP_SEARCH_DISTANCE` blocks without turning back.
+fn drop_distance(ctx: &TickCtx, pos: IVec3, came_from: IVec3, fluid: BlockId, depth: u32) -> Option<u32> {
+    if can_flow_into(ctx, pos + IVec3::NEG_Y, fluid, FLUID_FALLING) {
+        return Some(depth);
+    }
+    if depth >= DROP_SEARCH_DISTANCE {
+        return None;
+    }
+    HORIZONTAL
+        .into_iter()
+        .filter(|d| *d != came_from && passable(ctx, pos + *d, fluid))
+        .filter_map(|d| drop_distance(ctx, pos + d, -d, fluid, depth + 1))
+        .min()
+}
+
+/// Whether spreading fluid could pass through `pos` on its way to a drop.
+fn passable(ctx: &TickCtx, pos: IVec3, fluid: BlockId) -> bool {
+    let id = ctx.get_block(pos);
+    if id == fluid {
+        return ctx.get_state(pos) != 0;
+    }
+    id == blocks::AIR || ctx.blocks.get_by_id(id).is_some_and(|b| b.replaceable && !b.is_fluid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{Block, DirtyChunks};
+    use crate::chunk::Chunk;
+    use crate::world::WorldRules;
+
+    const STONE: u8 = 1;
+    const WATER: u8 = 2;
+    const LAVA: u8 = 3;
+    const FLOOR_Y: i32 = 10;
+
+    fn fluid(id: u8, name: &str, infinite_source: bool) -> Block {
+        Block {
+            id,
+            name: name.to_string(),
+            solid: false,
+            ticks: true,
+            fluid: Some(FluidProperties { flow_distance: 3, infinite_source }),
+            ..Block::default()
+        }
+    }
+
+    fn registry() -> BlockRegistry {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: STONE, name: "stone".to_string(), ..Block::default() });
+        registry.register(fluid(WATER, "water", true));
+        registry.register(fluid(LAVA, "lava", false));
+        registry
+    }
+
+    /// A loaded chunk (0, 0) with a stone floor at `FLOOR_Y`, minus `holes`.
+    fn floor_world(holes: &[IVec3], registry: &BlockRegistry) -> World {
+        let mut world = World::new();
+        world.chunks.insert((0, 0), Chunk::new());
+        for x in 0..32 {
+            for z in 0..32 {
+                if !holes.contains(&IVec3::new(x, FLOOR_Y, z)) {
+                    world.set_block(x, FLOOR_Y, z, STONE, registry);
+                }
+            }
+        }
+        world
+    }
+
+    /// Tick every fluid at or next to a changed block, like
+    /// `schedule_fluid_ticks` without the tick delay, until nothing changes.
+    fn settle(world: &mut World, registry: &BlockRegistry) {
+        let rules = WorldRules::default();
+        let mut scheduler = BlockTickScheduler::default();
+        let mut dirty = DirtyChunks::default();
+        for tick in 0..500 {
+            let mut due: Vec<IVec3> = world
+                .take_block_updates()
+                .into_iter()
+                .flat_map(|pos| {
+                    [IVec3::ZERO, IVec3::Y, IVec3::NEG_Y, IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z].map(|d| pos + d)
+                })
+                .filter(|p| registry.get_by_id(world.get_block(p.x, p.y, p.z)).is_some_and(Block::is_fluid))
+                .collect();
+            if due.is_empty() {
+                return;
+            }
+            due.sort_by_key(|p| (p.y, p.x, p.z));
+            due.dedup();
+
+            let mut ctx = TickCtx { world: &mut *world, blocks: registry, scheduler: &mut scheduler, dirty: &mut dirty, rules: &rules, tick };
+            for pos in due {
+                fluid_tick(&mut ctx, pos, TickKind::Scheduled);
+            }
+        }
+        panic!("fluid did not settle");
+    }
+
+    fn at(world: &World, x: i32, y: i32, z: i32) -> (u8, u8) {
+        (world.get_block(x, y, z), world.get_block_state(x, y, z))
+    }
+
+    #[test]
+    fn source_spreads_sideways_up_to_flow_distance() {
+        let registry = registry();
+        let mut world = floor_world(&[], &registry);
+        world.set_block(8, FLOOR_Y + 1, 8, WATER, &registry);
+        settle(&mut world, &registry);
+
+        assert_eq!(at(&world, 8, FLOOR_Y + 1, 8), (WATER, 0));
+        assert_eq!(at(&world, 9, FLOOR_Y + 1, 8), (WATER, 1));
+        assert_eq!(at(&world, 8, FLOOR_Y + 1, 5), (WATER, 3));
+        assert_eq!(at(&world, 9, FLOOR_Y + 1, 10), (WATER, 3), "levels follow the walking distance");
+        assert_eq!(at(&world, 12, FLOOR_Y + 1, 8).0, blocks::AIR);
+        assert_eq!(at(&world, 8, FLOOR_Y + 2, 8).0, blocks::AIR);
+    }
+
+    #[test]
+    fn spreading_fluid_flows_towards_the_nearest_drop() {
+        let registry = registry();
+        let mut world = floor_world(&[IVec3::new(10, FLOOR_Y, 8)], &registry);
+        world.set_block(10, FLOOR_Y - 3, 8, STONE, &registry);
+        let source = IVec3::new(8, FLOOR_Y + 1, 8);
+        world.set_block(source.x, source.y, source.z, WATER, &registry);
+
+        // The source's first spread only goes towards the hole two blocks away
+        let (rules, mut scheduler, mut dirty) = (WorldRules::default(), BlockTickScheduler::default(), DirtyChunks::default());
+        let mut ctx = TickCtx { world: &mut world, blocks: &registry, scheduler: &mut scheduler, dirty: &mut dirty, rules: &rules, tick: 0 };
+        fluid_tick(&mut ctx, source, TickKind::Scheduled);
+        assert_eq!(at(&world, 9, FLOOR_Y + 1, 8), (WATER, 1));
+        for away in [IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z].map(|d| source + d) {
+            assert_eq!(world.get_block(away.x, away.y, away.z), blocks::AIR, "{away} is away from the drop");
+        }
+
+        settle(&mut world, &registry);
+        assert_eq!(at(&world, 10, FLOOR_Y + 1, 8), (WATER, 2));
+        assert_eq!(at(&world, 10, FLOOR_Y, 8), (WATER, FLUID_FALLING));
+        assert_eq!(at(&world, 10, FLOOR_Y - 2, 8), (WATER, FLUID_FALLING));
+        assert_eq!(at(&world, 11, FLOOR_Y - 2, 8), (WATER, 1), "the column spreads where it lands");
+    }
+
+    #[test]
+    fn flowing_fluid_drains_when_its_source_is_removed() {
+        let registry = registry();
+        let mut world = floor_world(&[], &registry);
+        world.set_block(8, FLOOR_Y + 1, 8, LAVA, &registry);
+        settle(&mut world, &registry);
+        assert_eq!(at(&world, 10, FLOOR_Y + 1, 8), (LAVA, 2));
+
+        world.set_block(8, FLOOR_Y + 1, 8, blocks::AIR, &registry);
+        settle(&mut world, &registry);
+        for x in 4..=12 {
+            assert_eq!(world.get_block(x, FLOOR_Y + 1, 8), blocks::AIR, "x = {x} drained");
+        }
+    }
+
+    #[test]
+    fn infinite_sources_form_between_two_sources_over_solid_ground() {
+        let registry = registry();
+        let between = IVec3::new(9, FLOOR_Y + 1, 8);
+        for (id, expected) in [(WATER, 0), (LAVA, 1)] {
+            let mut world = floor_world(&[], &registry);
+            world.set_block(8, FLOOR_Y + 1, 8, id, &registry);
+            world.set_block(10, FLOOR_Y + 1, 8, id, &registry);
+            settle(&mut world, &registry);
+            assert_eq!(at(&world, between.x, between.y, between.z), (id, expected));
+        }
+
+        // Without ground below, the water in between pours down instead
+        let mut world = floor_world(&[IVec3::new(9, FLOOR_Y, 8)], &registry);
+        world.set_block(9, FLOOR_Y - 1, 8, STONE, &registry);
+        world.set_block(8, FLOOR_Y + 1, 8, WATER, &registry);
+        world.set_block(10, FLOOR_Y + 1, 8, WATER, &registry);
+        settle(&mut world, &registry);
+        assert_eq!(at(&world, 9, FLOOR_Y, 8), (WATER, FLUID_FALLING));
+        assert_ne!(at(&world, between.x, between.y, between.z), (WATER, 0));
+    }
+
+    #[test]
+    fn falling_fluid_forms_a_column_and_spreads_where_it_lands() {
+        let registry = registry();
+        let mut world = floor_world(&[], &registry);
+        world.set_block(8, FLOOR_Y + 6, 8, WATER, &registry);
+        settle(&mut world, &registry);
+
+        for y in FLOOR_Y + 1..FLOOR_Y + 6 {
+            assert_eq!(at(&world, 8, y, 8), (WATER, FLUID_FALLING), "y = {y}");
+        }
+        assert_eq!(at(&world, 9, FLOOR_Y + 1, 8), (WATER, 1));
+        assert_eq!(at(&world, 9, FLOOR_Y + 6, 8).0, blocks::AIR, "the source itself has nothing to spread on");
+    }
+
+    #[test]
+    fn fluids_never_flow_into_unloaded_chunks() {
+        let registry = registry();
+        let mut world = floor_world(&[], &registry);
+        world.set_block(31, FLOOR_Y + 1, 8, WATER, &registry);
+        settle(&mut world, &registry);
+
+        assert_eq!(at(&world, 30, FLOOR_Y + 1, 8), (WATER, 1));
+        assert!(!world.is_loaded(32, 8), "no chunk was generated for the flow");
+    }
+
+    #[test]
+    fn fluid_height_drops_with_flow_level() {
+        assert!((fluid_height(FLUID_FALLING, 7) - 1.0).abs() < f32::EPSILON);
+        let source = fluid_height(0, 7);
+        let flowing = fluid_height(3, 7);
+        let edge = fluid_height(7, 7);
+        assert!(source > flowing && flowing > edge && edge > 0.0);
+    }
+}
@@ -33,11 +33,16 @@
 //! assert_eq!(hit_pos, IVec3::new(1, 1, 0));
 //! ```
 use crate::atlas_builder::AtlasUVMap;
-use crate::block::{blocks, BlockRegistry};
+use crate::block::{blocks, Block, BlockId, BlockRegistry};
 use crate::chunk::ChunkEntity;
 use crate::chunk::VoxelMaterialHandle;
 use crate::chunk::CHUNK_SIZE;
+use crate::input::ActionState;
+use crate::block::falling::BlockMeshCache;
+use crate::item::{spawn_item_drop, Inventory, ItemRegistry, ItemStack, ToolClass, ToolProperties};
+use crate::player::GameMode;
 use crate::world::World;
+use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
 use bevy::window::{CursorGrabMode, PrimaryWindow};
 
@@ -99,6 +104,31 @@ pub fn raycast_block(
     origin: Vec3,
     direction: Vec3,
     max_distance: f32,
+) -> Option<(IVec3, IVec3)> {
+    raycast_until(world, origin, direction, max_distance, |id| id != blocks::AIR)
+}
+
+/// Like `raycast_block`, but passes through blocks that are not solid
+/// (fluids, fire, ...), so they can neither be broken nor placed against.
+/// The returned place position may be such a block; placing replaces it.
+#[must_use]
+pub fn raycast_solid_block(
+    world: &World,
+    block_registry: &BlockRegistry,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+) -> Option<(IVec3, IVec3)> {
+    raycast_until(world, origin, direction, max_distance, |id| block_registry.is_solid(id))
+}
+
+/// March along the ray until `hits` accepts the block id under it.
+fn raycast_until(
+    world: &World,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+    hits: impl Fn(BlockId) -> bool,
 ) -> Option<(IVec3, IVec3)> {
     let mut pos = origin;
     let step = direction.normalize() * 0.1;
@@ -115,7 +145,7 @@ pub fn raycast_block(
             f32_floor_to_i32(pos.y),
             f32_floor_to_i32(pos.z),
         );
-        if world.get_block(block_pos.x, block_pos.y, block_pos.z) != blocks::AIR {
+        if hits(world.get_block(block_pos.x, block_pos.y, block_pos.z)) {
             return Some((block_pos, last_air_pos));
         }
         last_air_pos = block_pos;
@@ -166,13 +196,16 @@ pub fn next_block_pos(origin: Vec3, direction: Vec3) -> IVec3 {
 /// raycasting, world updates, chunk mesh rebuild and interaction logic.
 ///
 /// # Arguments
-/// * `mouse_button` - Resource tracking mouse button input state.
+/// * `actions` - Action state (`break`, `place` and `pick_block`).
 /// * `world` - Mutable reference to the game world for updating block data.
 /// * `meshes` - Mutable reference to the asset collection for chunk meshes, used for updating meshes when blocks change.
 /// * `camera_query` - Query to get the player's camera transform for raycasting.
 /// * `chunk_query` - Query to find chunk entities for rebuilding meshes.
 /// * `window_query` - Query to access the primary window for checking cursor state.
 /// * `block_registry` - Resource containing block definitions, used for looking up block ids and
+/// * `item_registry` - Resource containing item definitions, used to resolve drops and the held block item.
+/// * `inventory` - The player inventory; broken blocks drop into it and placing consumes the selected hotbar item.
+/// * `mesh_cache` - Block meshes for drops that do not fit into the inventory.
 /// * `commands` - Commands for spawning/updating entities when rebuilding chunk meshes.
 /// * `chunk_entities` - Resource tracking which chunk entities exist and their mesh handles, used for updating meshes when blocks change.
 /// * `stats` - Resource for tracking mesh generation stats, updated when chunks are rebuilt.
@@ -203,10 +236,18 @@ pub fn next_block_pos(origin: Vec3, direction: Vec3) -> IVec3 {
 /// ```
 #[derive(bevy::ecs::system::SystemParam)]
 pub struct BlockInteractionCtx<'w, 's> {
-    pub mouse_button: Res<'w, ButtonInput<MouseButton>>,
+    pub actions: Res<'w, ActionState>,
+    pub mouse_wheel: EventReader<'w, 's, MouseWheel>,
+    pub time: Res<'w, Time>,
+    pub mode: Res<'w, GameMode>,
+    pub mining: ResMut<'w, MiningProgress>,
+    pub creative: ResMut<'w, CreativeBlock>,
     pub world: ResMut<'w, World>,
     pub meshes: ResMut<'w, Assets<Mesh>>,
     pub block_registry: Res<'w, BlockRegistry>,
+    pub item_registry: Res<'w, ItemRegistry>,
+    pub inventory: ResMut<'w, Inventory>,
+    pub mesh_cache: ResMut<'w, BlockMeshCache>,
     pub chunk_entities: ResMut<'w, crate::chunk::streaming::ChunkEntities>,
     pub stats: ResMut<'w, crate::chunk::MeshGenerationStats>,
     pub layer_map: Option<Res<'w, AtlasUVMap>>,
@@ -217,8 +258,112 @@ pub struct BlockInteractionCtx<'w, 's> {
     pub commands: Commands<'w, 's>,
 }
 
+/// Progress of the block being mined in survival mode.
+///
+/// # Fields
+/// * `target` - the block being mined, if the mouse button is held on one
+/// * `progress` - fraction (0..1) of its mining time already spent
+#[derive(Resource, Debug, Default)]
+pub struct MiningProgress {
+    pub target: Option<IVec3>,
+    pub progress: f32,
+}
+
+impl MiningProgress {
+    /// Advance mining of `target` by `dt` seconds while `held`. Switching
+    /// targets or releasing the button starts over.
+    ///
+    /// # Return
+    /// * `bool` - `true` once the block has been mined for `mining_time` seconds
+    pub fn advance(&mut self, target: IVec3, held: bool, dt: f32, mining_time: f32) -> bool {
+        if !held {
+            *self = Self::default();
+            return false;
+        }
+        if self.target != Some(target) {
+            self.target = Some(target);
+            self.progress = 0.0;
+        }
+        self.progress += if mining_time > 0.0 { dt / mining_time } else { 1.0 };
+        if self.progress >= 1.0 {
+            *self = Self::default();
+            return true;
+        }
+        false
+    }
+}
+
+/// The block placed in creative mode, picked from the block registry
+/// instead of the inventory.
+///
+/// # Fields
+/// * `block` - the picked block; `None` (or a block that is no longer
+///   registered) places the lowest registered block id
+#[derive(Resource, Debug, Default)]
+pub struct CreativeBlock {
+    pub block: Option<BlockId>,
+}
+
+impl CreativeBlock {
+    /// Every registered block except air, by id.
+    fn choices(registry: &BlockRegistry) -> Vec<BlockId> {
+        let mut ids: Vec<BlockId> = registry.blocks_by_id.keys().copied().filter(|id| *id != blocks::AIR).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The block creative placement uses.
+    #[must_use]
+    pub fn current(&self, registry: &BlockRegistry) -> Option<BlockId> {
+        self.block
+            .filter(|id| *id != blocks::AIR && registry.get_by_id(*id).is_some())
+            .or_else(|| Self::choices(registry).first().copied())
+    }
+
+    /// Step `steps` blocks forward (or back) through the registry by id,
+    /// wrapping around.
+    pub fn cycle(&mut self, registry: &BlockRegistry, steps: i32) {
+        let choices = Self::choices(registry);
+        let Some(current) = self.current(registry) else { return };
+        let index = choices.iter().position(|id| *id == current).unwrap_or(0);
+        #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let next = (index as i32 + steps).rem_euclid(choices.len() as i32) as usize;
+        self.block = Some(choices[next]);
+    }
+}
+
+/// Seconds a survival player needs to mine `block` while holding `tool`:
+/// `hardness * 1.5`, divided by the tool's `speed` if it is of the block's
+/// `tool_required` class and at least its `tool_tier`.
+#[must_use]
+pub fn mining_time(block: &Block, tool: Option<&ToolProperties>) -> f32 {
+    let base = block.hardness.max(0.0) * 1.5;
+    let required = block.tool_required.as_deref().and_then(ToolClass::from_name);
+    match tool {
+        Some(t) if Some(t.class) == required && t.tier >= block.tool_tier => base / t.speed.max(f32::EPSILON),
+        _ => base,
+    }
+}
+
+/// Add the drop of a mined `block` to `inventory`.
+///
+/// # Return
+/// The part of the drop that did not fit, to be dropped in the world.
+#[must_use]
+pub fn collect_block_drop(inventory: &mut Inventory, items: &ItemRegistry, block: &Block) -> Option<ItemStack> {
+    let drop = items.get(&block.drop_item)?;
+    let left = inventory.add(drop, block.drop_count);
+    (left > 0).then(|| ItemStack { item: drop.name.clone(), count: left })
+}
+
 /// Function to handle player interactions with blocks (breaking/placing)
-/// and updating the world state and chunk meshes accordingly.
+/// and updating the world state and chunk meshes accordingly. What the
+/// player may do depends on the `GameMode`: survival mines blocks over their
+/// `mining_time` and places the selected hotbar item, using it up; creative
+/// breaks instantly and places the `CreativeBlock` without touching the
+/// inventory (middle click picks the targeted block, the mouse wheel steps
+/// through the registry); spectators cannot interact.
+/// Only solid blocks are targeted (see `raycast_solid_block`).
 ///
 /// # Arguments
 /// * `ctx` - A `BlockInteractionCtx` containing all necessary resources and queries for handling block interactions and updating chunk meshes.
@@ -236,24 +381,130 @@ pub fn block_interaction(mut ctx: BlockInteractionCtx) {
         return;
     };
 
+    if !ctx.mode.can_interact() {
+        *ctx.mining = MiningProgress::default();
+        return;
+    }
+
     let camera = ctx.camera_query.single();
     let direction = camera.forward();
     let origin = camera.translation;
 
-    if let Some((hit_pos, place_pos)) = raycast_block(&ctx.world, origin, *direction, 5.0) {
-        // Break block
-        if ctx.mouse_button.just_pressed(MouseButton::Left) {
-            let cx = hit_pos.x.div_euclid(CHUNK_SIZE_I32);
-            let cz = hit_pos.z.div_euclid(CHUNK_SIZE_I32);
-            if ctx.world.set_block(hit_pos.x, hit_pos.y, hit_pos.z, blocks::AIR, &ctx.block_registry)
+    let target = raycast_solid_block(&ctx.world, &ctx.block_registry, origin, *direction, 5.0);
+
+    if ctx.mode.infinite_blocks() {
+        let scroll: f32 = ctx.mouse_wheel.read().map(|wheel| wheel.y).sum();
+        if ctx.actions.just_pressed("pick_block")
+            && let Some((hit_pos, _)) = target
+        {
+            ctx.creative.block = Some(ctx.world.get_block(hit_pos.x, hit_pos.y, hit_pos.z));
+        } else if scroll != 0.0 {
+            ctx.creative.cycle(&ctx.block_registry, if scroll < 0.0 { 1 } else { -1 });
+        }
+    }
+
+    let Some((hit_pos, place_pos)) = target else {
+        *ctx.mining = MiningProgress::default();
+        return;
+    };
+
+    // Break block
+    let broken = ctx.world.get_block(hit_pos.x, hit_pos.y, hit_pos.z);
+    let breaking = match ctx.block_registry.get_by_id(broken) {
+        Some(block) if !block.breakable => false,
+        _ if ctx.mode.instant_break() => ctx.actions.just_pressed("break"),
+        block => {
+            let tool = ctx
+                .inventory
+                .selected_stack()
+                .and_then(|stack| ctx.item_registry.get(&stack.item))
+                .and_then(|item| item.tool());
+            let time = block.map_or(0.0, |b| mining_time(b, tool));
+            let held = ctx.actions.pressed("break");
+            ctx.mining.advance(hit_pos, held, ctx.time.delta_seconds(), time)
+        }
+    };
+    if breaking {
+        let cx = hit_pos.x.div_euclid(CHUNK_SIZE_I32);
+        let cz = hit_pos.z.div_euclid(CHUNK_SIZE_I32);
+        if ctx.world.set_block(hit_pos.x, hit_pos.y, hit_pos.z, blocks::AIR, &ctx.block_registry)
+            .is_some()
+        {
+            if !ctx.mode.infinite_blocks()
+                && let Some(block) = ctx.block_registry.get_by_id(broken)
+                && let Some(left) = collect_block_drop(&mut ctx.inventory, &ctx.item_registry, block)
+            {
+                // The inventory is full: leave the rest lying where the block was
+                let mesh = ctx.mesh_cache.get_or_build(broken, &ctx.block_registry, layer_map, &mut ctx.meshes);
+                spawn_item_drop(&mut ctx.commands, hit_pos.as_vec3() + Vec3::splat(0.5), left, mesh, mat_handle);
+            }
+
+            // Rebuild affected chunks
+            rebuild_all_affected_chunks(
+                &ctx.world,
+                cx,
+                cz,
+                hit_pos,
+                &mut ctx.commands,
+                &mut ctx.meshes,
+                &mut ctx.chunk_query,
+                &ctx.block_registry,
+                layer_map,
+                mat_handle,
+                &mut ctx.chunk_entities,
+                &mut ctx.stats,
+            );
+        }
+    }
+
+    // Place block
+    if ctx.actions.just_pressed("place") {
+        let py = origin.y;
+        let feet = f32_floor_to_i32(py - 1.7);
+        let head = f32_floor_to_i32(py);
+        let px = f32_floor_to_i32(origin.x);
+        let pz = f32_floor_to_i32(origin.z);
+        let intersect = place_pos.x == px
+            && place_pos.z == pz
+            && place_pos.y >= feet
+            && place_pos.y <= head;
+
+        if !intersect {
+            let cx = place_pos.x.div_euclid(CHUNK_SIZE_I32);
+            let cz = place_pos.z.div_euclid(CHUNK_SIZE_I32);
+
+            // Creative places from the registry; survival only places block
+            // items, anything else is a no-op.
+            let place_id = if ctx.mode.infinite_blocks() {
+                ctx.creative.current(&ctx.block_registry)
+            } else {
+                ctx.inventory
+                    .selected_stack()
+                    .and_then(|stack| ctx.item_registry.block_for_item(&stack.item, &ctx.block_registry))
+            };
+            let Some(place_id) = place_id else {
+                return;
+            };
+
+            if ctx
+                .world
+                .set_block(
+                    place_pos.x,
+                    place_pos.y,
+                    place_pos.z,
+                    place_id,
+                    &ctx.block_registry,
+                )
                 .is_some()
             {
-                // Rebuild affected chunks
+                if !ctx.mode.infinite_blocks() {
+                    ctx.inventory.take_selected();
+                }
                 rebuild_all_affected_chunks(
                     &ctx.world,
                     cx,
                     cz,
-                    hit_pos,
+                    place_pos,
                     &mut ctx.commands,
                     &mut ctx.meshes,
                     &mut ctx.chunk_query,
@@ -265,59 +516,6 @@ pub fn block_interaction(mut ctx: BlockInteractionCtx) {
                 );
             }
         }
-
-        // Place block
-        if ctx.mouse_button.just_pressed(MouseButton::Right) {
-            let py = origin.y;
-            let feet = f32_floor_to_i32(py - 1.7);
-            let head = f32_floor_to_i32(py);
-            let px = f32_floor_to_i32(origin.x);
-            let pz = f32_floor_to_i32(origin.z);
-            let intersect = place_pos.x == px
-                && place_pos.z == pz
-                && place_pos.y >= feet
-                && place_pos.y <= head;
-
-            if !intersect {
-                let cx = place_pos.x.div_euclid(CHUNK_SIZE_I32);
-                let cz = place_pos.z.div_euclid(CHUNK_SIZE_I32);
-
-                // used as a temp feature for being able to place blocks
-                // This will need to change at some point to allow placing a
-                // variety of blocks rather than just dirt specifically
-                let dirt_id = ctx
-                    .block_registry
-                    .id_for_name("dirt")
-                    .unwrap_or(ctx.block_registry.missing_id());
-
-                if ctx
-                    .world
-                    .set_block(
-                        place_pos.x,
-                        place_pos.y,
-                        place_pos.z,
-                        dirt_id,
-                        &ctx.block_registry,
-                    )
-                    .is_some()
-                {
-                    rebuild_all_affected_chunks(
-                        &ctx.world,
-                        cx,
-                        cz,
-                        place_pos,
-                        &mut ctx.commands,
-                        &mut ctx.meshes,
-                        &mut ctx.chunk_query,
-                        &ctx.block_registry,
-                        layer_map,
-                        mat_handle,
-                        &mut ctx.chunk_entities,
-                        &mut ctx.stats,
-                    );
-                }
-            }
-        }
     }
 }
 
@@ -354,29 +552,13 @@ fn rebuild_all_affected_chunks(
     chunk_entities: &mut crate::chunk::streaming::ChunkEntities,
     stats: &mut crate::chunk::MeshGenerationStats,
 ) {
-    // Rebuild the visual mesh for the chunk containing the changed block
-    rebuild_chunk_visual(
-        world,
-        chunk_x,
-        chunk_z,
-        commands,
-        meshes,
-        chunk_query,
-        block_registry,
-        layer_map,
-        mat_handle,
-        chunk_entities,
-        stats,
-    );
-
-    let local_x = block_pos.x.rem_euclid(CHUNK_SIZE_I32);
-    let local_z = block_pos.z.rem_euclid(CHUNK_SIZE_I32);
-
-    if local_x == 0 {
+    // Rebuild the visual mesh for the chunk containing the changed block,
+    // plus any neighbour whose border faces the changed block
+    for (cx, cz) in affected_chunks(chunk_x, chunk_z, block_pos) {
         rebuild_chunk_visual(
             world,
-            chunk_x - 1,
-            chunk_z,
+            cx,
+            cz,
             commands,
             meshes,
             chunk_query,
@@ -387,49 +569,97 @@ fn rebuild_all_affected_chunks(
             stats,
         );
     }
+}
+
+/// Chunks whose mesh depends on the block at `block_pos`: the chunk that
+/// contains it plus any neighbour it borders (faces on the chunk boundary
+/// are culled against the neighbour).
+///
+/// # Arguments
+/// * `chunk_x` - The x coordinate of the chunk containing the changed block.
+/// * `chunk_z` - The z coordinate of the chunk containing the changed block.
+/// * `block_pos` - The world position of the changed block.
+///
+/// # Returns
+/// The affected chunk coordinates, starting with `(chunk_x, chunk_z)`.
+#[must_use]
+pub fn affected_chunks(chunk_x: i32, chunk_z: i32, block_pos: IVec3) -> Vec<(i32, i32)> {
+    let mut chunks = vec![(chunk_x, chunk_z)];
+
+    let local_x = block_pos.x.rem_euclid(CHUNK_SIZE_I32);
+    let local_z = block_pos.z.rem_euclid(CHUNK_SIZE_I32);
+
+    if local_x == 0 {
+        chunks.push((chunk_x - 1, chunk_z));
+    }
     if local_x == (CHUNK_SIZE_I32 - 1) {
-        rebuild_chunk_visual(
-            world,
-            chunk_x + 1,
-            chunk_z,
-            commands,
-            meshes,
-            chunk_query,
-            block_registry,
-            layer_map,
-            mat_handle,
-            chunk_entities,
-            stats,
-        );
+        chunks.push((chunk_x + 1, chunk_z));
     }
     if local_z == 0 {
-        rebuild_chunk_visual(
-            world,
-            chunk_x,
-            chunk_z - 1,
-            commands,
-            meshes,
-            chunk_query,
-            block_registry,
-            layer_map,
-            mat_handle,
-            chunk_entities,
-            stats,
-        );
+        chunks.push((chunk_x, chunk_z - 1));
     }
     if local_z == (CHUNK_SIZE_I32 - 1) {
+        chunks.push((chunk_x, chunk_z + 1));
+    }
+
+    chunks
+}
+
+/// Chunks waiting for a mesh rebuild after block edits made outside of
+/// `block_interaction` (falling blocks, fluids, ...). Marking the same chunk
+/// several times in a frame still rebuilds it once.
+#[derive(Resource, Default)]
+pub struct DirtyChunks(pub std::collections::HashSet<(i32, i32)>);
+
+impl DirtyChunks {
+    /// Mark every chunk affected by a change at `block_pos` (see `affected_chunks`).
+    pub fn mark_block(&mut self, block_pos: IVec3) {
+        let cx = block_pos.x.div_euclid(CHUNK_SIZE_I32);
+        let cz = block_pos.z.div_euclid(CHUNK_SIZE_I32);
+        self.0.extend(affected_chunks(cx, cz, block_pos));
+    }
+}
+
+/// System parameters used by `rebuild_dirty_chunks`.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct DirtyChunkCtx<'w, 's> {
+    pub dirty: ResMut<'w, DirtyChunks>,
+    pub world: Res<'w, World>,
+    pub meshes: ResMut<'w, Assets<Mesh>>,
+    pub block_registry: Res<'w, BlockRegistry>,
+    pub chunk_entities: ResMut<'w, crate::chunk::streaming::ChunkEntities>,
+    pub stats: ResMut<'w, crate::chunk::MeshGenerationStats>,
+    pub layer_map: Option<Res<'w, AtlasUVMap>>,
+    pub material_handle: Option<Res<'w, VoxelMaterialHandle>>,
+    pub chunk_query: Query<'w, 's, (&'static ChunkEntity, Entity)>,
+    pub commands: Commands<'w, 's>,
+}
+
+/// Rebuild the mesh of every chunk in `DirtyChunks` once, then clear it.
+///
+/// # Arguments
+/// * `ctx` - A `DirtyChunkCtx` with the world, mesh assets and chunk bookkeeping needed to rebuild meshes.
+pub fn rebuild_dirty_chunks(mut ctx: DirtyChunkCtx) {
+    if ctx.dirty.0.is_empty() {
+        return;
+    }
+    let (Some(layer_map), Some(mat_handle)) = (ctx.layer_map.as_ref(), ctx.material_handle.as_ref()) else {
+        return; // Keep the chunks dirty until the atlas/material are ready
+    };
+
+    for (cx, cz) in std::mem::take(&mut ctx.dirty.0) {
         rebuild_chunk_visual(
-            world,
-            chunk_x,
-            chunk_z + 1,
-            commands,
-            meshes,
-            chunk_query,
-            block_registry,
+            &ctx.world,
+            cx,
+            cz,
+            &mut ctx.commands,
+            &mut ctx.meshes,
+            &mut ctx.chunk_query,
+            &ctx.block_registry,
             layer_map,
             mat_handle,
-            chunk_entities,
-            stats,
+            &mut ctx.chunk_entities,
+            &mut ctx.stats,
         );
     }
 }
@@ -560,4 +790,75 @@ fn rebuild_chunk_visual(
             .map
             .insert((chunk_x, chunk_z), (entity, handles, 0));
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::{Item, INVENTORY_SLOTS};
+
+    #[test]
+    fn matching_tool_speeds_up_mining_until_done() {
+        let block = Block { hardness: 2.0, tool_required: Some("pickaxe".to_string()), tool_tier: 1, ..Block::default() };
+        let pickaxe = |tier| ToolProperties { class: ToolClass::Pickaxe, tier, speed: 4.0 };
+        assert!((mining_time(&block, None) - 3.0).abs() < f32::EPSILON);
+        assert!((mining_time(&block, Some(&pickaxe(0))) - 3.0).abs() < f32::EPSILON);
+        assert!((mining_time(&block, Some(&pickaxe(1))) - 0.75).abs() < f32::EPSILON);
+
+        let mut mining = MiningProgress::default();
+        let target = IVec3::new(1, 2, 3);
+        assert!(!mining.advance(target, true, 0.5, 0.75));
+        assert!(!mining.advance(IVec3::ZERO, true, 0.5, 0.75), "switching target restarts");
+        assert!(!mining.advance(IVec3::ZERO, false, 0.5, 0.75));
+        assert!(!mining.advance(target, true, 0.5, 0.75));
+        assert!(mining.advance(target, true, 0.5, 0.75));
+        assert_eq!(mining.target, None);
+    }
+
+    #[test]
+    fn drops_that_do_not_fit_are_left_over() {
+        let mut items = ItemRegistry::default();
+        items.register(Item { name: "stone".to_string(), id: 256, max_stack_size: 64, ..Item::default() });
+        items.register(Item { name: "cobblestone".to_string(), id: 257, max_stack_size: 64, ..Item::default() });
+        let block = Block { name: "stone".to_string(), drop_item: "cobblestone".to_string(), drop_count: 2, ..Block::default() };
+
+        let mut inventory = Inventory::default();
+        inventory.add(items.get("stone").unwrap(), 64 * INVENTORY_SLOTS as u32);
+        assert_eq!(
+            collect_block_drop(&mut inventory, &items, &block),
+            Some(ItemStack { item: "cobblestone".to_string(), count: 2 })
+        );
+
+        // One slot with room for a single item takes what fits
+        inventory.slots[0] = Some(ItemStack { item: "cobblestone".to_string(), count: 63 });
+        assert_eq!(
+            collect_block_drop(&mut inventory, &items, &block),
+            Some(ItemStack { item: "cobblestone".to_string(), count: 1 })
+        );
+        assert_eq!(inventory.count("cobblestone"), 64);
+
+        assert_eq!(collect_block_drop(&mut Inventory::default(), &items, &block), None);
+    }
+
+    #[test]
+    fn solid_raycast_passes_fluids_and_creative_cycles_blocks() {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: 1, name: "stone".to_string(), ..Block::default() });
+        registry.register(Block { id: 4, name: "water".to_string(), solid: false, ..Block::default() });
+        let mut world = World::new();
+        world.chunks.insert((0, 0), crate::chunk::Chunk::new());
+        world.set_block(0, 0, 1, 4, &registry);
+        world.set_block(0, 0, 2, 1, &registry);
+
+        let (origin, dir) = (Vec3::new(0.5, 0.5, 0.5), Vec3::Z);
+        assert_eq!(raycast_block(&world, origin, dir, 5.0).map(|h| h.0), Some(IVec3::new(0, 0, 1)));
+        assert_eq!(raycast_solid_block(&world, &registry, origin, dir, 5.0), Some((IVec3::new(0, 0, 2), IVec3::new(0, 0, 1))));
+
+        let mut creative = CreativeBlock::default();
+        assert_eq!(creative.current(&registry), Some(1));
+        creative.cycle(&registry, 1);
+        assert_eq!(creative.current(&registry), Some(4));
+        creative.cycle(&registry, 1);
+        assert_eq!(creative.current(&registry), Some(1), "cycling wraps around");
+    }
+}
@@ -0,0 +1,223 @@
+//! Point lights for light emitting blocks.
+//!
+//! There is no voxel light propagation, so blocks with a `light_level`
+//! (fire, lava, ...) get a Bevy `PointLight` instead. Lights are added and
+//! removed as `BlockChanged` events report emitting blocks appearing or
+//! disappearing; generated terrain does not contain emitters. Point lights
+//! are expensive, so only the `MAX_BLOCK_LIGHTS` emitters nearest the camera
+//! are lit; the choice is revisited as emitters change and the camera moves.
+
+use super::{Block, BlockRegistry};
+use crate::world::{BlockChanged, World};
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Upper bound on point lights spawned for blocks.
+pub const MAX_BLOCK_LIGHTS: usize = 64;
+
+/// Light intensity (lumens) per block light level.
+pub const LUMENS_PER_LIGHT_LEVEL: f32 = 300.0;
+
+/// Distance (in blocks) the camera moves before the lit emitters are picked again.
+pub const RESELECT_DISTANCE: f32 = 4.0;
+
+/// Marks a point light that belongs to the block at the given position.
+#[derive(Component, Debug)]
+pub struct BlockLight(pub IVec3);
+
+/// Emitting blocks and the light entities of those that are lit.
+///
+/// # Fields
+/// * `lights` - light entities keyed by the block position they belong to
+/// * `emitters` - every emitting block in loaded chunks, lit or not
+/// * `center` - camera position the lit emitters were last picked for
+#[derive(Resource, Default)]
+pub struct BlockLights {
+    pub lights: HashMap<IVec3, Entity>,
+    pub emitters: HashSet<IVec3>,
+    pub center: Option<Vec3>,
+}
+
+/// Spawn the point light of the emitting `block` at `pos`.
+fn spawn_block_light(commands: &mut Commands, pos: IVec3, block: &Block) -> Entity {
+    let level = f32::from(block.light_level);
+    let (r, g, b) = block.color_tint;
+    commands
+        .spawn((
+            PointLightBundle {
+                point_light: PointLight {
+                    intensity: level * LUMENS_PER_LIGHT_LEVEL,
+                    range: level,
+                    color: Color::srgb(r, g, b),
+                    shadows_enabled: false,
+                    ..default()
+                },
+                transform: Transform::from_translation(pos.as_vec3() + Vec3::splat(0.5)),
+                ..default()
+            },
+            BlockLight(pos),
+        ))
+        .id()
+}
+
+/// Track emitting blocks as they change or their chunks unload, and light
+/// the `MAX_BLOCK_LIGHTS` of them nearest the camera.
+///
+/// # Arguments
+/// * `commands` - `Commands` to spawn/despawn light entities
+/// * `changes` - block changes made through `World::set_block`
+/// * `world` - world used to look up the changed blocks
+/// * `blocks` - block registry providing `light_level` and `color_tint`
+/// * `camera` - camera transform; emitters nearest to it are lit first
+/// * `lights` - known emitters and their spawned lights
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_block_lights(
+    mut commands: Commands,
+    mut changes: EventReader<BlockChanged>,
+    world: Res<World>,
+    blocks: Res<BlockRegistry>,
+    camera: Query<&Transform, With<Camera3d>>,
+    mut lights: ResMut<BlockLights>,
+) {
+    let mut changed = false;
+    for BlockChanged(pos) in changes.read() {
+        changed = true;
+        // A changed emitter may have a different light; it is spawned again below
+        if let Some(entity) = lights.lights.remove(pos) {
+            commands.entity(entity).despawn();
+        }
+        lights.emitters.remove(pos);
+        if blocks.get_by_id(world.get_block(pos.x, pos.y, pos.z)).is_some_and(|b| b.light_level > 0) {
+            lights.emitters.insert(*pos);
+        }
+    }
+
+    let before = lights.emitters.len();
+    lights.emitters.retain(|pos| world.is_loaded(pos.x, pos.z));
+    changed |= lights.emitters.len() != before;
+
+    let center = camera.get_single().map_or(Vec3::ZERO, |tf| tf.translation);
+    let moved = lights.center.is_none_or(|c| c.distance(center) >= RESELECT_DISTANCE);
+    if !changed && !moved {
+        return;
+    }
+    lights.center = Some(center);
+
+    let mut nearest: Vec<IVec3> = lights.emitters.iter().copied().collect();
+    if nearest.len() > MAX_BLOCK_LIGHTS {
+        let distance = |pos: &IVec3| (pos.as_vec3() + Vec3::splat(0.5)).distance_squared(center);
+        nearest.select_nth_unstable_by(MAX_BLOCK_LIGHTS, |a, b| distance(a).total_cmp(&distance(b)));
+        nearest.truncate(MAX_BLOCK_LIGHTS);
+    }
+    let nearest: HashSet<IVec3> = nearest.into_iter().collect();
+
+    lights.lights.retain(|pos, entity| {
+        let keep = nearest.contains(pos);
+        if !keep {
+            commands.entity(*entity).despawn();
+        }
+        keep
+    });
+    for pos in nearest {
+        if lights.lights.contains_key(&pos) {
+            continue;
+        }
+        let Some(block) = blocks.get_by_id(world.get_block(pos.x, pos.y, pos.z)) else { continue };
+        let entity = spawn_block_light(&mut commands, pos, block);
+        lights.lights.insert(pos, entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::chunk::Chunk;
+
+    #[test]
+    fn lights_follow_emitting_blocks() {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: 1, name: "fire".to_string(), light_level: 12, ..Block::default() });
+        registry.register(Block { id: 2, name: "stone".to_string(), ..Block::default() });
+        let mut world = World::new();
+        world.chunks.insert((0, 0), Chunk::new());
+
+        let mut app = App::new();
+        app.add_event::<BlockChanged>();
+        app.insert_resource(registry);
+        app.insert_resource(world);
+        app.insert_resource(BlockLights::default());
+        app.add_systems(Update, update_block_lights);
+
+        let pos = IVec3::new(3, 20, 3);
+        let step = |app: &mut App, block| {
+            app.world_mut().resource_scope(|ecs, mut world: Mut<World>| {
+                world.set_block(pos.x, pos.y, pos.z, block, ecs.resource::<BlockRegistry>());
+            });
+            app.world_mut().send_event(BlockChanged(pos));
+            app.update();
+            let mut lights = app.world_mut().query::<(&BlockLight, &PointLight)>();
+            lights.iter(app.world()).map(|(l, p)| (l.0, p.range)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(step(&mut app, 1), vec![(pos, 12.0)]);
+        assert_eq!(step(&mut app, 2), vec![], "replacing the emitter removes its light");
+
+        step(&mut app, 1);
+        app.world_mut().resource_mut::<World>().chunks.clear();
+        app.update();
+        assert!(app.world().resource::<BlockLights>().lights.is_empty(), "unloaded chunks drop their lights");
+    }
+
+    #[test]
+    fn only_the_nearest_emitters_are_lit() {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: 1, name: "fire".to_string(), light_level: 12, ..Block::default() });
+        registry.register(Block { id: 2, name: "stone".to_string(), ..Block::default() });
+        let mut world = World::new();
+        for cx in 0..3 {
+            world.chunks.insert((cx, 0), Chunk::new());
+        }
+
+        let mut app = App::new();
+        app.add_event::<BlockChanged>();
+        app.insert_resource(registry);
+        app.insert_resource(world);
+        app.insert_resource(BlockLights::default());
+        app.add_systems(Update, update_block_lights);
+        let camera = app.world_mut().spawn((Camera3d::default(), Transform::default())).id();
+
+        // One emitter per block along x, one more than can be lit
+        let far = i32::try_from(MAX_BLOCK_LIGHTS).unwrap();
+        let set = |app: &mut App, x: i32, block| {
+            let pos = IVec3::new(x, 20, 0);
+            app.world_mut().resource_scope(|ecs, mut world: Mut<World>| {
+                world.set_block(pos.x, pos.y, pos.z, block, ecs.resource::<BlockRegistry>());
+            });
+            app.world_mut().send_event(BlockChanged(pos));
+        };
+        let lit = |app: &App, x: i32| app.world().resource::<BlockLights>().lights.contains_key(&IVec3::new(x, 20, 0));
+        for x in 0..=far {
+            set(&mut app, x, 1);
+        }
+        app.update();
+        assert_eq!(app.world().resource::<BlockLights>().lights.len(), MAX_BLOCK_LIGHTS);
+        assert!(lit(&app, 0) && !lit(&app, far));
+
+        // Removing a lit emitter frees a light for the skipped one
+        set(&mut app, 0, 2);
+        app.update();
+        assert!(lit(&app, far));
+        assert_eq!(app.world().resource::<BlockLights>().lights.len(), MAX_BLOCK_LIGHTS);
+
+        // Moving the camera lights the emitters it approaches instead
+        set(&mut app, 0, 1);
+        app.update();
+        assert!(lit(&app, 0) && !lit(&app, far));
+        app.world_mut().get_mut::<Transform>(camera).unwrap().translation = Vec3::new(80.0, 20.0, 0.0);
+        app.update();
+        assert!(!lit(&app, 0) && lit(&app, far));
+        let mut lights = app.world_mut().query::<&BlockLight>();
+        assert_eq!(lights.iter(app.world()).count(), MAX_BLOCK_LIGHTS);
+    }
+}
@@ -25,6 +25,22 @@
 pub mod interaction;
 pub use interaction::*;
 
+/// Falling (gravity affected) blocks.
+pub mod falling;
+pub use falling::{start_falling_blocks, update_falling_blocks, BlockMeshCache, FallingBlock, FallingBlockQueue};
+
+/// Flowing fluids (water, lava).
+pub mod fluid;
+
+/// Fire spread and burning.
+pub mod fire;
+
+/// Point lights for blocks with a `light_level`.
+pub mod light;
+
+/// Built-in block tick handlers (grass spread, ...).
+pub mod tick_handlers;
+
 /// Type used throughout the engine to represent a compact block identifier.
 ///
 /// This is intentionally a `u8` to keep chunk storage memory-efficient.
@@ -66,4 +82,4 @@ pub mod loader;
 /// Block registry and related data structures.
 pub mod registry;
 
-pub use registry::{Block, BlockRegistry, TextureConfig};
+pub use registry::{Block, BlockRegistry, FluidProperties, TextureConfig};
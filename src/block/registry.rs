@@ -50,6 +50,25 @@ impl Default for BlockTextures {
     }
 }
 
+/// Flow behaviour of a fluid block (water, lava). See `crate::block::fluid`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FluidProperties {
+    #[serde(default = "FluidProperties::default_flow_distance")]
+    pub flow_distance: u8, // How many blocks the fluid spreads sideways from a source
+    #[serde(default)]
+    pub infinite_source: bool, // Two sources next to each other create a new source between them
+}
+
+impl FluidProperties {
+    fn default_flow_distance() -> u8 { 7 }
+}
+
+impl Default for FluidProperties {
+    fn default() -> Self {
+        Self { flow_distance: Self::default_flow_distance(), infinite_source: false }
+    }
+}
+
 /// Texture configuration for a block whether to apply 1 texture to all faces or 
 // Texture configuration: blocks must specify per-face textures using
 // `BlockTextures`. The previous single-texture shortcut has been removed
@@ -60,6 +79,8 @@ pub type TextureConfig = BlockTextures;
 pub struct Block {
     pub name: String,
     pub id: u8,
+    #[serde(default)]
+    pub tags: Vec<String>, // Free-form tags (e.g. "dirt", "mineable_shovel") used by recipes and lookups
 
     /// Per-face textures are required. Use `textures` to specify `top`,
     /// `bottom`, and `side` image paths.
@@ -67,15 +88,83 @@ pub struct Block {
     pub textures: BlockTextures,
     pub hardness: f32,
     pub breakable: bool,
+    #[serde(default)]
+    pub tool_required: Option<String>, // Tool class that mines this block faster ("pickaxe", "shovel", ...)
+    #[serde(default)]
+    pub tool_tier: u8, // Minimum tool tier for `tool_required` tools to speed up mining
     pub solid: bool,
     pub color_tint: (f32, f32, f32),
     pub transparent: bool,
-    pub friction: f32,
+    pub friction: f32, // How quickly movement on top of this block speeds up and slows down (0..1)
+    #[serde(default = "Block::default_slipperiness")]
+    pub slipperiness: f32, // How much speed is kept when not moving on top of this block (ice = 0.98)
+    #[serde(default = "Block::default_fall_damage_multiplier")]
+    pub fall_damage_multiplier: f32, // Scales the fall distance of landing on this block (0 = no fall damage)
+    #[serde(default)]
+    pub climbable: bool, // Players standing next to this block climb it (ladders, vines)
+    #[serde(default = "Block::default_climb_speed")]
+    pub climb_speed: f32, // Climbing speed in blocks per second
+    #[serde(default)]
+    pub can_grab_ledge: bool, // Players falling past the top edge of this block can hang from it
     pub drop_item: String,
     pub drop_count: u32,
+    #[serde(default = "Block::default_max_stack_size")]
+    pub max_stack_size: u32, // Stack size of this block's block item
+    #[serde(default)]
+    pub affected_by_gravity: bool, // Falls when the block beneath it is removed (sand, gravel)
+    #[serde(default)]
+    pub replaceable: bool, // Other blocks (and falling blocks) can take this block's space (tall grass)
+    #[serde(default = "Block::default_collision_box")]
+    pub collision_box: String, // "full" for a whole 1x1x1 cube, anything else is a partial/no collision shape
+    #[serde(default)]
+    pub ticks: bool, // Receives scheduled ticks while placed (see `world::tick`)
+    #[serde(default = "Block::default_tick_rate")]
+    pub tick_rate: f32, // Scheduled ticks per second
+    #[serde(default)]
+    pub random_tick: bool, // Receives random ticks (grass spread, ...)
+    #[serde(default)]
+    pub fluid: Option<FluidProperties>, // Makes this a flowing fluid; `tick_rate` sets its flow speed
+    #[serde(default = "Block::default_blast_resistance")]
+    pub blast_resistance: f32, // How much explosion power this block absorbs (see `world::explosion`)
+    #[serde(default)]
+    pub light_level: u8, // Light emitted by this block (0 = none, 15 = brightest)
+    #[serde(default)]
+    pub flammable: bool, // Can catch fire and be burnt away
+    #[serde(default)]
+    pub burn_time: f32, // Seconds a fire next to this block burns before the block is consumed
+    #[serde(default)]
+    pub fire_spread_chance: f32, // Chance per fire tick that fire spreads to air next to this block
+    #[serde(default)]
+    pub fire_damage: f32, // Damage per second dealt to a player touching this block (fire, lava)
 }
 
 impl Block {
+    fn default_max_stack_size() -> u32 { 64 }
+    fn default_collision_box() -> String { "full".to_string() }
+    fn default_tick_rate() -> f32 { 1.0 }
+    fn default_blast_resistance() -> f32 { 1.0 }
+    fn default_slipperiness() -> f32 { 0.6 }
+    fn default_fall_damage_multiplier() -> f32 { 1.0 }
+    fn default_climb_speed() -> f32 { 2.0 }
+
+    /// Whether this block is a solid, full 1x1x1 cube (things can rest on it).
+    #[must_use]
+    pub fn is_full_cube(&self) -> bool {
+        self.solid && self.collision_box == "full"
+    }
+
+    /// Whether this block is a fluid.
+    #[must_use]
+    pub fn is_fluid(&self) -> bool {
+        self.fluid.is_some()
+    }
+
+    /// Whether this block carries `tag`.
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
     /// Return the per-face textures for this block.
     #[must_use]
     pub fn get_texture_config(&self) -> TextureConfig {
@@ -97,15 +186,37 @@ impl Default for Block {
         Self {
             name: "stone".to_string(),
             id: 1,
+            tags: Vec::new(),
             textures: BlockTextures::default(),
             hardness: 1.5,
             breakable: true,
+            tool_required: None,
+            tool_tier: 0,
             solid: true,
             color_tint: (1.0, 1.0, 1.0),
             transparent: false,
             friction: 0.6,
+            slipperiness: Self::default_slipperiness(),
+            fall_damage_multiplier: Self::default_fall_damage_multiplier(),
+            climbable: false,
+            climb_speed: Self::default_climb_speed(),
+            can_grab_ledge: false,
             drop_item: "stone".to_string(),
             drop_count: 1,
+            max_stack_size: Self::default_max_stack_size(),
+            affected_by_gravity: false,
+            replaceable: false,
+            collision_box: Self::default_collision_box(),
+            ticks: false,
+            tick_rate: Self::default_tick_rate(),
+            random_tick: false,
+            fluid: None,
+            blast_resistance: Self::default_blast_resistance(),
+            light_level: 0,
+            flammable: false,
+            burn_time: 0.0,
+            fire_spread_chance: 0.0,
+            fire_damage: 0.0,
         }
     }
 }
@@ -134,6 +245,14 @@ impl BlockRegistry {
             .and_then(|name| self.blocks.get(name))
     }
 
+    /// Whether block `id` stops players and entities. Air and blocks
+    /// declared with `solid: false` (fluids, fire, ...) do not; ids missing
+    /// from the registry do, so unknown blocks are never walked through.
+    #[must_use]
+    pub fn is_solid(&self, id: u8) -> bool {
+        id != super::blocks::AIR && self.get_by_id(id).is_none_or(|b| b.solid)
+    }
+
     /// Lookup numeric ID for a block `name`.
     #[must_use]
     pub fn id_for_name(&self, name: &str) -> Option<u8> {
@@ -0,0 +1,51 @@
+//! Built-in block tick handlers.
+//!
+//! Handlers are plain functions registered by block name in
+//! `BlockTickHandlers` (see `crate::world::tick`). Data files opt blocks into
+//! ticking with `ticks` / `random_tick`; the behaviour itself lives here.
+
+use super::{blocks, BlockId, BlockRegistry};
+use crate::world::tick::{BlockTickHandlers, TickCtx, TickKind};
+use bevy::prelude::*;
+
+/// Register every built-in handler. Fluid handlers are registered per fluid
+/// block by `fluid::sync_fluid_handlers`.
+pub fn register_builtin_handlers(handlers: &mut BlockTickHandlers) {
+    handlers.register("grass", grass_tick);
+    handlers.register("fire", super::fire::fire_tick);
+}
+
+/// Whether `id` blocks light from reaching the block below (solid and opaque).
+fn covers(blocks: &BlockRegistry, id: BlockId) -> bool {
+    blocks.get_by_id(id).is_some_and(|b| b.solid && !b.transparent)
+}
+
+/// Grass decays to dirt when covered, otherwise it spreads onto a nearby
+/// uncovered dirt block.
+#[allow(clippy::cast_possible_wrap)]
+fn grass_tick(ctx: &mut TickCtx, pos: IVec3, _kind: TickKind) {
+    let (Some(grass), Some(dirt)) = (ctx.blocks.get("grass").map(|b| b.id), ctx.blocks.get("dirt").map(|b| b.id))
+    else {
+        return;
+    };
+
+    if covers(ctx.blocks, ctx.get_block(pos + IVec3::Y)) {
+        ctx.set_block(pos, dirt);
+        return;
+    }
+
+    // Spread one block sideways, up to one block up or three blocks down
+    let target = pos
+        + IVec3::new(
+            ctx.scheduler.random_below(3) as i32 - 1,
+            ctx.scheduler.random_below(5) as i32 - 3,
+            ctx.scheduler.random_below(3) as i32 - 1,
+        );
+    if ctx.get_block(target) != dirt {
+        return;
+    }
+    let above = ctx.get_block(target + IVec3::Y);
+    if above == blocks::AIR || !covers(ctx.blocks, above) {
+        ctx.set_block(target, grass);
+    }
+}
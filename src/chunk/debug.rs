@@ -1,6 +1,6 @@
 //! Single helper system, `debug_chunk_report`, which
 //! samples information about chunks near the player when the
-//! debug key (`F1` or `FN + F1` depending on the keyboard configuration on the OS) is pressed.
+//! `dump_debug` action (default `F3`) is pressed.
 //!
 //! # Example
 //! ```rust
@@ -8,6 +8,7 @@
 //! ```
 use crate::chunk::streaming::ChunkEntities;
 use crate::chunk::CHUNK_SIZE;
+use crate::input::ActionState;
 use bevy::prelude::*;
 
 
@@ -27,13 +28,13 @@ use bevy::prelude::*;
 ///   happen for the hard-coded `sample_positions` values).
 ///
 pub fn debug_chunk_report(
-    input: &Res<ButtonInput<KeyCode>>,
+    actions: &ActionState,
     player_query: &Query<&GlobalTransform, With<Camera3d>>,
     world: &Res<crate::world::World>,
     chunk_entities: &Res<ChunkEntities>,
     meshes: &Res<Assets<Mesh>>,
 ) {
-    if !input.just_pressed(KeyCode::F3) {
+    if !actions.just_pressed("dump_debug") {
         return;
     }
 
@@ -0,0 +1,235 @@
+//! Adaptive frame-time governor for chunk streaming.
+//!
+//! The `StreamingBudget` built from the performance preset is a starting
+//! point; how much generation and meshing a frame can afford depends on the
+//! machine. The governor watches the frame time reported by
+//! `FrameTimeDiagnosticsPlugin` and scales the generation task cap and the
+//! per-frame meshing limits of the budget toward
+//! `performance.target_frame_time_ms`: it halves them at once on a
+//! spike, trims them while frames are slower than the target and slowly
+//! raises them again while there is headroom. Limits pinned in
+//! `performance.overrides` are never scaled.
+
+use super::lod::StreamingBudget;
+use crate::settings::{BudgetOverrides, PerformanceSettings};
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+/// Lowest scale applied to the preset's limits.
+pub const MIN_SCALE: f32 = 0.125;
+
+/// Highest scale applied to the preset's limits.
+pub const MAX_SCALE: f32 = 2.0;
+
+/// A frame this many times slower than the target counts as a spike.
+const SPIKE_FACTOR: f32 = 1.5;
+
+/// Seconds between regular adjustments; also the hold after a spike.
+const ADJUST_INTERVAL: f64 = 0.25;
+
+/// Scale added per adjustment while frames are comfortably fast.
+const SCALE_STEP_UP: f32 = 0.05;
+
+/// Factor applied per adjustment while frames are slower than the target.
+const SCALE_STEP_DOWN: f32 = 0.85;
+
+/// Scales the streaming budget to hold a target frame time.
+///
+/// # Fields
+/// * `base` - the budget from the settings, before scaling
+/// * `overrides` - limits pinned in the settings, which `budget` leaves unscaled
+/// * `enabled` - whether scaling is active (`performance.adaptive_budget`)
+/// * `target_ms` - frame time to aim for
+/// * `scale` - current factor applied to the limits of `base` that `budget` scales
+/// * `frame_ms` - smoothed frame time at the last observation
+/// * `spikes` - number of spikes backed off from
+#[derive(Resource, Debug, Clone)]
+pub struct StreamingGovernor {
+    pub base: StreamingBudget,
+    pub overrides: BudgetOverrides,
+    pub enabled: bool,
+    pub target_ms: f32,
+    pub scale: f32,
+    pub frame_ms: f32,
+    pub spikes: u32,
+    next_adjust: f64,
+}
+
+impl StreamingGovernor {
+    /// A governor for the budget built from `performance`, starting at the
+    /// preset's limits.
+    #[must_use]
+    pub fn from_settings(performance: &PerformanceSettings) -> Self {
+        Self {
+            base: StreamingBudget::from_settings(performance),
+            overrides: performance.overrides.clone(),
+            enabled: performance.adaptive_budget,
+            target_ms: performance.target_frame_time_ms.max(1.0),
+            scale: 1.0,
+            frame_ms: 0.0,
+            spikes: 0,
+            next_adjust: 0.0,
+        }
+    }
+
+    /// Take new settings, keeping the current scale.
+    pub fn apply_settings(&mut self, performance: &PerformanceSettings) {
+        self.base = StreamingBudget::from_settings(performance);
+        self.overrides = performance.overrides.clone();
+        self.enabled = performance.adaptive_budget;
+        self.target_ms = performance.target_frame_time_ms.max(1.0);
+        if !self.enabled {
+            self.scale = 1.0;
+        }
+    }
+
+    /// Feed one frame's timings (latest and smoothed, in ms) at time `now`
+    /// (seconds). Returns whether the scale changed.
+    pub fn observe(&mut self, latest_ms: f32, smoothed_ms: f32, now: f64) -> bool {
+        self.frame_ms = smoothed_ms;
+        if !self.enabled || now < self.next_adjust {
+            return false;
+        }
+        let old = self.scale;
+        if latest_ms > self.target_ms * SPIKE_FACTOR {
+            self.scale *= 0.5;
+            self.spikes += 1;
+        } else if smoothed_ms > self.target_ms * 1.05 {
+            self.scale *= SCALE_STEP_DOWN;
+        } else if smoothed_ms < self.target_ms * 0.85 {
+            self.scale += SCALE_STEP_UP;
+        }
+        self.scale = self.scale.clamp(MIN_SCALE, MAX_SCALE);
+        self.next_adjust = now + ADJUST_INTERVAL;
+        (self.scale - old).abs() > f32::EPSILON
+    }
+
+    /// `base` with its generation task cap (`generation_tasks`, the only
+    /// limit on generation) and its per-frame mesh and LOD limits scaled.
+    /// Mesh applies never exceed `max_chunk_meshes_per_frame`. Limits set in
+    /// `overrides`, the pending LOD task cap, LOD distances and prewarming are
+    /// left alone.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    pub fn budget(&self) -> StreamingBudget {
+        let scaled = |n: usize, pinned: Option<usize>| {
+            if pinned.is_some() { n } else { ((n as f32 * self.scale).round() as usize).max(1) }
+        };
+        let mut budget = self.base.clone();
+        budget.generation_tasks = scaled(budget.generation_tasks, self.overrides.generation_tasks);
+        budget.mesh_builds_per_frame = scaled(budget.mesh_builds_per_frame, self.overrides.mesh_builds_per_frame);
+        // `max_chunk_meshes_per_frame` is a ceiling: only ever scaled down
+        budget.mesh_applies_per_frame = scaled(budget.mesh_applies_per_frame, None).min(budget.mesh_applies_per_frame);
+        budget.lod_builds_per_frame = scaled(budget.lod_builds_per_frame, self.overrides.lod_builds_per_frame);
+        if !budget.background_meshing {
+            budget.mesh_builds_per_frame = budget.mesh_builds_per_frame.min(budget.mesh_applies_per_frame);
+            budget.lod_builds_per_frame = budget.lod_builds_per_frame.min(budget.mesh_applies_per_frame);
+        }
+        budget
+    }
+}
+
+impl Default for StreamingGovernor {
+    fn default() -> Self {
+        Self::from_settings(&PerformanceSettings::default())
+    }
+}
+
+/// Adjust the streaming budget from the latest frame time. Runs before
+/// `stream_chunks`.
+///
+/// # Arguments
+/// * `diagnostics` - frame time measurements
+/// * `time` - real time, so the adjustment interval ignores pausing
+/// * `governor` - governor state
+/// * `budget` - the budget `stream_chunks` reads, rewritten when the scale changes
+#[allow(clippy::needless_pass_by_value, clippy::cast_possible_truncation)]
+pub fn govern_streaming_budget(
+    diagnostics: Res<DiagnosticsStore>,
+    time: Res<Time<Real>>,
+    mut governor: ResMut<StreamingGovernor>,
+    mut budget: ResMut<StreamingBudget>,
+) {
+    let Some(frame_time) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME) else { return };
+    let (Some(latest), Some(smoothed)) = (frame_time.value(), frame_time.smoothed()) else { return };
+
+    if governor.observe(latest as f32, smoothed as f32, time.elapsed_seconds_f64()) {
+        let new = governor.budget();
+        if *budget != new {
+            debug!("Streaming governor: scale {:.2} at {:.1} ms (target {:.1} ms)", governor.scale, smoothed, governor.target_ms);
+            *budget = new;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_on_spikes_and_recovers_with_headroom() {
+        let mut governor = StreamingGovernor::default();
+        let base = governor.base.clone();
+
+        // A spike halves the per-frame limits at once
+        assert!(governor.observe(60.0, 17.0, 0.0));
+        assert!((governor.scale - 0.5).abs() < 1e-6);
+        let budget = governor.budget();
+        assert_eq!(budget.mesh_builds_per_frame, base.mesh_builds_per_frame / 2);
+        assert_eq!(budget.lod_distances, base.lod_distances);
+        assert_eq!(budget.pending_lod_tasks, base.pending_lod_tasks);
+
+        // Nothing changes until the hold has passed
+        assert!(!governor.observe(60.0, 30.0, 0.1));
+
+        // Fast frames raise the scale again, up to the ceiling
+        let mut now = 0.0;
+        for _ in 0..100 {
+            now += ADJUST_INTERVAL;
+            governor.observe(8.0, 8.0, now);
+        }
+        assert!((governor.scale - MAX_SCALE).abs() < 1e-6);
+
+        // Frames at the target hold the scale
+        assert!(!governor.observe(16.7, 16.7, now + ADJUST_INTERVAL));
+
+        // Disabled, the governor leaves the budget at the preset
+        governor.apply_settings(&PerformanceSettings { adaptive_budget: false, ..PerformanceSettings::default() });
+        assert!(!governor.observe(60.0, 60.0, now + 10.0));
+        assert_eq!(governor.budget(), base);
+    }
+
+    #[test]
+    fn limits_pinned_by_overrides_are_not_scaled() {
+        let performance = PerformanceSettings {
+            overrides: BudgetOverrides { generation_tasks: Some(3), lod_builds_per_frame: Some(5), ..BudgetOverrides::default() },
+            ..PerformanceSettings::default()
+        };
+        let mut governor = StreamingGovernor::from_settings(&performance);
+        let base = governor.base.clone();
+
+        let mut now = 0.0;
+        for _ in 0..100 {
+            now += ADJUST_INTERVAL;
+            governor.observe(8.0, 8.0, now);
+        }
+        assert!((governor.scale - MAX_SCALE).abs() < 1e-6);
+        let budget = governor.budget();
+        assert_eq!(budget.generation_tasks, 3);
+        assert_eq!(budget.lod_builds_per_frame, 5);
+        assert!(budget.mesh_builds_per_frame > base.mesh_builds_per_frame);
+        assert_eq!(budget.mesh_applies_per_frame, usize::from(performance.max_chunk_meshes_per_frame));
+
+        // Without background meshing every build is capped by the configured maximum too
+        let main_thread = PerformanceSettings { background_meshing: false, ..PerformanceSettings::default() };
+        governor.apply_settings(&main_thread);
+        let budget = governor.budget();
+        let max = usize::from(main_thread.max_chunk_meshes_per_frame);
+        assert_eq!(budget.mesh_applies_per_frame, max);
+        assert!(budget.mesh_builds_per_frame <= max && budget.lod_builds_per_frame <= max);
+
+        governor.apply_settings(&performance);
+        governor.observe(60.0, 60.0, now + 10.0);
+        assert_eq!(governor.budget().generation_tasks, 3);
+    }
+}
@@ -1,25 +1,129 @@
 //! Chunk Level-of-Detail (LOD) configuration and helpers.
 //!
-//! This module provides constants and resource types used to determine which
-//! LOD a chunk should use based on its distance from the player, and to
-//! manage LOD generation tasks and stability timers. Tuning these constants
-//! controls memory, build concurrency and visual popping behavior.
+//! This module provides the streaming budget and resource types used to
+//! determine which LOD a chunk should use based on its distance from the
+//! player, and to manage LOD generation tasks and stability timers. The
+//! budget's limits control memory, build concurrency and visual popping
+//! behavior; they come from the performance preset in the settings (see
+//! `StreamingBudget::from_settings`).
 use crate::chunk::MAX_LODS;
+use crate::settings::{PerformancePreset, PerformanceSettings};
 use bevy::prelude::*;
 
-pub const PREWARM_LEVELS: u8 = 1; // How many LOD levels to prebuild for chunks near load bounary
-pub const PREWARM_DISTANCE_MARGIN: i32 = 1; //Chunk distance beyond configured load distance
-pub const LOD_BUILD_BUDGET_PER_FRAME: usize = 4; // MAX number of LOD builds per frame
-pub const MAX_PENDING_GENERATION_TASKS: usize = 64; // Max number of pending chunk generation concurrently
-pub const MAX_PENDING_LOD_TASKS: usize = 256; // Max pending LOD builds
-
 /// Threshold distances (in chunk units) used to select LOD levels.
 /// The array must have length `MAX_LODS`. For a given `dist`, the first
 /// threshold value `d` where `dist <= d` determines the returned LOD index.
 /// More aggressive LOD thresholds so coarser LODs apply earlier (reduces
-/// triangles for distant chunks). Values are in chunk units.
+/// triangles for distant chunks). Values are in chunk units. These are the
+/// `medium` preset's thresholds.
 pub const LOD_DISTANCES: [i32; MAX_LODS] = [1, 2, 4, 8, 16, 32];
 
+/// Per-frame and in-flight limits of the chunk pipeline.
+///
+/// Read by `stream_chunks` every frame and rebuilt from the settings when
+/// they change (see `app::sync_streaming_budget`), so a new preset or
+/// override applies without a restart. With `performance.adaptive_budget`
+/// the per-frame limits are further scaled by the frame-time governor (see
+/// `chunk::governor`).
+///
+/// # Fields
+/// * `generation_tasks` - max chunk generation tasks in flight
+/// * `mesh_builds_per_frame` - max mesh builds started per frame for new chunks
+/// * `mesh_applies_per_frame` - max finished meshes uploaded / entities spawned per frame
+/// * `lod_builds_per_frame` - max LOD mesh builds started per frame
+/// * `pending_lod_tasks` - max mesh and LOD builds in flight
+/// * `prewarm_levels` - coarser LOD levels prebuilt for chunks near the load boundary
+/// * `prewarm_margin` - chunks beyond the load distance that still get prewarmed
+/// * `lod_distances` - chunk distance thresholds of each LOD level
+/// * `background_meshing` - build meshes on worker threads instead of the main thread
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct StreamingBudget {
+    pub generation_tasks: usize,
+    pub mesh_builds_per_frame: usize,
+    pub mesh_applies_per_frame: usize,
+    pub lod_builds_per_frame: usize,
+    pub pending_lod_tasks: usize,
+    pub prewarm_levels: u8,
+    pub prewarm_margin: i32,
+    pub lod_distances: [i32; MAX_LODS],
+    pub background_meshing: bool,
+}
+
+impl Default for StreamingBudget {
+    fn default() -> Self {
+        Self::for_preset(&PerformancePreset::Medium)
+    }
+}
+
+impl StreamingBudget {
+    /// The limits of a performance preset.
+    #[must_use]
+    pub fn for_preset(preset: &PerformancePreset) -> Self {
+        let (generation_tasks, mesh_builds_per_frame, lod_builds_per_frame, pending_lod_tasks, prewarm_levels, lod_distances) =
+            match preset {
+                PerformancePreset::VeryLow => (8, 2, 1, 32, 0, [1, 1, 2, 4, 8, 16]),
+                PerformancePreset::Low => (24, 4, 2, 96, 0, [1, 2, 3, 6, 12, 24]),
+                PerformancePreset::Medium => (64, 8, 4, 256, 1, LOD_DISTANCES),
+                PerformancePreset::High => (96, 12, 6, 384, 1, [2, 3, 6, 12, 24, 48]),
+                PerformancePreset::VeryHigh => (128, 16, 8, 512, 2, [2, 4, 8, 16, 32, 64]),
+            };
+        Self {
+            generation_tasks,
+            mesh_builds_per_frame,
+            mesh_applies_per_frame: 2,
+            lod_builds_per_frame,
+            pending_lod_tasks,
+            prewarm_levels,
+            prewarm_margin: 1,
+            lod_distances,
+            background_meshing: true,
+        }
+    }
+
+    /// The preset's limits with the settings' overrides applied. Without
+    /// background meshing, every build runs on the main thread, so all build
+    /// budgets are capped by `max_chunk_meshes_per_frame`.
+    #[must_use]
+    pub fn from_settings(performance: &PerformanceSettings) -> Self {
+        let mut budget = Self::for_preset(&performance.preset);
+        let overrides = &performance.overrides;
+        budget.generation_tasks = overrides.generation_tasks.unwrap_or(budget.generation_tasks).max(1);
+        budget.mesh_builds_per_frame = overrides.mesh_builds_per_frame.unwrap_or(budget.mesh_builds_per_frame).max(1);
+        budget.lod_builds_per_frame = overrides.lod_builds_per_frame.unwrap_or(budget.lod_builds_per_frame).max(1);
+        budget.pending_lod_tasks = overrides.pending_lod_tasks.unwrap_or(budget.pending_lod_tasks).max(1);
+        budget.prewarm_levels = overrides.prewarm_levels.unwrap_or(budget.prewarm_levels);
+        if let Some(distances) = &overrides.lod_distances {
+            match <[i32; MAX_LODS]>::try_from(distances.as_slice()) {
+                Ok(distances) if distances.is_sorted() => budget.lod_distances = distances,
+                _ => warn!("Ignoring performance.overrides.lod_distances: expected {MAX_LODS} ascending distances"),
+            }
+        }
+        budget.mesh_applies_per_frame = usize::from(performance.max_chunk_meshes_per_frame.max(1));
+        budget.background_meshing = performance.background_meshing;
+        if !budget.background_meshing {
+            budget.mesh_builds_per_frame = budget.mesh_builds_per_frame.min(budget.mesh_applies_per_frame);
+            budget.lod_builds_per_frame = budget.lod_builds_per_frame.min(budget.mesh_applies_per_frame);
+        }
+        budget
+    }
+
+    /// The LOD index for a chunk `dist` chunks away, using this budget's
+    /// `lod_distances` (see `compute_lod_from_dist`).
+    #[must_use]
+    pub fn lod_for_distance(&self, dist: i32) -> u8 {
+        lod_from_distances(&self.lod_distances, dist)
+    }
+}
+
+fn lod_from_distances(distances: &[i32; MAX_LODS], dist: i32) -> u8 {
+    for (i, &d) in distances.iter().enumerate() {
+        if dist <= d {
+            return u8::try_from(i).expect("LOD_DISTANCES length must be less than 256");
+        }
+    }
+    u8::try_from(MAX_LODS - 1).expect("MAX_LODS must be greater than 0")
+}
+
 /// Compute the LOD index for a chunk given its distance (in chunk units).
 ///
 /// # Panics
@@ -42,12 +146,7 @@ pub const LOD_DISTANCES: [i32; MAX_LODS] = [1, 2, 4, 8, 16, 32];
 /// ```
 #[must_use]
 pub fn compute_lod_from_dist(dist: i32) -> u8 {
-    for (i, &d) in LOD_DISTANCES.iter().enumerate() {
-        if dist <= d {
-            return u8::try_from(i).expect("LOD_DISTANCES length must be less than 256");
-        }
-    }
-    u8::try_from(MAX_LODS - 1).expect("MAX_LODS must be greater than 0")
+    lod_from_distances(&LOD_DISTANCES, dist)
 }
 
 /// Tracks how long a candidate LOD has been stable for each loaded chunk.
@@ -67,6 +166,7 @@ pub struct LodBuildResult {
     pub lod: u8, // Built LOD index
     pub mesh: Mesh, //Generated mesh
     pub triangle_count: usize, //Triangle Count
+    pub timing: crate::chunk::profiler::TaskTiming, // When the build was queued, started and finished
 }
 
 /// Type alias for an in-flight LOD build task.
@@ -83,3 +183,32 @@ pub struct PendingLodBuilds {
     pub tasks: Vec<LodTask>,
     pub coords: std::collections::HashSet<(i32, i32, u8)>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::BudgetOverrides;
+
+    #[test]
+    fn budget_follows_preset_and_overrides() {
+        let mut performance = PerformanceSettings { preset: PerformancePreset::VeryHigh, ..PerformanceSettings::default() };
+        let budget = StreamingBudget::from_settings(&performance);
+        assert!(budget.generation_tasks > StreamingBudget::default().generation_tasks);
+        assert_eq!(StreamingBudget::default().lod_for_distance(15), compute_lod_from_dist(15));
+
+        performance.overrides = BudgetOverrides {
+            generation_tasks: Some(5),
+            lod_distances: Some(vec![1, 2, 3, 4, 5, 6]),
+            ..BudgetOverrides::default()
+        };
+        performance.background_meshing = false;
+        performance.max_chunk_meshes_per_frame = 1;
+        let budget = StreamingBudget::from_settings(&performance);
+        assert_eq!(budget.generation_tasks, 5);
+        assert_eq!(budget.lod_for_distance(5), 4);
+        assert_eq!((budget.mesh_builds_per_frame, budget.lod_builds_per_frame), (1, 1));
+
+        performance.overrides.lod_distances = Some(vec![4, 3]);
+        assert_eq!(StreamingBudget::from_settings(&performance).lod_distances, [2, 4, 8, 16, 32, 64]);
+    }
+}
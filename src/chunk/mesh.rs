@@ -6,6 +6,11 @@
 //! exposed faces for each slice, and greedily grows rectangular regions of
 //! identical block types before emitting a single quad for each merged region.
 //!
+//! Fluid blocks are skipped by the greedy mesher (and do not hide the faces
+//! of solid blocks next to them). `mesh_fluids` emits them per block instead,
+//! with the top surface sloping between the flow levels of neighbouring
+//! fluid cells.
+//!
 //! # Example
 //! ```
 //! // Illustrative only; actual code requires a prepared `AtlasUVMap` and block registry
@@ -19,7 +24,10 @@
 
 use super::{CHUNK_SIZE, Chunk};
 use crate::atlas_builder::{AtlasUVMap, BlockFace};
-use crate::block::{blocks, BlockId};
+use crate::block::fluid::fluid_height;
+use crate::block::{blocks, BlockId, BlockRegistry};
+use crate::world::MAX_HEIGHT;
+use bevy::math::Vec3;
 
 // Bundle all mutable mesh output buffers to reduce function arity.
 pub(crate) struct MeshOutput<'a> {
@@ -51,8 +59,23 @@ struct SliceMask<'a> {
 }
 
 // Bundle mesh inputs that are constant per-mesh so helpers accept fewer args.
-struct MeshCtx {
+pub(crate) struct MeshCtx {
     lod: u8,
+    fluids: Vec<Option<u8>>, // Flow distance of fluid blocks, indexed by block id
+}
+
+impl MeshCtx {
+    pub(crate) fn new(lod: u8, registry: &BlockRegistry) -> Self {
+        let mut fluids = vec![None; 256];
+        for block in registry.blocks.values() {
+            fluids[usize::from(block.id)] = block.fluid.as_ref().map(|f| f.flow_distance);
+        }
+        Self { lod, fluids }
+    }
+
+    fn is_fluid(&self, id: BlockId) -> bool {
+        self.fluids[usize::from(id)].is_some()
+    }
 }
 
 impl Chunk {
@@ -63,7 +86,7 @@ impl Chunk {
         axis: usize,
         out: &mut MeshOutput,
         atlas_map: &AtlasUVMap,
-        lod: u8,
+        mesh_ctx: &MeshCtx,
         chunk_coords: (i32, i32),
         neighbors: Option<&std::collections::HashMap<(i32, i32), Chunk>>,
     ) {
@@ -83,14 +106,13 @@ impl Chunk {
                 }
 
                 // Delegate per-slice work to a helper to keep this function small.
-                let mesh_ctx = MeshCtx { lod };
                 let mut quads = Self::process_slice(
                     self,
                     axis,
                     slice,
                     direction,
                     &mut SliceMask { mask: &mut mask[..], done: &mut done[..] },
-                    &mesh_ctx,
+                    mesh_ctx,
                     chunk_coords,
                     neighbors,
                 );
@@ -126,7 +148,7 @@ impl Chunk {
                     if axis == 1 { slice } else if u_axis == 1 { col } else { row },
                     if axis == 2 { slice } else if u_axis == 2 { col } else { row },
                 );
-                if current == blocks::AIR {
+                if current == blocks::AIR || mesh_ctx.is_fluid(current) {
                     continue;
                 }
 
@@ -173,7 +195,7 @@ impl Chunk {
                     substituted
                 };
 
-                if neighbor == blocks::AIR {
+                if neighbor == blocks::AIR || mesh_ctx.is_fluid(neighbor) {
                     ctx.mask[col + row * size] = Some(current);
                 }
             }
@@ -453,3 +475,188 @@ impl Chunk {
         }
     }
 }
+
+impl Chunk {
+    /// Emit geometry for every fluid block in the meshed height range.
+    ///
+    /// The top surface of a fluid block is a quad whose corner heights are
+    /// averaged from the fluid cells sharing that corner (see
+    /// `fluid_corner_height`), so flowing fluid slopes down away from its
+    /// source. Sides and bottoms are emitted where the fluid borders air or a
+    /// different fluid; faces against solid blocks or unloaded neighbours are
+    /// culled like the greedy mesher does.
+    ///
+    /// # Arguments
+    /// * `out` - The `MeshOutput` bundle to append emitted quads to.
+    /// * `atlas_map` - The `AtlasUVMap` for looking up UV coordinates.
+    /// * `mesh_ctx` - Per-mesh inputs (fluid lookup).
+    /// * `chunk_coords` - Coordinates of this chunk, used for neighbour lookups.
+    /// * `neighbors` - Snapshot of the neighbouring chunks, if available.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_precision_loss)]
+    pub(crate) fn mesh_fluids(
+        &self,
+        out: &mut MeshOutput,
+        atlas_map: &AtlasUVMap,
+        mesh_ctx: &MeshCtx,
+        chunk_coords: (i32, i32),
+        neighbors: Option<&std::collections::HashMap<(i32, i32), Chunk>>,
+    ) {
+        let size = CHUNK_SIZE as i32;
+        let cell = |x: i32, y: i32, z: i32| self.fluid_cell(chunk_coords, neighbors, x, y, z);
+
+        for x in 0..size {
+            for z in 0..size {
+                for y in 0..size {
+                    let Some((fluid, _)) = cell(x, y, z) else { continue };
+                    let Some(flow_distance) = mesh_ctx.fluids[usize::from(fluid)] else { continue };
+
+                    // Exposed unless the neighbour is the same fluid, solid or unknown
+                    let exposed = |nx: i32, ny: i32, nz: i32| {
+                        cell(nx, ny, nz).is_some_and(|(id, _)| {
+                            id != fluid && (id == blocks::AIR || mesh_ctx.is_fluid(id))
+                        })
+                    };
+
+                    let covered = cell(x, y + 1, z).is_some_and(|(id, _)| id == fluid);
+                    let corner = |cx: i32, cz: i32| {
+                        if covered {
+                            1.0
+                        } else {
+                            self.fluid_corner_height(chunk_coords, neighbors, fluid, flow_distance, (cx, y, cz))
+                        }
+                    };
+                    // Corner heights at (x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)
+                    let h = [corner(x, z), corner(x + 1, z), corner(x + 1, z + 1), corner(x, z + 1)];
+
+                    let (fx, fy, fz) = (x as f32, y as f32, z as f32);
+                    let top = [
+                        [fx, fy + h[0], fz],
+                        [fx + 1.0, fy + h[1], fz],
+                        [fx + 1.0, fy + h[2], fz + 1.0],
+                        [fx, fy + h[3], fz + 1.0],
+                    ];
+
+                    if !covered {
+                        Self::add_fluid_quad(out, atlas_map, fluid, BlockFace::Top, top, Vec3::Y);
+                    }
+                    if exposed(x, y - 1, z) {
+                        let bottom = top.map(|[vx, _, vz]| [vx, fy, vz]);
+                        Self::add_fluid_quad(out, atlas_map, fluid, BlockFace::Bottom, bottom, Vec3::NEG_Y);
+                    }
+
+                    // Each side spans two of the top corners down to the block's base
+                    for (dx, dz, a, b) in [(-1, 0, 3, 0), (1, 0, 1, 2), (0, -1, 0, 1), (0, 1, 2, 3)] {
+                        if !exposed(x + dx, y, z + dz) {
+                            continue;
+                        }
+                        let [ax, _, az] = top[a];
+                        let [bx, _, bz] = top[b];
+                        let side = [[ax, fy, az], [bx, fy, bz], top[b], top[a]];
+                        let normal = Vec3::new(dx as f32, 0.0, dz as f32);
+                        Self::add_fluid_quad(out, atlas_map, fluid, BlockFace::Side, side, normal);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Block id and state at chunk-local coordinates, reaching into the
+    /// neighbouring chunks for coordinates outside this one. Returns `None`
+    /// when the containing chunk is not available.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn fluid_cell(
+        &self,
+        chunk_coords: (i32, i32),
+        neighbors: Option<&std::collections::HashMap<(i32, i32), Chunk>>,
+        x: i32,
+        y: i32,
+        z: i32,
+    ) -> Option<(BlockId, u8)> {
+        if y < 0 || y >= MAX_HEIGHT as i32 {
+            return Some((blocks::AIR, 0));
+        }
+        let size = CHUNK_SIZE as i32;
+        let (dx, dz) = (x.div_euclid(size), z.div_euclid(size));
+        let chunk = if dx == 0 && dz == 0 {
+            self
+        } else {
+            neighbors?.get(&(chunk_coords.0 + dx, chunk_coords.1 + dz))?
+        };
+        let (lx, ly, lz) = (x.rem_euclid(size) as usize, y as usize, z.rem_euclid(size) as usize);
+        Some((chunk.get(lx, ly, lz), chunk.get_state(lx, ly, lz)))
+    }
+
+    /// Surface height of `fluid` at a block corner: the average of the four
+    /// cells sharing the corner, counting air as height 0 and ignoring solid
+    /// cells. A corner touching fluid with the same fluid above it is full.
+    #[allow(clippy::cast_precision_loss)]
+    fn fluid_corner_height(
+        &self,
+        chunk_coords: (i32, i32),
+        neighbors: Option<&std::collections::HashMap<(i32, i32), Chunk>>,
+        fluid: BlockId,
+        flow_distance: u8,
+        (x, y, z): (i32, i32, i32),
+    ) -> f32 {
+        let mut sum = 0.0;
+        let mut count = 0;
+        for (cx, cz) in [(x - 1, z - 1), (x, z - 1), (x - 1, z), (x, z)] {
+            match self.fluid_cell(chunk_coords, neighbors, cx, y, cz) {
+                Some((id, state)) if id == fluid => {
+                    let above = self.fluid_cell(chunk_coords, neighbors, cx, y + 1, cz);
+                    if above.is_some_and(|(id, _)| id == fluid) {
+                        return 1.0;
+                    }
+                    sum += fluid_height(state, flow_distance);
+                    count += 1;
+                }
+                Some((blocks::AIR, _)) => count += 1,
+                _ => {}
+            }
+        }
+        if count == 0 { 1.0 } else { sum / count as f32 }
+    }
+
+    /// Append a single unmerged quad with the given corners, flipping the
+    /// winding when needed so the quad faces along `normal`. Side quads list
+    /// their bottom edge first so the texture stays upright.
+    fn add_fluid_quad(
+        out: &mut MeshOutput,
+        atlas_map: &AtlasUVMap,
+        fluid: BlockId,
+        face: BlockFace,
+        mut corners: [[f32; 3]; 4],
+        normal: Vec3,
+    ) {
+        let mut local_uvs = if face == BlockFace::Side {
+            [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]]
+        } else {
+            [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+        };
+
+        let [a, b, c, _] = corners.map(Vec3::from);
+        let mut facing = (b - a).cross(c - a);
+        if facing.dot(normal) < 0.0 {
+            corners.swap(1, 3);
+            local_uvs.swap(1, 3);
+            facing = -facing;
+        }
+        // Sloped tops use their real normal so lighting follows the surface
+        let normal = if face == BlockFace::Top { facing.normalize() } else { normal };
+
+        debug_assert!(u32::try_from(out.positions.len()).is_ok());
+        #[allow(clippy::cast_possible_truncation)]
+        let start = out.positions.len() as u32;
+        let uv_bounds = atlas_map.get_face_uvs(fluid, face);
+        let uv_range = atlas_map.uv_range;
+
+        out.positions.extend_from_slice(&corners);
+        out.indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+        for [u, v] in local_uvs {
+            out.normals.push(normal.to_array());
+            out.colors.push([1.0, 1.0, 1.0, 1.0]);
+            out.uvs_b.push([uv_range, 1.0]);
+            out.uvs.push([uv_bounds.min_u + u * uv_range, uv_bounds.min_v + v * uv_range]);
+        }
+    }
+}
@@ -24,6 +24,7 @@ use crate::world::MAX_HEIGHT;
 use bevy::prelude::*;
 use bevy::render::render_asset::RenderAssetUsages;
 use noise::{Fbm, MultiFractal, NoiseFn, Perlin, RidgedMulti, Simplex};
+use std::collections::HashMap;
 
 pub const CHUNK_SIZE: usize = 32;
 pub const MAX_LODS: usize = 6;
@@ -34,11 +35,14 @@ pub const WORLD_HEIGHT_BLOCKS: usize = CHUNK_DIM * CHUNK_LAYERS_Y;
 pub mod streaming;
 pub mod mesh;
 pub mod frustum;
+pub mod overview;
 
 pub mod stats;
 pub use stats::MeshGenerationStats;
 
 pub mod lod;
+pub mod governor;
+pub mod profiler;
 pub use lod::{compute_lod_from_dist, LodStability, PendingLodBuilds};
 
 pub mod debug;
@@ -52,9 +56,13 @@ pub struct ChunkEntity {
     pub chunk_z: i32,
 }
 
+/// Seed of the terrain noise.
+pub const WORLD_SEED: u32 = 12345;
+
 #[derive(Clone)]
 pub struct Chunk {
     pub blocks: Vec<BlockId>,
+    pub states: HashMap<u32, u8>, // Sparse per-block state (fluid flow level, ...), keyed by block index
 }
 
 impl Chunk {
@@ -66,6 +74,7 @@ impl Chunk {
     pub fn new() -> Self {
         Chunk {
             blocks: vec![blocks::AIR; CHUNK_SIZE * MAX_HEIGHT * CHUNK_SIZE],
+            states: HashMap::new(),
         }
     }
     /// Read a block ID at the given local chunk coordinates.
@@ -98,6 +107,37 @@ impl Chunk {
         }
     }
 
+    /// Read the block state at the given local chunk coordinates.
+    ///
+    /// # Return
+    /// * `u8` - the stored state, or `0` when none is stored or out of bounds.
+    #[must_use]
+    pub fn get_state(&self, x: usize, y: usize, z: usize) -> u8 {
+        if x >= CHUNK_SIZE || y >= MAX_HEIGHT || z >= CHUNK_SIZE {
+            return 0;
+        }
+        self.states.get(&Self::state_key(x, y, z)).copied().unwrap_or(0)
+    }
+
+    /// Set the block state at the given local chunk coordinates. A state of
+    /// `0` is the default and is not stored.
+    pub fn set_state(&mut self, x: usize, y: usize, z: usize, state: u8) {
+        if x >= CHUNK_SIZE || y >= MAX_HEIGHT || z >= CHUNK_SIZE {
+            return;
+        }
+        let key = Self::state_key(x, y, z);
+        if state == 0 {
+            self.states.remove(&key);
+        } else {
+            self.states.insert(key, state);
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn state_key(x: usize, y: usize, z: usize) -> u32 {
+        (x + y * CHUNK_SIZE + z * CHUNK_SIZE * MAX_HEIGHT) as u32
+    }
+
     /// Procedurally generate terrain content for this chunk.
     ///
     /// Fills the chunk's internal block buffer using layered noise
@@ -115,7 +155,16 @@ impl Chunk {
     /// - If the compile-time `CHUNK_SIZE` constant cannot be converted to `i32`.
     /// - If a local index (`x`, `y`, or `z`) cannot be converted to `i32`.
     pub fn generate(&mut self, chunk_x: i32, chunk_z: i32, block_registry: &crate::block::BlockRegistry) {
-        let seed: u32 = 12345; 
+        self.generate_with_seed(chunk_x, chunk_z, WORLD_SEED, block_registry);
+    }
+
+    /// Like `generate`, but with the noise seeded by `seed` instead of
+    /// `WORLD_SEED` (used by the `stratum-gen` tool).
+    ///
+    /// # Panics
+    ///
+    /// Same as `generate`.
+    pub fn generate_with_seed(&mut self, chunk_x: i32, chunk_z: i32, seed: u32, block_registry: &crate::block::BlockRegistry) {
 
         // Base terrain noise (fractal brownian motion for smooth hills)
         let base_fbm: Fbm<Perlin> = Fbm::new(seed)
@@ -124,19 +173,19 @@ impl Chunk {
             .set_persistence(0.5);
 
         // Ridged noise for mountains
-        let ridged: RidgedMulti<Perlin> = RidgedMulti::new(seed + 1)
+        let ridged: RidgedMulti<Perlin> = RidgedMulti::new(seed.wrapping_add(1))
             .set_octaves(3)
             .set_frequency(0.008);
 
-        // Biome selector (low frequency)
-        let biome_noise = Simplex::new(seed + 2);
+        // Biome selector (low frequency), also the continentalness of `biome::climate`
+        let biome_noise = Simplex::new(seed.wrapping_add(2));
 
         // 3D noise for caves
-        let cave_noise = Simplex::new(seed + 3);
-        let cave_noise_2 = Simplex::new(seed + 4); // Second layer for spaghetti caves
+        let cave_noise = Simplex::new(seed.wrapping_add(3));
+        let cave_noise_2 = Simplex::new(seed.wrapping_add(4)); // Second layer for spaghetti caves
 
         // Detail noise for surface variation
-        let detail_noise = Perlin::new(seed + 5);
+        let detail_noise = Perlin::new(seed.wrapping_add(5));
 
         // Precompute CHUNK_SIZE as i32 for safe integer arithmetic.
         let chunk_size_i32 = i32::try_from(CHUNK_SIZE).expect("CHUNK_SIZE fits in i32");
@@ -216,7 +265,8 @@ impl Chunk {
     /// (higher value -> more aggressive merging and fewer triangles).
     ///
     /// # Arguments
-    /// * `_block_registry` - currently unused; retained for future use
+    /// * `block_registry` - used to find fluid blocks, which are meshed
+    ///   separately with sloped surfaces (see `mesh_fluids`)
     /// * `atlas_map` - texture atlas UV lookup used to compute face UVs
     /// * `lod` - level-of-detail hint controlling merge size
     ///
@@ -225,7 +275,7 @@ impl Chunk {
     #[must_use]
     pub fn build_mesh(
         &self,
-        _block_registry: &BlockRegistry,
+        block_registry: &BlockRegistry,
         atlas_map: &AtlasUVMap,
         lod: u8,
         chunk_coords: (i32, i32),
@@ -244,9 +294,11 @@ impl Chunk {
 
         let mut out = crate::chunk::mesh::MeshOutput { positions: &mut positions, normals: &mut normals, colors: &mut colors, uvs: &mut uvs, uvs_b: &mut uvs_b, indices: &mut indices };
         let neigh_ref = neighbors.as_ref();
-        self.greedy_mesh_axis(0, &mut out, atlas_map, lod, chunk_coords, neigh_ref);
-        self.greedy_mesh_axis(1, &mut out, atlas_map, lod, chunk_coords, neigh_ref);
-        self.greedy_mesh_axis(2, &mut out, atlas_map, lod, chunk_coords, neigh_ref);
+        let mesh_ctx = crate::chunk::mesh::MeshCtx::new(lod, block_registry);
+        self.greedy_mesh_axis(0, &mut out, atlas_map, &mesh_ctx, chunk_coords, neigh_ref);
+        self.greedy_mesh_axis(1, &mut out, atlas_map, &mesh_ctx, chunk_coords, neigh_ref);
+        self.greedy_mesh_axis(2, &mut out, atlas_map, &mesh_ctx, chunk_coords, neigh_ref);
+        self.mesh_fluids(&mut out, atlas_map, &mesh_ctx, chunk_coords, neigh_ref);
 
         let mut mesh = Mesh::new(
             bevy::render::mesh::PrimitiveTopology::TriangleList,
@@ -0,0 +1,272 @@
+//! Chunk overview images for the minimap and the full-screen map.
+//!
+//! Every chunk gets a cached top-down tile: one colour per column, the
+//! average colour of the top block's top texture in the atlas, shaded by
+//! the height step to the column north of it so the relief stays readable.
+//! Tiles are (re)built when the chunk's blocks change or it is marked for a
+//! remesh, and for loaded chunks near the player that have no tile yet.
+//! Tiles are kept after their chunk unloads, so the map remembers explored
+//! terrain.
+//!
+//! `ChunkOverview::render` stitches tiles into RGBA pixels around a block
+//! position; the map UI (`ui::map`) turns those into images.
+
+use super::{Chunk, CHUNK_SIZE};
+use crate::atlas_builder::{AtlasTextureHandle, AtlasUVMap, BlockFace};
+use crate::block::{blocks, BlockId, BlockRegistry, DirtyChunks};
+use crate::player::Player;
+use crate::world::{BlockChanged, World, MAX_HEIGHT};
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Colour of map pixels with no tile (unexplored terrain).
+pub const UNEXPLORED_COLOUR: [u8; 4] = [16, 16, 20, 255];
+
+/// Chunks around the player that get a tile even before they are remeshed.
+pub const OVERVIEW_RADIUS: i32 = 12;
+
+/// Most tiles built per frame for chunks that have none yet.
+pub const MAX_NEW_TILES_PER_FRAME: usize = 8;
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const CHUNK_SIZE_I32: i32 = CHUNK_SIZE as i32;
+
+/// Colour blended into a chunk's pixels by `ChunkOverview::render`, if any.
+pub type ChunkTint<'a> = &'a dyn Fn((i32, i32)) -> Option<[u8; 3]>;
+
+/// Average top-texture colour (RGBA) of every block, sampled from the atlas.
+#[derive(Resource, Default)]
+pub struct BlockColours {
+    pub colours: HashMap<BlockId, [u8; 4]>,
+}
+
+/// Cached overview tiles by chunk coordinates.
+///
+/// # Fields
+/// * `tiles` - `CHUNK_SIZE * CHUNK_SIZE` colours per chunk, row by row (z, then x)
+/// * `revision` - bumped whenever a tile changes, so maps know to redraw
+#[derive(Resource, Default)]
+pub struct ChunkOverview {
+    pub tiles: HashMap<(i32, i32), Vec<[u8; 4]>>,
+    pub revision: u64,
+}
+
+impl ChunkOverview {
+    /// Colour of the column at world `(x, z)`, if its chunk has a tile.
+    #[must_use]
+    pub fn colour_at(&self, x: i32, z: i32) -> Option<[u8; 4]> {
+        let tile = self.tiles.get(&(x.div_euclid(CHUNK_SIZE_I32), z.div_euclid(CHUNK_SIZE_I32)))?;
+        let (lx, lz) = (x.rem_euclid(CHUNK_SIZE_I32), z.rem_euclid(CHUNK_SIZE_I32));
+        tile.get(usize::try_from(lz * CHUNK_SIZE_I32 + lx).ok()?).copied()
+    }
+
+    /// RGBA pixels of the square of `2 * radius` blocks centred on world
+    /// `(center.x, center.y)` (x to the right, z down). `tint` may blend a
+    /// colour into each chunk (e.g. its biome).
+    #[must_use]
+    pub fn render(&self, center: IVec2, radius: i32, tint: Option<ChunkTint>) -> Vec<u8> {
+        let size = usize::try_from(radius * 2).unwrap_or(0);
+        let mut pixels = Vec::with_capacity(size * size * 4);
+        let mut tints: HashMap<(i32, i32), Option<[u8; 3]>> = HashMap::new();
+        for z in center.y - radius..center.y + radius {
+            for x in center.x - radius..center.x + radius {
+                let mut colour = self.colour_at(x, z).unwrap_or(UNEXPLORED_COLOUR);
+                if let Some(tint) = tint {
+                    let chunk = (x.div_euclid(CHUNK_SIZE_I32), z.div_euclid(CHUNK_SIZE_I32));
+                    if let Some(t) = *tints.entry(chunk).or_insert_with(|| tint(chunk)) {
+                        for i in 0..3 {
+                            colour[i] = blend(colour[i], t[i], 0.45);
+                        }
+                    }
+                }
+                pixels.extend(colour);
+            }
+        }
+        pixels
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn blend(a: u8, b: u8, t: f32) -> u8 {
+    (f32::from(a) * (1.0 - t) + f32::from(b) * t).round() as u8
+}
+
+/// Average colour of a rectangle of an RGBA8 image, weighted by alpha.
+///
+/// # Return
+/// * `Option<[u8; 4]>` - the average, or `None` if the rectangle is fully
+///   transparent or outside the image
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn average_colour(data: &[u8], width: u32, min: UVec2, max: UVec2) -> Option<[u8; 4]> {
+    let (mut sum, mut weight, mut count) = ([0u64; 3], 0u64, 0u64);
+    for y in min.y..max.y {
+        for x in min.x..max.x {
+            let i = usize::try_from((y * width + x) * 4).ok()?;
+            let [r, g, b, a] = *data.get(i..i + 4)?.first_chunk::<4>()?;
+            for (s, c) in sum.iter_mut().zip([r, g, b]) {
+                *s += u64::from(c) * u64::from(a);
+            }
+            weight += u64::from(a);
+            count += 1;
+        }
+    }
+    if weight == 0 {
+        return None;
+    }
+    let alpha = (weight / count) as u8;
+    Some([(sum[0] / weight) as u8, (sum[1] / weight) as u8, (sum[2] / weight) as u8, alpha])
+}
+
+/// Top-down colours of a chunk (see the module docs).
+#[must_use]
+pub fn chunk_tile(chunk: &Chunk, colours: &BlockColours) -> Vec<[u8; 4]> {
+    let top = |x: usize, z: usize| (0..MAX_HEIGHT).rev().find(|&y| chunk.get(x, y, z) != blocks::AIR);
+    let mut tile = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE);
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let Some(y) = top(x, z) else {
+                tile.push(UNEXPLORED_COLOUR);
+                continue;
+            };
+            let [r, g, b, _] = colours.colours.get(&chunk.get(x, y, z)).copied().unwrap_or([255, 0, 255, 255]);
+            // Lighter where the ground rises towards the north, darker where it drops
+            let north = if z > 0 { top(x, z - 1).unwrap_or(y) } else { y };
+            let shade = match y.cmp(&north) {
+                std::cmp::Ordering::Greater => 1.15,
+                std::cmp::Ordering::Less => 0.8,
+                std::cmp::Ordering::Equal => 1.0,
+            };
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let scale = |c: u8| (f32::from(c) * shade).min(255.0) as u8;
+            tile.push([scale(r), scale(g), scale(b), 255]);
+        }
+    }
+    tile
+}
+
+/// Sample the average top-texture colour of every block from the atlas
+/// image once it has loaded, and again whenever the atlas is rebuilt.
+/// Cached tiles are dropped so they are redrawn with the new colours.
+///
+/// # Arguments
+/// * `atlas` - handle of the atlas image
+/// * `uv_map` - per-block atlas UVs
+/// * `images` - image assets
+/// * `registry` - block registry
+/// * `colours` - block colours to fill
+/// * `overview` - tile cache
+#[allow(clippy::needless_pass_by_value, clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn update_block_colours(
+    atlas: Option<Res<AtlasTextureHandle>>,
+    uv_map: Option<Res<AtlasUVMap>>,
+    images: Res<Assets<Image>>,
+    registry: Res<BlockRegistry>,
+    mut colours: ResMut<BlockColours>,
+    mut overview: ResMut<ChunkOverview>,
+) {
+    let (Some(atlas), Some(uv_map)) = (atlas, uv_map) else { return };
+    if !colours.colours.is_empty() && !uv_map.is_changed() && !registry.is_changed() {
+        return;
+    }
+    let Some(image) = images.get(&atlas.0) else { return };
+    let (width, height) = (image.width(), image.height());
+    if image.data.len() != (width * height * 4) as usize {
+        return; // Only RGBA8 atlases are sampled
+    }
+
+    let size = Vec2::new(width as f32, height as f32);
+    colours.colours = registry
+        .blocks_by_id
+        .keys()
+        .filter_map(|&id| {
+            let uv = uv_map.get_face_uvs(id, BlockFace::Top);
+            let min = (Vec2::new(uv.min_u, uv.min_v) * size).as_uvec2();
+            let max = (Vec2::new(uv.max_u, uv.max_v) * size).as_uvec2().min(UVec2::new(width, height));
+            average_colour(&image.data, width, min, max).map(|c| (id, c))
+        })
+        .collect();
+    overview.tiles.clear();
+    overview.revision += 1;
+}
+
+/// Rebuild the tiles of chunks that changed or are about to be remeshed, and
+/// build missing tiles near the player. Runs before `rebuild_dirty_chunks`
+/// so it sees the chunks marked dirty this frame.
+///
+/// # Arguments
+/// * `world` - loaded chunks
+/// * `dirty` - chunks waiting for a remesh
+/// * `changed` - block change events
+/// * `colours` - block colours
+/// * `overview` - tile cache
+/// * `player` - player position
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_chunk_overview(
+    world: Res<World>,
+    dirty: Res<DirtyChunks>,
+    mut changed: EventReader<BlockChanged>,
+    colours: Res<BlockColours>,
+    mut overview: ResMut<ChunkOverview>,
+    player: Query<&Transform, With<Player>>,
+) {
+    if colours.colours.is_empty() {
+        changed.clear();
+        return;
+    }
+    let mut stale: HashSet<(i32, i32)> = dirty.0.clone();
+    stale.extend(changed.read().map(|BlockChanged(p)| (p.x.div_euclid(CHUNK_SIZE_I32), p.z.div_euclid(CHUNK_SIZE_I32))));
+
+    if let Ok(tf) = player.get_single() {
+        let pos = tf.translation.floor().as_ivec3();
+        let (pcx, pcz) = (pos.x.div_euclid(CHUNK_SIZE_I32), pos.z.div_euclid(CHUNK_SIZE_I32));
+        let mut missing: Vec<(i32, i32)> = world
+            .chunks
+            .keys()
+            .filter(|(cx, cz)| (cx - pcx).abs().max((cz - pcz).abs()) <= OVERVIEW_RADIUS)
+            .filter(|c| !overview.tiles.contains_key(c))
+            .copied()
+            .collect();
+        missing.sort_by_key(|(cx, cz)| (cx - pcx).abs() + (cz - pcz).abs());
+        stale.extend(missing.into_iter().take(MAX_NEW_TILES_PER_FRAME));
+    }
+
+    for coords in stale {
+        if let Some(chunk) = world.chunks.get(&coords) {
+            overview.tiles.insert(coords, chunk_tile(chunk, &colours));
+            overview.revision += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_use_the_top_block_colour() {
+        // 2x1 RGBA image: opaque red and fully transparent green
+        let data = [255, 0, 0, 255, 0, 255, 0, 0];
+        assert_eq!(average_colour(&data, 2, UVec2::ZERO, UVec2::new(2, 1)), Some([255, 0, 0, 127]));
+        assert_eq!(average_colour(&data, 2, UVec2::new(1, 0), UVec2::new(2, 1)), None);
+
+        let mut colours = BlockColours::default();
+        colours.colours.insert(1, [100, 150, 50, 255]);
+        colours.colours.insert(2, [10, 10, 200, 255]);
+        let mut chunk = Chunk::new();
+        chunk.set(0, 3, 0, 1);
+        chunk.set(0, 5, 0, 2);
+        chunk.set(1, 3, 0, 1);
+
+        let tile = chunk_tile(&chunk, &colours);
+        assert_eq!(tile[0], [10, 10, 200, 255]);
+        assert_eq!(tile[1], [100, 150, 50, 255]);
+        assert_eq!(tile[2], UNEXPLORED_COLOUR);
+
+        let mut overview = ChunkOverview::default();
+        overview.tiles.insert((0, 0), tile);
+        assert_eq!(overview.colour_at(1, 0), Some([100, 150, 50, 255]));
+        assert_eq!(overview.colour_at(-1, 0), None);
+        assert_eq!(overview.render(IVec2::new(1, 1), 1, None)[..4], [10, 10, 200, 255]);
+    }
+}
@@ -0,0 +1,578 @@
+//! Profiling of the chunk pipeline.
+//!
+//! `stream_chunks` records one `StageSample` per finished piece of work:
+//!
+//! * `generate` - terrain generation of a chunk on the compute pool
+//! * `mesh` - first mesh build of a freshly generated chunk
+//! * `upload` - applying a finished mesh on the main thread (asset upload,
+//!   entity spawn or handle swap)
+//! * `lod_rebuild` - LOD and prewarm mesh builds of loaded chunks
+//!
+//! Each sample holds the work's own duration and its latency (from being
+//! queued until it finished, so time spent waiting for a worker or for the
+//! per-frame budget counts). Queue depths are sampled every frame. Both go
+//! into fixed-size ring buffers in `ChunkProfiler`, so profiling can stay on
+//! all the time.
+//!
+//! `ChunkProfiler::export` writes the buffers to `profiles/` as JSON (with a
+//! per-stage percentile summary and run metadata such as the git commit that
+//! `build.rs` recorded at build time, so runs on different commits can be
+//! compared with `compare_profiles`) or in the Chrome trace event format for
+//! `chrome://tracing` / Perfetto.
+
+use crate::input::ActionState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Instant;
+
+/// Directory profiles are exported to.
+pub const PROFILE_DIR: &str = "profiles";
+
+/// Stage samples kept by default.
+pub const DEFAULT_SAMPLE_CAPACITY: usize = 8192;
+
+/// Queue depth samples (one per frame) kept by default.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 4096;
+
+/// A stage of the chunk pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Generate,
+    Mesh,
+    Upload,
+    LodRebuild,
+}
+
+impl Stage {
+    /// Every stage, in pipeline order.
+    pub const ALL: [Self; 4] = [Self::Generate, Self::Mesh, Self::Upload, Self::LodRebuild];
+
+    /// Name used in exports and the console.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Generate => "generate",
+            Self::Mesh => "mesh",
+            Self::Upload => "upload",
+            Self::LodRebuild => "lod_rebuild",
+        }
+    }
+}
+
+/// When a task was queued, started and finished. Measured by the task itself
+/// (see `TaskTiming::measure`) and carried back with its result.
+#[derive(Debug, Clone, Copy)]
+pub struct TaskTiming {
+    pub queued: Instant,
+    pub started: Instant,
+    pub finished: Instant,
+    /// Profiler index of the thread that ran the task (see `thread_index`)
+    pub thread: u32,
+}
+
+impl TaskTiming {
+    /// Run `work`, timing it against the moment it was `queued`.
+    pub fn measure<T>(queued: Instant, work: impl FnOnce() -> T) -> (T, Self) {
+        let started = Instant::now();
+        let result = work();
+        (result, Self { queued, started, finished: Instant::now(), thread: thread_index() })
+    }
+}
+
+// Names of the threads seen by the profiler, by `thread_index`
+static THREAD_NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static NEXT_THREAD: AtomicU32 = AtomicU32::new(0);
+
+thread_local! {
+    static THREAD_INDEX: u32 = {
+        let index = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+        let name = std::thread::current().name().map_or_else(|| format!("thread {index}"), str::to_string);
+        let mut names = THREAD_NAMES.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let slot = index as usize;
+        if names.len() <= slot {
+            names.resize(slot + 1, String::new());
+        }
+        names[slot] = name;
+        index
+    };
+}
+
+/// A small stable index for the current thread, used as the trace thread id.
+#[must_use]
+pub fn thread_index() -> u32 {
+    THREAD_INDEX.with(|i| *i)
+}
+
+/// One finished piece of pipeline work. Times are in microseconds; `start_us`
+/// counts from the profiler's creation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageSample {
+    pub stage: Stage,
+    pub chunk: (i32, i32),
+    pub lod: u8,
+    pub start_us: u64,
+    pub duration_us: u64,
+    pub latency_us: u64,
+    pub thread: u32,
+}
+
+/// Pipeline queue depths at the end of a frame.
+///
+/// # Fields
+/// * `generation` - generation tasks in flight
+/// * `generated` - generated chunks waiting for a mesh build
+/// * `mesh` - mesh builds in flight (or finished but not applied)
+/// * `lod` - LOD builds in flight
+/// * `pending_spawn` - chunks with meshes waiting for their entity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueDepths {
+    pub t_us: u64,
+    pub generation: usize,
+    pub generated: usize,
+    pub mesh: usize,
+    pub lod: usize,
+    pub pending_spawn: usize,
+}
+
+impl QueueDepths {
+    /// Each queue's name and depth.
+    pub const QUEUES: [(&'static str, QueueDepth); 5] = [
+        ("generation", |q| q.generation),
+        ("generated", |q| q.generated),
+        ("mesh", |q| q.mesh),
+        ("lod", |q| q.lod),
+        ("pending_spawn", |q| q.pending_spawn),
+    ];
+}
+
+/// Reads one queue's depth from `QueueDepths`.
+pub type QueueDepth = fn(&QueueDepths) -> usize;
+
+/// Duration and latency percentiles of one stage (microseconds).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageStats {
+    pub count: usize,
+    pub mean_us: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+    pub latency_p50_us: u64,
+    pub latency_p90_us: u64,
+    pub latency_p99_us: u64,
+}
+
+/// Mean and maximum depth of each queue.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub mean: BTreeMap<String, f32>,
+    pub max: BTreeMap<String, usize>,
+}
+
+/// Where and how a profile was recorded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileMeta {
+    pub version: String,
+    pub commit: String,
+    pub build: String,
+    pub created: String,
+    pub seconds: f32,
+    pub note: String,
+}
+
+/// A JSON profile export.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileExport {
+    pub meta: ProfileMeta,
+    pub stages: BTreeMap<Stage, StageStats>,
+    pub queues: QueueStats,
+    pub samples: Vec<StageSample>,
+    pub queue_samples: Vec<QueueDepths>,
+}
+
+/// Export format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// Samples, summary and metadata (`ProfileExport`)
+    Json,
+    /// Chrome trace event format
+    ChromeTrace,
+}
+
+/// Ring buffers of pipeline samples.
+///
+/// # Fields
+/// * `enabled` - whether samples are recorded
+/// * `note` - free text stored in exports (e.g. the preset)
+#[derive(Resource, Debug)]
+pub struct ChunkProfiler {
+    pub enabled: bool,
+    pub note: String,
+    epoch: Instant,
+    samples: VecDeque<StageSample>,
+    queues: VecDeque<QueueDepths>,
+    sample_capacity: usize,
+    queue_capacity: usize,
+}
+
+impl Default for ChunkProfiler {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_SAMPLE_CAPACITY, DEFAULT_QUEUE_CAPACITY)
+    }
+}
+
+/// Nearest-rank percentile of sorted values.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn micros(from: Instant, to: Instant) -> u64 {
+    u64::try_from(to.saturating_duration_since(from).as_micros()).unwrap_or(u64::MAX)
+}
+
+impl ChunkProfiler {
+    /// A profiler keeping up to `samples` stage samples and `queues` queue
+    /// depth samples.
+    #[must_use]
+    pub fn with_capacity(samples: usize, queues: usize) -> Self {
+        Self {
+            enabled: true,
+            note: String::new(),
+            epoch: Instant::now(),
+            samples: VecDeque::with_capacity(samples),
+            queues: VecDeque::with_capacity(queues),
+            sample_capacity: samples.max(1),
+            queue_capacity: queues.max(1),
+        }
+    }
+
+    /// Record a task that ran on the compute pool (or inline, without
+    /// background meshing).
+    pub fn record_task(&mut self, stage: Stage, chunk: (i32, i32), lod: u8, timing: &TaskTiming) {
+        self.push(StageSample {
+            stage,
+            chunk,
+            lod,
+            start_us: micros(self.epoch, timing.started),
+            duration_us: micros(timing.started, timing.finished),
+            latency_us: micros(timing.queued, timing.finished),
+            thread: timing.thread,
+        });
+    }
+
+    /// Record main-thread work that started at `started` and ends now, for a
+    /// result that became ready at `ready`.
+    pub fn record_main(&mut self, stage: Stage, chunk: (i32, i32), lod: u8, ready: Instant, started: Instant) {
+        let now = Instant::now();
+        self.push(StageSample {
+            stage,
+            chunk,
+            lod,
+            start_us: micros(self.epoch, started),
+            duration_us: micros(started, now),
+            latency_us: micros(ready, now),
+            thread: thread_index(),
+        });
+    }
+
+    fn push(&mut self, sample: StageSample) {
+        if !self.enabled {
+            return;
+        }
+        if self.samples.len() == self.sample_capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Record the queue depths at the end of a frame.
+    pub fn record_queues(&mut self, mut depths: QueueDepths) {
+        if !self.enabled {
+            return;
+        }
+        depths.t_us = micros(self.epoch, Instant::now());
+        if self.queues.len() == self.queue_capacity {
+            self.queues.pop_front();
+        }
+        self.queues.push_back(depths);
+    }
+
+    /// Forget every sample.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.queues.clear();
+    }
+
+    /// Recorded stage samples, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = &StageSample> {
+        self.samples.iter()
+    }
+
+    /// Percentiles of every stage with samples.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn stage_stats(&self) -> BTreeMap<Stage, StageStats> {
+        let mut out = BTreeMap::new();
+        for stage in Stage::ALL {
+            let mut durations: Vec<u64> = self.samples.iter().filter(|s| s.stage == stage).map(|s| s.duration_us).collect();
+            if durations.is_empty() {
+                continue;
+            }
+            let mut latencies: Vec<u64> = self.samples.iter().filter(|s| s.stage == stage).map(|s| s.latency_us).collect();
+            durations.sort_unstable();
+            latencies.sort_unstable();
+            let total: u128 = durations.iter().map(|&d| u128::from(d)).sum();
+            out.insert(
+                stage,
+                StageStats {
+                    count: durations.len(),
+                    mean_us: (total / durations.len() as u128) as u64,
+                    p50_us: percentile(&durations, 50.0),
+                    p90_us: percentile(&durations, 90.0),
+                    p99_us: percentile(&durations, 99.0),
+                    max_us: durations[durations.len() - 1],
+                    latency_p50_us: percentile(&latencies, 50.0),
+                    latency_p90_us: percentile(&latencies, 90.0),
+                    latency_p99_us: percentile(&latencies, 99.0),
+                },
+            );
+        }
+        out
+    }
+
+    /// Mean and maximum of each queue's depth.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn queue_stats(&self) -> QueueStats {
+        let mut stats = QueueStats::default();
+        for (name, depth) in QueueDepths::QUEUES {
+            let total: usize = self.queues.iter().map(depth).sum();
+            let mean = if self.queues.is_empty() { 0.0 } else { total as f32 / self.queues.len() as f32 };
+            stats.mean.insert(name.to_string(), mean);
+            stats.max.insert(name.to_string(), self.queues.iter().map(depth).max().unwrap_or(0));
+        }
+        stats
+    }
+
+    /// One line per stage with its percentiles, for the console.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn summary_text(&self) -> String {
+        let stats = self.stage_stats();
+        if stats.is_empty() {
+            return "No chunk pipeline samples recorded".to_string();
+        }
+        let ms = |us: u64| us as f64 / 1000.0;
+        stats
+            .iter()
+            .map(|(stage, s)| {
+                format!(
+                    "{:<12} n={:<5} p50 {:.2} ms  p99 {:.2} ms  max {:.2} ms  latency p50 {:.1} ms  p99 {:.1} ms",
+                    stage.name(),
+                    s.count,
+                    ms(s.p50_us),
+                    ms(s.p99_us),
+                    ms(s.max_us),
+                    ms(s.latency_p50_us),
+                    ms(s.latency_p99_us)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn meta(&self) -> ProfileMeta {
+        let times = self.samples.iter().map(|s| s.start_us).chain(self.queues.iter().map(|q| q.t_us));
+        let (first, last) = times.fold((u64::MAX, 0), |(lo, hi), t| (lo.min(t), hi.max(t)));
+        ProfileMeta {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: option_env!("STRATUM_GIT_COMMIT").unwrap_or("unknown").to_string(),
+            build: if cfg!(debug_assertions) { "debug" } else { "release" }.to_string(),
+            created: chrono::Utc::now().to_rfc3339(),
+            seconds: last.saturating_sub(first) as f32 / 1_000_000.0,
+            note: self.note.clone(),
+        }
+    }
+
+    /// The recorded samples with their summary and metadata.
+    #[must_use]
+    pub fn to_export(&self) -> ProfileExport {
+        ProfileExport {
+            meta: self.meta(),
+            stages: self.stage_stats(),
+            queues: self.queue_stats(),
+            samples: self.samples.iter().cloned().collect(),
+            queue_samples: self.queues.iter().copied().collect(),
+        }
+    }
+
+    /// The recorded samples in the Chrome trace event format: a complete
+    /// event per stage sample on its thread's track, and a counter track of
+    /// queue depths.
+    #[must_use]
+    pub fn to_chrome_trace(&self) -> serde_json::Value {
+        use serde_json::json;
+        let mut events = Vec::with_capacity(self.samples.len() + self.queues.len() + 8);
+        let names = THREAD_NAMES.lock().unwrap_or_else(std::sync::PoisonError::into_inner).clone();
+        for (tid, name) in names.iter().enumerate().filter(|(_, n)| !n.is_empty()) {
+            events.push(json!({ "name": "thread_name", "ph": "M", "pid": 1, "tid": tid, "args": { "name": name } }));
+        }
+        for s in &self.samples {
+            events.push(json!({
+                "name": s.stage.name(),
+                "cat": "chunk",
+                "ph": "X",
+                "ts": s.start_us,
+                "dur": s.duration_us,
+                "pid": 1,
+                "tid": s.thread,
+                "args": { "chunk": [s.chunk.0, s.chunk.1], "lod": s.lod, "latency_us": s.latency_us },
+            }));
+        }
+        for q in &self.queues {
+            events.push(json!({
+                "name": "queues",
+                "ph": "C",
+                "ts": q.t_us,
+                "pid": 1,
+                "args": {
+                    "generation": q.generation,
+                    "generated": q.generated,
+                    "mesh": q.mesh,
+                    "lod": q.lod,
+                    "pending_spawn": q.pending_spawn,
+                },
+            }));
+        }
+        json!({ "traceEvents": events, "displayTimeUnit": "ms", "otherData": self.meta() })
+    }
+
+    /// Write the samples to `dir` and return the file's path. Files are
+    /// named after the local time in milliseconds, with a counter added if
+    /// that name is taken, so quick exports never overwrite each other.
+    ///
+    /// # Errors
+    /// Returns an error if the directory or file cannot be written.
+    pub fn export(&self, dir: &str, format: ProfileFormat) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {dir}: {e}"))?;
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+        let (extension, text) = match format {
+            ProfileFormat::Json => ("json", serde_json::to_string_pretty(&self.to_export())),
+            ProfileFormat::ChromeTrace => ("trace.json", serde_json::to_string(&self.to_chrome_trace())),
+        };
+        let path = (0..)
+            .map(|n| match n {
+                0 => PathBuf::from(dir).join(format!("chunks-{stamp}.{extension}")),
+                n => PathBuf::from(dir).join(format!("chunks-{stamp}-{n}.{extension}")),
+            })
+            .find(|p| !p.exists())
+            .expect("a free file name");
+        let text = text.map_err(|e| format!("cannot encode the profile: {e}"))?;
+        std::fs::write(&path, text).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Export the profile as JSON and as a Chrome trace when the
+/// `export_profile` action (default F5) is pressed.
+///
+/// # Arguments
+/// * `actions` - action state
+/// * `profiler` - the recorded samples
+#[allow(clippy::needless_pass_by_value)]
+pub fn export_profile_on_key(actions: Res<ActionState>, profiler: Res<ChunkProfiler>) {
+    if !actions.just_pressed("export_profile") {
+        return;
+    }
+    for format in [ProfileFormat::Json, ProfileFormat::ChromeTrace] {
+        match profiler.export(PROFILE_DIR, format) {
+            Ok(path) => info!("Chunk profile written to {}", path.display()),
+            Err(e) => warn!("Chunk profile export failed: {e}"),
+        }
+    }
+}
+
+/// Load a JSON profile written by `ChunkProfiler::export`.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is not a JSON profile.
+pub fn load_profile(path: &str) -> Result<ProfileExport, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {path}: {e}"))?;
+    serde_json::from_str(&text).map_err(|e| format!("{path} is not a chunk profile: {e}"))
+}
+
+/// Per-stage comparison of two profiles: p50/p99 duration and p99 latency of
+/// `base` and `new`, with the change in percent.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn compare_profiles(base: &ProfileExport, new: &ProfileExport) -> String {
+    let ms = |us: u64| us as f64 / 1000.0;
+    let change = |a: u64, b: u64| if a == 0 { 0.0 } else { (b as f64 - a as f64) / a as f64 * 100.0 };
+    let mut lines = vec![format!("{} ({}) -> {} ({})", base.meta.commit, base.meta.build, new.meta.commit, new.meta.build)];
+    for stage in Stage::ALL {
+        let (Some(a), Some(b)) = (base.stages.get(&stage), new.stages.get(&stage)) else { continue };
+        lines.push(format!(
+            "{:<12} p50 {:.2} -> {:.2} ms ({:+.0}%)  p99 {:.2} -> {:.2} ms ({:+.0}%)  latency p99 {:.1} -> {:.1} ms ({:+.0}%)",
+            stage.name(),
+            ms(a.p50_us),
+            ms(b.p50_us),
+            change(a.p50_us, b.p50_us),
+            ms(a.p99_us),
+            ms(b.p99_us),
+            change(a.p99_us, b.p99_us),
+            ms(a.latency_p99_us),
+            ms(b.latency_p99_us),
+            change(a.latency_p99_us, b.latency_p99_us)
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn ring_buffer_keeps_latest_and_exports_round_trip() {
+        let mut profiler = ChunkProfiler::with_capacity(4, 2);
+        let queued = Instant::now();
+        for i in 0..10u64 {
+            let started = queued + Duration::from_micros(100 * i);
+            let timing = TaskTiming { queued, started, finished: started + Duration::from_micros(10 * (i + 1)), thread: 0 };
+            profiler.record_task(Stage::Mesh, (0, 0), 0, &timing);
+        }
+        for depth in 0..3 {
+            profiler.record_queues(QueueDepths { mesh: depth, ..QueueDepths::default() });
+        }
+
+        // Only the last four samples remain: durations 70..=100 us
+        let stats = profiler.stage_stats();
+        let mesh = &stats[&Stage::Mesh];
+        assert_eq!((mesh.count, mesh.p50_us, mesh.max_us), (4, 80, 100));
+        assert_eq!(mesh.latency_p99_us, 1000);
+        assert_eq!(profiler.queue_stats().max["mesh"], 2);
+
+        let export = profiler.to_export();
+        let text = serde_json::to_string(&export).expect("encodes");
+        let back: ProfileExport = serde_json::from_str(&text).expect("decodes");
+        assert_eq!(back, export);
+        assert!(compare_profiles(&export, &back).contains("mesh"));
+
+        let trace = profiler.to_chrome_trace();
+        let events = trace["traceEvents"].as_array().expect("event list");
+        assert_eq!(events.iter().filter(|e| e["ph"] == "X").count(), 4);
+        assert_eq!(events.iter().filter(|e| e["ph"] == "C").count(), 2);
+    }
+}
@@ -21,14 +21,14 @@ const CHUNK_SIZE_I32: i32 = CHUNK_SIZE as i32;
 // Make complex map value easier to read via a type alias
 type ChunkEntry = (Entity, Vec<Option<Handle<Mesh>>>, u8);
 
-use crate::chunk::lod::{
-    LOD_BUILD_BUDGET_PER_FRAME, PREWARM_DISTANCE_MARGIN, PREWARM_LEVELS, LodBuildResult, MAX_PENDING_GENERATION_TASKS, MAX_PENDING_LOD_TASKS,
-};
+use crate::chunk::lod::{LodBuildResult, StreamingBudget};
+use crate::chunk::profiler::{ChunkProfiler, QueueDepths, Stage, TaskTiming};
 use crate::chunk::MeshGenerationStats;
 use crate::chunk::{LodStability, PendingLodBuilds};
 use std::collections::HashSet as StdHashSet;
 use bevy::tasks::Task as BevyTask;
 use std::collections::HashMap as StdHashMap;
+use std::time::Instant;
 
 /// Result produced by a completed mesh build task for a freshly generated chunk.
 pub struct MeshBuildResult {
@@ -38,6 +38,7 @@ pub struct MeshBuildResult {
     pub mesh: Mesh,
     pub triangle_count: usize,
     pub lod: u8,
+    pub timing: TaskTiming,
 }
 
 /// Pending mesh build tasks scheduled on the compute pool.
@@ -56,11 +57,18 @@ pub struct PendingMeshHandles {
     pub map: StdHashMap<(i32, i32), Vec<Option<Handle<Mesh>>>>,
 }
 
-// How many mesh builds to start per frame to avoid sustained queue growth.
-const MESH_SCHEDULE_BUDGET_PER_FRAME: usize = 8;
-// How many finished meshes to apply per frame to avoid main-thread stalls.
-const MESH_APPLY_BUDGET_PER_FRAME: usize = 2;
-
+/// Start a mesh build: on the compute pool with background meshing, or
+/// right away on the calling (main) thread without it, in which case the
+/// returned task only hands over the finished result.
+fn spawn_build<T: Send + 'static>(background: bool, build: impl FnOnce() -> T + Send + 'static) -> BevyTask<T> {
+    let pool = AsyncComputeTaskPool::get();
+    if background {
+        pool.spawn(async move { build() })
+    } else {
+        let result = build();
+        pool.spawn(async move { result })
+    }
+}
 
 /// Lightweight diagnostics for streaming to allow periodic logging without
 /// allocating or spamming logs every frame.
@@ -104,8 +112,6 @@ impl Default for ChunkStreamingConfig {
     }
 }
 
-use crate::chunk::compute_lod_from_dist;
-
 #[derive(Resource)]
 pub struct StartupTimer {
     pub elapsed: f32,
@@ -133,13 +139,15 @@ pub struct StreamChunksCtx<'w, 's> {
     pub material_handle: Option<Res<'w, VoxelMaterialHandle>>,
     pub mesh_diag: ResMut<'w, MeshStreamingDiagnostics>,
     pub pending_handles: ResMut<'w, PendingMeshHandles>,
+    pub budget: Res<'w, StreamingBudget>,
+    pub profiler: ResMut<'w, ChunkProfiler>,
 }
 
 /// Represents an in-flight chunk generation task scheduled on the compute
 /// pool.
 pub struct ChunkTask {
     pub coords: (i32, i32), // the x and z chunks that are being generated
-    pub task: Task<(i32, i32, Chunk)>, // the background task producing the chunk
+    pub task: Task<(i32, i32, Chunk, TaskTiming)>, // the background task producing the chunk and its timing
 } 
 
 /// A generated chunk that is ready for mesh building.
@@ -189,6 +197,8 @@ pub struct VoxelMaterialHandle(pub Handle<ExtendedMaterial<StandardMaterial, Vox
 /// * `pending_lod` - pending LOD build tasks resource used to schedule/detail builds
 /// * `lod_stability` - hysteresis tracking to prevent LOD thrash
 /// * `material_handle` - optional shared voxel material used to spawn entities
+/// * `budget` - per-frame and in-flight limits from the performance settings
+/// * `profiler` - records stage timings and queue depths (see `chunk::profiler`)
 #[allow(clippy::implicit_hasher, clippy::needless_pass_by_value)]
 pub fn stream_chunks(mut ctx: StreamChunksCtx<'_, '_>) {
     crate::debug::record_thread_global("stream_chunks_system");
@@ -250,11 +260,22 @@ pub fn stream_chunks(mut ctx: StreamChunksCtx<'_, '_>) {
         let completed_gen = ctx.pending.completed.len();
         let loaded = ctx.loaded_chunks.len();
         let spawned = ctx.chunk_entities.map.len();
-        info!("StreamingDiag: pending_mesh_tasks={} coords={} pending_gen_tasks={} completed_gen={} loaded={} spawned={}",
-            pending_mesh_tasks, pending_mesh_coords, pending_gen_tasks, completed_gen, loaded, spawned);
+        info!("StreamingDiag: pending_mesh_tasks={} coords={} pending_gen_tasks={} completed_gen={} loaded={} spawned={} budget gen={} mesh={} lod={}",
+            pending_mesh_tasks, pending_mesh_coords, pending_gen_tasks, completed_gen, loaded, spawned,
+            ctx.budget.generation_tasks, ctx.budget.mesh_builds_per_frame, ctx.budget.lod_builds_per_frame);
     }
 
     unload_and_cleanup(&mut ctx, player_chunk_x, player_chunk_z);
+
+    let depths = QueueDepths {
+        generation: ctx.pending.tasks.len(),
+        generated: ctx.pending.completed.len(),
+        mesh: ctx.pending_mesh.tasks.len(),
+        lod: ctx.pending_lod.tasks.len(),
+        pending_spawn: ctx.pending_handles.map.len(),
+        ..QueueDepths::default()
+    };
+    ctx.profiler.record_queues(depths);
 }
 
 fn queue_generation(ctx: &mut StreamChunksCtx<'_, '_>, p_x: i32, p_z: i32, load_dist: i32, pool: &bevy::tasks::AsyncComputeTaskPool) {
@@ -270,17 +291,21 @@ fn queue_generation(ctx: &mut StreamChunksCtx<'_, '_>, p_x: i32, p_z: i32, load_
 
     for (cx, cz, _d) in coords {
         // Cap concurrent generation tasks to avoid unbounded queuing
-        if ctx.pending.tasks.len() >= MAX_PENDING_GENERATION_TASKS { break; }
+        if ctx.pending.tasks.len() >= ctx.budget.generation_tasks { break; }
         if ctx.loaded_chunks.contains(&(cx, cz)) { continue; }
         if ctx.pending.tasks.iter().any(|t| t.coords == (cx, cz)) { continue; }
 
         let cloned_registry = (*ctx.block_registry).clone();
+        let queued = Instant::now();
         let task = pool.spawn(async move {
             // Record worker-thread execution for the chunk generation task
             crate::debug::record_thread_global("chunk_generation_task");
-            let mut chunk = Chunk::new();
-            chunk.generate(cx, cz, &cloned_registry);
-            (cx, cz, chunk)
+            let (chunk, timing) = TaskTiming::measure(queued, || {
+                let mut chunk = Chunk::new();
+                chunk.generate(cx, cz, &cloned_registry);
+                chunk
+            });
+            (cx, cz, chunk, timing)
         });
 
         ctx.pending.tasks.push(ChunkTask { coords: (cx, cz), task });
@@ -291,9 +316,10 @@ fn collect_completed_generation(ctx: &mut StreamChunksCtx<'_, '_>) -> Vec<Genera
     let mut newly_completed = Vec::new();
     ctx.pending.tasks.retain_mut(|gen_task| {
         if gen_task.task.is_finished() {
-            if let Ok((cx, cz, chunk)) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            if let Ok((cx, cz, chunk, timing)) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 futures::executor::block_on(&mut gen_task.task)
             })) {
+                ctx.profiler.record_task(Stage::Generate, (cx, cz), 0, &timing);
                 newly_completed.push(GeneratedChunk { coords: (cx, cz), chunk });
             }
             false
@@ -329,7 +355,6 @@ fn build_and_apply_meshes(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i32
         let dist = (player_chunk_x - cx).abs().max((player_chunk_z - cz).abs());
         dist
     });
-    let pool = AsyncComputeTaskPool::get();
     let mut scheduled_this_frame = 0usize;
     for generated in gen_list {
         let (cx, cz) = generated.coords;
@@ -337,7 +362,7 @@ fn build_and_apply_meshes(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i32
         if ctx.pending_mesh.coords.contains(&(cx, cz)) { continue; }
 
         let dist = (player_chunk_x - cx).abs().max((player_chunk_z - cz).abs());
-        let lod = compute_lod_from_dist(dist);
+        let lod = ctx.budget.lod_for_distance(dist);
 
         
         // Build neighbor snapshot for this generated chunk
@@ -351,7 +376,7 @@ fn build_and_apply_meshes(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i32
 
         // Cap pending mesh builds to avoid overwhelming the compute pool
         // and limit how many we start this frame.
-        if ctx.pending_mesh.tasks.len() >= MAX_PENDING_LOD_TASKS || scheduled_this_frame >= MESH_SCHEDULE_BUDGET_PER_FRAME {
+        if ctx.pending_mesh.tasks.len() >= ctx.budget.pending_lod_tasks || scheduled_this_frame >= ctx.budget.mesh_builds_per_frame {
             // requeue this generated chunk for later
             ctx.pending.completed.push(GeneratedChunk { coords: (cx, cz), chunk: generated.chunk });
             continue;
@@ -362,10 +387,13 @@ fn build_and_apply_meshes(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i32
         let registry_clone = block_registry_clone.clone();
         let neigh_clone = if neigh.is_empty() { None } else { Some(neigh) };
 
-        let task = pool.spawn(async move {
+        let queued = Instant::now();
+        let task = spawn_build(ctx.budget.background_meshing, move || {
             crate::debug::record_thread_global("mesh_build_task");
-            let (mesh, tri_count) = chunk_clone.build_mesh(&registry_clone, &atlas_clone, lod, (cx, cz), neigh_clone);
-            MeshBuildResult { chunk_x: cx, chunk_z: cz, chunk: chunk_clone, mesh, triangle_count: tri_count, lod }
+            let ((mesh, tri_count), timing) = TaskTiming::measure(queued, || {
+                chunk_clone.build_mesh(&registry_clone, &atlas_clone, lod, (cx, cz), neigh_clone)
+            });
+            MeshBuildResult { chunk_x: cx, chunk_z: cz, chunk: chunk_clone, mesh, triangle_count: tri_count, lod, timing }
         });
 
         ctx.pending_mesh.coords.insert((cx, cz));
@@ -378,7 +406,7 @@ fn update_lods_and_schedule(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i
     let mut builds_scheduled = 0usize;
     for &(cx, cz) in &ctx.loaded_chunks {
         let dist = (player_chunk_x - cx).abs().max((player_chunk_z - cz).abs());
-        let candidate_lod = compute_lod_from_dist(dist);
+        let candidate_lod = ctx.budget.lod_for_distance(dist);
 
         let entry = ctx.lod_stability.map.entry((cx, cz)).or_insert((candidate_lod, 0.0));
         if entry.0 == candidate_lod { entry.1 += ctx.time.delta_seconds(); } else { entry.0 = candidate_lod; entry.1 = 0.0; }
@@ -398,7 +426,8 @@ fn update_lods_and_schedule(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i
 
                 let coord = (cx, cz, candidate_lod);
                 if !ctx.pending_lod.coords.contains(&coord)
-                    && builds_scheduled < LOD_BUILD_BUDGET_PER_FRAME
+                    && builds_scheduled < ctx.budget.lod_builds_per_frame
+                    && ctx.pending_lod.tasks.len() < ctx.budget.pending_lod_tasks
                     && let Some(chunk) = ctx.world.chunks.get(&(cx, cz)) {
                         let chunk_clone = chunk.clone();
                         let atlas_clone = atlas_map.clone();
@@ -413,12 +442,14 @@ fn update_lods_and_schedule(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i
                             }
                         }
 
-                        let pool = AsyncComputeTaskPool::get();
-                        let task = pool.spawn(async move {
+                        let queued = Instant::now();
+                        let task = spawn_build(ctx.budget.background_meshing, move || {
                             // Record worker-thread execution for LOD build
                             crate::debug::record_thread_global("lod_build_task");
-                            let (mesh, tri_count) = chunk_clone.build_mesh(&registry_clone, &atlas_clone, candidate_lod, (cx, cz), if neigh.is_empty() { None } else { Some(neigh) });
-                            LodBuildResult { chunk_x: cx, chunk_z: cz, lod: candidate_lod, mesh, triangle_count: tri_count }
+                            let ((mesh, tri_count), timing) = TaskTiming::measure(queued, || {
+                                chunk_clone.build_mesh(&registry_clone, &atlas_clone, candidate_lod, (cx, cz), if neigh.is_empty() { None } else { Some(neigh) })
+                            });
+                            LodBuildResult { chunk_x: cx, chunk_z: cz, lod: candidate_lod, mesh, triangle_count: tri_count, timing }
                         });
                         ctx.pending_lod.coords.insert(coord);
                         ctx.pending_lod.tasks.push(task);
@@ -426,19 +457,19 @@ fn update_lods_and_schedule(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i
                     }
             }
 
-            if dist <= load_dist + PREWARM_DISTANCE_MARGIN {
+            if dist <= load_dist + ctx.budget.prewarm_margin {
                 let mut target = candidate_lod;
-                for _ in 0..PREWARM_LEVELS {
+                for _ in 0..ctx.budget.prewarm_levels {
                     target = (target + 1).min(u8::try_from(MAX_LODS - 1).expect("MAX_LODS fits in u8"));
                     let coord = (cx, cz, target);
                     if !ctx.pending_lod.coords.contains(&coord)
-                        && builds_scheduled < LOD_BUILD_BUDGET_PER_FRAME
+                        && builds_scheduled < ctx.budget.lod_builds_per_frame
+                        && ctx.pending_lod.tasks.len() < ctx.budget.pending_lod_tasks
                         && (handles.len() <= target as usize || handles[target as usize].is_none())
                         && let Some(chunk) = ctx.world.chunks.get(&(cx, cz)) {
                             let chunk_clone = chunk.clone();
                             let atlas_clone = atlas_map.clone();
                             let registry_clone = ctx.block_registry.clone();
-                            let pool = AsyncComputeTaskPool::get();
                             // Snapshot neighbors for this chunk to allow neighbor-aware meshing
                             let mut neigh: std::collections::HashMap<(i32, i32), Chunk> = std::collections::HashMap::new();
                             for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
@@ -448,11 +479,14 @@ fn update_lods_and_schedule(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x: i
                                 }
                             }
 
-                            let task = pool.spawn(async move {
+                            let queued = Instant::now();
+                            let task = spawn_build(ctx.budget.background_meshing, move || {
                                 // Record worker-thread execution for prewarm LOD build
                                 crate::debug::record_thread_global("lod_prewarm_task");
-                                let (mesh, tri_count) = chunk_clone.build_mesh(&registry_clone, &atlas_clone, target, (cx, cz), if neigh.is_empty() { None } else { Some(neigh) });
-                                LodBuildResult { chunk_x: cx, chunk_z: cz, lod: target, mesh, triangle_count: tri_count }
+                                let ((mesh, tri_count), timing) = TaskTiming::measure(queued, || {
+                                    chunk_clone.build_mesh(&registry_clone, &atlas_clone, target, (cx, cz), if neigh.is_empty() { None } else { Some(neigh) })
+                                });
+                                LodBuildResult { chunk_x: cx, chunk_z: cz, lod: target, mesh, triangle_count: tri_count, timing }
                             });
                             ctx.pending_lod.coords.insert(coord);
                             ctx.pending_lod.tasks.push(task);
@@ -468,9 +502,11 @@ fn process_finished_lod_tasks(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x:
     let mut i = 0usize;
     while i < ctx.pending_lod.tasks.len() {
         if ctx.pending_lod.tasks[i].is_finished() {
-            if let Ok(LodBuildResult { chunk_x: cx, chunk_z: cz, lod, mesh, triangle_count: tri_count }) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            if let Ok(LodBuildResult { chunk_x: cx, chunk_z: cz, lod, mesh, triangle_count: tri_count, timing }) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 futures::executor::block_on(&mut ctx.pending_lod.tasks[i])
             })) {
+                ctx.profiler.record_task(Stage::LodRebuild, (cx, cz), lod, &timing);
+                let apply_started = Instant::now();
                 ctx.pending_lod.coords.remove(&(cx, cz, lod));
                 let slot = lod as usize;
                 if let Some((entity, handles, active_lod)) = ctx.chunk_entities.map.get_mut(&(cx, cz)) {
@@ -504,11 +540,12 @@ fn process_finished_lod_tasks(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_x:
                         let handle = ctx.meshes.add(mesh);
                         handles[slot] = Some(handle.clone());
                         let dist = (player_chunk_x - cx).abs().max((player_chunk_z - cz).abs());
-                        let desired_lod_now = compute_lod_from_dist(dist);
+                        let desired_lod_now = ctx.budget.lod_for_distance(dist);
                         if desired_lod_now == lod { ctx.commands.entity(*entity).insert(handle.clone()); *active_lod = lod; }
                         ctx.stats.update_chunk((cx, cz), tri_count);
                     }
                 }
+                ctx.profiler.record_main(Stage::Upload, (cx, cz), lod, timing.finished, apply_started);
             }
             std::mem::drop(ctx.pending_lod.tasks.swap_remove(i));
         } else { i += 1; }
@@ -539,12 +576,14 @@ fn process_finished_mesh_builds(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_
     let mut applied = 0usize;
     while i < ctx.pending_mesh.tasks.len() {
         if ctx.pending_mesh.tasks[i].is_finished() {
-            if applied >= MESH_APPLY_BUDGET_PER_FRAME {
+            if applied >= ctx.budget.mesh_applies_per_frame {
                 break; // defer remaining finished tasks to next frame
             }
-            if let Ok(MeshBuildResult { chunk_x: cx, chunk_z: cz, chunk, mesh, triangle_count: tri_count, lod }) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            if let Ok(MeshBuildResult { chunk_x: cx, chunk_z: cz, chunk, mesh, triangle_count: tri_count, lod, timing }) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 futures::executor::block_on(&mut ctx.pending_mesh.tasks[i])
             })) {
+                ctx.profiler.record_task(Stage::Mesh, (cx, cz), lod, &timing);
+                let apply_started = Instant::now();
                 ctx.pending_mesh.coords.remove(&(cx, cz));
                 
                 let slot = lod as usize;
@@ -578,7 +617,7 @@ fn process_finished_mesh_builds(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_
                         if handles.len() < MAX_LODS { handles.resize(MAX_LODS, None); }
                         handles[slot] = Some(handle.clone());
                         let dist = (player_chunk_x - cx).abs().max((player_chunk_z - cz).abs());
-                        let desired_lod_now = compute_lod_from_dist(dist);
+                        let desired_lod_now = ctx.budget.lod_for_distance(dist);
                         if desired_lod_now == lod { ctx.commands.entity(*entity).insert(handle.clone()); *active_lod = lod; }
                         ctx.stats.update_chunk((cx, cz), tri_count);
                         ctx.world.chunks.insert((cx, cz), chunk);
@@ -595,6 +634,7 @@ fn process_finished_mesh_builds(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_
                     }
                     applied += 1;
                 }
+                ctx.profiler.record_main(Stage::Upload, (cx, cz), lod, timing.finished, apply_started);
             }
             std::mem::drop(ctx.pending_mesh.tasks.swap_remove(i));
         } else { i += 1; }
@@ -607,10 +647,10 @@ fn process_finished_mesh_builds(ctx: &mut StreamChunksCtx<'_, '_>, player_chunk_
     let mut spawns_this_frame = 0usize;
     let mut to_remove_coords = Vec::new();
     for (&coord, handles_vec) in ctx.pending_handles.map.iter() {
-        if spawns_this_frame >= MESH_APPLY_BUDGET_PER_FRAME { break; }
+        if spawns_this_frame >= ctx.budget.mesh_applies_per_frame { break; }
         let (cx, cz) = coord;
         let dist = (player_chunk_x - cx).abs().max((player_chunk_z - cz).abs());
-        let desired_lod_now = compute_lod_from_dist(dist) as usize;
+        let desired_lod_now = ctx.budget.lod_for_distance(dist) as usize;
         if desired_lod_now < handles_vec.len() {
             if let Some(Some(mesh_handle)) = handles_vec.get(desired_lod_now) {
                 // spawn entity using this handle and move other handles into map
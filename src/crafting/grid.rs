@@ -0,0 +1,110 @@
+//! The crafting grid.
+//!
+//! A 3x3 grid of cells, each holding at most one item. Items are moved in
+//! from the player `Inventory` (one at a time from the selected hotbar slot,
+//! or all at once by `Recipe::fill_grid`) and go back to it when taken out
+//! or when the crafting panel closes. Shaped recipes are matched against
+//! where the items sit; see `Recipe::matches_grid`.
+//!
+//! Example:
+//! ```rust
+//! use stratum::crafting::CraftingGrid;
+//! use stratum::item::{Inventory, Item, ItemRegistry};
+//!
+//! let mut items = ItemRegistry::default();
+//! items.register(Item { name: "stick".to_string(), id: 256, ..Default::default() });
+//! let mut inventory = Inventory::default();
+//! inventory.add(items.get("stick").unwrap(), 2);
+//!
+//! let mut grid = CraftingGrid::default();
+//! assert!(grid.put_selected(4, &mut inventory));
+//! assert_eq!(grid.get(1, 1), Some("stick"));
+//! assert_eq!(inventory.count("stick"), 1);
+//!
+//! grid.return_all(&mut inventory, &items);
+//! assert!(grid.is_empty());
+//! assert_eq!(inventory.count("stick"), 2);
+//! ```
+
+use super::recipe::MAX_PATTERN_SIZE;
+use crate::item::{Inventory, ItemRegistry};
+use bevy::prelude::Resource;
+
+/// Width and height of the crafting grid.
+pub const GRID_SIZE: usize = MAX_PATTERN_SIZE;
+
+/// Number of cells in the crafting grid.
+pub const GRID_CELLS: usize = GRID_SIZE * GRID_SIZE;
+
+/// Items placed in the crafting grid, row by row. Cells hold item names so
+/// they survive item registry reloads, like `ItemStack`.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CraftingGrid {
+    pub cells: [Option<String>; GRID_CELLS],
+}
+
+impl CraftingGrid {
+    /// The item in column `x`, row `y`.
+    #[must_use]
+    pub fn get(&self, x: usize, y: usize) -> Option<&str> {
+        if x >= GRID_SIZE || y >= GRID_SIZE {
+            return None;
+        }
+        self.cells[y * GRID_SIZE + x].as_deref()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(Option::is_none)
+    }
+
+    /// Move one item from the selected hotbar slot into the empty cell `index`.
+    ///
+    /// # Return
+    /// `true` if an item was placed.
+    pub fn put_selected(&mut self, index: usize, inventory: &mut Inventory) -> bool {
+        let Some(cell) = self.cells.get_mut(index) else { return false };
+        if cell.is_some() {
+            return false;
+        }
+        let Some(item) = inventory.selected_stack().map(|stack| stack.item.clone()) else { return false };
+
+        inventory.take_selected();
+        *cell = Some(item);
+        true
+    }
+
+    /// Move the item in cell `index` back into `inventory`. The item stays in
+    /// the grid if the inventory is full or the item no longer exists.
+    ///
+    /// # Return
+    /// `true` if the cell was emptied.
+    pub fn take(&mut self, index: usize, inventory: &mut Inventory, items: &ItemRegistry) -> bool {
+        let Some(cell) = self.cells.get_mut(index) else { return false };
+        let Some(item) = cell.as_deref().and_then(|name| items.get(name)) else { return false };
+
+        if inventory.add(item, 1) > 0 {
+            return false;
+        }
+        *cell = None;
+        true
+    }
+
+    /// Move every item back into `inventory`; items that do not fit stay.
+    pub fn return_all(&mut self, inventory: &mut Inventory, items: &ItemRegistry) {
+        for index in 0..GRID_CELLS {
+            self.take(index, inventory, items);
+        }
+    }
+
+    /// Smallest rectangle holding every item, as `(x, y, width, height)`.
+    #[must_use]
+    pub fn bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        let filled = || (0..GRID_CELLS).filter(|i| self.cells[*i].is_some());
+        let min_x = filled().map(|i| i % GRID_SIZE).min()?;
+        let max_x = filled().map(|i| i % GRID_SIZE).max()?;
+        let min_y = filled().map(|i| i / GRID_SIZE).min()?;
+        let max_y = filled().map(|i| i / GRID_SIZE).max()?;
+        Some((min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+    }
+}
@@ -0,0 +1,105 @@
+//! Recipe loader and watcher for loading recipe definitions from RON files
+//! and monitoring changes for hot reloading during runtime.
+//!
+//! Recipes are validated against the `ItemRegistry` when loaded. Invalid
+//! recipes are reported on stderr and left out of the `RecipeBook` so a
+//! typo in one file never breaks the rest.
+//!
+//! # Example
+//! ```
+//! use stratum::block::loader as block_loader;
+//! use stratum::crafting::loader as recipe_loader;
+//! use stratum::item::loader as item_loader;
+//!
+//! let blocks = block_loader::load_blocks_from_dir("data/blocks");
+//! let items = item_loader::load_items_from_dir("data/items", &blocks);
+//! let recipes = recipe_loader::load_recipes_from_dir("data/recipes", &items);
+//! assert!(recipes.get("wooden_shovel").is_some());
+//! ```
+
+use super::{Recipe, RecipeBook};
+use crate::item::ItemRegistry;
+use crate::ron_loader::{load_ron_files, setup_ron_watcher};
+use bevy::prelude::{DetectChanges, Res, ResMut, Resource};
+
+#[derive(Resource)]
+pub struct RecipeWatcher(pub crate::ron::RonWatcher);
+
+/// Load all recipe definitions from RON files, dropping invalid ones.
+///
+/// # Arguments
+/// * `path` - The directory path where recipe RON files are located (e.g., "data/recipes").
+/// * `items` - The item registry recipes are validated against.
+///
+/// # Returns
+/// A `RecipeBook` containing every recipe that passed validation.
+#[must_use]
+pub fn load_recipes_from_dir(path: &str, items: &ItemRegistry) -> RecipeBook {
+    let mut book = RecipeBook::default();
+    let mut recipes: Vec<Recipe> = load_ron_files(path);
+    recipes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for recipe in recipes {
+        if book.get(&recipe.name).is_some() {
+            eprintln!("Recipe '{}' is defined more than once; skipping duplicate", recipe.name);
+            continue;
+        }
+
+        let problems = recipe.validate(items);
+        if !problems.is_empty() {
+            for problem in problems {
+                eprintln!("Recipe '{}' is invalid: {problem}", recipe.name);
+            }
+            continue;
+        }
+
+        book.register(recipe);
+    }
+
+    book
+}
+
+/// Set up a file watcher to monitor changes in recipe RON files.
+///
+/// # Errors
+/// Returns a `notify::Error` if the underlying file watcher could not be created or configured.
+pub fn setup_recipe_watcher(path: &str) -> Result<RecipeWatcher, notify::Error> {
+    setup_ron_watcher(path).map(RecipeWatcher)
+}
+
+/// Reload the recipe book when recipe files change or the item registry is
+/// reloaded (recipes are validated against it).
+///
+/// # Arguments
+/// * `watcher` - A `RecipeWatcher` resource that monitors changes in recipe RON files
+/// * `book` - The `RecipeBook` resource replaced on reload
+/// * `items` - The current `ItemRegistry`
+#[allow(clippy::needless_pass_by_value)]
+pub fn check_recipe_changes(
+    watcher: Res<RecipeWatcher>,
+    mut book: ResMut<RecipeBook>,
+    items: Res<ItemRegistry>,
+) {
+    let items_reloaded = items.is_changed() && !items.is_added();
+    let mut flag = match watcher.0.changed.lock() {
+        Ok(flag) => flag,
+        Err(poisoned) => {
+            eprintln!("warning: recipe watcher mutex poisoned — recovering");
+            poisoned.into_inner()
+        }
+    };
+
+    if *flag || items_reloaded {
+        println!("Recipes changed, reloading...");
+        *book = load_recipes_from_dir("data/recipes", &items);
+        *flag = false;
+    }
+}
+
+impl RecipeWatcher {
+    /// Create a stub `RecipeWatcher` that does not have an active OS watcher.
+    #[must_use]
+    pub fn stub() -> Self {
+        RecipeWatcher(crate::ron::RonWatcher::stub())
+    }
+}
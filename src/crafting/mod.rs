@@ -0,0 +1,32 @@
+//! Crafting recipes.
+//!
+//! Recipes live in `data/recipes/*.ron`, one recipe per file, and turn
+//! the items placed in the 3x3 `CraftingGrid` into new items. Ingredients
+//! are keyed by item name or by tag (block items carry their block's tags).
+//! The `RecipeBook` resource holds every recipe that passed validation and
+//! is hot-reloaded through the usual `RonWatcher` machinery.
+//!
+//! Example recipe file:
+//!
+//! ```ron
+//! (
+//!     name: "wooden_shovel",
+//!     kind: Shaped(
+//!         pattern: ["W", "S", "S"],
+//!         key: { 'W': Tag("planks"), 'S': Item("stick") },
+//!     ),
+//!     output: (item: "wooden_shovel", count: 1),
+//! )
+//! ```
+
+/// Loader/watchers for recipe RON files.
+pub mod loader;
+
+/// Recipe definitions, validation and the recipe book.
+pub mod recipe;
+
+/// The 3x3 grid recipes are crafted from.
+pub mod grid;
+
+pub use grid::{CraftingGrid, GRID_CELLS, GRID_SIZE};
+pub use recipe::{Ingredient, Recipe, RecipeBook, RecipeKind, RecipeOutput};
@@ -0,0 +1,558 @@
+//! Recipe definitions, validation and the `RecipeBook` registry.
+//!
+//! A recipe is either `Shaped` (a pattern of up to 3x3 characters with a
+//! key mapping each character to an ingredient) or `Shapeless` (a plain
+//! list of up to 9 ingredients). Ingredients name either a specific item or
+//! a tag; block items inherit their block's tags, so `Tag("dirt")` matches
+//! every block tagged `dirt`.
+//!
+//! Recipes are crafted from the `CraftingGrid`. A shaped recipe matches when
+//! the grid holds its pattern anywhere in the grid, as is or mirrored left
+//! to right, with every other cell empty; a shapeless recipe matches when
+//! the grid holds exactly its ingredients in any cells. `fill_grid` lays a
+//! recipe out from the player `Inventory`, taking exact item ingredients
+//! before tag ingredients so a tag cannot use up an item that is named
+//! explicitly.
+//!
+//! Example:
+//! ```rust
+//! use stratum::crafting::{Ingredient, Recipe, RecipeKind, RecipeOutput};
+//!
+//! let recipe = Recipe {
+//!     name: "dirt_from_grass".to_string(),
+//!     kind: RecipeKind::Shapeless { ingredients: vec![Ingredient::Tag("grass".to_string())] },
+//!     output: RecipeOutput { item: "dirt".to_string(), count: 1 },
+//! };
+//! assert_eq!(recipe.requirements(), vec![(Ingredient::Tag("grass".to_string()), 1)]);
+//! ```
+
+use super::grid::{CraftingGrid, GRID_SIZE};
+use crate::item::{Inventory, Item, ItemRegistry};
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Width and height of the largest shaped recipe pattern.
+pub const MAX_PATTERN_SIZE: usize = 3;
+
+/// Maximum number of ingredients in a shapeless recipe.
+pub const MAX_SHAPELESS_INGREDIENTS: usize = MAX_PATTERN_SIZE * MAX_PATTERN_SIZE;
+
+/// Something a recipe consumes: a specific item or any item with a tag.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Ingredient {
+    Item(String),
+    Tag(String),
+}
+
+impl Ingredient {
+    /// Whether `item` satisfies this ingredient.
+    #[must_use]
+    pub fn matches(&self, item: &Item) -> bool {
+        match self {
+            Ingredient::Item(name) => item.name == *name,
+            Ingredient::Tag(tag) => item.has_tag(tag),
+        }
+    }
+
+    /// Whether the item named `name` satisfies this ingredient.
+    #[must_use]
+    pub fn matches_name(&self, name: &str, items: &ItemRegistry) -> bool {
+        match self {
+            Ingredient::Item(item) => item == name,
+            Ingredient::Tag(_) => items.get(name).is_some_and(|item| self.matches(item)),
+        }
+    }
+
+    /// Short human readable label (`stick`, `#wood`).
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self {
+            Ingredient::Item(name) => name.clone(),
+            Ingredient::Tag(tag) => format!("#{tag}"),
+        }
+    }
+}
+
+/// The item and count produced by a recipe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeOutput {
+    pub item: String,
+    #[serde(default = "RecipeOutput::default_count")]
+    pub count: u32,
+}
+
+impl RecipeOutput {
+    fn default_count() -> u32 { 1 }
+}
+
+/// Shaped or shapeless recipe input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecipeKind {
+    /// Rows of key characters; a space is an empty cell.
+    Shaped {
+        pattern: Vec<String>,
+        key: HashMap<char, Ingredient>,
+    },
+    /// Ingredients in any arrangement.
+    Shapeless { ingredients: Vec<Ingredient> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    pub kind: RecipeKind,
+    pub output: RecipeOutput,
+}
+
+impl Recipe {
+    /// The ingredients this recipe consumes with their counts, in the order
+    /// they first appear in the pattern / ingredient list.
+    #[must_use]
+    pub fn requirements(&self) -> Vec<(Ingredient, u32)> {
+        let mut out: Vec<(Ingredient, u32)> = Vec::new();
+        let mut push = |ingredient: &Ingredient| {
+            if let Some((_, count)) = out.iter_mut().find(|(i, _)| i == ingredient) {
+                *count += 1;
+            } else {
+                out.push((ingredient.clone(), 1));
+            }
+        };
+
+        match &self.kind {
+            RecipeKind::Shaped { pattern, key } => {
+                for c in pattern.iter().flat_map(|row| row.chars()) {
+                    if let Some(ingredient) = key.get(&c) {
+                        push(ingredient);
+                    }
+                }
+            }
+            RecipeKind::Shapeless { ingredients } => ingredients.iter().for_each(&mut push),
+        }
+
+        out
+    }
+
+    /// Check this recipe against the item registry.
+    ///
+    /// # Return
+    /// A list of human readable problems; empty when the recipe is usable.
+    #[must_use]
+    pub fn validate(&self, items: &ItemRegistry) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        match items.get(&self.output.item) {
+            None => problems.push(format!("output item '{}' does not exist", self.output.item)),
+            Some(item) if self.output.count > item.max_stack_size => problems.push(format!(
+                "output count {} exceeds the max stack size {} of '{}'",
+                self.output.count, item.max_stack_size, item.name
+            )),
+            Some(_) => {}
+        }
+        if self.output.count == 0 {
+            problems.push("output count is 0".to_string());
+        }
+
+        let ingredients: Vec<&Ingredient> = match &self.kind {
+            RecipeKind::Shaped { pattern, key } => {
+                validate_pattern(pattern, key, &mut problems);
+                let mut used: Vec<&Ingredient> = key.values().collect();
+                used.sort_by_key(|i| i.label());
+                used
+            }
+            RecipeKind::Shapeless { ingredients } => {
+                if ingredients.is_empty() || ingredients.len() > MAX_SHAPELESS_INGREDIENTS {
+                    problems.push(format!(
+                        "shapeless recipes need 1 to {MAX_SHAPELESS_INGREDIENTS} ingredients, found {}",
+                        ingredients.len()
+                    ));
+                }
+                ingredients.iter().collect()
+            }
+        };
+
+        for ingredient in ingredients {
+            match ingredient {
+                Ingredient::Item(name) if items.get(name).is_none() => {
+                    problems.push(format!("ingredient item '{name}' does not exist"));
+                }
+                Ingredient::Tag(tag) if !items.items.values().any(|i| i.has_tag(tag)) => {
+                    problems.push(format!("no item has the ingredient tag '{tag}'"));
+                }
+                _ => {}
+            }
+        }
+
+        problems
+    }
+
+    /// Whether the items in `grid` make this recipe.
+    #[must_use]
+    pub fn matches_grid(&self, grid: &CraftingGrid, items: &ItemRegistry) -> bool {
+        match &self.kind {
+            RecipeKind::Shaped { pattern, key } => {
+                let Some((px, py, width, height)) = pattern_bounds(pattern) else { return false };
+                let Some((gx, gy, grid_width, grid_height)) = grid.bounds() else { return false };
+                if (width, height) != (grid_width, grid_height) {
+                    return false;
+                }
+
+                let rows: Vec<Vec<char>> = pattern.iter().map(|row| row.chars().collect()).collect();
+                let fits = |mirrored: bool| {
+                    (0..height).all(|y| {
+                        (0..width).all(|x| {
+                            let column = if mirrored { width - 1 - x } else { x };
+                            let c = rows[py + y].get(px + column).copied().unwrap_or(' ');
+                            match (key.get(&c), grid.get(gx + x, gy + y)) {
+                                (None, None) => true,
+                                (Some(ingredient), Some(name)) => ingredient.matches_name(name, items),
+                                _ => false,
+                            }
+                        })
+                    })
+                };
+                fits(false) || fits(true)
+            }
+            RecipeKind::Shapeless { ingredients } => {
+                let placed: Vec<&str> = grid.cells.iter().flatten().map(String::as_str).collect();
+                placed.len() == ingredients.len()
+                    && assign_shapeless(ingredients, &placed, &mut vec![false; placed.len()], items)
+            }
+        }
+    }
+
+    /// Craft the items in `grid` into this recipe's output, which is added
+    /// to `inventory`. Nothing changes unless the grid matches and the
+    /// output fits.
+    ///
+    /// # Return
+    /// `true` if the recipe was crafted.
+    pub fn craft(&self, grid: &mut CraftingGrid, inventory: &mut Inventory, items: &ItemRegistry) -> bool {
+        let Some(output) = items.get(&self.output.item) else { return false };
+        if !self.matches_grid(grid, items) {
+            return false;
+        }
+
+        let mut next = inventory.clone();
+        if next.add(output, self.output.count) > 0 {
+            return false;
+        }
+
+        *inventory = next;
+        *grid = CraftingGrid::default();
+        true
+    }
+
+    /// Whether `inventory` holds every ingredient needed by `fill_grid`.
+    #[must_use]
+    pub fn can_fill_grid(&self, inventory: &Inventory, items: &ItemRegistry) -> bool {
+        self.fill_grid(&mut CraftingGrid::default(), &mut inventory.clone(), items)
+    }
+
+    /// Lay this recipe out in the empty `grid` with items taken from
+    /// `inventory`: shaped recipes in the top left corner, shapeless ones in
+    /// the first cells. Nothing changes if the grid is not empty or an
+    /// ingredient is missing. `Item` ingredients are taken before `Tag`
+    /// ones, so a tag that also matches a named item only takes what is
+    /// left over.
+    ///
+    /// # Return
+    /// `true` if the grid was filled.
+    pub fn fill_grid(&self, grid: &mut CraftingGrid, inventory: &mut Inventory, items: &ItemRegistry) -> bool {
+        if !grid.is_empty() {
+            return false;
+        }
+
+        let cells: Vec<(usize, &Ingredient)> = match &self.kind {
+            RecipeKind::Shaped { pattern, key } => pattern
+                .iter()
+                .enumerate()
+                .flat_map(|(y, row)| row.chars().enumerate().map(move |(x, c)| (y * GRID_SIZE + x, c)))
+                .filter_map(|(index, c)| key.get(&c).map(|ingredient| (index, ingredient)))
+                .collect(),
+            RecipeKind::Shapeless { ingredients } => ingredients.iter().enumerate().collect(),
+        };
+
+        let mut next_grid = CraftingGrid::default();
+        let mut next = inventory.clone();
+        let named = cells.iter().filter(|(_, i)| matches!(i, Ingredient::Item(_)));
+        let tagged = cells.iter().filter(|(_, i)| matches!(i, Ingredient::Tag(_)));
+        for (index, ingredient) in named.chain(tagged) {
+            let Some(name) = take_matching(&mut next, ingredient, items) else { return false };
+            let Some(cell) = next_grid.cells.get_mut(*index) else { return false };
+            *cell = Some(name);
+        }
+
+        *inventory = next;
+        *grid = next_grid;
+        true
+    }
+}
+
+/// Smallest rectangle of `pattern` holding every non-space character, as
+/// `(x, y, width, height)`.
+fn pattern_bounds(pattern: &[String]) -> Option<(usize, usize, usize, usize)> {
+    let filled = || {
+        pattern
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.chars().enumerate().filter(|(_, c)| *c != ' ').map(move |(x, _)| (x, y)))
+    };
+    let min_x = filled().map(|(x, _)| x).min()?;
+    let max_x = filled().map(|(x, _)| x).max()?;
+    let min_y = filled().map(|(_, y)| y).min()?;
+    let max_y = filled().map(|(_, y)| y).max()?;
+    Some((min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+/// Give every ingredient its own placed item, backtracking when a tag took
+/// an item a later ingredient needed.
+fn assign_shapeless(ingredients: &[Ingredient], placed: &[&str], used: &mut [bool], items: &ItemRegistry) -> bool {
+    let Some((ingredient, rest)) = ingredients.split_first() else { return true };
+
+    for i in 0..placed.len() {
+        if used[i] || !ingredient.matches_name(placed[i], items) {
+            continue;
+        }
+        used[i] = true;
+        if assign_shapeless(rest, placed, used, items) {
+            return true;
+        }
+        used[i] = false;
+    }
+    false
+}
+
+/// Remove one item satisfying `ingredient` from `inventory`, taking from the
+/// last slots first like `Inventory::remove_where`.
+///
+/// # Return
+/// The name of the removed item.
+fn take_matching(inventory: &mut Inventory, ingredient: &Ingredient, items: &ItemRegistry) -> Option<String> {
+    let name = inventory
+        .slots
+        .iter()
+        .rev()
+        .flatten()
+        .find(|stack| ingredient.matches_name(&stack.item, items))
+        .map(|stack| stack.item.clone())?;
+    inventory.remove(&name, 1).then_some(name)
+}
+
+/// Report pattern size problems and characters missing from (or unused in) `key`.
+fn validate_pattern(pattern: &[String], key: &HashMap<char, Ingredient>, problems: &mut Vec<String>) {
+    let width = pattern.first().map_or(0, |row| row.chars().count());
+    if pattern.is_empty() || pattern.len() > MAX_PATTERN_SIZE || width == 0 || width > MAX_PATTERN_SIZE {
+        problems.push(format!(
+            "pattern must be between 1x1 and {MAX_PATTERN_SIZE}x{MAX_PATTERN_SIZE}, found {}x{}",
+            width,
+            pattern.len()
+        ));
+    }
+    if pattern.iter().any(|row| row.chars().count() != width) {
+        problems.push("pattern rows must all be the same width".to_string());
+    }
+
+    let mut missing: Vec<char> = pattern
+        .iter()
+        .flat_map(|row| row.chars())
+        .filter(|c| *c != ' ' && !key.contains_key(c))
+        .collect();
+    missing.sort_unstable();
+    missing.dedup();
+    for c in missing {
+        problems.push(format!("pattern character '{c}' is not in the key"));
+    }
+
+    let mut unused: Vec<char> = key
+        .keys()
+        .filter(|c| !pattern.iter().any(|row| row.contains(**c)))
+        .copied()
+        .collect();
+    unused.sort_unstable();
+    for c in unused {
+        problems.push(format!("key character '{c}' is not used in the pattern"));
+    }
+}
+
+/// Runtime resource holding every valid recipe, keyed by recipe name.
+#[derive(Resource, Default, Clone)]
+pub struct RecipeBook {
+    pub recipes: HashMap<String, Recipe>,
+}
+
+impl RecipeBook {
+    pub fn register(&mut self, recipe: Recipe) {
+        self.recipes.insert(recipe.name.clone(), recipe);
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Recipe> {
+        self.recipes.get(name)
+    }
+
+    /// All recipes sorted by name, for stable UI ordering.
+    #[must_use]
+    pub fn sorted(&self) -> Vec<&Recipe> {
+        let mut recipes: Vec<&Recipe> = self.recipes.values().collect();
+        recipes.sort_by(|a, b| a.name.cmp(&b.name));
+        recipes
+    }
+
+    /// The first recipe (by name) made by the items in `grid`.
+    #[must_use]
+    pub fn find_match(&self, grid: &CraftingGrid, items: &ItemRegistry) -> Option<&Recipe> {
+        self.sorted().into_iter().find(|recipe| recipe.matches_grid(grid, items))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> ItemRegistry {
+        let mut items = ItemRegistry::default();
+        items.register(Item { name: "stick".to_string(), id: 256, tags: vec!["wood".to_string()], ..Default::default() });
+        items.register(Item { name: "shovel".to_string(), id: 257, max_stack_size: 1, ..Default::default() });
+        items
+    }
+
+    fn shovel() -> Recipe {
+        Recipe {
+            name: "shovel".to_string(),
+            kind: RecipeKind::Shaped {
+                pattern: vec!["W".to_string(), "S".to_string(), "S".to_string()],
+                key: HashMap::from([
+                    ('W', Ingredient::Tag("wood".to_string())),
+                    ('S', Ingredient::Item("stick".to_string())),
+                ]),
+            },
+            output: RecipeOutput { item: "shovel".to_string(), count: 1 },
+        }
+    }
+
+    #[test]
+    fn invalid_recipes_are_reported() {
+        let items = items();
+        assert!(shovel().validate(&items).is_empty());
+
+        let mut broken = shovel();
+        broken.output.count = 2;
+        if let RecipeKind::Shaped { pattern, key } = &mut broken.kind {
+            pattern.push("XX".to_string());
+            key.insert('P', Ingredient::Tag("planks".to_string()));
+        }
+        assert_eq!(
+            broken.validate(&items),
+            vec![
+                "output count 2 exceeds the max stack size 1 of 'shovel'".to_string(),
+                "pattern must be between 1x1 and 3x3, found 1x4".to_string(),
+                "pattern rows must all be the same width".to_string(),
+                "pattern character 'X' is not in the key".to_string(),
+                "key character 'P' is not used in the pattern".to_string(),
+                "no item has the ingredient tag 'planks'".to_string(),
+            ]
+        );
+    }
+
+    fn axe() -> Recipe {
+        Recipe {
+            name: "axe".to_string(),
+            kind: RecipeKind::Shaped {
+                pattern: vec!["WW".to_string(), "WS".to_string(), " S".to_string()],
+                key: HashMap::from([
+                    ('W', Ingredient::Tag("wood".to_string())),
+                    ('S', Ingredient::Item("stick".to_string())),
+                ]),
+            },
+            output: RecipeOutput { item: "shovel".to_string(), count: 1 },
+        }
+    }
+
+    fn grid(rows: [&str; GRID_SIZE]) -> CraftingGrid {
+        let mut grid = CraftingGrid::default();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                grid.cells[y * GRID_SIZE + x] = match c {
+                    's' => Some("stick".to_string()),
+                    'p' => Some("planks".to_string()),
+                    _ => None,
+                };
+            }
+        }
+        grid
+    }
+
+    fn with_planks() -> ItemRegistry {
+        let mut items = items();
+        items.register(Item { name: "planks".to_string(), id: 258, tags: vec!["wood".to_string()], ..Default::default() });
+        items
+    }
+
+    #[test]
+    fn shaped_recipes_match_anywhere_and_mirrored() {
+        let items = with_planks();
+        assert!(axe().matches_grid(&grid(["pp ", "ps ", " s "]), &items));
+        assert!(axe().matches_grid(&grid([" pp", " ps", "  s"]), &items));
+        assert!(axe().matches_grid(&grid([" pp", " sp", " s "]), &items));
+
+        // Upside down, an extra item, or planks where a stick is named
+        assert!(!axe().matches_grid(&grid([" s ", "ps ", "pp "]), &items));
+        assert!(!axe().matches_grid(&grid(["pps", "ps ", " s "]), &items));
+        assert!(!axe().matches_grid(&grid(["pp ", "pp ", " s "]), &items));
+        assert!(!axe().matches_grid(&CraftingGrid::default(), &items));
+    }
+
+    #[test]
+    fn shapeless_recipes_match_in_any_cells() {
+        let items = with_planks();
+        let recipe = Recipe {
+            name: "mix".to_string(),
+            kind: RecipeKind::Shapeless {
+                ingredients: vec![Ingredient::Tag("wood".to_string()), Ingredient::Item("stick".to_string())],
+            },
+            output: RecipeOutput { item: "shovel".to_string(), count: 1 },
+        };
+
+        assert!(recipe.matches_grid(&grid(["s  ", "   ", "  p"]), &items));
+        // Sticks are tagged `wood` too, so `#wood` has to give the stick up
+        assert!(recipe.matches_grid(&grid(["   ", " sp", "   "]), &items));
+        assert!(!recipe.matches_grid(&grid(["s  ", " p ", "  s"]), &items));
+        assert!(!recipe.matches_grid(&grid(["p  ", "   ", "  p"]), &items));
+    }
+
+    #[test]
+    fn crafting_consumes_the_grid() {
+        let items = with_planks();
+        let mut inventory = Inventory::default();
+        let mut partial = grid(["p  ", "s  ", "   "]);
+        assert!(!shovel().craft(&mut partial, &mut inventory, &items));
+        assert_eq!(partial, grid(["p  ", "s  ", "   "]));
+
+        let mut full = grid([" p ", " s ", " s "]);
+        assert!(shovel().craft(&mut full, &mut inventory, &items));
+        assert!(full.is_empty());
+        assert_eq!(inventory.count("shovel"), 1);
+    }
+
+    #[test]
+    fn filling_the_grid_takes_named_items_before_tags() {
+        let items = with_planks();
+        // The sticks sit first, so a greedy `#wood` would eat one of them
+        let mut inventory = Inventory::default();
+        inventory.add(items.get("stick").unwrap(), 2);
+        assert!(!shovel().can_fill_grid(&inventory, &items));
+
+        inventory.add(items.get("planks").unwrap(), 1);
+        let mut grid = CraftingGrid::default();
+        assert!(shovel().fill_grid(&mut grid, &mut inventory, &items));
+        assert_eq!((inventory.count("stick"), inventory.count("planks")), (0, 0));
+        assert_eq!(grid, self::grid(["p  ", "s  ", "s  "]));
+        assert!(shovel().matches_grid(&grid, &items));
+
+        // A filled grid is not filled again
+        inventory.add(items.get("planks").unwrap(), 1);
+        assert!(!shovel().fill_grid(&mut grid, &mut inventory, &items));
+        assert_eq!(inventory.count("planks"), 1);
+    }
+}
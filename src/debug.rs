@@ -3,6 +3,7 @@
 //!
 //! This is a useful module for quickly capturing a snapshot of the game's internal state
 //! and performance characteristics without needing to set up an external profiler or attach a debugger.
+use crate::input::ActionState;
 use bevy::diagnostic::{Diagnostic, DiagnosticsStore, FrameTimeDiagnosticsPlugin};
 use bevy::pbr::StandardMaterial;
 use bevy::prelude::*;
@@ -113,17 +114,17 @@ fn bytes_to_mb(bytes: usize) -> String {
     format!("{:.2} MB", (bytes as f64) / 1024.0 / 1024.0)
 }
 
-/// A Bevy system that listens for the (debug, default F3) key press
+/// A Bevy system that listens for the `dump_debug` action (default F3)
 /// and generates a debug dump of diagnostics, entity counts, asset counts, and system thread usage.
 ///
 /// # Arguments
-/// * `keys` - Bevy resource for keyboard input, used to detect when the debug key is pressed.
+/// * `actions` - Action state, used to detect when the `dump_debug` action is pressed.
 /// * `diagnostics` - Bevy resource that stores performance diagnostics like FPS and frame time.
 /// * `query_entities` - A Bevy query that counts the total number of entities in the world.
 /// * `meshes`, `materials`, `images` - Bevy asset resources that count the number of loaded meshes, materials, and images.
 /// * `sys_log` - An optional resource that tracks which systems are running on which threads, for inclusion in the debug dump.
 fn debug_input_system(
-    keys: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     diagnostics: Res<DiagnosticsStore>,
     query_entities: Query<Entity>,
     meshes: Res<Assets<Mesh>>,
@@ -132,7 +133,7 @@ fn debug_input_system(
     sys_log: Option<Res<SystemThreadLog>>,
     asset_paths: Option<Res<AssetPathRegistry>>,
 ) {
-    if !keys.just_pressed(KeyCode::F3) {
+    if !actions.just_pressed("dump_debug") {
         return;
     }
 
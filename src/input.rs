@@ -0,0 +1,633 @@
+//! Action-based input.
+//!
+//! Systems ask for named actions ("jump", "break", "toggle_debug") instead of
+//! keys. `InputMap` maps every action to one or more bindings, read from
+//! `controls.keybinds` in the settings (with the built-in defaults for
+//! actions the file leaves out) and rebuilt whenever the settings change.
+//! A keybind value lists its bindings separated by `|`:
+//!
+//! * keys by name, as accepted by `Settings::keycode_from_str` (`"W"`, `"F1"`)
+//! * modifier combos: `"Ctrl+Z"`, `"Shift+Alt+F5"` (Ctrl, Shift, Alt, Super)
+//! * mouse buttons: `"Mouse:Left"`, `"Mouse:Right"`, `"Mouse:Middle"`,
+//!   `"Mouse:Back"`, `"Mouse:Forward"` or `"Mouse:<n>"`
+//! * gamepad buttons: `"Pad:South"` (also `A`/`B`/`X`/`Y`, `LB`/`RB`/`LT`/`RT`,
+//!   `Start`, `Select`, `DPadUp`, ...)
+//! * gamepad axis directions: `"Pad:LeftStickY+"`, `"Pad:RightStickX-"`
+//!
+//! `update_action_state` evaluates the map once per frame (in `PreUpdate`,
+//! after the console and keybind capture have taken their keys) into the
+//! `ActionState` resource that gameplay systems read. A combo suppresses
+//! plain bindings of the same key while it is held, so `Ctrl+Z` does not
+//! also trigger an action bound to `Z`. The same binding on two actions is
+//! reported as a conflict when the map is built.
+//!
+//! The gameplay simulation runs in `FixedUpdate`, zero or more times per
+//! frame, and reads `FixedActionState` instead: presses are latched every
+//! frame (`latch_fixed_actions`) and handed to the next simulation step
+//! (`begin_fixed_actions`), so a press is seen exactly once even when a
+//! frame runs no step or several.
+
+use crate::settings::{ControlsSettings, Settings};
+use bevy::input::gamepad::{GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, Gamepads};
+use bevy::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+/// Stick deflection below which an axis binding is not pressed.
+pub const AXIS_DEADZONE: f32 = 0.25;
+
+/// Modifier keys of a combo binding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub super_key: bool,
+}
+
+impl Modifiers {
+    /// Whether no modifier is required.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self == Self::default()
+    }
+
+    /// Whether every required modifier is held (left or right).
+    #[must_use]
+    pub fn held(self, keys: &ButtonInput<KeyCode>) -> bool {
+        let either = |l, r| keys.pressed(l) || keys.pressed(r);
+        (!self.ctrl || either(KeyCode::ControlLeft, KeyCode::ControlRight))
+            && (!self.shift || either(KeyCode::ShiftLeft, KeyCode::ShiftRight))
+            && (!self.alt || either(KeyCode::AltLeft, KeyCode::AltRight))
+            && (!self.super_key || either(KeyCode::SuperLeft, KeyCode::SuperRight))
+    }
+}
+
+/// The physical input of a binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputSource {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    PadButton(GamepadButtonType),
+    /// An axis pushed in one direction (`true` for positive)
+    PadAxis(GamepadAxisType, bool),
+}
+
+/// One way to trigger an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub source: InputSource,
+    pub modifiers: Modifiers,
+}
+
+/// Gamepad button names (lowercase) accepted after `Pad:`.
+const PAD_BUTTONS: [(&str, GamepadButtonType); 27] = [
+    ("south", GamepadButtonType::South),
+    ("a", GamepadButtonType::South),
+    ("east", GamepadButtonType::East),
+    ("b", GamepadButtonType::East),
+    ("north", GamepadButtonType::North),
+    ("y", GamepadButtonType::North),
+    ("west", GamepadButtonType::West),
+    ("x", GamepadButtonType::West),
+    ("c", GamepadButtonType::C),
+    ("z", GamepadButtonType::Z),
+    ("lefttrigger", GamepadButtonType::LeftTrigger),
+    ("lb", GamepadButtonType::LeftTrigger),
+    ("lefttrigger2", GamepadButtonType::LeftTrigger2),
+    ("lt", GamepadButtonType::LeftTrigger2),
+    ("righttrigger", GamepadButtonType::RightTrigger),
+    ("rb", GamepadButtonType::RightTrigger),
+    ("righttrigger2", GamepadButtonType::RightTrigger2),
+    ("rt", GamepadButtonType::RightTrigger2),
+    ("select", GamepadButtonType::Select),
+    ("start", GamepadButtonType::Start),
+    ("mode", GamepadButtonType::Mode),
+    ("leftthumb", GamepadButtonType::LeftThumb),
+    ("rightthumb", GamepadButtonType::RightThumb),
+    ("dpadup", GamepadButtonType::DPadUp),
+    ("dpaddown", GamepadButtonType::DPadDown),
+    ("dpadleft", GamepadButtonType::DPadLeft),
+    ("dpadright", GamepadButtonType::DPadRight),
+];
+
+/// Gamepad axis names (lowercase) accepted after `Pad:`.
+const PAD_AXES: [(&str, GamepadAxisType); 6] = [
+    ("leftstickx", GamepadAxisType::LeftStickX),
+    ("leftsticky", GamepadAxisType::LeftStickY),
+    ("leftz", GamepadAxisType::LeftZ),
+    ("rightstickx", GamepadAxisType::RightStickX),
+    ("rightsticky", GamepadAxisType::RightStickY),
+    ("rightz", GamepadAxisType::RightZ),
+];
+
+fn parse_pad(name: &str) -> Option<InputSource> {
+    let lower = name.to_ascii_lowercase();
+    if let Some((_, button)) = PAD_BUTTONS.iter().find(|(n, _)| *n == lower) {
+        return Some(InputSource::PadButton(*button));
+    }
+    let (axis, positive) = match lower.strip_suffix('+') {
+        Some(axis) => (axis, true),
+        None => (lower.strip_suffix('-')?, false),
+    };
+    PAD_AXES.iter().find(|(n, _)| *n == axis).map(|(_, a)| InputSource::PadAxis(*a, positive))
+}
+
+fn parse_mouse(name: &str) -> Option<MouseButton> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "left" => MouseButton::Left,
+        "right" => MouseButton::Right,
+        "middle" => MouseButton::Middle,
+        "back" => MouseButton::Back,
+        "forward" => MouseButton::Forward,
+        n => MouseButton::Other(n.parse().ok()?),
+    })
+}
+
+impl Binding {
+    /// Parse one binding (see the module docs for the syntax).
+    ///
+    /// # Errors
+    /// Returns an error naming the part that is not a known input.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if let Some(name) = text.strip_prefix("Pad:").or_else(|| text.strip_prefix("pad:")) {
+            let source = parse_pad(name).ok_or_else(|| format!("unknown gamepad input '{name}'"))?;
+            return Ok(Self { source, modifiers: Modifiers::default() });
+        }
+
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let main = parts.pop().filter(|m| !m.is_empty()).ok_or_else(|| format!("'{text}' has no key"))?;
+        let mut modifiers = Modifiers::default();
+        for part in parts {
+            match part.to_ascii_uppercase().as_str() {
+                "CTRL" | "CONTROL" => modifiers.ctrl = true,
+                "SHIFT" => modifiers.shift = true,
+                "ALT" => modifiers.alt = true,
+                "SUPER" | "CMD" | "WINDOWS" => modifiers.super_key = true,
+                _ => return Err(format!("unknown modifier '{part}' in '{text}'")),
+            }
+        }
+        let source = match main.strip_prefix("Mouse:").or_else(|| main.strip_prefix("mouse:")) {
+            Some(button) => InputSource::Mouse(parse_mouse(button).ok_or_else(|| format!("unknown mouse button '{button}'"))?),
+            None => InputSource::Key(Settings::keycode_from_str(main).ok_or_else(|| format!("unknown key '{main}'"))?),
+        };
+        Ok(Self { source, modifiers })
+    }
+
+    /// Parse a keybind value: bindings separated by `|`.
+    ///
+    /// # Errors
+    /// Returns the first binding that does not parse.
+    pub fn parse_list(text: &str) -> Result<Vec<Self>, String> {
+        text.split('|').filter(|b| !b.trim().is_empty()).map(Self::parse).collect()
+    }
+
+    /// Whether the binding is a gamepad input.
+    #[must_use]
+    pub fn is_gamepad(&self) -> bool {
+        matches!(self.source, InputSource::PadButton(_) | InputSource::PadAxis(..))
+    }
+
+    /// Current value (0 when released, 1 for a held button, the deflection
+    /// for an axis) and whether it was pressed this frame.
+    fn read(&self, raw: &RawInput) -> (f32, bool) {
+        if !self.modifiers.held(raw.keys) {
+            return (0.0, false);
+        }
+        let button = |pressed: bool, just: bool| (if pressed { 1.0 } else { 0.0 }, just);
+        match self.source {
+            InputSource::Key(key) => button(raw.keys.pressed(key), raw.keys.just_pressed(key)),
+            InputSource::Mouse(b) => button(raw.mouse.pressed(b), raw.mouse.just_pressed(b)),
+            InputSource::PadButton(kind) => raw.gamepads.iter().fold((0.0, false), |(v, j), pad| {
+                let b = GamepadButton::new(pad, kind);
+                (if raw.pad_buttons.pressed(b) { 1.0 } else { v }, j || raw.pad_buttons.just_pressed(b))
+            }),
+            InputSource::PadAxis(kind, positive) => {
+                let value = raw
+                    .gamepads
+                    .iter()
+                    .filter_map(|pad| raw.pad_axes.get(GamepadAxis::new(pad, kind)))
+                    .map(|v| if positive { v } else { -v })
+                    .fold(0.0_f32, f32::max);
+                (if value > AXIS_DEADZONE { value.min(1.0) } else { 0.0 }, false)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.modifiers;
+        for (held, name) in [(m.ctrl, "Ctrl"), (m.shift, "Shift"), (m.alt, "Alt"), (m.super_key, "Super")] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.source {
+            InputSource::Key(key) => match Settings::key_name(key) {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "{key:?}"),
+            },
+            InputSource::Mouse(MouseButton::Other(n)) => write!(f, "Mouse:{n}"),
+            InputSource::Mouse(button) => write!(f, "Mouse:{button:?}"),
+            InputSource::PadButton(GamepadButtonType::Other(n)) => write!(f, "Pad:Button{n}"),
+            InputSource::PadButton(button) => write!(f, "Pad:{button:?}"),
+            InputSource::PadAxis(axis, positive) => write!(f, "Pad:{axis:?}{}", if positive { '+' } else { '-' }),
+        }
+    }
+}
+
+/// Borrowed raw input state for evaluating bindings.
+pub struct RawInput<'a> {
+    pub keys: &'a ButtonInput<KeyCode>,
+    pub mouse: &'a ButtonInput<MouseButton>,
+    pub gamepads: &'a Gamepads,
+    pub pad_buttons: &'a ButtonInput<GamepadButton>,
+    pub pad_axes: &'a Axis<GamepadAxis>,
+}
+
+/// A binding used by more than one action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub binding: Binding,
+    pub actions: Vec<String>,
+}
+
+/// Bindings of every action.
+///
+/// # Fields
+/// * `conflicts` - bindings shared by several actions
+/// * `errors` - keybind values that did not parse (those actions keep their
+///   default bindings)
+#[derive(Resource, Debug, Clone, Default)]
+pub struct InputMap {
+    actions: BTreeMap<String, Vec<Binding>>,
+    pub conflicts: Vec<Conflict>,
+    pub errors: Vec<String>,
+}
+
+impl InputMap {
+    /// Build the map from keybind values over the built-in defaults.
+    #[must_use]
+    pub fn from_keybinds<'a>(keybinds: impl IntoIterator<Item = (&'a String, &'a String)>) -> Self {
+        let mut map = Self::default();
+        for (action, text) in &ControlsSettings::default_keybinds() {
+            if let Ok(bindings) = Binding::parse_list(text) {
+                map.actions.insert(action.clone(), bindings);
+            }
+        }
+        for (action, text) in keybinds {
+            match Binding::parse_list(text) {
+                Ok(bindings) => {
+                    map.actions.insert(action.clone(), bindings);
+                }
+                Err(e) => map.errors.push(format!("{action}: {e}")),
+            }
+        }
+        map.errors.sort();
+        map.find_conflicts();
+        map
+    }
+
+    /// Build the map from the controls settings.
+    #[must_use]
+    pub fn from_settings(controls: &ControlsSettings) -> Self {
+        Self::from_keybinds(&controls.keybinds)
+    }
+
+    fn find_conflicts(&mut self) {
+        let mut users: BTreeMap<String, (Binding, Vec<String>)> = BTreeMap::new();
+        for (action, bindings) in &self.actions {
+            for binding in bindings {
+                users.entry(binding.to_string()).or_insert((*binding, Vec::new())).1.push(action.clone());
+            }
+        }
+        self.conflicts = users
+            .into_values()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|(binding, actions)| Conflict { binding, actions })
+            .collect();
+    }
+
+    /// Bindings of `action` (empty if it is unknown).
+    #[must_use]
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every action, sorted by name.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    /// Whether `action` shares a binding with another action.
+    #[must_use]
+    pub fn in_conflict(&self, action: &str) -> bool {
+        self.conflicts.iter().any(|c| c.actions.iter().any(|a| a == action))
+    }
+
+    /// Whether one of `action`'s keyboard bindings was pressed this frame.
+    /// For systems that run before `update_action_state` and only care
+    /// about the keyboard (the console toggle).
+    #[must_use]
+    pub fn key_just_pressed(&self, action: &str, keys: &ButtonInput<KeyCode>) -> bool {
+        self.bindings(action).iter().any(|b| match b.source {
+            InputSource::Key(key) => keys.just_pressed(key) && b.modifiers.held(keys),
+            _ => false,
+        })
+    }
+
+    /// Whether `key` is one of `action`'s keyboard bindings.
+    #[must_use]
+    pub fn binds_key(&self, action: &str, key: KeyCode) -> bool {
+        self.bindings(action).iter().any(|b| b.source == InputSource::Key(key))
+    }
+
+    /// Evaluate every action against the raw input.
+    #[must_use]
+    pub fn evaluate(&self, raw: &RawInput, previous: &ActionState) -> ActionState {
+        // Inputs held as part of a combo do not also trigger plain bindings
+        let chorded: HashSet<InputSource> = self
+            .actions
+            .values()
+            .flatten()
+            .filter(|b| !b.modifiers.is_empty() && b.read(raw).0 > 0.0)
+            .map(|b| b.source)
+            .collect();
+
+        let mut state = ActionState::default();
+        for (action, bindings) in &self.actions {
+            let mut value = 0.0_f32;
+            let mut just = false;
+            for binding in bindings {
+                if binding.modifiers.is_empty() && chorded.contains(&binding.source) {
+                    continue;
+                }
+                let (v, j) = binding.read(raw);
+                value = value.max(v);
+                just |= j;
+            }
+            let was_pressed = previous.pressed(action);
+            if value > 0.0 {
+                state.values.insert(action.clone(), value);
+                // Axes have no "just pressed" of their own
+                if just || !was_pressed {
+                    state.just_pressed.insert(action.clone());
+                }
+            } else if was_pressed {
+                state.just_released.insert(action.clone());
+            }
+        }
+        state
+    }
+}
+
+/// Replace the keyboard and mouse bindings of a keybind value with `key`,
+/// keeping its gamepad bindings.
+#[must_use]
+pub fn rebind_keyboard(value: &str, key: &str) -> String {
+    let pads = value.split('|').map(str::trim).filter(|b| Binding::parse(b).is_ok_and(|b| b.is_gamepad()));
+    std::iter::once(key).chain(pads).collect::<Vec<_>>().join(" | ")
+}
+
+/// State of every action this frame.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct ActionState {
+    values: BTreeMap<String, f32>,
+    just_pressed: BTreeSet<String>,
+    just_released: BTreeSet<String>,
+}
+
+impl ActionState {
+    /// Whether `action` is held.
+    #[must_use]
+    pub fn pressed(&self, action: &str) -> bool {
+        self.values.contains_key(action)
+    }
+
+    /// Whether `action` started this frame.
+    #[must_use]
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.just_pressed.contains(action)
+    }
+
+    /// Whether `action` ended this frame.
+    #[must_use]
+    pub fn just_released(&self, action: &str) -> bool {
+        self.just_released.contains(action)
+    }
+
+    /// How far `action` is pressed: 1 for buttons, the stick deflection for
+    /// axes and 0 when released.
+    #[must_use]
+    pub fn value(&self, action: &str) -> f32 {
+        self.values.get(action).copied().unwrap_or(0.0)
+    }
+
+    /// Press `action` (with `value`), as if its binding went down this frame.
+    pub fn press(&mut self, action: &str, value: f32) {
+        if !self.pressed(action) {
+            self.just_pressed.insert(action.to_string());
+        }
+        self.values.insert(action.to_string(), value);
+    }
+
+    /// Release `action`.
+    pub fn release(&mut self, action: &str) {
+        if self.values.remove(action).is_some() {
+            self.just_released.insert(action.to_string());
+        }
+    }
+
+    /// Replace the state with one simulation tick's input: the `held`
+    /// actions with their values and the actions `pressed` since the last
+    /// tick (which may already be released again). Replays use this for both
+    /// recording and playback so they see the same transitions.
+    pub fn set_tick(&mut self, held: &[(String, f32)], pressed: &[String]) {
+        let previous = std::mem::take(&mut self.values);
+        self.values = held.iter().cloned().collect();
+        self.just_released = previous.keys().filter(|a| !self.values.contains_key(*a)).cloned().collect();
+        self.just_pressed = held
+            .iter()
+            .map(|(a, _)| a)
+            .filter(|a| !previous.contains_key(*a))
+            .chain(pressed)
+            .cloned()
+            .collect();
+    }
+
+    /// Actions that started this frame.
+    pub fn just_pressed_actions(&self) -> impl Iterator<Item = &str> {
+        self.just_pressed.iter().map(String::as_str)
+    }
+
+    /// Held actions with their values.
+    pub fn held(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.values.iter().map(|(a, v)| (a.as_str(), *v))
+    }
+}
+
+/// Action state of the current simulation step (see the module docs).
+/// Dereferences to the `ActionState` the fixed-step systems read.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FixedActionState {
+    actions: ActionState,
+    pending: BTreeSet<String>,
+}
+
+impl FixedActionState {
+    /// Remember the actions `live` pressed this frame for the next step.
+    pub fn latch(&mut self, live: &ActionState) {
+        self.pending.extend(live.just_pressed_actions().map(str::to_string));
+    }
+
+    /// Start a simulation step: the actions held in `live` plus every press
+    /// latched since the previous step.
+    pub fn begin_step(&mut self, live: &ActionState) {
+        let held: Vec<(String, f32)> = live.held().map(|(a, v)| (a.to_string(), v)).collect();
+        let pressed: Vec<String> = std::mem::take(&mut self.pending).into_iter().collect();
+        self.actions.set_tick(&held, &pressed);
+    }
+}
+
+impl std::ops::Deref for FixedActionState {
+    type Target = ActionState;
+
+    fn deref(&self) -> &ActionState {
+        &self.actions
+    }
+}
+
+/// Rebuild the `InputMap` when the settings change (hot reload, settings
+/// menu) and report conflicts and unknown inputs.
+///
+/// # Arguments
+/// * `settings` - settings providing `controls.keybinds`
+/// * `map` - the input map
+#[allow(clippy::needless_pass_by_value)]
+pub fn rebuild_input_map(settings: Res<Settings>, mut map: ResMut<InputMap>) {
+    if !settings.is_changed() {
+        return;
+    }
+    *map = InputMap::from_settings(&settings.controls);
+    for error in &map.errors {
+        warn!("Keybind {error}");
+    }
+    for conflict in &map.conflicts {
+        warn!("Keybind conflict: {} is bound to {}", conflict.binding, conflict.actions.join(", "));
+    }
+}
+
+/// Evaluate the input map into `ActionState`. Runs in `PreUpdate` after the
+/// systems that consume raw input (console, keybind capture).
+///
+/// # Arguments
+/// * `map` - the input map
+/// * `keys` / `mouse` - keyboard and mouse buttons
+/// * `gamepads` / `pad_buttons` / `pad_axes` - connected gamepads and their state
+/// * `state` - the action state, replaced every frame
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_action_state(
+    map: Res<InputMap>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    pad_axes: Res<Axis<GamepadAxis>>,
+    mut state: ResMut<ActionState>,
+) {
+    let raw = RawInput { keys: &keys, mouse: &mouse, gamepads: &gamepads, pad_buttons: &pad_buttons, pad_axes: &pad_axes };
+    let next = map.evaluate(&raw, &state);
+    if *state != next {
+        *state = next;
+    }
+}
+
+/// Latch this frame's presses into `FixedActionState`. Runs in `PreUpdate`
+/// after `update_action_state`.
+///
+/// # Arguments
+/// * `live` - this frame's action state
+/// * `fixed` - the simulation's action state
+#[allow(clippy::needless_pass_by_value)]
+pub fn latch_fixed_actions(live: Res<ActionState>, mut fixed: ResMut<FixedActionState>) {
+    if live.just_pressed_actions().next().is_some() {
+        fixed.latch(&live);
+    }
+}
+
+/// Start the simulation step's action state. Runs in `FixedFirst`.
+///
+/// # Arguments
+/// * `live` - the latest action state
+/// * `fixed` - the simulation's action state
+#[allow(clippy::needless_pass_by_value)]
+pub fn begin_fixed_actions(live: Res<ActionState>, mut fixed: ResMut<FixedActionState>) {
+    fixed.begin_step(&live);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bindings_and_resolves_combos_and_conflicts() {
+        let combo = Binding::parse("Ctrl+Z").expect("combo");
+        assert_eq!(combo.source, InputSource::Key(KeyCode::KeyZ));
+        assert!(combo.modifiers.ctrl && !combo.modifiers.shift);
+        assert_eq!(Binding::parse("Pad:LeftStickY-").expect("axis").source, InputSource::PadAxis(GamepadAxisType::LeftStickY, false));
+        assert_eq!(Binding::parse("Mouse:Right").expect("mouse").source, InputSource::Mouse(MouseButton::Right));
+        for text in ["Ctrl+Z", "Pad:South", "Pad:RightStickX+", "Mouse:Left", "F3"] {
+            assert_eq!(Binding::parse(text).expect("parses").to_string(), text);
+        }
+        assert!(Binding::parse("Hyper+Q").is_err());
+        assert_eq!(rebind_keyboard("W | Pad:LeftStickY+", "Up"), "Up | Pad:LeftStickY+");
+
+        let keybinds: BTreeMap<String, String> = [
+            ("undo", "Ctrl+Z"),
+            ("zoom", "Z"),
+            ("jump", "Space | Pad:South"),
+            ("fly", "Space"),
+            ("broken", "Nope"),
+        ]
+        .into_iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect();
+        let map = InputMap::from_keybinds(&keybinds);
+        assert!(map.in_conflict("fly") && map.in_conflict("jump") && !map.in_conflict("undo"));
+        assert_eq!(map.errors.len(), 1);
+        // Defaults fill in actions the keybinds leave out
+        assert!(!map.bindings("forward").is_empty());
+
+        let mut keys = ButtonInput::default();
+        let (mouse, gamepads, pad_buttons, pad_axes) = (ButtonInput::default(), Gamepads::default(), ButtonInput::default(), Axis::default());
+        keys.press(KeyCode::ControlLeft);
+        keys.press(KeyCode::KeyZ);
+        let raw = RawInput { keys: &keys, mouse: &mouse, gamepads: &gamepads, pad_buttons: &pad_buttons, pad_axes: &pad_axes };
+        let state = map.evaluate(&raw, &ActionState::default());
+        assert!(state.just_pressed("undo") && !state.pressed("zoom"));
+
+        keys.clear();
+        keys.release(KeyCode::ControlLeft);
+        keys.release(KeyCode::KeyZ);
+        let raw = RawInput { keys: &keys, mouse: &mouse, gamepads: &gamepads, pad_buttons: &pad_buttons, pad_axes: &pad_axes };
+        let released = map.evaluate(&raw, &state);
+        assert!(released.just_released("undo") && !released.pressed("undo"));
+    }
+
+    #[test]
+    fn fixed_steps_see_each_press_once() {
+        let mut live = ActionState::default();
+        let mut fixed = FixedActionState::default();
+        // Tapped and released between two steps: still pressed in the next one
+        live.press("jump", 1.0);
+        fixed.latch(&live);
+        live.release("jump");
+        fixed.begin_step(&live);
+        assert!(fixed.just_pressed("jump") && !fixed.pressed("jump"));
+        // A second step in the same frame does not repeat it
+        fixed.begin_step(&live);
+        assert!(!fixed.just_pressed("jump"));
+    }
+}
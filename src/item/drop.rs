@@ -0,0 +1,124 @@
+//! Dropped items lying in the world.
+//!
+//! A `DroppedItem` is a small entity holding an `ItemStack`. It falls with
+//! the player's gravity until it rests on a block and is picked up into the
+//! `Inventory` once the player walks close to it. Drops are drawn as a
+//! shrunken copy of a block mesh (see `BlockMeshCache`).
+
+use super::{Inventory, ItemRegistry, ItemStack};
+use crate::block::blocks;
+use crate::chunk::VoxelMaterialHandle;
+use crate::player::{Player, GRAVITY, TERMINAL_VELOCITY};
+use crate::world::World;
+use bevy::prelude::*;
+
+/// Distance (from the player's body centre) at which drops are collected.
+pub const PICKUP_RADIUS: f32 = 1.5;
+
+/// Seconds before a fresh drop can be collected, so it is visible briefly.
+pub const PICKUP_DELAY: f32 = 0.5;
+
+/// Edge length of a dropped item cube, in blocks.
+pub const DROP_SIZE: f32 = 0.3;
+
+/// An item stack lying in the world.
+#[derive(Component, Debug)]
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    pub velocity: Vec3,
+    pub age: f32, // Seconds since the drop was spawned
+}
+
+/// Spawn a dropped item centred on `center`.
+///
+/// # Arguments
+/// * `commands` - `Commands` to spawn the entity
+/// * `center` - world position of the drop's centre
+/// * `stack` - the items held by the drop
+/// * `mesh` - unit block mesh (spanning `0..1`) used to draw the drop
+/// * `material` - voxel material the mesh is drawn with
+///
+/// # Return
+/// The spawned entity.
+pub fn spawn_item_drop(
+    commands: &mut Commands,
+    center: Vec3,
+    stack: ItemStack,
+    mesh: Handle<Mesh>,
+    material: &VoxelMaterialHandle,
+) -> Entity {
+    commands
+        .spawn((
+            MaterialMeshBundle {
+                mesh,
+                material: material.0.clone(),
+                transform: Transform::from_translation(center - Vec3::splat(DROP_SIZE * 0.5))
+                    .with_scale(Vec3::splat(DROP_SIZE)),
+                ..default()
+            },
+            DroppedItem { stack, velocity: Vec3::ZERO, age: 0.0 },
+        ))
+        .id()
+}
+
+/// Let drops fall onto the ground and move them into the inventory when the
+/// player is close enough. Drops that do not fully fit keep the remainder.
+///
+/// # Arguments
+/// * `commands` - `Commands` used to despawn collected drops
+/// * `time` - time resource for delta timing
+/// * `world` - world access for ground checks
+/// * `items` - item registry used to look up stack sizes
+/// * `inventory` - inventory drops are collected into
+/// * `player` - player transform
+/// * `drops` - dropped item entities
+#[allow(clippy::needless_pass_by_value, clippy::cast_possible_truncation)]
+pub fn update_item_drops(
+    mut commands: Commands,
+    time: Res<Time>,
+    world: Res<World>,
+    items: Res<ItemRegistry>,
+    mut inventory: ResMut<Inventory>,
+    player: Query<&Transform, (With<Player>, Without<DroppedItem>)>,
+    mut drops: Query<(Entity, &mut Transform, &mut DroppedItem)>,
+) {
+    let dt = time.delta_seconds();
+    // Body centre: the player transform sits at eye height (1.7 above the feet)
+    let body = player.get_single().ok().map(|t| t.translation - Vec3::Y * 0.85);
+
+    for (entity, mut tf, mut drop) in &mut drops {
+        drop.age += dt;
+
+        let bottom = tf.translation;
+        let below = world.get_block(
+            (bottom.x + DROP_SIZE * 0.5).floor() as i32,
+            (bottom.y - 0.01).floor() as i32,
+            (bottom.z + DROP_SIZE * 0.5).floor() as i32,
+        );
+        if below == blocks::AIR {
+            drop.velocity.y = (drop.velocity.y + GRAVITY * dt).max(TERMINAL_VELOCITY);
+            tf.translation += drop.velocity * dt;
+            if tf.translation.y < 0.0 {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        } else if drop.velocity.y < 0.0 {
+            drop.velocity = Vec3::ZERO;
+            tf.translation.y = (bottom.y - 0.01).floor() + 1.0;
+        }
+
+        let Some(body) = body else { continue };
+        let center = tf.translation + Vec3::splat(DROP_SIZE * 0.5);
+        if drop.age < PICKUP_DELAY || center.distance(body) > PICKUP_RADIUS {
+            continue;
+        }
+        let Some(item) = items.get(&drop.stack.item) else { continue };
+
+        let left = inventory.add(item, drop.stack.count);
+        if left == 0 {
+            commands.entity(entity).despawn();
+        } else {
+            drop.stack.count = left;
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Item icon atlas.
+//!
+//! Item icons are packed into their own atlas (`assets/textures/items/atlas.png`)
+//! separate from the block atlas so inventory/HUD code can draw any item with
+//! a single texture. Block items use their block's side texture as an icon.
+//! The block `default.png` tile is always packed so items with a missing icon
+//! still render something. Tiles are keyed by the icon's path, so icons with
+//! the same file name in different directories stay apart.
+//!
+//! The atlas is built at startup (`app::setup::setup_item_icons`) and rebuilt
+//! by `refresh_item_icon_atlas` when the item registry is hot reloaded.
+
+use super::{ItemId, ItemRegistry};
+use crate::atlas::{AtlasBuilder, UVBounds};
+use bevy::prelude::{AssetServer, Handle, Image, Resource};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Path (relative to `assets/`) of the generated item icon atlas.
+pub const ITEM_ATLAS_PATH: &str = "textures/items/atlas.png";
+
+/// Fallback icon packed into every item atlas.
+const FALLBACK_ICON: &str = "textures/blocks/default.png";
+
+/// Runtime resource holding the item icon atlas image and per-item UVs.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ItemIconAtlas {
+    pub image: Handle<Image>, // Handle to the loaded atlas image
+    pub uvs: HashMap<ItemId, UVBounds>, // Item id -> icon UV bounds in the atlas
+    pub fallback: UVBounds, // UVs used for items without a packed icon
+}
+
+impl ItemIconAtlas {
+    /// UV bounds of the icon for `id`, falling back to the default tile.
+    #[must_use]
+    pub fn icon_uvs(&self, id: ItemId) -> UVBounds {
+        self.uvs.get(&id).copied().unwrap_or(self.fallback)
+    }
+}
+
+/// Pack every icon referenced by `registry` into the item atlas on disk.
+///
+/// # Errors
+/// Returns an `Err` if no icon (including the fallback) could be loaded or
+/// the atlas image cannot be written.
+///
+/// # Return
+/// The per-item UV map and the fallback UVs. The caller is responsible for
+/// loading `ITEM_ATLAS_PATH` through the asset server.
+pub fn build_item_icon_atlas(
+    registry: &ItemRegistry,
+) -> Result<(HashMap<ItemId, UVBounds>, UVBounds), Box<dyn std::error::Error>> {
+    let assets = Path::new("assets");
+    // The fallback tile is named "default" so `get_uv_bounds` falls back to it
+    let mut files: Vec<(String, PathBuf)> = vec![("default".to_string(), assets.join(FALLBACK_ICON))];
+    let mut icons: Vec<&str> = registry.items.values().map(|i| i.icon.as_str()).collect();
+    icons.sort_unstable();
+    icons.dedup();
+    files.extend(icons.into_iter().map(|icon| (icon.to_string(), assets.join(icon))));
+
+    let info = AtlasBuilder::build_atlas_from_files(&files, &assets.join(ITEM_ATLAS_PATH))?;
+
+    let uvs = registry.items.values().map(|item| (item.id, info.get_uv_bounds(&item.icon))).collect();
+
+    Ok((uvs, info.get_uv_bounds("default")))
+}
+
+/// Repack the item atlas after the registry changed and reload its image.
+///
+/// On failure the old atlas is kept and the error is printed.
+///
+/// # Arguments
+/// * `atlas` - The `ItemIconAtlas` resource to update
+/// * `registry` - The reloaded item registry
+/// * `asset_server` - Used to reload the rewritten atlas image
+pub fn refresh_item_icon_atlas(atlas: &mut ItemIconAtlas, registry: &ItemRegistry, asset_server: &AssetServer) {
+    match build_item_icon_atlas(registry) {
+        Ok((uvs, fallback)) => {
+            atlas.uvs = uvs;
+            atlas.fallback = fallback;
+            asset_server.reload(ITEM_ATLAS_PATH);
+        }
+        Err(e) => eprintln!("Failed to rebuild item icon atlas: {e}"),
+    }
+}
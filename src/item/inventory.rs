@@ -0,0 +1,201 @@
+//! Player inventory.
+//!
+//! The inventory is a fixed row of slots holding `ItemStack`s. The first
+//! `HOTBAR_SLOTS` slots make up the hotbar and the selected hotbar slot is
+//! the item placed/used with right-click. Stacks store item names rather
+//! than ids so they survive item registry reloads.
+//!
+//! Example:
+//! ```rust
+//! use stratum::item::{Inventory, Item};
+//!
+//! let mut stick = Item::default();
+//! stick.max_stack_size = 64;
+//!
+//! let mut inventory = Inventory::default();
+//! assert_eq!(inventory.add(&stick, 70), 0);
+//! assert_eq!(inventory.count("stick"), 70);
+//! assert!(inventory.remove("stick", 10));
+//! assert_eq!(inventory.count("stick"), 60);
+//! ```
+
+use super::Item;
+use crate::input::ActionState;
+use bevy::prelude::*;
+
+/// Number of slots in the player inventory (including the hotbar).
+pub const INVENTORY_SLOTS: usize = 36;
+
+/// Number of hotbar slots, selected with the number keys.
+pub const HOTBAR_SLOTS: usize = 9;
+
+/// Items a new player starts with, so there is something to place and
+/// craft with in survival.
+pub const STARTING_ITEMS: &[(&str, u32)] = &[("dirt", 32), ("planks", 16), ("stick", 4)];
+
+/// A number of the same item occupying one inventory slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: String, // Item name (see `ItemRegistry`)
+    pub count: u32,
+}
+
+/// The player's inventory resource.
+#[derive(Resource, Debug, Clone)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+    pub selected: usize, // Selected hotbar slot (0..HOTBAR_SLOTS)
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; INVENTORY_SLOTS],
+            selected: 0,
+        }
+    }
+}
+
+impl Inventory {
+    /// An inventory holding `STARTING_ITEMS`; items missing from `items`
+    /// are left out.
+    #[must_use]
+    pub fn with_starting_items(items: &super::ItemRegistry) -> Self {
+        let mut inventory = Self::default();
+        for (name, count) in STARTING_ITEMS {
+            if let Some(item) = items.get(name) {
+                inventory.add(item, *count);
+            }
+        }
+        inventory
+    }
+
+    /// Add `count` of `item`, topping up existing stacks before using empty
+    /// slots. Stacks never exceed the item's `max_stack_size`.
+    ///
+    /// # Return
+    /// The number of items that did not fit.
+    pub fn add(&mut self, item: &Item, mut count: u32) -> u32 {
+        let max = item.max_stack_size.max(1);
+
+        for stack in self.slots.iter_mut().flatten() {
+            if count == 0 {
+                break;
+            }
+            if stack.item == item.name && stack.count < max {
+                let moved = count.min(max - stack.count);
+                stack.count += moved;
+                count -= moved;
+            }
+        }
+
+        for slot in &mut self.slots {
+            if count == 0 {
+                break;
+            }
+            if slot.is_none() {
+                let moved = count.min(max);
+                *slot = Some(ItemStack { item: item.name.clone(), count: moved });
+                count -= moved;
+            }
+        }
+
+        count
+    }
+
+    /// Total number of items named `name` across all slots.
+    #[must_use]
+    pub fn count(&self, name: &str) -> u32 {
+        self.count_where(|item| item == name)
+    }
+
+    /// Total number of items whose name satisfies `matches`.
+    #[must_use]
+    pub fn count_where(&self, matches: impl Fn(&str) -> bool) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|s| matches(&s.item))
+            .map(|s| s.count)
+            .sum()
+    }
+
+    /// Remove `count` items named `name`. Nothing is removed unless the
+    /// inventory holds at least `count` of them.
+    ///
+    /// # Return
+    /// `true` if the items were removed.
+    pub fn remove(&mut self, name: &str, count: u32) -> bool {
+        self.remove_where(count, |item| item == name)
+    }
+
+    /// Remove `count` items whose name satisfies `matches`, taking from the
+    /// last slots first so the hotbar is emptied last. Nothing is removed
+    /// unless enough matching items are present.
+    ///
+    /// # Return
+    /// `true` if the items were removed.
+    pub fn remove_where(&mut self, mut count: u32, matches: impl Fn(&str) -> bool) -> bool {
+        if self.count_where(&matches) < count {
+            return false;
+        }
+
+        for slot in self.slots.iter_mut().rev() {
+            if count == 0 {
+                break;
+            }
+            let Some(stack) = slot else { continue };
+            if !matches(&stack.item) {
+                continue;
+            }
+            let taken = count.min(stack.count);
+            stack.count -= taken;
+            count -= taken;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+
+        true
+    }
+
+    /// The stack in the selected hotbar slot, if any.
+    #[must_use]
+    pub fn selected_stack(&self) -> Option<&ItemStack> {
+        self.slots.get(self.selected).and_then(Option::as_ref)
+    }
+
+    /// Remove a single item from the selected hotbar slot.
+    ///
+    /// # Return
+    /// `true` if an item was taken.
+    pub fn take_selected(&mut self) -> bool {
+        let Some(slot) = self.slots.get_mut(self.selected) else { return false };
+        let Some(stack) = slot else { return false };
+        stack.count -= 1;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        true
+    }
+}
+
+/// Select a hotbar slot with the `hotbar_1`-`hotbar_9` actions (number keys
+/// by default), or step through the slots with `hotbar_next`/`hotbar_prev`.
+///
+/// # Arguments
+/// * `actions` - action state
+/// * `inventory` - the player inventory whose `selected` slot is updated
+#[allow(clippy::needless_pass_by_value)]
+pub fn select_hotbar_slot(actions: Res<ActionState>, mut inventory: ResMut<Inventory>) {
+    const ACTIONS: [&str; HOTBAR_SLOTS] =
+        ["hotbar_1", "hotbar_2", "hotbar_3", "hotbar_4", "hotbar_5", "hotbar_6", "hotbar_7", "hotbar_8", "hotbar_9"];
+
+    if let Some(slot) = ACTIONS.iter().position(|a| actions.just_pressed(a)) {
+        inventory.selected = slot;
+    } else if actions.just_pressed("hotbar_next") {
+        inventory.selected = (inventory.selected + 1) % HOTBAR_SLOTS;
+    } else if actions.just_pressed("hotbar_prev") {
+        inventory.selected = (inventory.selected + HOTBAR_SLOTS - 1) % HOTBAR_SLOTS;
+    }
+}
@@ -0,0 +1,125 @@
+//! Item loader and watcher for loading item definitions from RON files
+//! and monitoring changes for hot reloading during runtime.
+//!
+//! Loading an item registry needs the `BlockRegistry`: every block gets a
+//! block item, and block `drop_item` names are validated against the
+//! resulting registry so typos show up at load time instead of as missing
+//! drops in game.
+//!
+//! # Example
+//! ```
+//! use stratum::block::loader as block_loader;
+//! use stratum::item::loader as item_loader;
+//!
+//! let blocks = block_loader::load_blocks_from_dir("data/blocks");
+//! let items = item_loader::load_items_from_dir("data/items", &blocks);
+//! assert!(items.get("dirt").is_some());
+//! ```
+
+use super::icons::{refresh_item_icon_atlas, ItemIconAtlas};
+use super::{Item, ItemRegistry};
+use crate::block::BlockRegistry;
+use crate::ron_loader::{load_ron_files, setup_ron_watcher};
+use bevy::prelude::{AssetServer, DetectChanges, Res, ResMut, Resource};
+
+#[derive(Resource)]
+pub struct ItemWatcher(pub crate::ron::RonWatcher);
+
+/// Load all item definitions from RON files and add block items.
+///
+/// Items with an id below `FIRST_ITEM_ID` or an id already taken are
+/// reported and skipped. Problems found while validating block drops and
+/// block items are printed to stderr; they do not abort loading.
+///
+/// # Arguments
+/// * `path` - The directory path where item RON files are located (e.g., "data/items").
+/// * `blocks` - The loaded block registry used to create block items and validate drops.
+///
+/// # Returns
+/// An `ItemRegistry` containing all loaded items, indexed by both name and numeric ID.
+#[must_use]
+pub fn load_items_from_dir(path: &str, blocks: &BlockRegistry) -> ItemRegistry {
+    let mut registry = ItemRegistry::default();
+    let mut items: Vec<Item> = load_ron_files(path);
+    items.sort_by_key(|i| i.id);
+
+    for item in items {
+        if item.id < super::FIRST_ITEM_ID && item.placed_block().is_none() {
+            eprintln!(
+                "Item '{}' uses id {} which is reserved for block items (use {} or above); skipping",
+                item.name, item.id, super::FIRST_ITEM_ID
+            );
+            continue;
+        }
+        if let Some(existing) = registry.items_by_id.get(&item.id) {
+            eprintln!("Item '{}' reuses id {} of item '{existing}'; skipping", item.name, item.id);
+            continue;
+        }
+        registry.register(item);
+    }
+
+    registry.add_block_items(blocks);
+
+    for problem in registry
+        .validate_block_items(blocks)
+        .into_iter()
+        .chain(registry.validate_block_drops(blocks))
+    {
+        eprintln!("Item validation: {problem}");
+    }
+
+    registry
+}
+
+/// Set up a file watcher to monitor changes in item RON files.
+///
+/// # Errors
+/// Returns a `notify::Error` if the underlying file watcher could not be created or configured.
+pub fn setup_item_watcher(path: &str) -> Result<ItemWatcher, notify::Error> {
+    setup_ron_watcher(path).map(ItemWatcher)
+}
+
+/// Reload the item registry when item files change or the block registry is
+/// reloaded (block items and drop validation depend on it), then repack the
+/// item icon atlas so new or changed icons show up.
+///
+/// # Arguments
+/// * `watcher` - An `ItemWatcher` resource that monitors changes in item RON files
+/// * `registry` - The `ItemRegistry` resource replaced on reload
+/// * `blocks` - The current `BlockRegistry`
+/// * `icons` - The item icon atlas, if it was built
+/// * `asset_server` - Used to reload the rebuilt atlas image
+#[allow(clippy::needless_pass_by_value)]
+pub fn check_item_changes(
+    watcher: Res<ItemWatcher>,
+    mut registry: ResMut<ItemRegistry>,
+    blocks: Res<BlockRegistry>,
+    icons: Option<ResMut<ItemIconAtlas>>,
+    asset_server: Res<AssetServer>,
+) {
+    let blocks_reloaded = blocks.is_changed() && !blocks.is_added();
+    let mut flag = match watcher.0.changed.lock() {
+        Ok(flag) => flag,
+        Err(poisoned) => {
+            eprintln!("warning: item watcher mutex poisoned — recovering");
+            poisoned.into_inner()
+        }
+    };
+
+    if *flag || blocks_reloaded {
+        println!("Items changed, reloading...");
+        *registry = load_items_from_dir("data/items", &blocks);
+        *flag = false;
+        if let Some(mut icons) = icons {
+            refresh_item_icon_atlas(&mut icons, &registry, &asset_server);
+        }
+    }
+}
+
+impl ItemWatcher {
+    /// Create a stub `ItemWatcher` that does not have an active OS watcher.
+    #[must_use]
+    pub fn stub() -> Self {
+        ItemWatcher(crate::ron::RonWatcher::stub())
+    }
+}
@@ -0,0 +1,40 @@
+//! This module contains the item types and helpers.
+//! It exposes item definitions (`Item`), the `ItemRegistry` which stores
+//! all loaded items, the player `Inventory`, dropped items, the item icon
+//! atlas and the runtime loader/watchers used for hot-reloading item data
+//! from RON files.
+//!
+//! Items are separate from blocks: a block's `drop_item` names an item, and
+//! placing a block is done through a block item (`ItemKind::Block`).
+//!
+//! Example:
+//!
+//! ```rust
+//! use stratum::block::loader as block_loader;
+//! use stratum::item::loader as item_loader;
+//!
+//! let blocks = block_loader::load_blocks_from_dir("data/blocks");
+//! let items = item_loader::load_items_from_dir("data/items", &blocks);
+//! // every block has a matching block item
+//! assert_eq!(items.block_for_item("dirt", &blocks), blocks.id_for_name("dirt"));
+//! ```
+
+/// Loader/watchers for item RON files.
+pub mod loader;
+
+/// Item registry and related data structures.
+pub mod registry;
+
+/// Item icon atlas building and lookup.
+pub mod icons;
+
+/// Player inventory and hotbar selection.
+pub mod inventory;
+
+/// Dropped items lying in the world.
+pub mod drop;
+
+pub use drop::{spawn_item_drop, update_item_drops, DroppedItem};
+pub use icons::ItemIconAtlas;
+pub use inventory::{select_hotbar_slot, Inventory, ItemStack, HOTBAR_SLOTS, INVENTORY_SLOTS};
+pub use registry::{Item, ItemId, ItemKind, ItemRegistry, ToolClass, ToolProperties, FIRST_ITEM_ID};
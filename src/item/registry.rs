@@ -0,0 +1,304 @@
+//! This module defines the `Item` and `ItemRegistry` types. Items are
+//! everything that can live in an inventory slot: tools, ore drops, food
+//! and "block items" which place a block when used.
+//!
+//! Every registered block automatically gets a block item with the same
+//! name and numeric id (see `ItemRegistry::add_block_items`), so block ids
+//! `0..=255` are reserved for block items and hand-authored items in
+//! `data/items` must use ids from `FIRST_ITEM_ID` upwards.
+//!
+//! Example:
+//! ```rust
+//! use stratum::item::{Item, ItemKind, ItemRegistry};
+//!
+//! let mut registry = ItemRegistry::default();
+//! let mut stick = Item::default();
+//! stick.name = "stick".to_string();
+//! stick.id = 256;
+//! registry.register(stick);
+//!
+//! assert_eq!(registry.id_for_name("stick"), Some(256));
+//! assert!(matches!(registry.get("stick").unwrap().kind, ItemKind::Material));
+//! ```
+use crate::block::{BlockId, BlockRegistry};
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Type used to represent a compact item identifier.
+///
+/// Wider than `BlockId` because items are not stored per-voxel and the
+/// lower 256 ids are reserved for block items.
+pub type ItemId = u16;
+
+/// First id available to hand-authored (non-block) items.
+pub const FIRST_ITEM_ID: ItemId = 256;
+
+/// The class of tool an item belongs to, used to match `tool_required` on blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolClass {
+    Pickaxe,
+    Shovel,
+    Axe,
+    Hoe,
+    Sword,
+    Shears,
+}
+
+impl ToolClass {
+    /// Parse a tool class from the lowercase names used in block RON files
+    /// (e.g. `tool_required: Some("shovel")`).
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "pickaxe" => Some(Self::Pickaxe),
+            "shovel" => Some(Self::Shovel),
+            "axe" => Some(Self::Axe),
+            "hoe" => Some(Self::Hoe),
+            "sword" => Some(Self::Sword),
+            "shears" => Some(Self::Shears),
+            _ => None,
+        }
+    }
+}
+
+/// Tool properties for items of kind `ItemKind::Tool`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolProperties {
+    pub class: ToolClass, // Which blocks this tool is effective on
+    #[serde(default)]
+    pub tier: u8, // Tool tier (0 = wood, 1 = stone, 2 = iron, 3 = diamond, ...)
+    #[serde(default = "ToolProperties::default_speed")]
+    pub speed: f32, // Mining speed multiplier against matching blocks
+}
+
+impl ToolProperties {
+    fn default_speed() -> f32 { 1.0 }
+}
+
+/// What an item does when used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum ItemKind {
+    /// Plain crafting material / drop with no use action.
+    #[default]
+    Material,
+    /// Places the named block when used.
+    Block(String),
+    /// A tool with a class and tier.
+    Tool(ToolProperties),
+    /// Consumable food restoring `nutrition` points.
+    Food { nutrition: f32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Item {
+    pub name: String,
+    pub id: ItemId,
+
+    #[serde(default)]
+    pub display_name: Option<String>, // Human readable name shown in the UI (defaults to `name`)
+    #[serde(default = "Item::default_icon")]
+    pub icon: String, // Icon texture path relative to `assets/`, packed into the item icon atlas
+    #[serde(default)]
+    pub kind: ItemKind,
+    #[serde(default = "Item::default_max_stack_size")]
+    pub max_stack_size: u32, // Maximum number that can stack in one inventory slot
+    #[serde(default)]
+    pub durability: Option<u32>, // Number of uses before the item breaks (tools only)
+    #[serde(default)]
+    pub tags: Vec<String>, // Free-form tags used by recipes and gameplay lookups
+}
+
+impl Item {
+    fn default_icon() -> String { "textures/blocks/default.png".to_string() }
+    fn default_max_stack_size() -> u32 { 64 }
+
+    /// Whether this item carries `tag`.
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Name shown to the player.
+    #[must_use]
+    pub fn label(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Name of the block this item places, if it is a block item.
+    #[must_use]
+    pub fn placed_block(&self) -> Option<&str> {
+        match &self.kind {
+            ItemKind::Block(block) => Some(block.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Tool properties, if this item is a tool.
+    #[must_use]
+    pub fn tool(&self) -> Option<&ToolProperties> {
+        match &self.kind {
+            ItemKind::Tool(tool) => Some(tool),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Item {
+    fn default() -> Self {
+        Self {
+            name: "stick".to_string(),
+            id: FIRST_ITEM_ID,
+            display_name: None,
+            icon: Self::default_icon(),
+            kind: ItemKind::Material,
+            max_stack_size: Self::default_max_stack_size(),
+            durability: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+#[derive(Resource, Default, Clone)]
+pub struct ItemRegistry {
+    pub items: HashMap<String, Item>,
+    pub items_by_id: HashMap<ItemId, String>,
+}
+
+impl ItemRegistry {
+    pub fn register(&mut self, item: Item) {
+        self.items_by_id.insert(item.id, item.name.clone());
+        self.items.insert(item.name.clone(), item);
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Item> {
+        self.items.get(name)
+    }
+
+    #[must_use]
+    pub fn get_by_id(&self, id: ItemId) -> Option<&Item> {
+        self.items_by_id
+            .get(&id)
+            .and_then(|name| self.items.get(name))
+    }
+
+    /// Lookup numeric ID for an item `name`.
+    #[must_use]
+    pub fn id_for_name(&self, name: &str) -> Option<ItemId> {
+        self.items.get(name).map(|i| i.id)
+    }
+
+    /// Register a block item for every block that does not already have an
+    /// item with the same name. Block items reuse the block's numeric id and
+    /// tags, and take their icon from the block's side texture. The
+    /// `__missing__` placeholder block is skipped.
+    pub fn add_block_items(&mut self, blocks: &BlockRegistry) {
+        let missing_id = blocks.missing_id();
+        let mut sorted: Vec<_> = blocks.blocks.values().filter(|b| b.id != missing_id).collect();
+        sorted.sort_by_key(|b| b.id);
+
+        for block in sorted {
+            if self.items.contains_key(&block.name) {
+                continue;
+            }
+            let id = ItemId::from(block.id);
+            if let Some(existing) = self.items_by_id.get(&id) {
+                eprintln!("Item '{existing}' uses id {id}, which is reserved for block item '{}'", block.name);
+                continue;
+            }
+            self.register(Item {
+                name: block.name.clone(),
+                id,
+                icon: block.textures.side.clone(),
+                kind: ItemKind::Block(block.name.clone()),
+                max_stack_size: block.max_stack_size,
+                tags: block.tags.clone(),
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Resolve the block placed by the item `name`.
+    ///
+    /// # Return
+    /// The `BlockId` to place, or `None` if the item does not exist, is not a
+    /// block item, or names a block missing from `blocks`.
+    #[must_use]
+    pub fn block_for_item(&self, name: &str, blocks: &BlockRegistry) -> Option<BlockId> {
+        self.get(name)
+            .and_then(Item::placed_block)
+            .and_then(|block| blocks.id_for_name(block))
+    }
+
+    /// Check every block's `drop_item` against this registry.
+    ///
+    /// # Return
+    /// A list of human readable problems; empty when every drop resolves.
+    #[must_use]
+    pub fn validate_block_drops(&self, blocks: &BlockRegistry) -> Vec<String> {
+        let missing_id = blocks.missing_id();
+        let mut problems: Vec<String> = blocks
+            .blocks
+            .values()
+            .filter(|b| b.id != missing_id && b.drop_count > 0 && !b.drop_item.is_empty())
+            .filter(|b| !self.items.contains_key(&b.drop_item))
+            .map(|b| format!("block '{}' drops unknown item '{}'", b.name, b.drop_item))
+            .collect();
+        problems.sort();
+        problems
+    }
+
+    /// Check that block items point at blocks that exist.
+    ///
+    /// # Return
+    /// A list of human readable problems; empty when every block item resolves.
+    #[must_use]
+    pub fn validate_block_items(&self, blocks: &BlockRegistry) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .items
+            .values()
+            .filter_map(|i| i.placed_block().map(|b| (i, b)))
+            .filter(|(_, block)| blocks.get(block).is_none())
+            .map(|(item, block)| format!("item '{}' places unknown block '{block}'", item.name))
+            .collect();
+        problems.sort();
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::default();
+        blocks.register(Block { name: "dirt".to_string(), id: 2, drop_item: "dirt".to_string(), ..Default::default() });
+        blocks.register(Block { name: "ore".to_string(), id: 3, drop_item: "raw_ore".to_string(), ..Default::default() });
+        blocks
+    }
+
+    #[test]
+    fn block_items_place_their_block() {
+        let blocks = blocks();
+        let mut items = ItemRegistry::default();
+        items.add_block_items(&blocks);
+
+        assert_eq!(items.id_for_name("dirt"), Some(2));
+        assert_eq!(items.block_for_item("dirt", &blocks), Some(2));
+    }
+
+    #[test]
+    fn unknown_drops_are_reported() {
+        let blocks = blocks();
+        let mut items = ItemRegistry::default();
+        items.add_block_items(&blocks);
+
+        assert_eq!(items.validate_block_drops(&blocks), vec!["block 'ore' drops unknown item 'raw_ore'".to_string()]);
+
+        items.register(Item { name: "raw_ore".to_string(), ..Default::default() });
+        assert!(items.validate_block_drops(&blocks).is_empty());
+    }
+}
@@ -4,6 +4,8 @@ pub mod atlas_builder {
 }
 pub mod biome;
 pub mod block;
+pub mod item;
+pub mod crafting;
 pub mod chunk;
 pub mod player;
 pub mod ron;
@@ -15,4 +17,5 @@ pub mod world;
 
 pub mod lighting;
 pub mod settings;
+pub mod input;
 pub mod debug;
\ No newline at end of file
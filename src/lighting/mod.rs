@@ -1,5 +1,8 @@
 use bevy::prelude::*;
 
+/// Night sky maths (moon phases, stars, moonlight).
+pub mod sky;
+
 /// Result of the daylight math for a single time/sample.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct DaylightInfo {
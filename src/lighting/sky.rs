@@ -0,0 +1,185 @@
+//! Night sky maths: moon phases and position, the star field layout and
+//! the faint moonlight.
+//!
+//! The moon follows the same circle as the sun (see `WorldTime::sun_angle`),
+//! offset by its phase: at new moon it rises with the sun, at full moon it
+//! rises at dusk opposite it. A full cycle of `MOON_PHASES` phases takes
+//! `MOON_CYCLE_DAYS` days and a new world starts at full moon. The functions
+//! here are pure so the night sky systems (`app::night_sky`) and tests share
+//! them.
+
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+/// Days for the moon to go through all its phases.
+pub const MOON_CYCLE_DAYS: f32 = 8.0;
+
+/// Distinct moon phase images (new, waxing crescent, ..., waning crescent).
+pub const MOON_PHASES: usize = 8;
+
+/// Illuminance (lux) of the moonlight at full moon high in the night sky.
+pub const MOON_MAX_ILLUMINANCE: f32 = 40.0;
+
+/// Colour of the moonlight.
+pub const MOON_LIGHT_COLOR: Vec3 = Vec3::new(0.62, 0.70, 1.0);
+
+/// Stars in the star field.
+pub const STAR_COUNT: usize = 1500;
+
+/// A star of the star field.
+///
+/// # Fields
+/// * `direction` - unit direction from the viewer
+/// * `size` - angular size (radians)
+/// * `brightness` - 0..1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Star {
+    pub direction: Vec3,
+    pub size: f32,
+    pub brightness: f32,
+}
+
+/// Position in the moon's cycle (0..1) on `day` at `time_of_day`:
+/// 0 is new moon, 0.5 full moon.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn moon_phase(day: u64, time_of_day: f32) -> f32 {
+    let days = (day % MOON_CYCLE_DAYS as u64) as f32 + time_of_day;
+    (days / MOON_CYCLE_DAYS + 0.5).fract()
+}
+
+/// Index (0..`MOON_PHASES`) of the phase image closest to `phase`.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn moon_phase_index(phase: f32) -> usize {
+    (phase * MOON_PHASES as f32).round() as usize % MOON_PHASES
+}
+
+/// Lit fraction of the moon's disc (0 at new moon, 1 at full moon).
+#[must_use]
+pub fn moon_illumination(phase: f32) -> f32 {
+    (1.0 - (phase * TAU).cos()) * 0.5
+}
+
+/// Angle of the moon around the world for the sun at `sun_angle`.
+#[must_use]
+pub fn moon_angle(sun_angle: f32, phase: f32) -> f32 {
+    sun_angle + phase * TAU
+}
+
+/// Unit direction towards a body at `angle` on the sun's circle (0 rises in
+/// the east of the +z horizon, pi/2 is overhead).
+#[must_use]
+pub fn sky_direction(angle: f32) -> Vec3 {
+    Vec3::new(0.0, angle.sin(), angle.cos())
+}
+
+/// Illuminance (lux) of the moonlight for a moon at height `moon_height`
+/// (sine of its altitude) in a sky `night_factor` dark.
+#[must_use]
+pub fn moonlight_illuminance(phase: f32, moon_height: f32, night_factor: f32) -> f32 {
+    let above_horizon = super::smoothstep(moon_height / 0.15);
+    MOON_MAX_ILLUMINANCE * moon_illumination(phase) * above_horizon * night_factor
+}
+
+/// Opacity of the star field for a sky `night_factor` dark: stars appear
+/// once dusk is well under way.
+#[must_use]
+pub fn star_alpha(night_factor: f32) -> f32 {
+    super::smoothstep((night_factor - 0.35) / 0.65)
+}
+
+/// RGBA8 pixels of a `size` x `size` moon image at `phase`: a lit disc with
+/// the terminator where sunlight from the phase angle stops, faint
+/// earthshine on the dark side and a transparent background. The sun is
+/// towards -x (left) while the moon waxes.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn moon_phase_image(phase: f32, size: u32) -> Vec<u8> {
+    let angle = phase * TAU;
+    let light = Vec3::new(-angle.sin(), 0.0, -angle.cos());
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for py in 0..size {
+        for px in 0..size {
+            let x = (px as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+            let y = 1.0 - (py as f32 + 0.5) / size as f32 * 2.0;
+            let r2 = x * x + y * y;
+            if r2 > 1.0 {
+                pixels.extend([0, 0, 0, 0]);
+                continue;
+            }
+            let normal = Vec3::new(x, y, (1.0 - r2).sqrt());
+            let lit = super::smoothstep(normal.dot(light) / 0.08 + 0.5);
+            // A few darker "seas" so the disc does not look flat
+            let sea = ((x * 7.0).sin() * (y * 5.0 + 1.0).cos()).max(0.0) * 0.12;
+            let shade = (0.06 + lit * (0.94 - sea)).clamp(0.0, 1.0);
+            let edge = super::smoothstep((1.0 - r2.sqrt()) * size as f32 * 0.5);
+            let alpha = edge * (0.25 + lit * 0.75);
+            pixels.extend([
+                (shade * 235.0) as u8,
+                (shade * 238.0) as u8,
+                (shade * 245.0) as u8,
+                (alpha * 255.0) as u8,
+            ]);
+        }
+    }
+    pixels
+}
+
+/// Deterministic star field of `count` stars for `seed`, spread evenly
+/// over the sphere.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn star_field(seed: u32, count: usize) -> Vec<Star> {
+    let mut state = u64::from(seed) ^ 0x5EED_57A2_0000_0000;
+    let mut next = move || {
+        // splitmix64
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        ((z ^ (z >> 31)) >> 40) as f32 / (1u64 << 24) as f32
+    };
+    (0..count)
+        .map(|_| {
+            let y = next() * 2.0 - 1.0;
+            let around = next() * TAU;
+            let ring = (1.0 - y * y).sqrt();
+            // Most stars are faint; a few are bright and slightly larger
+            let brightness = next().powi(3) * 0.8 + 0.2;
+            Star {
+                direction: Vec3::new(ring * around.cos(), y, ring * around.sin()),
+                size: 0.0015 + brightness * 0.002,
+                brightness,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moon_phases_follow_the_days() {
+        // A new world starts at full moon, opposite the sun
+        let full = moon_phase(0, 0.0);
+        assert_eq!(moon_phase_index(full), MOON_PHASES / 2);
+        assert!((moon_illumination(full) - 1.0).abs() < 1e-5);
+        assert!(sky_direction(moon_angle(0.3, full)).dot(sky_direction(0.3)) < -0.999);
+
+        let new = moon_phase(4, 0.0);
+        assert_eq!(moon_phase_index(new), 0);
+        assert!(moonlight_illuminance(new, 1.0, 1.0) < 1e-3);
+        assert_eq!(moon_phase(MOON_CYCLE_DAYS as u64, 0.0), full);
+
+        // A new moon image is dark, a full one bright in the middle
+        let centre = |pixels: &[u8]| pixels[(16 * 32 + 16) * 4];
+        assert!(centre(&moon_phase_image(new, 32)) < 40);
+        assert!(centre(&moon_phase_image(full, 32)) > 180);
+
+        let stars = star_field(7, 100);
+        assert_eq!(stars, star_field(7, 100));
+        assert!(stars.iter().all(|s| (s.direction.length() - 1.0).abs() < 1e-4));
+    }
+}
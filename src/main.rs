@@ -1,29 +1,86 @@
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::pbr::{ExtendedMaterial, MaterialPlugin, StandardMaterial};
 use bevy::prelude::*;
+use bevy::transform::TransformSystem;
 use bevy::window::{PresentMode, Window, WindowPlugin};
 use bevy_atmosphere::prelude::*;
 use stratum::biome::loader as biome_loader;
 use stratum::block::loader as block_loader;
+use stratum::item::loader as item_loader;
+use stratum::crafting::loader as recipe_loader;
 use stratum::settings::loader as settings_loader;
-use stratum::block::block_interaction;
+use stratum::block::{
+    block_interaction, rebuild_dirty_chunks, start_falling_blocks, update_falling_blocks,
+};
+use stratum::block::fluid::{schedule_fluid_ticks, sync_fluid_handlers};
+use stratum::block::light::{update_block_lights, BlockLights};
+use stratum::block::tick_handlers::register_builtin_handlers;
+use stratum::world::{dispatch_block_updates, sync_world_rules, BlockChanged, WorldRules};
+use stratum::world::explosion::{handle_explosions, ExplosionEvent};
+use stratum::world::edit::{draw_region_selection, select_region_corners, RegionEditor};
+use stratum::world::tick::{
+    game_tick_system, run_block_ticks, schedule_block_ticks, BlockTickHandlers,
+    BlockTickScheduler, GameTicks, TickTimer,
+};
+use stratum::world::time::{advance_world_time, DayPhaseEvent};
+use stratum::world::weather::{
+    update_precipitation_heightmap, update_weather, ActiveWeather, PrecipitationHeightmap, WorldWeather,
+};
 use stratum::chunk::{stream_chunks, ChunkStreamingConfig, PendingChunks, StartupTimer};
 use stratum::chunk::frustum::cull_chunk_entities_system;
-use stratum::player::{camera_look, camera_movement, cursor_grab, player_physics};
+use stratum::chunk::governor::{govern_streaming_budget, StreamingGovernor};
+use stratum::chunk::profiler::{export_profile_on_key, ChunkProfiler};
+use stratum::chunk::lod::StreamingBudget;
+use stratum::chunk::overview::{update_block_colours, update_chunk_overview, BlockColours, ChunkOverview};
+use stratum::player::{
+    apply_damage, camera_look, camera_movement, cursor_grab, environment_damage, fall_damage,
+    interpolate_camera, player_physics, record_simulated_position, restore_simulated_position,
+    toggle_game_mode, update_flight, DamageEvent, PlayerDied, PlayerLanded,
+};
+use stratum::world::save::{save_world_meta, WorldMeta, SAVE_DIR};
+use stratum::ui::hud::{spawn_hud, update_hud};
+use stratum::ui::map::{spawn_map_ui, toggle_map, update_map_ui};
+use stratum::ui::console::{
+    console_closed, console_input, register_builtin_commands, run_console_commands, spawn_console,
+    update_console_ui, ConsoleCommands, ConsoleState,
+};
+use stratum::ui::settings_menu::{
+    capture_keybind, drag_settings_sliders, handle_menu_buttons, rebuild_settings_menu,
+    pause_while_menu_open, settings_menu_closed, spawn_settings_menu, toggle_settings_menu, update_settings_menu,
+};
+use stratum::item::select_hotbar_slot;
+use stratum::ui::crafting::{
+    crafting_panel_closed, handle_craft_buttons, handle_grid_buttons, refresh_crafting_panel,
+    spawn_crafting_panel, toggle_crafting_panel,
+};
 use stratum::ui::{
     render_chunk_grid, setup_debug_overlay, spawn_debug_overlay,
     toggle_debug_grid, toggle_debug_overlay, update_debug_overlay,
 };
+use stratum::material::precipitation_material::PrecipitationMaterial;
 use stratum::voxel_material::VoxelMaterial;
 
 mod app;
 use stratum::debug::DebugDumpPlugin;
+use stratum::player::replay::{capture_replay_look, replay_idle, run_replay, ReplayState};
+use stratum::input::{
+    begin_fixed_actions, latch_fixed_actions, rebuild_input_map, update_action_state, ActionState,
+    FixedActionState, InputMap,
+};
 use app::{
     ensure_atlas_sampler,
     setup_texture_array,
+    setup_item_icons,
     setup_voxel_material,
     setup,
     daylight_cycle,
+    respawn_player,
+    sync_simulation_rate,
+    spawn_night_sky,
+    update_night_sky,
+    spawn_precipitation,
+    update_precipitation,
+    update_weather_fog,
     update_player_fill_light,
 };
 
@@ -33,37 +90,27 @@ struct Sun;
 #[derive(Component)]
 struct Moon;
 
+#[derive(Component)]
+struct MoonLight;
+
+#[derive(Component)]
+struct StarField;
+
 #[derive(Component)]
 struct Skylight;
 
 #[derive(Component)]
 struct PlayerFillLight;
 
-// Game tick constants
-pub const GAME_TICK_RATE: f32 = 20.0;
-pub const FULL_DAY_SECONDS: f32 = 48.0 * 60.0;
-
 #[derive(Resource)]
 struct CycleTimer(Timer);
 
-#[derive(Resource)]
-struct TickTimer(Timer);
-
-#[derive(Resource, Default)]
-struct GameTicks { pub count: u64 }
-
 #[derive(Resource, Default)]
 struct TextureArrayReady(bool);
 
 #[derive(Resource, Default)]
 struct AtlasSamplerReady(bool);
 
-fn game_tick_system(mut ticks: ResMut<GameTicks>, mut timer: ResMut<TickTimer>, time: Res<Time>) {
-    if timer.0.tick(time.delta()).just_finished() {
-        ticks.count = ticks.count.wrapping_add(1);
-    }
-}
-
 fn main() {
     let settings = settings_loader::load_settings_from_dir("data/settings");
     let settings_watcher = settings_loader::setup_settings_watcher("data/settings")
@@ -82,6 +129,11 @@ fn main() {
         .add_plugins(MaterialPlugin::<
             ExtendedMaterial<StandardMaterial, VoxelMaterial>,
         >::default())
+        .add_plugins(MaterialPlugin::<PrecipitationMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        })
         .add_plugins(FrameTimeDiagnosticsPlugin)
         .add_plugins(LogDiagnosticsPlugin::default())
         .add_plugins(DebugDumpPlugin);
@@ -100,14 +152,36 @@ fn main() {
     }
 
     app.insert_resource(ChunkStreamingConfig::default());
+    app.insert_resource(StreamingBudget::from_settings(&settings.performance));
+    app.insert_resource(StreamingGovernor::from_settings(&settings.performance));
+    app.insert_resource(ChunkProfiler::default());
     app.insert_resource(PendingChunks::default());
     app.insert_resource(StartupTimer {
         elapsed: 0.0,
         startup_complete: false,
     });
     app.insert_resource(CycleTimer(Timer::from_seconds(0.10, TimerMode::Repeating)));
-    app.insert_resource(TickTimer(Timer::from_seconds(1.0 / GAME_TICK_RATE, TimerMode::Repeating)));
+    app.insert_resource(TickTimer::default());
     app.insert_resource(GameTicks::default());
+    app.insert_resource(BlockTickScheduler::default());
+    app.insert_resource(WorldRules::from_settings(&settings.gameplay));
+    let mut tick_handlers = BlockTickHandlers::default();
+    register_builtin_handlers(&mut tick_handlers);
+    app.insert_resource(tick_handlers);
+    app.add_event::<BlockChanged>();
+    app.add_event::<ExplosionEvent>();
+    app.add_event::<PlayerLanded>();
+    app.add_event::<DamageEvent>();
+    app.add_event::<PlayerDied>();
+    app.add_event::<DayPhaseEvent>();
+    let world_meta = WorldMeta::load(SAVE_DIR);
+    app.insert_resource(world_meta.game_mode);
+    app.insert_resource(world_meta.time);
+    app.insert_resource(world_meta);
+    app.insert_resource(WorldWeather::default());
+    app.insert_resource(ActiveWeather::default());
+    app.insert_resource(PrecipitationHeightmap::default());
+    app.insert_resource(BlockLights::default());
     app.insert_resource(app::lighting::DaylightPrev::default());
     app.insert_resource(TextureArrayReady::default());
     app.insert_resource(AtlasSamplerReady::default());
@@ -117,21 +191,94 @@ fn main() {
             biome_loader::BiomeWatcher::stub()
         }),
     );
-    app.insert_resource(block_loader::load_blocks_from_dir("data/blocks"));
+    let block_registry = block_loader::load_blocks_from_dir("data/blocks");
+    let item_registry = item_loader::load_items_from_dir("data/items", &block_registry);
+    app.insert_resource(recipe_loader::load_recipes_from_dir("data/recipes", &item_registry));
+    app.insert_resource(
+        recipe_loader::setup_recipe_watcher("data/recipes").unwrap_or_else(|_| {
+            recipe_loader::RecipeWatcher::stub()
+        }),
+    );
+    app.insert_resource(stratum::item::Inventory::with_starting_items(&item_registry));
+    app.insert_resource(stratum::crafting::CraftingGrid::default());
+    app.insert_resource(item_registry);
+    app.insert_resource(
+        item_loader::setup_item_watcher("data/items").unwrap_or_else(|_| {
+            item_loader::ItemWatcher::stub()
+        }),
+    );
+    app.insert_resource(stratum::block::DirtyChunks::default());
+    app.insert_resource(stratum::block::MiningProgress::default());
+    app.insert_resource(stratum::block::CreativeBlock::default());
+    let mut console_commands = ConsoleCommands::default();
+    register_builtin_commands(&mut console_commands);
+    app.insert_resource(console_commands);
+    app.insert_resource(ConsoleState::default());
+    app.insert_resource(RegionEditor::default());
+    app.insert_resource(BlockColours::default());
+    app.insert_resource(ChunkOverview::default());
+    app.insert_resource(stratum::block::FallingBlockQueue::default());
+    app.insert_resource(stratum::block::BlockMeshCache::default());
+    app.insert_resource(block_registry);
     app.insert_resource(
         block_loader::setup_block_watcher("data/blocks").unwrap_or_else(|_| {
             block_loader::BlockWatcher::stub()
         }),
     );
 
+    app.insert_resource(InputMap::from_settings(&settings.controls));
+    app.insert_resource(ActionState::default());
+    app.insert_resource(FixedActionState::default());
+    app.insert_resource(ReplayState::default());
     app.insert_resource(settings.clone());
     app.insert_resource(settings_watcher);
 
     app.add_systems(Startup, setup_debug_overlay);
     app.add_systems(Startup, spawn_debug_overlay);
     app.add_systems(Startup, setup);
+    app.add_systems(Startup, spawn_night_sky);
+    app.add_systems(Startup, spawn_precipitation);
     app.add_systems(Startup, setup_texture_array);
-    app.add_systems(PreUpdate, game_tick_system);
+    app.add_systems(Startup, setup_item_icons);
+    app.add_systems(Startup, spawn_crafting_panel);
+    app.add_systems(Startup, spawn_hud);
+    app.add_systems(Startup, spawn_console);
+    app.add_systems(Startup, spawn_map_ui);
+    app.add_systems(Startup, spawn_settings_menu);
+    app.add_systems(PreUpdate, console_input.after(bevy::input::InputSystem));
+    app.add_systems(PreUpdate, capture_keybind.after(bevy::input::InputSystem));
+    app.add_systems(PreUpdate, rebuild_input_map);
+    app.add_systems(
+        PreUpdate,
+        update_action_state
+            .after(bevy::input::InputSystem)
+            .after(console_input)
+            .after(capture_keybind)
+            .after(rebuild_input_map),
+    );
+    app.add_systems(PreUpdate, latch_fixed_actions.after(update_action_state));
+
+    // Gameplay simulation: runs at `gameplay.simulation_rate` steps per second
+    app.add_systems(FixedFirst, (begin_fixed_actions, restore_simulated_position));
+    app.add_systems(FixedUpdate, update_flight.run_if(replay_idle));
+    app.add_systems(FixedUpdate, camera_movement.after(update_flight).run_if(replay_idle));
+    app.add_systems(FixedUpdate, player_physics.after(camera_movement).run_if(replay_idle));
+    app.add_systems(FixedUpdate, run_replay);
+    app.add_systems(FixedUpdate, fall_damage.after(player_physics).after(run_replay));
+    app.add_systems(FixedUpdate, environment_damage.after(player_physics).after(run_replay));
+    app.add_systems(FixedUpdate, stratum::item::update_item_drops.after(player_physics).after(run_replay));
+    app.add_systems(FixedUpdate, update_falling_blocks);
+    app.add_systems(FixedUpdate, game_tick_system);
+    app.add_systems(FixedUpdate, run_block_ticks.after(game_tick_system));
+    app.add_systems(FixedUpdate, advance_world_time);
+    app.add_systems(FixedUpdate, update_weather.after(player_physics).after(run_replay).after(advance_world_time));
+    app.add_systems(FixedLast, record_simulated_position);
+    app.add_systems(PostUpdate, interpolate_camera.before(TransformSystem::TransformPropagate));
+    app.add_systems(
+        PostUpdate,
+        update_night_sky.after(interpolate_camera).before(TransformSystem::TransformPropagate),
+    );
+
     app.add_systems(Update, setup_voxel_material);
     app.add_systems(Update, ensure_atlas_sampler);
     app.add_systems(Update, stream_chunks);
@@ -140,6 +287,11 @@ fn main() {
     app.add_systems(Update, toggle_debug_grid);
     app.add_systems(Update, update_debug_overlay);
     app.add_systems(Update, render_chunk_grid);
+    app.add_systems(Update, select_hotbar_slot);
+    app.add_systems(Update, toggle_crafting_panel);
+    app.add_systems(Update, refresh_crafting_panel.after(toggle_crafting_panel));
+    app.add_systems(Update, handle_craft_buttons);
+    app.add_systems(Update, handle_grid_buttons);
 
     // Add daylight and atmosphere sync
     if settings.atmosphere.enabled {
@@ -148,16 +300,68 @@ fn main() {
     }
 
     app.add_systems(Update, crate::app::sync_streaming_settings);
+    app.add_systems(Update, crate::app::sync_streaming_budget.before(stream_chunks));
+    app.add_systems(Update, govern_streaming_budget.after(crate::app::sync_streaming_budget).before(stream_chunks));
+    app.add_systems(Update, export_profile_on_key.run_if(console_closed));
     app.add_systems(Update, crate::app::sync_vsync_settings);
+    app.add_systems(Update, sync_simulation_rate);
+    app.add_systems(Update, update_precipitation_heightmap);
+    app.add_systems(Update, update_precipitation.after(update_precipitation_heightmap));
+    app.add_systems(Update, update_weather_fog);
 
     app.add_systems(Update, biome_loader::check_biome_changes);
     app.add_systems(Update, block_loader::check_block_changes);
+    app.add_systems(Update, item_loader::check_item_changes.after(block_loader::check_block_changes));
+    app.add_systems(Update, recipe_loader::check_recipe_changes.after(item_loader::check_item_changes));
     app.add_systems(Update, settings_loader::check_settings_changes);
-    app.add_systems(Update, camera_movement);
-    app.add_systems(Update, camera_look);
-    app.add_systems(Update, cursor_grab);
-    app.add_systems(Update, player_physics);
+    app.add_systems(Update, camera_look.run_if(replay_idle));
+    app.add_systems(Update, capture_replay_look.after(run_console_commands));
+    app.add_systems(
+        Update,
+        cursor_grab
+            .run_if(crafting_panel_closed)
+            .run_if(console_closed)
+            .run_if(settings_menu_closed)
+            .before(toggle_settings_menu),
+    );
     app.add_systems(Update, block_interaction);
+    app.add_systems(Update, handle_explosions.after(block_interaction));
+    app.add_systems(Update, dispatch_block_updates.after(block_interaction).after(handle_explosions));
+    app.add_systems(Update, start_falling_blocks.after(dispatch_block_updates));
+    app.add_systems(Update, schedule_block_ticks.after(dispatch_block_updates));
+    app.add_systems(Update, schedule_fluid_ticks.after(dispatch_block_updates));
+    app.add_systems(Update, sync_fluid_handlers);
+    app.add_systems(Update, sync_world_rules.after(settings_loader::check_settings_changes));
+    app.add_systems(Update, update_block_lights.after(dispatch_block_updates));
+    app.add_systems(Update, rebuild_dirty_chunks.after(start_falling_blocks));
+    app.add_systems(Update, toggle_game_mode);
+    app.add_systems(Last, save_world_meta);
+    app.add_systems(Update, apply_damage.after(handle_explosions));
+    app.add_systems(Update, respawn_player.after(apply_damage));
+    app.add_systems(Update, update_hud.after(respawn_player));
+    app.add_systems(Update, run_console_commands.before(block_interaction));
+    app.add_systems(Update, update_console_ui.after(run_console_commands));
+    app.add_systems(Update, select_region_corners);
+    app.add_systems(Update, draw_region_selection.after(select_region_corners));
+    app.add_systems(Update, update_block_colours);
+    app.add_systems(
+        Update,
+        update_chunk_overview
+            .after(update_block_colours)
+            .after(dispatch_block_updates)
+            .before(rebuild_dirty_chunks),
+    );
+    app.add_systems(Update, toggle_map);
+    app.add_systems(Update, update_map_ui.after(toggle_map).after(update_chunk_overview));
+    app.add_systems(Update, toggle_settings_menu);
+    app.add_systems(Update, handle_menu_buttons.after(toggle_settings_menu));
+    app.add_systems(Update, pause_while_menu_open.after(handle_menu_buttons));
+    app.add_systems(Update, drag_settings_sliders);
+    app.add_systems(Update, rebuild_settings_menu.after(handle_menu_buttons));
+    app.add_systems(
+        Update,
+        update_settings_menu.after(rebuild_settings_menu).after(drag_settings_sliders),
+    );
     app.add_systems(Update, update_player_fill_light);
 
     app.run();
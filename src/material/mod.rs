@@ -1 +1,2 @@
+pub mod precipitation_material;
 pub mod voxel_material;
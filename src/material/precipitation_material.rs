@@ -0,0 +1,90 @@
+//! Material for rain and snow.
+//!
+//! Precipitation is drawn as one mesh holding a quad per rain drop and snow
+//! flake (see `app::weather`). The quads carry their particle's data in
+//! vertex attributes and `shaders/precipitation.wgsl` places them: it wraps
+//! each particle into a box around the camera, moves it down from the
+//! global shader time, turns it towards the camera and collapses it when it
+//! is above the intensity or under a block. The CPU only updates the
+//! uniforms and the heightmap texture, and only when they change.
+
+use bevy::asset::Asset;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::mesh::{MeshVertexAttribute, MeshVertexBufferLayoutRef};
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError, VertexFormat,
+};
+
+/// Shader drawing the precipitation mesh.
+const PRECIPITATION_SHADER: &str = "shaders/precipitation.wgsl";
+
+/// Per-particle data: spot in the particle box (0..1 on each axis) and the
+/// intensity above which the particle shows (0..1).
+pub const ATTRIBUTE_PARTICLE: MeshVertexAttribute =
+    MeshVertexAttribute::new("Particle", 0x5052_4543_0001, VertexFormat::Float32x4);
+
+/// Per-particle kind: 0 for a rain drop, 1 for a snow flake.
+pub const ATTRIBUTE_PARTICLE_KIND: MeshVertexAttribute =
+    MeshVertexAttribute::new("ParticleKind", 0x5052_4543_0002, VertexFormat::Float32);
+
+/// Material of the precipitation mesh.
+///
+/// The binding indices are fixed; keep them in step with
+/// `shaders/precipitation.wgsl`.
+#[derive(AsBindGroup, Asset, TypePath, Clone, Default)]
+pub struct PrecipitationMaterial {
+    /// Linear colour of rain, alpha included.
+    #[uniform(0)]
+    pub rain_color: Vec4,
+    /// Linear colour of snow, alpha included.
+    #[uniform(1)]
+    pub snow_color: Vec4,
+    /// Half the width of the particle box, its height, and the rain and
+    /// snow fall speeds (blocks, blocks per second).
+    #[uniform(2)]
+    pub fall: Vec4,
+    /// Rain and snow intensity (0..1) in `x` and `y`, sideways sway of
+    /// falling snow (blocks) in `z`.
+    #[uniform(3)]
+    pub intensity: Vec4,
+    /// Column `(x, z)` of the heightmap's first texel in `x` and `y`, and
+    /// columns along each side in `z` (0 while there is no heightmap).
+    #[uniform(4)]
+    pub heightmap_area: Vec4,
+    /// `R32Float` texture with the highest block of each column (see
+    /// `world::weather::PrecipitationHeightmap`).
+    #[texture(5, sample_type = "float", filterable = false)]
+    pub heightmap: Handle<Image>,
+}
+
+impl Material for PrecipitationMaterial {
+    fn vertex_shader() -> ShaderRef {
+        PRECIPITATION_SHADER.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        PRECIPITATION_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    /// Read the corner and particle attributes instead of the standard mesh
+    /// layout, and draw both sides of the quads.
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.vertex.buffers = vec![layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_PARTICLE.at_shader_location(1),
+            ATTRIBUTE_PARTICLE_KIND.at_shader_location(2),
+        ])?];
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
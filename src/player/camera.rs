@@ -2,19 +2,25 @@
 //!
 //! Provides mouse-look handling via `camera_look` and cursor grabbing via
 //! `cursor_grab`. `camera_look` accumulates mouse motion for the current
-//! update and applies yaw/pitch to the player's transform. `cursor_grab`
-//! toggles cursor lock/visibility in response to input.
+//! update, adds the `look_*` actions (gamepad stick) and applies yaw/pitch to
+//! the player's transform. `cursor_grab` toggles cursor lock/visibility in
+//! response to input.
 
 use bevy::input::mouse::MouseMotion;
 use bevy::prelude::*;
 use bevy::window::{CursorGrabMode, PrimaryWindow};
 
+use crate::input::ActionState;
+use crate::settings::ControlsSettings;
 use crate::player::Player;
 
 // Centralized camera tuning constants — change these to adjust behavior used
 // by both the live system and benchmarks.
 const CAMERA_MAX_PITCH_DEG: f32 = 85.0;
 
+/// Turn rate (radians per second) of a fully deflected look stick.
+const STICK_LOOK_SPEED: f32 = 3.0;
+
 /// Stores the player's look orientation (yaw and pitch) in radians.
 ///
 /// - `yaw`: horizontal rotation around the Y axis.
@@ -36,39 +42,75 @@ impl PlayerLook {
         delta: Vec2,
         settings: &crate::settings::Settings,
     ) {
+        self.turn(Self::look_delta(delta, Vec2::ZERO, 0.0, settings.controls.mouse_sensitivity));
+    }
+
+    /// Yaw and pitch change (radians) for a mouse delta plus a look-stick
+    /// deflection (`x` right, `y` up, each -1..1) held for `dt` seconds.
+    #[must_use]
+    pub fn look_delta(mouse: Vec2, stick: Vec2, dt: f32, sensitivity: f32) -> Vec2 {
+        let mouse_scale = sensitivity / 10000.0;
+        Vec2::new(
+            -mouse.x * mouse_scale - stick.x * STICK_LOOK_SPEED * dt,
+            -mouse.y * mouse_scale + stick.y * STICK_LOOK_SPEED * dt,
+        )
+    }
+
+    /// Turn by a yaw/pitch change (radians), clamping the pitch.
+    pub fn turn(&mut self, delta: Vec2) {
         let max_pitch = CAMERA_MAX_PITCH_DEG.to_radians();
-        let min_pitch = -max_pitch;
 
-        self.yaw -= delta.x * (settings.controls.mouse_sensitivity / 10000.0 );
-        self.pitch -= delta.y * (settings.controls.mouse_sensitivity / 10000.0);
-        self.pitch = self.pitch.clamp(min_pitch, max_pitch);
+        self.yaw += delta.x;
+        self.pitch = (self.pitch + delta.y).clamp(-max_pitch, max_pitch);
+    }
+
+    /// Camera rotation for this look: yaw around Y, pitch around X.
+    #[must_use]
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(bevy::math::EulerRot::YXZ, self.yaw, self.pitch, 0.0)
     }
 }
 
+/// Mouse motion and look-stick deflection of this update, with the invert
+/// settings applied.
+///
+/// # Arguments
+/// * `motion_events` - mouse motion events
+/// * `actions` - action state for the `look_*` actions
+/// * `controls` - controls settings (`invert_x`, `invert_y`)
+#[must_use]
+pub fn look_input(motion_events: &Events<MouseMotion>, actions: &ActionState, controls: &ControlsSettings) -> (Vec2, Vec2) {
+    let invert = Vec2::new(
+        if controls.invert_x { -1.0 } else { 1.0 },
+        if controls.invert_y { -1.0 } else { 1.0 },
+    );
+    let mouse: Vec2 = motion_events.iter_current_update_events().map(|ev| ev.delta).sum();
+    let stick = Vec2::new(
+        actions.value("look_right") - actions.value("look_left"),
+        actions.value("look_up") - actions.value("look_down"),
+    );
+    (mouse * invert, stick * invert)
+}
+
 /// Apply mouse-look to players with a `PlayerLook` component.
 ///
 /// # Arguments
 /// * `windows` - query for the primary window (used to check cursor visibility)
 /// * `motion_events` - mouse motion events for this update
+/// * `actions` - action state for the `look_*` actions
+/// * `time` - delta time for stick look
 /// * `query` - query for `(Transform, PlayerLook)` to update
 #[allow(clippy::needless_pass_by_value)]
 pub fn camera_look(
     windows: Query<&Window, With<PrimaryWindow>>,
     motion_events: Res<Events<MouseMotion>>, // use Events iterator for current update (Bevy 0.14)
+    actions: Res<ActionState>,
+    time: Res<Time>,
     mut query: Query<(&mut Transform, &mut PlayerLook), With<Player>>,
     settings: Res<crate::settings::Settings>,
 ) {
-    // accumulate mouse delta since this update's events
-    let mut delta = Vec2::ZERO;
-    for ev in motion_events.iter_current_update_events() {
-         let mut axis = ev.delta;
-         if settings.controls.invert_x { axis.x = -axis.x; }
-         if settings.controls.invert_y { axis.y = -axis.y; }
-         delta += axis;
-
-    }
-
-    if delta == Vec2::ZERO {
+    let (mouse, stick) = look_input(&motion_events, &actions, &settings.controls);
+    if mouse == Vec2::ZERO && stick == Vec2::ZERO {
         return;
     }
 
@@ -78,12 +120,11 @@ pub fn camera_look(
         return;
     }
 
+    // shared helpers keep this system, replays and benchmarks consistent
+    let delta = PlayerLook::look_delta(mouse, stick, time.delta_seconds(), settings.controls.mouse_sensitivity);
     for (mut transform, mut look) in &mut query {
-        // update using shared helper (keeps system and benchmarks consistent)
-        look.apply_delta(delta, &*settings);
-
-        // apply rotation: yaw around Y, pitch around X
-        transform.rotation = Quat::from_euler(bevy::math::EulerRot::YXZ, look.yaw, look.pitch, 0.0);
+        look.turn(delta);
+        transform.rotation = look.rotation();
     }
 }
 
@@ -91,29 +132,19 @@ pub fn camera_look(
 ///
 /// # Arguments
 /// * `wq` - mutable window query to change cursor state
-/// * `mb` - mouse button input to detect left-click for grabbing
-/// * `kb` - keyboard input to detect Escape to release cursor
+/// * `actions` - action state: `break` grabs the cursor, `pause` releases it
 #[allow(clippy::needless_pass_by_value)]
 pub fn cursor_grab(
     mut wq: Query<&mut Window, With<PrimaryWindow>>,
-    mb: Res<ButtonInput<MouseButton>>,
-    kb: Res<ButtonInput<KeyCode>>,
-    settings: Res<crate::settings::Settings>,
+    actions: Res<ActionState>,
 ) {
     let mut w = wq.single_mut();
-    if mb.just_pressed(MouseButton::Left) {
+    if actions.just_pressed("break") {
         w.cursor.grab_mode = CursorGrabMode::Locked;
         w.cursor.visible = false;
     }
 
-    let pause_kc = settings
-        .controls
-        .keybinds
-        .get("pause")
-        .and_then(|s| crate::settings::Settings::keycode_from_str(s))
-        .unwrap_or(KeyCode::Escape);
-
-    if kb.just_pressed(pause_kc) {
+    if actions.just_pressed("pause") {
         w.cursor.grab_mode = CursorGrabMode::None;
         w.cursor.visible = true;
     }
@@ -0,0 +1,138 @@
+//! Game modes.
+//!
+//! The `GameMode` resource holds the rules the player currently plays by:
+//!
+//! * survival: gravity, damage and timed mining; blocks placed are used up;
+//! * creative: no damage, flight toggled with the `fly` action, instant
+//!   breaking and any registered block placed without using the inventory
+//!   (see `block::CreativeBlock`);
+//! * spectator: always flying through blocks, no damage and no interaction.
+//!
+//! Press the `gamemode` keybind to cycle through the modes. The mode is saved
+//! with the world (see `world::save`).
+
+use super::Player;
+use crate::input::{ActionState, FixedActionState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The rules the player currently plays by.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    #[default]
+    Survival,
+    Creative,
+    Spectator,
+}
+
+impl GameMode {
+    /// Every mode, in the order the `gamemode` keybind cycles through them.
+    pub const ALL: [GameMode; 3] = [GameMode::Survival, GameMode::Creative, GameMode::Spectator];
+
+    /// Lowercase name, as used in saves and console commands.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Spectator => "spectator",
+        }
+    }
+
+    /// Parse a mode from its name (case-insensitive).
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the player can be hurt in this mode.
+    #[must_use]
+    pub fn takes_damage(self) -> bool {
+        matches!(self, GameMode::Survival)
+    }
+
+    /// Whether the player may toggle flight.
+    #[must_use]
+    pub fn can_fly(self) -> bool {
+        matches!(self, GameMode::Creative)
+    }
+
+    /// Whether the player passes through blocks.
+    #[must_use]
+    pub fn noclip(self) -> bool {
+        matches!(self, GameMode::Spectator)
+    }
+
+    /// Whether the player can break and place blocks.
+    #[must_use]
+    pub fn can_interact(self) -> bool {
+        !matches!(self, GameMode::Spectator)
+    }
+
+    /// Whether blocks break on the first click instead of after their mining time.
+    #[must_use]
+    pub fn instant_break(self) -> bool {
+        matches!(self, GameMode::Creative)
+    }
+
+    /// Whether blocks are placed from the block registry (`CreativeBlock`)
+    /// and breaking and placing leave the inventory untouched.
+    #[must_use]
+    pub fn infinite_blocks(self) -> bool {
+        matches!(self, GameMode::Creative)
+    }
+
+    /// The mode the `gamemode` keybind switches to from this one.
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            GameMode::Survival => GameMode::Creative,
+            GameMode::Creative => GameMode::Spectator,
+            GameMode::Spectator => GameMode::Survival,
+        }
+    }
+}
+
+/// Switch to the next game mode when the `gamemode` action is pressed.
+///
+/// # Arguments
+/// * `actions` - action state
+/// * `mode` - current game mode
+#[allow(clippy::needless_pass_by_value)]
+pub fn toggle_game_mode(actions: Res<ActionState>, mut mode: ResMut<GameMode>) {
+    if actions.just_pressed("gamemode") {
+        *mode = mode.next();
+        info!("Game mode: {}", mode.name());
+    }
+}
+
+/// Apply the game mode's flight rules: survival never flies, spectator
+/// always does, and creative toggles flight with the `fly` action. Runs
+/// each simulation step.
+///
+/// # Arguments
+/// * `actions` - step action state
+/// * `mode` - current game mode
+/// * `player` - player state
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_flight(actions: Res<FixedActionState>, mode: Res<GameMode>, mut player: Query<&mut Player>) {
+    for mut player in &mut player {
+        flight_step(&mut player, *mode, &actions);
+    }
+}
+
+/// Apply the flight rules of `mode` to `player` for one step. Shared by
+/// `update_flight` and replays.
+pub fn flight_step(player: &mut Player, mode: GameMode, actions: &ActionState) {
+    let flying = match mode {
+        GameMode::Survival => false,
+        GameMode::Spectator => true,
+        GameMode::Creative => player.flying ^ actions.just_pressed("fly"),
+    };
+    if player.flying != flying {
+        player.flying = flying;
+        player.velocity = Vec3::ZERO;
+        player.fall_distance = 0.0;
+    }
+}
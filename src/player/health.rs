@@ -0,0 +1,295 @@
+//! Player health and damage.
+//!
+//! Damage reaches the player as `DamageEvent`s from several sources:
+//!
+//! * falling further than `SAFE_FALL_DISTANCE` (see `PlayerLanded`; the
+//!   distance is already scaled by the landing block's `fall_damage_multiplier`);
+//! * being below the bottom of the world (`VOID_DAMAGE_PER_SECOND`);
+//! * touching a block with `fire_damage` (fire, lava);
+//! * explosions (see `world::explosion`).
+//!
+//! `apply_damage` subtracts the damage from the player's `Health` unless the
+//! `GameMode` disables damage, and sends `PlayerDied` when it runs out. The
+//! game then respawns the player (see `app::player::respawn_player`).
+
+use super::{GameMode, Player, PlayerLanded};
+use crate::block::{blocks, BlockRegistry};
+use crate::world::World;
+use bevy::prelude::*;
+
+/// Health of a freshly spawned player.
+pub const MAX_HEALTH: f32 = 20.0;
+
+/// Falls up to this many blocks do not hurt; each block beyond costs one health.
+pub const SAFE_FALL_DISTANCE: f32 = 3.0;
+
+/// Damage per second while below the bottom of the world.
+pub const VOID_DAMAGE_PER_SECOND: f32 = 8.0;
+
+/// The player's health.
+///
+/// # Fields
+/// * `current` - remaining health; the player dies at zero
+/// * `max` - health after respawning
+/// * `dead` - set on death, cleared when the player respawns
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    pub dead: bool,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self { current: MAX_HEALTH, max: MAX_HEALTH, dead: false }
+    }
+}
+
+impl Health {
+    /// Remaining health as a fraction (0..1) of `max`.
+    #[must_use]
+    pub fn fraction(&self) -> f32 {
+        (self.current / self.max.max(f32::EPSILON)).clamp(0.0, 1.0)
+    }
+}
+
+/// What hurt the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageSource {
+    Fall,
+    Void,
+    Explosion,
+    Fire,
+}
+
+/// Damage dealt to the player.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DamageEvent {
+    pub amount: f32,
+    pub source: DamageSource,
+}
+
+/// Sent when the player's health runs out.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerDied {
+    pub source: DamageSource,
+}
+
+/// Turn landings into fall damage.
+///
+/// # Arguments
+/// * `landings` - player landings with their scaled fall distance
+/// * `damage` - damage event writer
+pub fn fall_damage(mut landings: EventReader<PlayerLanded>, mut damage: EventWriter<DamageEvent>) {
+    for landing in landings.read() {
+        let amount = landing.fall_distance - SAFE_FALL_DISTANCE;
+        if amount > 0.0 {
+            damage.send(DamageEvent { amount: amount.ceil(), source: DamageSource::Fall });
+        }
+    }
+}
+
+/// Highest `fire_damage` of the blocks the player stands on or touches.
+#[allow(clippy::cast_possible_truncation)]
+fn touching_fire_damage(world: &World, blocks: &BlockRegistry, eye: Vec3) -> f32 {
+    let reach = 0.35;
+    let feet = eye.y - 1.7;
+    let mut cells = vec![Vec3::new(eye.x, feet - 0.05, eye.z)];
+    for y in [feet + 0.1, eye.y - 0.85] {
+        for d in [Vec3::ZERO, Vec3::X, Vec3::NEG_X, Vec3::Z, Vec3::NEG_Z] {
+            cells.push(Vec3::new(eye.x, y, eye.z) + d * reach);
+        }
+    }
+    cells
+        .into_iter()
+        .map(|p| world.get_block(p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32))
+        .filter(|id| *id != blocks::AIR)
+        .filter_map(|id| blocks.get_by_id(id))
+        .map(|b| b.fire_damage)
+        .fold(0.0, f32::max)
+}
+
+/// Hurt the player below the world and next to burning blocks.
+///
+/// # Arguments
+/// * `time` - time resource for delta timing
+/// * `world` - world used to look up touched blocks
+/// * `blocks` - block registry providing `fire_damage`
+/// * `player` - player transform
+/// * `damage` - damage event writer
+#[allow(clippy::needless_pass_by_value)]
+pub fn environment_damage(
+    time: Res<Time>,
+    world: Res<World>,
+    blocks: Res<BlockRegistry>,
+    player: Query<&Transform, With<Player>>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    let Ok(tf) = player.get_single() else { return };
+    let dt = time.delta_seconds();
+
+    if tf.translation.y < 0.0 {
+        damage.send(DamageEvent { amount: VOID_DAMAGE_PER_SECOND * dt, source: DamageSource::Void });
+    }
+    let fire = touching_fire_damage(&world, &blocks, tf.translation);
+    if fire > 0.0 {
+        damage.send(DamageEvent { amount: fire * dt, source: DamageSource::Fire });
+    }
+}
+
+/// Subtract damage from the player's health and report death.
+///
+/// # Arguments
+/// * `mode` - current game mode; modes without damage ignore every event
+/// * `events` - damage dealt this frame
+/// * `player` - player health
+/// * `died` - death event writer
+#[allow(clippy::needless_pass_by_value)]
+pub fn apply_damage(
+    mode: Res<GameMode>,
+    mut events: EventReader<DamageEvent>,
+    mut player: Query<&mut Health, With<Player>>,
+    mut died: EventWriter<PlayerDied>,
+) {
+    let Ok(mut health) = player.get_single_mut() else {
+        events.clear();
+        return;
+    };
+    for event in events.read() {
+        if !mode.takes_damage() || health.dead || event.amount <= 0.0 {
+            continue;
+        }
+        health.current = (health.current - event.amount).max(0.0);
+        if health.current <= 0.0 {
+            health.dead = true;
+            died.send(PlayerDied { source: event.source });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::chunk::Chunk;
+    use std::time::Duration;
+
+    const FIRE: u8 = 1;
+    const LAVA: u8 = 2;
+
+    /// An app running the damage systems for a player with its eyes at
+    /// `eye`, in a world with a fire block at (2, 5, 2) and lava at (6, 5, 2).
+    fn damage_app(mode: GameMode, eye: Vec3) -> App {
+        let mut blocks = BlockRegistry::default();
+        blocks.register(Block { id: FIRE, name: "fire".to_string(), solid: false, fire_damage: 1.0, ..Block::default() });
+        blocks.register(Block { id: LAVA, name: "lava".to_string(), solid: false, fire_damage: 4.0, ..Block::default() });
+        let mut world = World::new();
+        let mut chunk = Chunk::new();
+        chunk.set(2, 5, 2, FIRE);
+        chunk.set(6, 5, 2, LAVA);
+        world.chunks.insert((0, 0), chunk);
+
+        let mut app = App::new();
+        app.add_event::<PlayerLanded>()
+            .add_event::<DamageEvent>()
+            .add_event::<PlayerDied>()
+            .insert_resource(mode)
+            .insert_resource(world)
+            .insert_resource(blocks)
+            .init_resource::<Time>()
+            .add_systems(Update, (fall_damage, environment_damage, apply_damage).chain());
+        app.world_mut().spawn((Player::default(), Health::default(), Transform::from_translation(eye)));
+        app
+    }
+
+    /// Run one update lasting `seconds`.
+    fn step(app: &mut App, seconds: f32) {
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn health(app: &mut App) -> Health {
+        *app.world_mut().query::<&Health>().single(app.world())
+    }
+
+    fn deaths(app: &App) -> Vec<DamageSource> {
+        let events = app.world().resource::<Events<PlayerDied>>();
+        events.get_reader().read(events).map(|e| e.source).collect()
+    }
+
+    #[test]
+    fn falling_further_than_the_safe_distance_hurts() {
+        let mut app = damage_app(GameMode::Survival, Vec3::new(10.0, 20.0, 10.0));
+        app.world_mut().send_event(PlayerLanded { fall_distance: SAFE_FALL_DISTANCE });
+        step(&mut app, 0.1);
+        assert!((health(&mut app).current - MAX_HEALTH).abs() < f32::EPSILON);
+
+        app.world_mut().send_event(PlayerLanded { fall_distance: 7.5 });
+        step(&mut app, 0.1);
+        assert!((health(&mut app).current - (MAX_HEALTH - 5.0)).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn the_void_hurts_below_the_world() {
+        let mut app = damage_app(GameMode::Survival, Vec3::new(10.0, 1.0, 10.0));
+        step(&mut app, 0.5);
+        assert!((health(&mut app).current - MAX_HEALTH).abs() < f32::EPSILON);
+
+        app.world_mut().query::<&mut Transform>().single_mut(app.world_mut()).translation.y = -5.0;
+        step(&mut app, 0.5);
+        let expected = MAX_HEALTH - VOID_DAMAGE_PER_SECOND * 0.5;
+        assert!((health(&mut app).current - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn fire_and_lava_hurt_by_their_fire_damage() {
+        // Standing in the fire block
+        let mut app = damage_app(GameMode::Survival, Vec3::new(2.5, 5.0 + 1.7, 2.5));
+        step(&mut app, 1.0);
+        assert!((health(&mut app).current - (MAX_HEALTH - 1.0)).abs() < 1e-4);
+
+        // Standing next to the lava block
+        let mut app = damage_app(GameMode::Survival, Vec3::new(5.8, 5.0 + 1.7, 2.5));
+        step(&mut app, 1.0);
+        assert!((health(&mut app).current - (MAX_HEALTH - 4.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn explosions_hurt_by_their_amount() {
+        let mut app = damage_app(GameMode::Survival, Vec3::new(10.0, 20.0, 10.0));
+        app.world_mut().send_event(DamageEvent { amount: 6.5, source: DamageSource::Explosion });
+        step(&mut app, 0.1);
+        assert!((health(&mut app).current - (MAX_HEALTH - 6.5)).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn creative_and_spectator_players_take_no_damage() {
+        for mode in [GameMode::Creative, GameMode::Spectator] {
+            let mut app = damage_app(mode, Vec3::new(2.5, 5.0 + 1.7, 2.5));
+            app.world_mut().send_event(PlayerLanded { fall_distance: 50.0 });
+            app.world_mut().send_event(DamageEvent { amount: 50.0, source: DamageSource::Explosion });
+            step(&mut app, 1.0);
+            app.world_mut().query::<&mut Transform>().single_mut(app.world_mut()).translation.y = -50.0;
+            step(&mut app, 10.0);
+
+            let health = health(&mut app);
+            assert!((health.current - MAX_HEALTH).abs() < f32::EPSILON, "{mode:?}");
+            assert!(!health.dead);
+            assert!(deaths(&app).is_empty());
+        }
+    }
+
+    #[test]
+    fn running_out_of_health_sends_player_died_once() {
+        let mut app = damage_app(GameMode::Survival, Vec3::new(10.0, 20.0, 10.0));
+        app.world_mut().send_event(DamageEvent { amount: 15.0, source: DamageSource::Fall });
+        app.world_mut().send_event(DamageEvent { amount: 15.0, source: DamageSource::Explosion });
+        app.world_mut().send_event(DamageEvent { amount: 15.0, source: DamageSource::Fire });
+        step(&mut app, 0.1);
+
+        let health = health(&mut app);
+        assert!(health.dead);
+        assert!(health.current.abs() < f32::EPSILON);
+        assert_eq!(deaths(&app), vec![DamageSource::Explosion]);
+    }
+}
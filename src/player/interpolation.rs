@@ -0,0 +1,137 @@
+//! Smooth camera motion between simulation steps.
+//!
+//! The player moves in `FixedUpdate` at `gameplay.simulation_rate` steps per
+//! second, which rarely matches the frame rate. `SimulatedPosition` keeps the
+//! player's position after the last two steps and `interpolate_camera` places
+//! the camera between them by `Time<Fixed>::overstep_fraction` every frame,
+//! so the view moves smoothly at any frame rate while the simulation stays
+//! fixed. The view trails the simulation by less than one step.
+//!
+//! The transform holds the drawn position between steps:
+//! `restore_simulated_position` puts the simulated one back before the steps
+//! run and `record_simulated_position` stores the result after them. A
+//! transform moved outside the simulation (`tp`, respawning) or by more than
+//! `SNAP_DISTANCE` in one step (replay start) snaps instead of sliding.
+//! Rotation is not interpolated: `camera_look` turns the camera every frame.
+
+use super::Player;
+use bevy::prelude::*;
+
+/// Distance (blocks) moved in one step beyond which the camera snaps.
+pub const SNAP_DISTANCE: f32 = 8.0;
+
+/// Player position after the last two simulation steps.
+///
+/// # Fields
+/// * `previous` - position after the step before the last
+/// * `current` - position after the last step
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct SimulatedPosition {
+    pub previous: Vec3,
+    pub current: Vec3,
+    written: Option<Vec3>,
+}
+
+impl SimulatedPosition {
+    /// Jump to `position` without interpolating from the old one.
+    pub fn snap(&mut self, position: Vec3) {
+        self.previous = position;
+        self.current = position;
+        self.written = Some(position);
+    }
+
+    /// Snap to `translation` if something other than the simulation moved
+    /// the transform since this component last wrote it.
+    pub fn follow_external(&mut self, translation: Vec3) {
+        if self.written != Some(translation) {
+            self.snap(translation);
+        }
+    }
+
+    /// Record the position after a simulation step.
+    pub fn step(&mut self, position: Vec3) {
+        if position.distance(self.current) > SNAP_DISTANCE {
+            self.snap(position);
+            return;
+        }
+        self.previous = self.current;
+        self.current = position;
+        self.written = Some(position);
+    }
+
+    /// Position `alpha` (0..1) of the way from `previous` to `current`.
+    #[must_use]
+    pub fn lerp(&self, alpha: f32) -> Vec3 {
+        self.previous.lerp(self.current, alpha.clamp(0.0, 1.0))
+    }
+
+    /// Mark `translation` as written by this component.
+    fn wrote(&mut self, translation: Vec3) -> Vec3 {
+        self.written = Some(translation);
+        translation
+    }
+}
+
+/// Put the simulated position back into the transform before the steps of
+/// this frame run. Runs in `FixedFirst`.
+///
+/// # Arguments
+/// * `query` - the player's transform and simulated position
+pub fn restore_simulated_position(mut query: Query<(&mut Transform, &mut SimulatedPosition), With<Player>>) {
+    for (mut tf, mut sim) in &mut query {
+        sim.follow_external(tf.translation);
+        let position = sim.current;
+        tf.translation = sim.wrote(position);
+    }
+}
+
+/// Store the position a simulation step ended at. Runs in `FixedLast`.
+///
+/// # Arguments
+/// * `query` - the player's transform and simulated position
+pub fn record_simulated_position(mut query: Query<(&Transform, &mut SimulatedPosition), With<Player>>) {
+    for (tf, mut sim) in &mut query {
+        sim.step(tf.translation);
+    }
+}
+
+/// Place the camera between the last two simulation steps. Runs in
+/// `PostUpdate` before transform propagation.
+///
+/// # Arguments
+/// * `time` - fixed time providing the overstep fraction
+/// * `query` - the player's transform and simulated position
+#[allow(clippy::needless_pass_by_value)]
+pub fn interpolate_camera(time: Res<Time<Fixed>>, mut query: Query<(&mut Transform, &mut SimulatedPosition), With<Player>>) {
+    let alpha = time.overstep_fraction();
+    for (mut tf, mut sim) in &mut query {
+        sim.follow_external(tf.translation);
+        let position = sim.lerp(alpha);
+        tf.translation = sim.wrote(position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_between_steps_and_snaps_on_teleport() {
+        let mut sim = SimulatedPosition::default();
+        sim.follow_external(Vec3::ZERO);
+        sim.step(Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(sim.lerp(0.25), Vec3::new(0.25, 0.0, 0.0));
+
+        // The transform still holds what the component wrote: no snap
+        sim.follow_external(Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(sim.previous, Vec3::ZERO);
+
+        // Moved from outside the simulation
+        sim.follow_external(Vec3::new(1.0, 5.0, 0.0));
+        assert_eq!(sim.lerp(0.0), Vec3::new(1.0, 5.0, 0.0));
+
+        // Moved too far within one step
+        sim.step(Vec3::new(100.0, 5.0, 0.0));
+        assert_eq!(sim.lerp(0.0), Vec3::new(100.0, 5.0, 0.0));
+    }
+}
@@ -9,26 +9,33 @@
 //! // spawn an entity with camera and player state
 //! commands.spawn((
 //!     Camera3dBundle::default(),
-//!     Player { velocity: Vec3::ZERO, on_ground: true, flying: false },
+//!     Player { on_ground: true, ..default() },
 //!     PlayerLook::default(),
+//!     SimulatedPosition::default(),
 //! ));
 //! // register systems
-//! app.add_system(camera_look);
-//! app.add_system(camera_movement);
-//! app.add_system(player_physics);
+//! app.add_systems(Update, camera_look);
+//! app.add_systems(FixedUpdate, (camera_movement, player_physics).chain());
 //! ```
 pub mod camera;
+pub mod game_mode;
+pub mod health;
+pub mod interpolation;
 pub mod movement;
 pub mod physics;
+pub mod replay;
 
 use bevy::prelude::*;
 
 pub use camera::*;
+pub use game_mode::*;
+pub use health::*;
+pub use interpolation::*;
 pub use movement::*;
 pub use physics::*;
 
 /// Component tracking player state used by movement and physics systems.
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct Player {
     /// Current player velocity in world units per second.
     pub velocity: Vec3,
@@ -36,4 +43,10 @@ pub struct Player {
     pub on_ground: bool,
     /// Whether the player is in flying mode (disables gravity).
     pub flying: bool,
+    /// Whether the player is next to a climbable block (ladder, vines).
+    pub climbing: bool,
+    /// Whether the player hangs from a ledge.
+    pub hanging: bool,
+    /// Distance fallen since the player last stood, climbed or hung.
+    pub fall_distance: f32,
 }
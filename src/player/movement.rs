@@ -1,9 +1,14 @@
 //! Player movement system with collision detection.
 //!
 //! Handles WASD movement, flying, and collision checks against the world.
-
-use crate::block::blocks;
-use crate::player::Player;
+//! Flying players collide with blocks too, except in spectator mode.
+//! Walking speed changes gradually: on the ground at a rate set by the
+//! `friction` and `slipperiness` of the block underfoot (ice keeps sliding),
+//! in the air at the fixed `AIR_CONTROL` rate.
+
+use crate::block::BlockRegistry;
+use crate::input::{ActionState, FixedActionState};
+use crate::player::{GameMode, Player};
 use crate::world::World;
 use bevy::prelude::*;
 
@@ -15,92 +20,119 @@ fn floor_to_i32(v: f32) -> i32 {
     i32::try_from(f as i64).expect("floored value fits in i32")
 }
 
-/// Handle camera/player movement and collisions each frame.
+/// Walking speed in blocks per second.
+pub const WALK_SPEED: f32 = 5.0;
+
+/// Rate (per second) at which horizontal velocity approaches the walking
+/// velocity on a block with `friction` 1.0 and the default slipperiness.
+pub const GROUND_CONTROL: f32 = 20.0;
+
+/// Rate (per second) at which horizontal velocity approaches the walking
+/// velocity while airborne.
+pub const AIR_CONTROL: f32 = 4.0;
+
+/// `slipperiness` of ordinary blocks; `GROUND_CONTROL` is scaled relative to it.
+pub const DEFAULT_SLIPPERINESS: f32 = 0.6;
+
+/// Whether a player body with its eyes at `eye` overlaps any solid block.
+fn body_collides(world: &World, blocks: &BlockRegistry, eye: Vec3, height: f32, radius: f32) -> bool {
+    let (min_x, max_x) = (floor_to_i32(eye.x - radius), floor_to_i32(eye.x + radius));
+    let (min_z, max_z) = (floor_to_i32(eye.z - radius), floor_to_i32(eye.z + radius));
+    (floor_to_i32(eye.y - height + 0.01)..=floor_to_i32(eye.y)).any(|y| {
+        (min_x..=max_x).any(|x| (min_z..=max_z).any(|z| blocks.is_solid(world.get_block(x, y, z))))
+    })
+}
+
+/// Handle camera/player movement and collisions each simulation step.
 ///
 /// # Arguments
-/// * `keyboard_input` - current keyboard state for movement/flying input
+/// * `actions` - step action state for the movement, jump and sneak actions
 /// * `world` - voxel world used for collision checks
-/// * `time` - delta time resource used to scale movement
+/// * `time` - fixed time resource used to scale movement
+/// * `blocks` - block registry providing `friction` and `slipperiness`
+/// * `mode` - current game mode (spectators fly through blocks)
 /// * `query` - query for `(Transform, Player)` to apply movement to
 #[allow(clippy::needless_pass_by_value)]
 pub fn camera_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<FixedActionState>,
     world: Res<World>,
     time: Res<Time>,
-    settings: Res<crate::settings::Settings>,
+    blocks: Res<BlockRegistry>,
+    mode: Res<GameMode>,
     mut query: Query<(&mut Transform, &mut Player), With<Camera3d>>,
 ) {
     let (mut camera, mut player) = query.single_mut();
-    let base_speed = 5.0;
+    movement_step(&mut camera, &mut player, &world, &blocks, *mode, &actions, time.delta_seconds());
+}
+
+/// Move the player for one step of `dt` seconds from the movement actions.
+///
+/// Extracted helper so the system and replays (see `replay`) run identical
+/// logic.
+pub fn movement_step(
+    camera: &mut Transform,
+    player: &mut Player,
+    world: &World,
+    blocks: &BlockRegistry,
+    mode: GameMode,
+    actions: &ActionState,
+    dt: f32,
+) {
     let fly_speed = 40.0;
     let player_height = 1.7;
     let player_radius = 0.35;
-    let dt = time.delta_seconds();
-
-    // Map movement keys from settings with defaults
-    let map_key = |name: &str, default: KeyCode| {
-        settings
-            .controls
-            .keybinds
-            .get(name)
-            .and_then(|s| crate::settings::Settings::keycode_from_str(s))
-            .unwrap_or(default)
-    };
-
-    let forward_kc = map_key("forward", KeyCode::KeyW);
-    let back_kc = map_key("back", KeyCode::KeyS);
-    let left_kc = map_key("left", KeyCode::KeyA);
-    let right_kc = map_key("right", KeyCode::KeyD);
-    let fly_kc = map_key("fly", KeyCode::Tab);
-    let jump_kc = map_key("jump", KeyCode::Space);
-
-    let mut dir = Vec3::ZERO;
 
     let forward_raw = camera.forward();
     let fwd = Vec3::new(forward_raw.x, 0.0, forward_raw.z).normalize_or_zero();
     let right_raw = camera.right();
     let right = Vec3::new(right_raw.x, 0.0, right_raw.z).normalize_or_zero();
 
-    if keyboard_input.pressed(forward_kc) {
-        dir += fwd;
-    }
-    if keyboard_input.pressed(back_kc) {
-        dir -= fwd;
-    }
-    if keyboard_input.pressed(left_kc) {
-        dir -= right;
-    }
-    if keyboard_input.pressed(right_kc) {
-        dir += right;
-    }
-
-    // Toggle flying while mapped fly key is held
-    player.flying = keyboard_input.pressed(fly_kc);
+    // Analog sticks give partial values; diagonals are capped at full speed
+    let dir = (fwd * (actions.value("forward") - actions.value("back"))
+        + right * (actions.value("right") - actions.value("left")))
+    .clamp_length_max(1.0);
 
     if player.flying {
-        // Flying: direct movement with no collisions and vertical control (mapped jump)
-        let mut movement = if dir.length_squared() > 0.0001 {
-            dir.normalize() * fly_speed * dt
+        // Flying (see `update_flight`): direct movement with vertical control (mapped jump / sneak)
+        let mut movement = dir * fly_speed * dt;
+        movement.y += (actions.value("jump") - actions.value("sneak")) * fly_speed * dt;
+
+        if mode.noclip() {
+            camera.translation += movement;
         } else {
-            Vec3::ZERO
-        };
-        if keyboard_input.pressed(jump_kc) {
-            movement.y += fly_speed * dt;
+            // Move one axis at a time so the player slides along walls
+            for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+                let next = camera.translation + movement * axis;
+                if !body_collides(world, blocks, next, player_height, player_radius) {
+                    camera.translation = next;
+                }
+            }
         }
-
-        camera.translation += movement;
-        // Reset vertical velocity so physics doesn't interfere when un-flying
-        player.velocity.y = 0.0;
+        // Reset velocity so physics doesn't interfere when un-flying
+        player.velocity = Vec3::ZERO;
         player.on_ground = false;
         return;
     }
 
-    // Grounded movement (existing collision checks)
-    let speed = base_speed;
-    if dir.length_squared() > 0.0001 {
-        dir = dir.normalize() * speed * dt;
-    }
-    let new_pos = camera.translation + dir;
+    // Grounded movement: steer the horizontal velocity towards the walking velocity
+    let target = dir * WALK_SPEED;
+    let rate = if player.on_ground {
+        let below = world.get_block(
+            floor_to_i32(camera.translation.x),
+            floor_to_i32(camera.translation.y - player_height - 0.05),
+            floor_to_i32(camera.translation.z),
+        );
+        blocks.get_by_id(below).map_or(GROUND_CONTROL, |b| {
+            GROUND_CONTROL * b.friction * (1.0 - b.slipperiness) / (1.0 - DEFAULT_SLIPPERINESS)
+        })
+    } else {
+        AIR_CONTROL
+    };
+    let blend = 1.0 - (-rate.max(0.0) * dt).exp();
+    let horizontal = Vec3::new(player.velocity.x, 0.0, player.velocity.z).lerp(target, blend);
+    player.velocity.x = horizontal.x;
+    player.velocity.z = horizontal.z;
+    let new_pos = camera.translation + horizontal * dt;
 
     // Check collision, but if jumping (velocity.y > 0), check from a higher position
     let y_offset = if player.velocity.y > 0.0 { 0.5 } else { 0.0 };
@@ -109,20 +141,13 @@ pub fn camera_movement(
 
     let mut can_move_x = true;
     let mut can_move_z = true;
+    let solid = |x: f32, y: i32, z: f32| blocks.is_solid(world.get_block(floor_to_i32(x), y, floor_to_i32(z)));
 
     // Check X movement separately
     for y in feet_y..=head_y {
         for dz in [-player_radius, 0.0, player_radius] {
-            if world.get_block(
-                floor_to_i32(new_pos.x + player_radius),
-                y,
-                floor_to_i32(camera.translation.z + dz),
-            ) != blocks::AIR
-            || world.get_block(
-                floor_to_i32(new_pos.x - player_radius),
-                y,
-                floor_to_i32(camera.translation.z + dz),
-            ) != blocks::AIR
+            if solid(new_pos.x + player_radius, y, camera.translation.z + dz)
+                || solid(new_pos.x - player_radius, y, camera.translation.z + dz)
             {
                 can_move_x = false;
             }
@@ -132,16 +157,8 @@ pub fn camera_movement(
     // Check Z movement separately
     for y in feet_y..=head_y {
         for dx in [-player_radius, 0.0, player_radius] {
-            if world.get_block(
-                floor_to_i32(camera.translation.x + dx),
-                y,
-                floor_to_i32(new_pos.z + player_radius),
-            ) != blocks::AIR
-            || world.get_block(
-                floor_to_i32(camera.translation.x + dx),
-                y,
-                floor_to_i32(new_pos.z - player_radius),
-            ) != blocks::AIR
+            if solid(camera.translation.x + dx, y, new_pos.z + player_radius)
+                || solid(camera.translation.x + dx, y, new_pos.z - player_radius)
             {
                 can_move_z = false;
             }
@@ -151,8 +168,53 @@ pub fn camera_movement(
     // Apply movement if no collision
     if can_move_x {
         camera.translation.x = new_pos.x;
+    } else {
+        player.velocity.x = 0.0;
     }
     if can_move_z {
         camera.translation.z = new_pos.z;
+    } else {
+        player.velocity.z = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::chunk::Chunk;
+
+    /// Horizontal speed after walking forward for `steps` steps on a floor of `floor`.
+    fn speed_after(floor: Block, steps: usize) -> f32 {
+        let mut blocks = BlockRegistry::default();
+        blocks.register(Block { id: 1, ..floor });
+        let mut world = World::new();
+        let mut chunk = Chunk::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set(x, 10, z, 1);
+            }
+        }
+        world.chunks.insert((0, 0), chunk);
+
+        let mut camera = Transform::from_xyz(8.0, 11.0 + 1.7, 12.0);
+        let mut player = Player { on_ground: true, ..Player::default() };
+        let mut actions = ActionState::default();
+        actions.press("forward", 1.0);
+        for _ in 0..steps {
+            movement_step(&mut camera, &mut player, &world, &blocks, GameMode::Survival, &actions, 1.0 / 60.0);
+        }
+        Vec3::new(player.velocity.x, 0.0, player.velocity.z).length()
+    }
+
+    #[test]
+    fn speed_builds_up_more_slowly_on_slippery_blocks() {
+        let stone = Block { name: "stone".to_string(), ..Block::default() };
+        let ice = Block { name: "ice".to_string(), slipperiness: 0.98, ..Block::default() };
+
+        let on_stone = speed_after(stone.clone(), 6);
+        let on_ice = speed_after(ice, 6);
+        assert!(on_ice < on_stone * 0.25, "ice {on_ice}, stone {on_stone}");
+        assert!((speed_after(stone, 60) - WALK_SPEED).abs() < 0.01);
     }
 }
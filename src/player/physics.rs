@@ -1,101 +1,310 @@
 //! Player physics: gravity, jumping, and ground detection.
 //!
-//! Applies gravity each frame, handles jumping input, and performs ground
+//! Applies gravity each step, handles jumping input, and performs ground
 //! collision checks to maintain `on_ground` and correct vertical position.
-//! Register `player_physics` as a system to run it each frame.
+//! Climbable blocks and grabbable ledges around the player replace gravity,
+//! and the distance fallen is reported through `PlayerLanded` on landing.
+//! Register `player_physics` in `FixedUpdate` to run it each simulation step.
 
-use crate::block::blocks;
+use crate::block::{blocks, Block, BlockRegistry};
+use crate::input::{ActionState, FixedActionState};
 use crate::player::Player;
 use crate::world::World;
 use bevy::prelude::*;
 
 pub const GRAVITY: f32 = -32.0;
 pub const JUMP_VELOCITY: f32 = 8.0;
+pub const TERMINAL_VELOCITY: f32 = -50.0;
 
-/// Apply gravity, jumping and ground detection for the player each frame.
+/// Speed (blocks per second) at which the player slides down a climbable
+/// block while not holding jump.
+pub const CLIMB_SLIDE_SPEED: f32 = 1.5;
+
+/// Upward velocity when pulling up from a ledge (enough to clear a block).
+pub const LEDGE_CLIMB_VELOCITY: f32 = 11.0;
+
+/// How far below a ledge's top edge the player's hands (eye height) can be
+/// and still grab it.
+pub const LEDGE_REACH: f32 = 0.35;
+
+const PLAYER_HEIGHT: f32 = 1.7;
+const PLAYER_RADIUS: f32 = 0.3;
+
+/// Sent when the player lands after falling.
+///
+/// # Fields
+/// * `fall_distance` - blocks fallen, multiplied by the landing block's `fall_damage_multiplier`
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerLanded {
+    pub fall_distance: f32,
+}
+
+/// Block definition at world position `pos`, if it is not air.
+#[allow(clippy::cast_possible_truncation)]
+fn block_at<'a>(world: &World, blocks: &'a BlockRegistry, pos: Vec3) -> Option<&'a Block> {
+    let id = world.get_block(pos.x.floor() as i32, pos.y.floor() as i32, pos.z.floor() as i32);
+    if id == blocks::AIR { None } else { blocks.get_by_id(id) }
+}
+
+/// The climbable block touching the player's feet or body, if any. Blocks
+/// stop the player before it can overlap them, so cells just outside the
+/// player's radius count as well.
+#[must_use]
+pub fn climbable_block<'a>(world: &World, blocks: &'a BlockRegistry, eye: Vec3) -> Option<&'a Block> {
+    let reach = PLAYER_RADIUS + 0.1;
+    [eye.y - PLAYER_HEIGHT + 0.1, eye.y - PLAYER_HEIGHT * 0.5].into_iter().find_map(|y| {
+        [Vec3::ZERO, Vec3::X, Vec3::NEG_X, Vec3::Z, Vec3::NEG_Z]
+            .into_iter()
+            .filter_map(|d| block_at(world, blocks, Vec3::new(eye.x, y, eye.z) + d * reach))
+            .find(|b| b.climbable)
+    })
+}
+
+/// Whether the player's hands (at eye height) are just below the top edge of
+/// a `can_grab_ledge` block next to them with free space above it.
+#[allow(clippy::cast_possible_truncation)]
+#[must_use]
+pub fn ledge_in_reach(world: &World, blocks: &BlockRegistry, eye: Vec3) -> bool {
+    let top = eye.y.floor() + 1.0;
+    if top - eye.y > LEDGE_REACH {
+        return false;
+    }
+    [Vec3::X, Vec3::NEG_X, Vec3::Z, Vec3::NEG_Z].into_iter().any(|d| {
+        let hand = eye + d * (PLAYER_RADIUS + 0.15);
+        block_at(world, blocks, hand).is_some_and(|b| b.can_grab_ledge)
+            && !blocks.is_solid(world.get_block(hand.x.floor() as i32, top as i32, hand.z.floor() as i32))
+    })
+}
+
+/// Apply gravity, jumping and ground detection for the player each simulation step.
 ///
 /// # Arguments
-/// * `time` - time resource for delta timing
+/// * `time` - fixed time resource for delta timing
 /// * `world` - world access for block queries (ground detection)
-/// * `kb` - keyboard input to detect jump/fly toggles
+/// * `blocks` - block registry; only solid blocks are ground
+/// * `actions` - step action state to detect jumps
 /// * `q` - query for `(Transform, Player)` to update
-/// Step the *core* player vertical-physics for one frame.
+/// Step the *core* player vertical-physics for one step of `dt` seconds.
 ///
 /// Extracted helper so systems and benchmarks exercise identical logic.
-pub fn physics_step(tf: &mut Transform, player: &mut Player, world: &World, dt: f32, kb: &ButtonInput<KeyCode>, fly_key: KeyCode, jump_key: KeyCode) {
-    // Flying: while the mapped fly key is held, disable gravity and allow vertical movement handled elsewhere
-    if kb.pressed(fly_key) {
-        player.flying = true;
+/// Next to a climbable block gravity is replaced by climbing (jump climbs
+/// up at the block's `climb_speed`, otherwise the player slides down slowly).
+/// Falling past a grabbable ledge stops the player until jump pulls them up
+/// or they move away from it.
+///
+/// # Return
+/// * `Option<f32>` - fall distance times the landing block's `fall_damage_multiplier`
+///   when the player landed during this step
+#[allow(clippy::cast_possible_truncation)]
+pub fn physics_step(tf: &mut Transform, player: &mut Player, world: &World, blocks: &BlockRegistry, dt: f32, actions: &ActionState) -> Option<f32> {
+    // Flying (see `update_flight`): disable gravity, vertical movement is handled elsewhere
+    if player.flying {
         player.velocity.y = 0.0;
+        player.fall_distance = 0.0;
         // do not apply gravity or ground logic while flying
-        return;
+        return None;
     }
 
-    // Ensure flying flag is cleared when fly key released
-    player.flying = false;
+    let climb = climbable_block(world, blocks, tf.translation);
+    player.climbing = climb.is_some();
+    player.hanging = !player.on_ground
+        && player.velocity.y <= 0.0
+        && climb.is_none()
+        && ledge_in_reach(world, blocks, tf.translation);
 
-    player.velocity.y += GRAVITY * dt;
-    if player.velocity.y < -50.0 {
-        player.velocity.y = -50.0;
+    if let Some(block) = climb {
+        player.velocity.y = if actions.pressed("jump") { block.climb_speed } else { -CLIMB_SLIDE_SPEED };
+        player.fall_distance = 0.0;
+    } else if player.hanging {
+        player.velocity.y = 0.0;
+        player.fall_distance = 0.0;
+        if actions.just_pressed("jump") {
+            player.velocity.y = LEDGE_CLIMB_VELOCITY;
+            player.hanging = false;
+        }
+    } else {
+        player.velocity.y += GRAVITY * dt;
+        if player.velocity.y < TERMINAL_VELOCITY {
+            player.velocity.y = TERMINAL_VELOCITY;
+        }
     }
 
-    if kb.just_pressed(jump_key) && player.on_ground {
+    if actions.just_pressed("jump") && player.on_ground {
         player.velocity.y = JUMP_VELOCITY;
         player.on_ground = false;
     }
 
     let new_y = tf.translation.y + player.velocity.y * dt;
-    let feet_y = new_y - 1.7;
-    let pr = 0.3;
-    let mut gnd = false;
+    let feet_y = new_y - PLAYER_HEIGHT;
+    let pr = PLAYER_RADIUS;
+    let mut ground = None;
     for dx in [-pr, pr] {
         for dz in [-pr, pr] {
-            if world.get_block(
+            let id = world.get_block(
                 (tf.translation.x + dx).floor() as i32,
                 feet_y.floor() as i32,
                 (tf.translation.z + dz).floor() as i32,
-            ) != blocks::AIR
-            {
-                gnd = true;
+            );
+            if blocks.is_solid(id) && ground.is_none() {
+                ground = Some(id);
             }
         }
     }
 
-    if gnd && player.velocity.y < 0.0 {
-        tf.translation.y = feet_y.floor() + 1.0 + 1.7;
+    let mut landed = None;
+    if let Some(id) = ground.filter(|_| player.velocity.y < 0.0) {
+        if !player.on_ground && player.fall_distance > 0.0 {
+            let multiplier = blocks.get_by_id(id).map_or(1.0, |b| b.fall_damage_multiplier);
+            landed = Some(player.fall_distance * multiplier);
+        }
+        tf.translation.y = feet_y.floor() + 1.0 + PLAYER_HEIGHT;
         player.velocity.y = 0.0;
         player.on_ground = true;
+        player.fall_distance = 0.0;
     } else {
+        if player.velocity.y < 0.0 && climb.is_none() {
+            player.fall_distance += tf.translation.y - new_y;
+        }
         tf.translation.y = new_y;
         if player.velocity.y < 0.0 {
             player.on_ground = false;
         }
     }
+    landed
 }
 
 #[allow(clippy::cast_possible_truncation, clippy::needless_pass_by_value)]
 pub fn player_physics(
     time: Res<Time>,
     world: Res<World>,
-    kb: Res<ButtonInput<KeyCode>>,
-    settings: Res<crate::settings::Settings>,
+    actions: Res<FixedActionState>,
+    blocks: Res<BlockRegistry>,
+    mut landings: EventWriter<PlayerLanded>,
     mut q: Query<(&mut Transform, &mut Player), With<Camera3d>>,
 ) {
     let (mut tf, mut player) = q.single_mut();
 
-    let fly_key = settings
-        .controls
-        .keybinds
-        .get("fly")
-        .and_then(|s| crate::settings::Settings::keycode_from_str(s))
-        .unwrap_or(KeyCode::Tab);
-
-    let jump_key = settings
-        .controls
-        .keybinds
-        .get("jump")
-        .and_then(|s| crate::settings::Settings::keycode_from_str(s))
-        .unwrap_or(KeyCode::Space);
-
-    physics_step(&mut tf, &mut player, &*world, time.delta_seconds(), &*kb, fly_key, jump_key);
+    if let Some(fall_distance) = physics_step(&mut tf, &mut player, &*world, &blocks, time.delta_seconds(), &actions) {
+        landings.send(PlayerLanded { fall_distance });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::player::movement::movement_step;
+    use crate::player::GameMode;
+
+    #[test]
+    fn landing_reports_fall_distance_scaled_by_block() {
+        let mut blocks = BlockRegistry::default();
+        blocks.register(Block { id: 1, name: "hay".to_string(), fall_damage_multiplier: 0.5, ..Block::default() });
+        let mut world = World::new();
+        let mut chunk = Chunk::new();
+        for x in 0..4 {
+            for z in 0..4 {
+                chunk.set(x, 0, z, 1);
+            }
+        }
+        world.chunks.insert((0, 0), chunk);
+
+        let mut tf = Transform::from_xyz(2.0, 11.0 + PLAYER_HEIGHT, 2.0);
+        let mut player = Player::default();
+        let actions = ActionState::default();
+        let landed = (0..600)
+            .find_map(|_| physics_step(&mut tf, &mut player, &world, &blocks, 1.0 / 60.0, &actions))
+            .expect("player lands");
+
+        assert!(player.on_ground);
+        assert!((landed - 5.0).abs() < 0.5, "landed after {landed}");
+    }
+
+    #[test]
+    fn player_climbs_a_ladder_column() {
+        let mut blocks = BlockRegistry::default();
+        blocks.register(Block { id: 1, name: "stone".to_string(), ..Block::default() });
+        blocks.register(Block { id: 2, name: "ladder".to_string(), solid: false, climbable: true, ..Block::default() });
+        let mut world = World::new();
+        let mut chunk = Chunk::new();
+        chunk.set(2, 0, 2, 1);
+        for y in 1..8 {
+            chunk.set(2, y, 3, 2);
+        }
+        world.chunks.insert((0, 0), chunk);
+
+        // Standing on the stone block, facing the ladder
+        let mut tf = Transform::from_xyz(2.5, 1.0 + PLAYER_HEIGHT, 2.65);
+        let mut player = Player { on_ground: true, ..Player::default() };
+        let mut actions = ActionState::default();
+        actions.set_tick(&[("jump".to_string(), 1.0)], &[]);
+        physics_step(&mut tf, &mut player, &world, &blocks, 1.0 / 60.0, &actions);
+        actions.set_tick(&[("jump".to_string(), 1.0)], &[]);
+        for _ in 0..59 {
+            physics_step(&mut tf, &mut player, &world, &blocks, 1.0 / 60.0, &actions);
+        }
+
+        assert!(player.climbing);
+        assert!((player.velocity.y - 2.0).abs() < f32::EPSILON);
+        let feet = tf.translation.y - PLAYER_HEIGHT;
+        assert!(feet > 3.0, "feet at {feet}");
+
+        // Letting go of jump slides back down without building up fall damage
+        actions.set_tick(&[], &[]);
+        let landed = (0..600).find_map(|_| {
+            physics_step(&mut tf, &mut player, &world, &blocks, 1.0 / 60.0, &actions);
+            player.on_ground.then_some(player.fall_distance)
+        });
+        assert_eq!(landed, Some(0.0));
+        assert!((tf.translation.y - (1.0 + PLAYER_HEIGHT)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ledge_grab_lifts_the_player_onto_the_block() {
+        let mut blocks = BlockRegistry::default();
+        blocks.register(Block { id: 1, name: "stone".to_string(), can_grab_ledge: true, ..Block::default() });
+        let mut world = World::new();
+        let mut chunk = Chunk::new();
+        for x in 0..8 {
+            for z in 0..8 {
+                chunk.set(x, 10, z, 1);
+            }
+        }
+        for y in 11..14 {
+            chunk.set(5, y, 4, 1);
+        }
+        world.chunks.insert((0, 0), chunk);
+
+        // Falling alongside the pillar, facing it, without walking
+        let mut tf = Transform::from_xyz(4.6, 16.0, 4.5).looking_to(Vec3::X, Vec3::Y);
+        let mut player = Player::default();
+        let dt = 1.0 / 60.0;
+        let forward = ("forward".to_string(), 1.0);
+        let mut actions = ActionState::default();
+        let step = |tf: &mut Transform, player: &mut Player, actions: &ActionState| {
+            movement_step(tf, player, &world, &blocks, GameMode::Survival, actions, dt);
+            physics_step(tf, player, &world, &blocks, dt, actions)
+        };
+
+        for _ in 0..60 {
+            step(&mut tf, &mut player, &actions);
+        }
+        assert!(player.hanging, "eye at {}", tf.translation.y);
+        assert!(tf.translation.y < 14.0 && 14.0 - tf.translation.y <= LEDGE_REACH);
+        assert!(tf.translation.x < 5.0);
+
+        actions.set_tick(&[forward.clone(), ("jump".to_string(), 1.0)], &[]);
+        step(&mut tf, &mut player, &actions);
+        actions.set_tick(&[forward], &[]);
+        for _ in 0..120 {
+            step(&mut tf, &mut player, &actions);
+            if player.on_ground {
+                break;
+            }
+        }
+
+        assert!(player.on_ground);
+        assert!(tf.translation.x > 5.0, "x at {}", tf.translation.x);
+        assert!((tf.translation.y - (14.0 + PLAYER_HEIGHT)).abs() < 1e-4);
+    }
 }
@@ -0,0 +1,472 @@
+//! Deterministic replays of player input.
+//!
+//! While recording (`replay record` in the console) every simulation step
+//! (`FixedUpdate`, at `gameplay.simulation_rate`) becomes a replay tick that
+//! stores the held actions, the actions pressed since the previous tick and
+//! the look (yaw/pitch) change gathered by `capture_replay_look` during the
+//! frames in between. Every `CHECKSUM_INTERVAL` ticks a checksum of the
+//! player position is taken. `replay stop` writes the recording, with the
+//! world seed, the tick rate and the starting player state, to
+//! `replays/replay-<time>.ron`.
+//!
+//! `replay play <file>` puts the player back at the recorded start and feeds
+//! one tick per simulation step through the same steps (`flight_step`,
+//! `movement_step`, `physics_step`) with the recorded tick length, reporting
+//! the first tick whose checksum does not match. A different simulation rate
+//! only changes the playback speed. The simulation holds while the player's
+//! chunk is not loaded, in both modes, so streaming speed does not change
+//! the outcome. The live player systems are paused (`replay_idle`) while a
+//! replay records or plays.
+
+use super::{flight_step, look_input, movement_step, physics_step, GameMode, Player, PlayerLanded, PlayerLook};
+use crate::block::BlockRegistry;
+use crate::chunk::{CHUNK_SIZE, WORLD_SEED};
+use crate::input::{ActionState, FixedActionState};
+use crate::settings::Settings;
+use crate::world::World;
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Directory replays are written to.
+pub const REPLAY_DIR: &str = "replays";
+
+/// Version of the replay file format.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Ticks between position checksums.
+pub const CHECKSUM_INTERVAL: u64 = 20;
+
+#[allow(clippy::cast_possible_truncation)]
+const CHUNK_SIZE_I32: i32 = CHUNK_SIZE as i32;
+
+/// Input of one simulation tick.
+///
+/// # Fields
+/// * `held` - held actions with their values
+/// * `pressed` - actions pressed since the previous tick
+/// * `look` - yaw and pitch change in radians
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTick {
+    #[serde(default)]
+    pub held: Vec<(String, f32)>,
+    #[serde(default)]
+    pub pressed: Vec<String>,
+    #[serde(default)]
+    pub look: (f32, f32),
+}
+
+/// Player state a replay starts from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+    pub flying: bool,
+    pub fall_distance: f32,
+}
+
+impl PlayerSnapshot {
+    /// Capture the player's state.
+    #[must_use]
+    pub fn capture(tf: &Transform, player: &Player, look: &PlayerLook) -> Self {
+        Self {
+            position: tf.translation.to_array(),
+            velocity: player.velocity.to_array(),
+            yaw: look.yaw,
+            pitch: look.pitch,
+            on_ground: player.on_ground,
+            flying: player.flying,
+            fall_distance: player.fall_distance,
+        }
+    }
+
+    /// Put the player into this state.
+    pub fn restore(&self, tf: &mut Transform, player: &mut Player, look: &mut PlayerLook) {
+        tf.translation = Vec3::from_array(self.position);
+        *player = Player {
+            velocity: Vec3::from_array(self.velocity),
+            on_ground: self.on_ground,
+            flying: self.flying,
+            fall_distance: self.fall_distance,
+            ..Player::default()
+        };
+        look.yaw = self.yaw;
+        look.pitch = self.pitch;
+        tf.rotation = look.rotation();
+    }
+}
+
+/// A recorded replay, as stored in the replay file.
+///
+/// # Fields
+/// * `version` - file format version (`REPLAY_VERSION`)
+/// * `seed` - world seed the replay was recorded in
+/// * `tick_rate` - simulation ticks per second
+/// * `checksum_interval` - ticks between checksums
+/// * `game_mode` - game mode during the recording
+/// * `start` - player state at the first tick
+/// * `ticks` - input of every tick
+/// * `checksums` - `(tick, checksum)` of the player position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u32,
+    pub tick_rate: f32,
+    pub checksum_interval: u64,
+    #[serde(default)]
+    pub game_mode: GameMode,
+    pub start: PlayerSnapshot,
+    #[serde(default)]
+    pub ticks: Vec<ReplayTick>,
+    #[serde(default)]
+    pub checksums: Vec<(u64, u64)>,
+}
+
+impl Replay {
+    /// An empty replay at `tick_rate` ticks per second starting from `start`.
+    #[must_use]
+    pub fn new(game_mode: GameMode, start: PlayerSnapshot, tick_rate: f32) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed: WORLD_SEED,
+            tick_rate,
+            checksum_interval: CHECKSUM_INTERVAL,
+            game_mode,
+            start,
+            ticks: Vec::new(),
+            checksums: Vec::new(),
+        }
+    }
+
+    /// The recorded checksum after `tick` ticks, if one was taken.
+    #[must_use]
+    pub fn checksum_at(&self, tick: u64) -> Option<u64> {
+        self.checksums.iter().find(|(t, _)| *t == tick).map(|(_, sum)| *sum)
+    }
+
+    /// Load a replay file.
+    ///
+    /// # Errors
+    /// Returns a message if the file cannot be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        ron::from_str(&text).map_err(|e| format!("cannot parse {}: {e}", path.display()))
+    }
+
+    /// Write the replay to `dir` as `replay-<time>.ron`.
+    ///
+    /// # Errors
+    /// Returns a message if the directory cannot be created or the file written.
+    pub fn save(&self, dir: &str) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {dir}: {e}"))?;
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let path = PathBuf::from(dir).join(format!("replay-{stamp}.ron"));
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+        std::fs::write(&path, text).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Checksum of a player position (FNV-1a over the coordinates' bits).
+#[must_use]
+pub fn position_checksum(position: Vec3) -> u64 {
+    position
+        .to_array()
+        .iter()
+        .flat_map(|c| c.to_bits().to_le_bytes())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Run one replay tick: apply its input to `actions` and step the player's
+/// look, flight, movement and physics by `dt`.
+///
+/// # Returns
+/// The fall distance if the player landed this tick.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_tick(
+    tick: &ReplayTick,
+    actions: &mut ActionState,
+    tf: &mut Transform,
+    player: &mut Player,
+    look: &mut PlayerLook,
+    world: &World,
+    blocks: &BlockRegistry,
+    mode: GameMode,
+    dt: f32,
+) -> Option<f32> {
+    actions.set_tick(&tick.held, &tick.pressed);
+    look.turn(Vec2::new(tick.look.0, tick.look.1));
+    tf.rotation = look.rotation();
+    flight_step(player, mode, actions);
+    movement_step(tf, player, world, blocks, mode, actions, dt);
+    physics_step(tf, player, world, blocks, dt, actions)
+}
+
+/// Whether the chunk holding `position` is loaded.
+#[allow(clippy::cast_possible_truncation)]
+fn chunk_loaded(world: &World, position: Vec3) -> bool {
+    let cx = (position.x.floor() as i32).div_euclid(CHUNK_SIZE_I32);
+    let cz = (position.z.floor() as i32).div_euclid(CHUNK_SIZE_I32);
+    world.chunks.contains_key(&(cx, cz))
+}
+
+/// What the replay system is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMode {
+    #[default]
+    Idle,
+    Recording,
+    Playing,
+}
+
+/// Replay recording and playback state.
+///
+/// # Fields
+/// * `mode` - idle, recording or playing
+/// * `replay` - the replay being recorded or played
+/// * `tick` - ticks simulated so far
+/// * `divergence` - first tick whose checksum did not match during playback
+#[derive(Resource, Default)]
+pub struct ReplayState {
+    pub mode: ReplayMode,
+    pub replay: Option<Replay>,
+    pub tick: u64,
+    pub divergence: Option<u64>,
+    started: bool,
+    actions: ActionState,
+    pending_look: Vec2,
+    pending_presses: BTreeSet<String>,
+}
+
+impl ReplayState {
+    /// Start recording from the player's current state.
+    pub fn record(&mut self) {
+        *self = Self { mode: ReplayMode::Recording, ..Self::default() };
+    }
+
+    /// Start playing `replay`.
+    pub fn play(&mut self, replay: Replay) {
+        *self = Self { mode: ReplayMode::Playing, replay: Some(replay), ..Self::default() };
+    }
+
+    /// Stop recording or playback.
+    ///
+    /// # Returns
+    /// The recording, when one was being made.
+    pub fn stop(&mut self) -> Option<Replay> {
+        let recorded = (self.mode == ReplayMode::Recording).then(|| self.replay.take()).flatten();
+        self.mode = ReplayMode::Idle;
+        recorded
+    }
+
+    /// One-line description of the current state.
+    #[must_use]
+    pub fn status(&self) -> String {
+        let total = self.replay.as_ref().map_or(0, |r| r.ticks.len());
+        match self.mode {
+            ReplayMode::Idle => "No replay running".to_string(),
+            ReplayMode::Recording => format!("Recording: {} ticks", self.tick),
+            ReplayMode::Playing => match self.divergence {
+                Some(tick) => format!("Playing: tick {}/{total}, diverged at tick {tick}", self.tick),
+                None => format!("Playing: tick {}/{total}, in sync", self.tick),
+            },
+        }
+    }
+}
+
+/// Run condition: true while no replay is recording or playing, so the live
+/// player systems run.
+#[allow(clippy::needless_pass_by_value)]
+#[must_use]
+pub fn replay_idle(state: Res<ReplayState>) -> bool {
+    state.mode == ReplayMode::Idle
+}
+
+/// Gather the look change of this frame for the next recorded tick. Runs in
+/// `Update` while recording.
+///
+/// # Arguments
+/// * `state` - replay state
+/// * `motion` - mouse motion events for this update
+/// * `actions` - action state for the `look_*` actions
+/// * `time` - delta time for stick look
+/// * `settings` - look sensitivity and invert settings
+/// * `windows` - primary window (looking only turns while the cursor is grabbed)
+#[allow(clippy::needless_pass_by_value)]
+pub fn capture_replay_look(
+    mut state: ResMut<ReplayState>,
+    motion: Res<Events<MouseMotion>>,
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    if state.mode != ReplayMode::Recording || !windows.get_single().is_ok_and(|w| !w.cursor.visible) {
+        return;
+    }
+    let (mouse, stick) = look_input(&motion, &actions, &settings.controls);
+    if mouse != Vec2::ZERO || stick != Vec2::ZERO {
+        state.pending_look +=
+            PlayerLook::look_delta(mouse, stick, time.delta_seconds(), settings.controls.mouse_sensitivity);
+    }
+}
+
+/// Resources used by `run_replay`.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct ReplayCtx<'w, 's> {
+    pub state: ResMut<'w, ReplayState>,
+    pub time: Res<'w, Time>,
+    pub world: Res<'w, World>,
+    pub blocks: Res<'w, BlockRegistry>,
+    pub mode: ResMut<'w, GameMode>,
+    pub actions: Res<'w, FixedActionState>,
+    pub landings: EventWriter<'w, PlayerLanded>,
+    pub player: Query<'w, 's, (&'static mut Transform, &'static mut Player, &'static mut PlayerLook), With<Camera3d>>,
+}
+
+/// Record or play back one replay tick per simulation step while a replay
+/// is running (see the module docs). Runs in `FixedUpdate`.
+///
+/// # Arguments
+/// * `ctx` - replay state, world, step input and the player
+pub fn run_replay(mut ctx: ReplayCtx) {
+    let mode = ctx.state.mode;
+    if mode == ReplayMode::Idle {
+        return;
+    }
+    let Ok((mut tf, mut player, mut look)) = ctx.player.get_single_mut() else { return };
+    let state = &mut *ctx.state;
+
+    if !state.started {
+        state.started = true;
+        match state.replay.as_ref() {
+            None => {
+                let tick_rate = (1.0 / ctx.time.delta_seconds()).round();
+                state.replay = Some(Replay::new(*ctx.mode, PlayerSnapshot::capture(&tf, &player, &look), tick_rate));
+                info!("Replay recording started");
+            }
+            Some(replay) => {
+                if replay.seed != WORLD_SEED {
+                    warn!("Replay was recorded with seed {}, this world uses {WORLD_SEED}", replay.seed);
+                }
+                replay.start.restore(&mut tf, &mut player, &mut look);
+                *ctx.mode = replay.game_mode;
+            }
+        }
+    }
+
+    if mode == ReplayMode::Recording {
+        state.pending_presses.extend(ctx.actions.just_pressed_actions().map(str::to_string));
+    }
+
+    let Some(replay) = state.replay.as_mut() else { return };
+    // Hold until the chunk under the player has streamed in
+    if !chunk_loaded(&ctx.world, tf.translation) {
+        return;
+    }
+
+    let tick = if mode == ReplayMode::Recording {
+        let tick = ReplayTick {
+            held: ctx.actions.held().map(|(a, v)| (a.to_string(), v)).collect(),
+            pressed: std::mem::take(&mut state.pending_presses).into_iter().collect(),
+            look: std::mem::take(&mut state.pending_look).into(),
+        };
+        replay.ticks.push(tick.clone());
+        tick
+    } else {
+        let Some(tick) = usize::try_from(state.tick).ok().and_then(|i| replay.ticks.get(i)) else {
+            match state.divergence {
+                Some(at) => warn!("Replay finished after {} ticks; diverged at tick {at}", state.tick),
+                None => info!("Replay finished after {} ticks, in sync", state.tick),
+            }
+            state.mode = ReplayMode::Idle;
+            return;
+        };
+        tick.clone()
+    };
+
+    let dt = 1.0 / replay.tick_rate;
+    let mode_now = *ctx.mode;
+    if let Some(fall_distance) =
+        simulate_tick(&tick, &mut state.actions, &mut tf, &mut player, &mut look, &ctx.world, &ctx.blocks, mode_now, dt)
+    {
+        ctx.landings.send(PlayerLanded { fall_distance });
+    }
+    state.tick += 1;
+
+    if state.tick.is_multiple_of(replay.checksum_interval.max(1)) {
+        let sum = position_checksum(tf.translation);
+        if mode == ReplayMode::Recording {
+            replay.checksums.push((state.tick, sum));
+        } else if state.divergence.is_none() && replay.checksum_at(state.tick).is_some_and(|expected| expected != sum) {
+            warn!("Replay diverged at tick {} (player at {:.3})", state.tick, tf.translation);
+            state.divergence = Some(state.tick);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::chunk::Chunk;
+
+    fn run(replay: &Replay, world: &World, blocks: &BlockRegistry) -> Vec<(u64, u64)> {
+        let (mut tf, mut player, mut look) = (Transform::default(), Player::default(), PlayerLook::default());
+        replay.start.restore(&mut tf, &mut player, &mut look);
+        let mut actions = ActionState::default();
+        let mut sums = Vec::new();
+        for (i, tick) in (1_u64..).zip(&replay.ticks) {
+            simulate_tick(tick, &mut actions, &mut tf, &mut player, &mut look, world, blocks, replay.game_mode, 1.0 / replay.tick_rate);
+            if i.is_multiple_of(replay.checksum_interval) {
+                sums.push((i, position_checksum(tf.translation)));
+            }
+        }
+        sums
+    }
+
+    #[test]
+    fn replays_reproduce_checksums_and_flag_divergence() {
+        let mut blocks = BlockRegistry::default();
+        blocks.register(Block { id: 1, name: "stone".to_string(), ..Block::default() });
+        let mut world = World::new();
+        let mut chunk = Chunk::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.set(x, 0, z, 1);
+            }
+        }
+        world.chunks.insert((0, 0), chunk);
+
+        let start = PlayerSnapshot { position: [16.0, 2.8, 24.0], on_ground: true, ..PlayerSnapshot::default() };
+        let mut replay = Replay::new(GameMode::Survival, start, 60.0);
+        for i in 0..120 {
+            let mut held = vec![("forward".to_string(), 1.0)];
+            if (30..34).contains(&i) {
+                held.push(("jump".to_string(), 1.0));
+            }
+            replay.ticks.push(ReplayTick { held, pressed: Vec::new(), look: (0.01, 0.0) });
+        }
+        replay.checksums = run(&replay, &world, &blocks);
+        assert_eq!(replay.checksums.len(), 6);
+
+        // A saved replay reproduces the recorded checksums exactly
+        let loaded: Replay = ron::from_str(&ron::to_string(&replay).expect("encodes")).expect("decodes");
+        assert_eq!(loaded, replay);
+        assert_eq!(run(&loaded, &world, &blocks), replay.checksums);
+
+        // Changed input shows up as a mismatch from the next checksum on
+        let mut changed = replay.clone();
+        changed.ticks[50].look = (0.5, 0.0);
+        let sums = run(&changed, &world, &blocks);
+        let first = sums.iter().find(|(t, sum)| replay.checksum_at(*t) != Some(*sum)).map(|(t, _)| *t);
+        assert_eq!(first, Some(60));
+    }
+}
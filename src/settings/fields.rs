@@ -0,0 +1,283 @@
+//! Editable settings fields for the in-game settings menu.
+//!
+//! `menu_fields` lists every setting the menu shows, by section, with the
+//! widget used to edit it and accessors into `Settings`. Labels and tooltips
+//! come from `Settings::field_descriptions`. Keybinds have their own screen
+//! and are not listed here.
+
+use super::{PerformancePreset, Settings};
+
+/// Widget used to edit a field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldKind {
+    /// On/off button
+    Toggle,
+    /// Draggable slider; values are snapped to `step`
+    Slider { min: f32, max: f32, step: f32 },
+    /// One of a fixed list of names
+    Dropdown(&'static [&'static str]),
+}
+
+/// Current value of a field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Bool(bool),
+    Number(f32),
+    Choice(String),
+}
+
+/// One editable setting.
+///
+/// # Fields
+/// * `section` - section of `Settings` (e.g. "graphics"), also the menu tab
+/// * `name` - field name, as in the RON file and `Settings::field_descriptions`
+/// * `kind` - widget used to edit it
+/// * `get` - reads the value from `Settings`
+/// * `set` - writes a value (of the kind's type) into `Settings`
+#[derive(Clone, Copy)]
+pub struct SettingField {
+    pub section: &'static str,
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub get: fn(&Settings) -> FieldValue,
+    pub set: fn(&mut Settings, &FieldValue),
+}
+
+/// Menu sections, in display order.
+pub const SECTIONS: [&str; 6] = ["graphics", "audio", "controls", "performance", "atmosphere", "gameplay"];
+
+/// Present modes offered for `graphics.present_mode`.
+pub const PRESENT_MODES: [&str; 5] = ["AutoVsync", "AutoNoVsync", "Fifo", "Immediate", "Mailbox"];
+
+impl FieldValue {
+    fn bool(&self) -> Option<bool> {
+        if let Self::Bool(b) = self { Some(*b) } else { None }
+    }
+
+    fn number(&self) -> Option<f32> {
+        if let Self::Number(n) = self { Some(*n) } else { None }
+    }
+
+    fn choice(&self) -> Option<&str> {
+        if let Self::Choice(c) = self { Some(c) } else { None }
+    }
+
+    /// Text shown on the field's widget.
+    #[must_use]
+    pub fn display(&self, kind: FieldKind) -> String {
+        match (self, kind) {
+            (Self::Bool(b), _) => if *b { "On" } else { "Off" }.to_string(),
+            (Self::Number(n), FieldKind::Slider { step, .. }) if step >= 1.0 => format!("{n:.0}"),
+            (Self::Number(n), _) => format!("{n:.2}"),
+            (Self::Choice(c), _) => c.clone(),
+        }
+    }
+}
+
+impl FieldKind {
+    /// Snap `value` into the slider's range and onto its steps.
+    #[must_use]
+    pub fn snap(self, value: f32) -> f32 {
+        match self {
+            Self::Slider { min, max, step } => (min + ((value - min) / step).round() * step).clamp(min, max),
+            _ => value,
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+fn whole(v: &FieldValue) -> Option<u32> {
+    v.number().map(|n| n.round().max(0.0) as u32)
+}
+
+/// Every field the settings menu shows, grouped by section in `SECTIONS`
+/// order.
+#[must_use]
+#[allow(clippy::too_many_lines, clippy::cast_precision_loss)]
+pub fn menu_fields() -> Vec<SettingField> {
+    use FieldKind::{Dropdown, Slider, Toggle};
+    use FieldValue::{Bool, Choice, Number};
+    vec![
+        SettingField {
+            section: "graphics",
+            name: "vsync",
+            kind: Toggle,
+            get: |s| Bool(s.graphics.vsync),
+            set: |s, v| s.graphics.vsync = v.bool().unwrap_or(s.graphics.vsync),
+        },
+        SettingField {
+            section: "graphics",
+            name: "present_mode",
+            kind: Dropdown(&PRESENT_MODES),
+            get: |s| Choice(s.graphics.present_mode.clone()),
+            set: |s, v| {
+                if let Some(c) = v.choice() {
+                    s.graphics.present_mode = c.to_string();
+                }
+            },
+        },
+        SettingField {
+            section: "graphics",
+            name: "render_distance",
+            kind: Slider { min: 2.0, max: 32.0, step: 1.0 },
+            get: |s| Number(s.graphics.render_distance as f32),
+            set: |s, v| s.graphics.render_distance = whole(v).unwrap_or(s.graphics.render_distance),
+        },
+        SettingField {
+            section: "graphics",
+            name: "shadows",
+            kind: Toggle,
+            get: |s| Bool(s.graphics.shadows),
+            set: |s, v| s.graphics.shadows = v.bool().unwrap_or(s.graphics.shadows),
+        },
+        SettingField {
+            section: "graphics",
+            name: "ambient_tint_strength",
+            kind: Slider { min: 0.0, max: 4.0, step: 0.1 },
+            get: |s| Number(s.graphics.ambient_tint_strength),
+            set: |s, v| s.graphics.ambient_tint_strength = v.number().unwrap_or(s.graphics.ambient_tint_strength),
+        },
+        SettingField {
+            section: "audio",
+            name: "master_volume",
+            kind: Slider { min: 0.0, max: 1.0, step: 0.05 },
+            get: |s| Number(s.audio.master_volume),
+            set: |s, v| s.audio.master_volume = v.number().unwrap_or(s.audio.master_volume),
+        },
+        SettingField {
+            section: "audio",
+            name: "music_volume",
+            kind: Slider { min: 0.0, max: 1.0, step: 0.05 },
+            get: |s| Number(s.audio.music_volume),
+            set: |s, v| s.audio.music_volume = v.number().unwrap_or(s.audio.music_volume),
+        },
+        SettingField {
+            section: "audio",
+            name: "effects_volume",
+            kind: Slider { min: 0.0, max: 1.0, step: 0.05 },
+            get: |s| Number(s.audio.effects_volume),
+            set: |s, v| s.audio.effects_volume = v.number().unwrap_or(s.audio.effects_volume),
+        },
+        SettingField {
+            section: "controls",
+            name: "invert_y",
+            kind: Toggle,
+            get: |s| Bool(s.controls.invert_y),
+            set: |s, v| s.controls.invert_y = v.bool().unwrap_or(s.controls.invert_y),
+        },
+        SettingField {
+            section: "controls",
+            name: "invert_x",
+            kind: Toggle,
+            get: |s| Bool(s.controls.invert_x),
+            set: |s, v| s.controls.invert_x = v.bool().unwrap_or(s.controls.invert_x),
+        },
+        SettingField {
+            section: "controls",
+            name: "mouse_sensitivity",
+            kind: Slider { min: 1.0, max: 100.0, step: 1.0 },
+            get: |s| Number(s.controls.mouse_sensitivity),
+            set: |s, v| s.controls.mouse_sensitivity = v.number().unwrap_or(s.controls.mouse_sensitivity),
+        },
+        SettingField {
+            section: "performance",
+            name: "preset",
+            kind: Dropdown(&PerformancePreset::NAMES),
+            get: |s| Choice(s.performance.preset.name().to_string()),
+            set: |s, v| {
+                if let Some(preset) = v.choice().and_then(PerformancePreset::from_name) {
+                    s.performance.preset = preset;
+                }
+            },
+        },
+        SettingField {
+            section: "performance",
+            name: "background_meshing",
+            kind: Toggle,
+            get: |s| Bool(s.performance.background_meshing),
+            set: |s, v| s.performance.background_meshing = v.bool().unwrap_or(s.performance.background_meshing),
+        },
+        SettingField {
+            section: "performance",
+            name: "max_chunk_meshes_per_frame",
+            kind: Slider { min: 1.0, max: 16.0, step: 1.0 },
+            get: |s| Number(f32::from(s.performance.max_chunk_meshes_per_frame)),
+            set: |s, v| {
+                if let Some(n) = whole(v).and_then(|n| u8::try_from(n).ok()) {
+                    s.performance.max_chunk_meshes_per_frame = n;
+                }
+            },
+        },
+        SettingField {
+            section: "performance",
+            name: "adaptive_budget",
+            kind: Toggle,
+            get: |s| Bool(s.performance.adaptive_budget),
+            set: |s, v| s.performance.adaptive_budget = v.bool().unwrap_or(s.performance.adaptive_budget),
+        },
+        SettingField {
+            section: "performance",
+            name: "target_frame_time_ms",
+            kind: Slider { min: 4.0, max: 50.0, step: 0.1 },
+            get: |s| Number(s.performance.target_frame_time_ms),
+            set: |s, v| s.performance.target_frame_time_ms = v.number().unwrap_or(s.performance.target_frame_time_ms),
+        },
+        SettingField {
+            section: "atmosphere",
+            name: "enabled",
+            kind: Toggle,
+            get: |s| Bool(s.atmosphere.enabled),
+            set: |s, v| s.atmosphere.enabled = v.bool().unwrap_or(s.atmosphere.enabled),
+        },
+        SettingField {
+            section: "atmosphere",
+            name: "resolution",
+            kind: Slider { min: 8.0, max: 1024.0, step: 8.0 },
+            get: |s| Number(s.atmosphere.resolution as f32),
+            set: |s, v| s.atmosphere.resolution = whole(v).unwrap_or(s.atmosphere.resolution),
+        },
+        SettingField {
+            section: "atmosphere",
+            name: "dithering",
+            kind: Toggle,
+            get: |s| Bool(s.atmosphere.dithering),
+            set: |s, v| s.atmosphere.dithering = v.bool().unwrap_or(s.atmosphere.dithering),
+        },
+        SettingField {
+            section: "gameplay",
+            name: "fire_spread",
+            kind: Toggle,
+            get: |s| Bool(s.gameplay.fire_spread),
+            set: |s, v| s.gameplay.fire_spread = v.bool().unwrap_or(s.gameplay.fire_spread),
+        },
+        SettingField {
+            section: "gameplay",
+            name: "simulation_rate",
+            kind: Slider { min: 20.0, max: 240.0, step: 10.0 },
+            get: |s| Number(s.gameplay.simulation_rate),
+            set: |s, v| s.gameplay.simulation_rate = v.number().unwrap_or(s.gameplay.simulation_rate),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_field_is_described_and_round_trips() {
+        let descriptions = Settings::field_descriptions();
+        let mut settings = Settings::default();
+        for field in menu_fields() {
+            assert!(SECTIONS.contains(&field.section));
+            assert!(descriptions.get(field.section).is_some_and(|m| m.contains_key(field.name)), "{}.{}", field.section, field.name);
+            let changed = match field.kind {
+                FieldKind::Toggle => FieldValue::Bool((field.get)(&settings) != FieldValue::Bool(true)),
+                FieldKind::Slider { max, .. } => FieldValue::Number(max),
+                FieldKind::Dropdown(names) => FieldValue::Choice(names[names.len() - 1].to_string()),
+            };
+            (field.set)(&mut settings, &changed);
+            assert_eq!((field.get)(&settings), changed, "{}.{}", field.section, field.name);
+        }
+    }
+}
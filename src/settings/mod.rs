@@ -84,19 +84,51 @@ pub struct ControlsSettings {
 impl ControlsSettings {
     fn default_sensitivity() -> f32 { 1.0 }
 
-    fn default_keybinds() -> HashMap<String, String> {
-        use std::collections::HashMap;
-        let mut m = HashMap::new();
-        m.insert("forward".to_string(), "W".to_string());
-        m.insert("back".to_string(), "S".to_string());
-        m.insert("left".to_string(), "A".to_string());
-        m.insert("right".to_string(), "D".to_string());
-        m.insert("jump".to_string(), "Space".to_string());
-        m.insert("sneak".to_string(), "LShift".to_string());
-        m.insert("toggle_debug".to_string(), "F1".to_string());
-        m.insert("toggle_grid".to_string(), "F2".to_string());
-        m.insert("dump_debug".to_string(), "F3".to_string());
-        m
+    /// Built-in bindings of every action. `InputMap` falls back to these
+    /// for actions missing from the settings file; see `crate::input` for
+    /// the binding syntax.
+    #[must_use]
+    pub fn default_keybinds() -> HashMap<String, String> {
+        let binds = [
+            ("forward", "W | Pad:LeftStickY+"),
+            ("back", "S | Pad:LeftStickY-"),
+            ("left", "A | Pad:LeftStickX-"),
+            ("right", "D | Pad:LeftStickX+"),
+            ("look_left", "Pad:RightStickX-"),
+            ("look_right", "Pad:RightStickX+"),
+            ("look_up", "Pad:RightStickY+"),
+            ("look_down", "Pad:RightStickY-"),
+            ("jump", "Space | Pad:South"),
+            ("sneak", "LShift | Pad:East"),
+            ("fly", "Tab | Pad:LeftThumb"),
+            ("break", "Mouse:Left | Pad:RightTrigger2"),
+            ("place", "Mouse:Right | Pad:LeftTrigger2"),
+            ("pick_block", "Mouse:Middle"),
+            ("hotbar_1", "1"),
+            ("hotbar_2", "2"),
+            ("hotbar_3", "3"),
+            ("hotbar_4", "4"),
+            ("hotbar_5", "5"),
+            ("hotbar_6", "6"),
+            ("hotbar_7", "7"),
+            ("hotbar_8", "8"),
+            ("hotbar_9", "9"),
+            ("hotbar_next", "Pad:RightTrigger"),
+            ("hotbar_prev", "Pad:LeftTrigger"),
+            ("toggle_debug", "F1"),
+            ("toggle_grid", "F2"),
+            ("dump_debug", "F3"),
+            ("crafting", "C | Pad:North"),
+            ("gamemode", "F4"),
+            ("console", "`"),
+            ("select_corner_1", "["),
+            ("select_corner_2", "]"),
+            ("map", "M | Pad:Select"),
+            ("map_biomes", "B"),
+            ("pause", "Esc | Pad:Start"),
+            ("export_profile", "F5"),
+        ];
+        binds.into_iter().map(|(action, bind)| (action.to_string(), bind.to_string())).collect()
     }
 }
 impl Default for ControlsSettings {
@@ -115,6 +147,36 @@ impl Default for ControlsSettings {
 #[serde(rename_all = "lowercase")]
 pub enum PerformancePreset { VeryLow, Low, Medium, High, VeryHigh }
 
+impl PerformancePreset {
+    /// Preset names as written in the RON file, lowest first.
+    pub const NAMES: [&'static str; 5] = ["verylow", "low", "medium", "high", "veryhigh"];
+
+    /// Name of the preset as written in the RON file.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::VeryLow => "verylow",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::VeryHigh => "veryhigh",
+        }
+    }
+
+    /// Preset for a name as written in the RON file.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "verylow" => Self::VeryLow,
+            "low" => Self::Low,
+            "medium" => Self::Medium,
+            "high" => Self::High,
+            "veryhigh" => Self::VeryHigh,
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceSettings {
     #[serde(default = "PerformanceSettings::default_preset")]
@@ -123,12 +185,33 @@ pub struct PerformanceSettings {
     pub background_meshing: bool, // Allow chunk meshing to run on background worker threads.
     #[serde(default = "PerformanceSettings::default_max_chunk_meshes_per_frame")]
     pub max_chunk_meshes_per_frame: u8, // Limit how many chunk meshes the main thread may build per frame.
+    #[serde(default = "PerformanceSettings::default_adaptive_budget")]
+    pub adaptive_budget: bool, // Scale the streaming budget up or down to hold the target frame time.
+    #[serde(default = "PerformanceSettings::default_target_frame_time_ms")]
+    pub target_frame_time_ms: f32, // Frame time (ms) the adaptive budget aims for.
+    #[serde(default)]
+    pub overrides: BudgetOverrides, // Per-limit overrides of the preset's streaming budget; unset limits follow the preset.
+}
+
+/// Optional overrides of individual `chunk::lod::StreamingBudget` limits.
+/// In RON each value is written as `Some(..)`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetOverrides {
+    pub generation_tasks: Option<usize>, // Max chunk generation tasks in flight
+    pub mesh_builds_per_frame: Option<usize>, // Max mesh builds started per frame for new chunks
+    pub lod_builds_per_frame: Option<usize>, // Max LOD mesh builds started per frame
+    pub pending_lod_tasks: Option<usize>, // Max mesh/LOD builds in flight
+    pub prewarm_levels: Option<u8>, // Coarser LOD levels prebuilt for chunks near the load boundary
+    pub lod_distances: Option<Vec<i32>>, // Chunk distance thresholds of each LOD level (one per level)
 }
 
 impl PerformanceSettings {
     fn default_preset() -> PerformancePreset { PerformancePreset::Medium }
     fn default_background_meshing() -> bool { true }
     fn default_max_chunk_meshes_per_frame() -> u8 { 2 }
+    fn default_adaptive_budget() -> bool { true }
+    fn default_target_frame_time_ms() -> f32 { 16.7 }
 }
 
 impl Default for PerformanceSettings {
@@ -137,6 +220,9 @@ impl Default for PerformanceSettings {
             preset: Self::default_preset(),
             background_meshing: Self::default_background_meshing(),
             max_chunk_meshes_per_frame: Self::default_max_chunk_meshes_per_frame(),
+            adaptive_budget: Self::default_adaptive_budget(),
+            target_frame_time_ms: Self::default_target_frame_time_ms(),
+            overrides: BudgetOverrides::default(),
         }
     }
 }
@@ -183,6 +269,29 @@ impl Default for AtmosphereSettings {
     }
 }
 
+/// Gameplay rules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameplaySettings {
+    #[serde(default = "GameplaySettings::default_fire_spread")]
+    pub fire_spread: bool, // Fire spreads to and burns away flammable blocks (turn off for creative worlds)
+    #[serde(default = "GameplaySettings::default_simulation_rate")]
+    pub simulation_rate: f32, // Gameplay simulation steps per second (physics, block ticks, entities)
+}
+
+impl GameplaySettings {
+    fn default_fire_spread() -> bool { true }
+    fn default_simulation_rate() -> f32 { 60.0 }
+}
+
+impl Default for GameplaySettings {
+    fn default() -> Self {
+        Self {
+            fire_spread: Self::default_fire_spread(),
+            simulation_rate: Self::default_simulation_rate(),
+        }
+    }
+}
+
 /// Top-level Settings
 #[derive(Resource, Clone, Debug, Serialize, Deserialize)]
 pub struct Settings {
@@ -196,6 +305,8 @@ pub struct Settings {
     pub performance: PerformanceSettings,
     #[serde(default)]
     pub atmosphere: AtmosphereSettings,
+    #[serde(default)]
+    pub gameplay: GameplaySettings,
 }
 
 impl Default for Settings {
@@ -206,6 +317,7 @@ impl Default for Settings {
             controls: ControlsSettings::default(),
             performance: PerformanceSettings::default(),
             atmosphere: AtmosphereSettings::default(),
+            gameplay: GameplaySettings::default(),
         }
     }
 }
@@ -259,6 +371,9 @@ impl Settings {
             m.insert("preset", "Quick performance preset (very_low..very_high) adjusting multiple subsystems.");
             m.insert("background_meshing", "Allow chunk meshing to run on background worker threads.");
             m.insert("max_chunk_meshes_per_frame", "Limit how many chunk meshes the main thread may build per frame.");
+            m.insert("adaptive_budget", "Scale the streaming budget up or down to hold the target frame time.");
+            m.insert("target_frame_time_ms", "Frame time (ms) the adaptive budget aims for; 16.7 ms is 60 FPS.");
+            m.insert("overrides", "Per-limit overrides of the preset's streaming budget (generation_tasks, mesh_builds_per_frame, lod_builds_per_frame, pending_lod_tasks, prewarm_levels, lod_distances).");
             m.insert("section", "Label used by the UI to group performance settings.");
             m
         });
@@ -271,6 +386,14 @@ impl Settings {
             m
         });
 
+        out.insert("gameplay", {
+            let mut m = HashMap::new();
+            m.insert("fire_spread", "Let fire spread to and burn away flammable blocks.");
+            m.insert("simulation_rate", "Gameplay simulation steps per second (physics, block ticks, entities), independent of the frame rate.");
+            m.insert("section", "Label used by the UI to group gameplay settings.");
+            m
+        });
+
         out
     }
 
@@ -432,6 +555,98 @@ impl Settings {
             _ => return None,
         })
     }
+
+    /// The identifier `keycode_from_str` reads back as `key`, used when
+    /// rebinding keys from the settings menu.
+    ///
+    /// # Returns
+    /// The key name, or `None` for keys that cannot be bound.
+    #[must_use]
+    pub fn key_name(key: KeyCode) -> Option<String> {
+        let letters = [
+            KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF, KeyCode::KeyG,
+            KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL, KeyCode::KeyM, KeyCode::KeyN,
+            KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR, KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU,
+            KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX, KeyCode::KeyY, KeyCode::KeyZ,
+        ];
+        let digits = [
+            KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+            KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+        ];
+        let functions = [
+            KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6, KeyCode::F7, KeyCode::F8,
+            KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12, KeyCode::F13, KeyCode::F14, KeyCode::F15, KeyCode::F16,
+            KeyCode::F17, KeyCode::F18, KeyCode::F19, KeyCode::F20, KeyCode::F21, KeyCode::F22, KeyCode::F23, KeyCode::F24,
+        ];
+        let numpad = [
+            KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+            KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+        ];
+        if let Some(i) = letters.iter().position(|k| *k == key) {
+            return Some(char::from(b'A' + u8::try_from(i).ok()?).to_string());
+        }
+        if let Some(i) = digits.iter().position(|k| *k == key) {
+            return Some(i.to_string());
+        }
+        if let Some(i) = functions.iter().position(|k| *k == key) {
+            return Some(format!("F{}", i + 1));
+        }
+        if let Some(i) = numpad.iter().position(|k| *k == key) {
+            return Some(format!("Numpad{i}"));
+        }
+
+        Some(match key {
+            KeyCode::ArrowLeft => "Left",
+            KeyCode::ArrowRight => "Right",
+            KeyCode::ArrowUp => "Up",
+            KeyCode::ArrowDown => "Down",
+            KeyCode::Home => "Home",
+            KeyCode::End => "End",
+            KeyCode::PageUp => "PageUp",
+            KeyCode::PageDown => "PageDown",
+            KeyCode::Insert => "Insert",
+            KeyCode::Delete => "Delete",
+            KeyCode::Escape => "Esc",
+            KeyCode::Space => "Space",
+            KeyCode::Tab => "Tab",
+            KeyCode::Enter => "Enter",
+            KeyCode::Backspace => "Backspace",
+            KeyCode::ShiftLeft => "LShift",
+            KeyCode::ShiftRight => "RShift",
+            KeyCode::ControlLeft => "LCtrl",
+            KeyCode::ControlRight => "RCtrl",
+            KeyCode::AltLeft => "LAlt",
+            KeyCode::AltRight => "RAlt",
+            KeyCode::SuperLeft => "LSuper",
+            KeyCode::SuperRight => "RSuper",
+            KeyCode::NumpadAdd => "NumpadAdd",
+            KeyCode::NumpadSubtract => "NumpadSubtract",
+            KeyCode::NumpadMultiply => "NumpadMultiply",
+            KeyCode::NumpadDivide => "NumpadDivide",
+            KeyCode::NumpadDecimal => "NumpadDecimal",
+            KeyCode::NumpadEnter => "NumpadEnter",
+            KeyCode::Minus => "-",
+            KeyCode::Equal => "=",
+            KeyCode::BracketLeft => "[",
+            KeyCode::BracketRight => "]",
+            KeyCode::Backslash => "\\",
+            KeyCode::Semicolon => ";",
+            KeyCode::Quote => "'",
+            KeyCode::Backquote => "`",
+            KeyCode::Comma => ",",
+            KeyCode::Period => ".",
+            KeyCode::Slash => "/",
+            KeyCode::CapsLock => "CapsLock",
+            KeyCode::ScrollLock => "ScrollLock",
+            KeyCode::Pause => "Pause",
+            KeyCode::PrintScreen => "PrintScreen",
+            KeyCode::NumLock => "NumLock",
+            _ => return None,
+        }
+        .to_string())
+    }
 }
 
-pub mod loader;
\ No newline at end of file
+pub mod fields;
+pub mod loader;
+pub mod writer;
\ No newline at end of file
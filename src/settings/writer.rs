@@ -0,0 +1,344 @@
+//! Writing settings back to their RON file.
+//!
+//! The settings file is edited by hand, so it is not simply re-serialized:
+//! `save_settings` serializes the old and the new `Settings`, finds the
+//! values that differ and patches only those in the file's text. Comments,
+//! blank lines, key order and formatting elsewhere stay as they were.
+//! Values missing from the file are appended to their section; map entries
+//! (keybinds) are never removed.
+
+use crate::settings::Settings;
+use std::ops::Range;
+
+/// The settings file the menu writes to.
+pub const SETTINGS_FILE: &str = "data/settings/settings.ron";
+
+/// Kind of bracketed container a value lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContainerKind {
+    /// `( key: value, ... )` with bare identifier keys
+    Struct,
+    /// `{ "key": value, ... }` with quoted keys
+    Map,
+}
+
+/// A container (struct or map) found while scanning a document.
+#[derive(Debug, Clone)]
+struct Container {
+    path: Vec<String>,
+    kind: ContainerKind,
+    /// Byte offset of the closing bracket
+    close: usize,
+    /// End of the last value inside, and whether a comma follows it
+    last_value: Option<(usize, bool)>,
+}
+
+/// The values and containers of a RON document by key path.
+#[derive(Debug, Default)]
+struct Document {
+    leaves: Vec<(Vec<String>, Range<usize>)>,
+    containers: Vec<Container>,
+}
+
+impl Document {
+    fn leaf(&self, path: &[String]) -> Option<Range<usize>> {
+        self.leaves.iter().find(|(p, _)| p == path).map(|(_, r)| r.clone())
+    }
+
+    fn container(&self, path: &[String]) -> Option<&Container> {
+        self.containers.iter().find(|c| c.path == path)
+    }
+}
+
+/// Minimal scanner that knows just enough RON to find key paths: comments,
+/// strings, structs, maps, and everything else as opaque balanced values.
+struct Scanner<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, bytes: text.as_bytes(), pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn error(&self, what: &str) -> String {
+        let line = self.text[..self.pos.min(self.text.len())].lines().count().max(1);
+        format!("line {line}: {what}")
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_trivia(&mut self) {
+        loop {
+            match (self.peek(), self.bytes.get(self.pos + 1)) {
+                (Some(c), _) if c.is_ascii_whitespace() => self.pos += 1,
+                (Some(b'/'), Some(b'/')) => {
+                    while self.peek().is_some_and(|c| c != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    self.pos += 2;
+                    while self.pos < self.bytes.len() && !self.text[self.pos..].starts_with("*/") {
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.bytes.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Skip a string literal starting at the current `"`.
+    fn skip_string(&mut self) -> Result<(), String> {
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                b'\\' => self.pos += 1,
+                b'"' => return Ok(()),
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn ident(&mut self) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_') {
+            self.pos += 1;
+        }
+        &self.text[start..self.pos]
+    }
+
+    /// Skip a bracketed group starting at the current opening bracket.
+    fn skip_group(&mut self) -> Result<(), String> {
+        let mut depth = 0usize;
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => return Err(self.error("unbalanced brackets")),
+                Some(b'"') => self.skip_string()?,
+                Some(b'(' | b'[' | b'{') => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                Some(b')' | b']' | b'}') => {
+                    depth -= 1;
+                    self.pos += 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Whether a `(` at the current position opens a struct, i.e. its first
+    /// item is `identifier:`.
+    fn opens_struct(&self) -> bool {
+        let mut probe = Scanner { text: self.text, bytes: self.bytes, pos: self.pos + 1 };
+        probe.skip_trivia();
+        if probe.ident().is_empty() {
+            return false;
+        }
+        probe.skip_trivia();
+        probe.peek() == Some(b':')
+    }
+
+    /// Scan a value, recording it (and its items, for containers) in `doc`.
+    fn value(&mut self, path: &mut Vec<String>, doc: &mut Document) -> Result<(), String> {
+        self.skip_trivia();
+        let start = self.pos;
+        match self.peek() {
+            Some(b'(') if self.opens_struct() => self.items(path, ContainerKind::Struct, b')', doc)?,
+            Some(b'{') => self.items(path, ContainerKind::Map, b'}', doc)?,
+            Some(b'(' | b'[') => self.skip_group()?,
+            Some(b'"') => self.skip_string()?,
+            Some(_) => {
+                // Numbers, booleans, identifiers and enum variants like `Fixed(1.0)`
+                while self.peek().is_some_and(|c| !matches!(c, b',' | b')' | b']' | b'}' | b'(' | b'/') && !c.is_ascii_whitespace()) {
+                    self.pos += 1;
+                }
+                if self.peek() == Some(b'(') {
+                    self.skip_group()?;
+                }
+                if self.pos == start {
+                    return Err(self.error("expected a value"));
+                }
+            }
+            None => return Err(self.error("unexpected end of file")),
+        }
+        doc.leaves.push((path.clone(), start..self.pos));
+        Ok(())
+    }
+
+    /// Scan the items of a struct or map up to and including `close`.
+    fn items(&mut self, path: &mut Vec<String>, kind: ContainerKind, close: u8, doc: &mut Document) -> Result<(), String> {
+        self.pos += 1;
+        let mut last_value = None;
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                Some(c) if c == close => break,
+                None => return Err(self.error("unclosed container")),
+                _ => {}
+            }
+            let key = if self.peek() == Some(b'"') {
+                let start = self.pos;
+                self.skip_string()?;
+                self.text[start + 1..self.pos - 1].to_string()
+            } else {
+                self.ident().to_string()
+            };
+            if key.is_empty() {
+                return Err(self.error("expected a key"));
+            }
+            self.skip_trivia();
+            if self.peek() != Some(b':') {
+                return Err(self.error(&format!("expected ':' after '{key}'")));
+            }
+            self.pos += 1;
+            path.push(key);
+            self.value(path, doc)?;
+            path.pop();
+            let end = self.pos;
+            self.skip_trivia();
+            let comma = self.peek() == Some(b',');
+            if comma {
+                self.pos += 1;
+            }
+            last_value = Some((end, comma));
+        }
+        doc.containers.push(Container { path: path.clone(), kind, close: self.pos, last_value });
+        self.pos += 1;
+        Ok(())
+    }
+}
+
+/// Scan a RON document into its key paths.
+fn scan(text: &str) -> Result<Document, String> {
+    let mut doc = Document::default();
+    let mut scanner = Scanner::new(text);
+    scanner.value(&mut Vec::new(), &mut doc)?;
+    Ok(doc)
+}
+
+/// Indentation of the line containing byte `pos`.
+fn indent_at(text: &str, pos: usize) -> &str {
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Set the value at `path` to the RON literal `value`, keeping the rest of
+/// `text` untouched. Missing keys are added at the end of the innermost
+/// existing container.
+fn set_value(text: &str, path: &[String], value: &str, missing: &dyn Fn(&[String]) -> String) -> Result<String, String> {
+    let doc = scan(text)?;
+    let mut out = text.to_string();
+    if let Some(range) = doc.leaf(path) {
+        out.replace_range(range, value);
+        return Ok(out);
+    }
+
+    // Innermost existing container; the rest of the path is inserted whole
+    let depth = (0..path.len()).rev().find(|&d| doc.container(&path[..d]).is_some()).ok_or("no container to insert into")?;
+    let container = doc.container(&path[..depth]).ok_or("no container to insert into")?;
+    let (key, value) = if depth + 1 == path.len() { (&path[depth], value.to_string()) } else { (&path[depth], missing(&path[..=depth])) };
+    let key = match container.kind {
+        ContainerKind::Struct => key.clone(),
+        ContainerKind::Map => format!("\"{key}\""),
+    };
+
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let close_indent = indent_at(text, container.close);
+    let indent = match container.last_value {
+        Some((end, _)) => indent_at(text, end).to_string(),
+        None => format!("{close_indent}    "),
+    };
+    // Insert on its own line before the closing bracket's line
+    let line_start = text[..container.close].rfind('\n').map_or(0, |i| i + 1);
+    let insert_at = if text[line_start..container.close].trim().is_empty() { line_start } else { container.close };
+    let prefix = if insert_at == container.close { newline } else { "" };
+    let suffix = if insert_at == container.close { close_indent } else { "" };
+    out.insert_str(insert_at, &format!("{prefix}{indent}{key}: {value},{newline}{suffix}"));
+    if let Some((end, false)) = container.last_value {
+        out.insert(end, ',');
+    }
+    Ok(out)
+}
+
+/// Patch `text` so it holds the values of `new`, changing only the values
+/// where `old` and `new` (both compact RON serializations of the same type)
+/// differ.
+///
+/// # Errors
+/// Returns `Err` if any of the documents cannot be scanned.
+pub fn patch_ron(text: &str, old: &str, new: &str) -> Result<String, String> {
+    let (old_doc, new_doc) = (scan(old)?, scan(new)?);
+    let mut out = text.to_string();
+    for (path, range) in &new_doc.leaves {
+        let is_container = new_doc.container(path).is_some();
+        let unchanged = old_doc.leaf(path).is_some_and(|r| old[r] == new[range.clone()]);
+        if is_container || unchanged {
+            continue;
+        }
+        let missing = |p: &[String]| new_doc.leaf(p).map_or_else(String::new, |r| new[r].to_string());
+        out = set_value(&out, path, &new[range.clone()], &missing)?;
+    }
+    Ok(out)
+}
+
+/// Write `settings` to the RON file at `path`, keeping its comments and
+/// layout (see the module docs). A missing file is created.
+///
+/// # Errors
+/// Returns `Err` if the file cannot be read, parsed or written.
+pub fn save_settings(path: &str, settings: &Settings) -> Result<(), String> {
+    let new = ron::to_string(settings).map_err(|e| e.to_string())?;
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => {
+            let old: Settings = ron::from_str(&text).map_err(|e| format!("cannot parse {path}: {e}"))?;
+            let old = ron::to_string(&old).map_err(|e| e.to_string())?;
+            patch_ron(&text, &old, &new)?
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            ron::ser::to_string_pretty(settings, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?
+        }
+        Err(e) => return Err(format!("cannot read {path}: {e}")),
+    };
+    std::fs::write(path, text).map_err(|e| format!("cannot write {path}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patching_keeps_comments_and_adds_missing_values() {
+        let text = "(\n    // Graphics\n    graphics: (\n        vsync: false, // keep me\n        render_distance: 8,\n    ),\n    controls: (\n        invert_x: false,\n        keybinds: {\n            \"forward\": \"W\"\n        },\n    ),\n)\n";
+        let mut settings: Settings = ron::from_str(text).expect("valid settings");
+        let old = ron::to_string(&settings).expect("serializes");
+        settings.graphics.render_distance = 12;
+        settings.controls.keybinds.insert("forward".to_string(), "Up".to_string());
+        settings.controls.keybinds.insert("jump".to_string(), "Space".to_string());
+        let new = ron::to_string(&settings).expect("serializes");
+
+        let patched = patch_ron(text, &old, &new).expect("patches");
+        assert!(patched.contains("vsync: false, // keep me"));
+        assert!(patched.contains("// Graphics"));
+        assert!(patched.contains("render_distance: 12,"));
+        assert!(patched.contains("\"forward\": \"Up\",\n            \"jump\": \"Space\",\n        },"));
+        let reparsed: Settings = ron::from_str(&patched).expect("still valid");
+        assert_eq!(reparsed.graphics.render_distance, 12);
+        assert_eq!(reparsed.controls.keybinds["jump"], "Space");
+        assert_eq!(patch_ron(text, &old, &old).expect("patches"), text);
+    }
+}
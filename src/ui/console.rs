@@ -0,0 +1,914 @@
+//! Developer console.
+//!
+//! A drop-down console toggled with the `console` keybind (default `` ` ``).
+//! While it is open it takes all keyboard input: Enter runs the typed
+//! command, Escape closes it, Up/Down walk through the command history and
+//! Tab completes command names, block names and other arguments.
+//!
+//! Commands are plain functions registered by name in `ConsoleCommands`
+//! (like block tick handlers). Each one gets a `ConsoleCtx` with the
+//! resources commands usually need and returns the text to print, or an
+//! error message. `register_builtin_commands` adds:
+//!
+//! * `help`
+//! * `tp x y z` (`~` for the current coordinate, `~5` relative to it)
+//! * `time` / `time set day|noon|night|midnight|<ticks>` (sending the day
+//!   phases it skips over), `time rate x`, `time freeze` and `time resume`
+//!   (see `world::time`)
+//! * `weather [clear|rain|snow|thunder]` (see `world::weather`)
+//! * `explode [power] [x y z]` (default: the targeted block; see
+//!   `world::explosion`)
+//! * `seed`
+//! * `fill x1 y1 z1 x2 y2 z2 block`
+//! * `biome`
+//! * `regen chunk [cx cz]`
+//! * `reload blocks|biomes|settings`
+//! * `gamemode [survival|creative|spectator]`
+//! * region tools (see `world::edit`): `pos1`/`pos2 [x y z]`, `set block`,
+//!   `replace from to`, `hollow block`, `copy`, `paste [degrees]`, `undo`,
+//!   `redo` and `export name`
+//! * `profile [summary|export json|trace|clear|on|off|note text|compare a b]`
+//!   (see `chunk::profiler`)
+
+use crate::biome::loader::BiomeWatcher;
+use crate::biome::BiomeRegistry;
+use crate::block::loader::BlockWatcher;
+use crate::block::{affected_chunks, blocks, raycast_block, BlockId, BlockRegistry, DirtyChunks};
+use crate::chunk::profiler::{compare_profiles, load_profile, ChunkProfiler, ProfileFormat, PROFILE_DIR};
+use crate::chunk::{Chunk, CHUNK_SIZE, WORLD_SEED};
+use crate::player::replay::{Replay, ReplayState, REPLAY_DIR};
+use crate::player::{GameMode, Player};
+use crate::settings::loader::SettingsWatcher;
+use crate::input::InputMap;
+use crate::world::time::{DayPhase, DayPhaseEvent, WorldTime, TICKS_PER_DAY};
+use crate::world::weather::{column_of, region_of, Weather, WorldWeather, MAX_SPELL_SECONDS};
+use crate::world::edit::{self, BlockEdit, Clipboard, Region, RegionEditor, SELECT_REACH};
+use crate::world::explosion::ExplosionEvent;
+use crate::world::structure::STRUCTURE_DIR;
+use crate::world::World;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+use std::collections::BTreeMap;
+
+/// Lines of output kept (and shown) by the console.
+pub const MAX_OUTPUT_LINES: usize = 200;
+
+/// Lines of output visible at once.
+pub const VISIBLE_OUTPUT_LINES: usize = 16;
+
+/// Explosion power used by `explode` without a power argument.
+pub const DEFAULT_EXPLODE_POWER: f32 = 4.0;
+
+/// Highest power `explode` accepts; larger explosions stall the frame.
+pub const MAX_EXPLODE_POWER: f32 = 16.0;
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const CHUNK_SIZE_I32: i32 = CHUNK_SIZE as i32;
+
+/// Console visibility, input line, output and history.
+#[derive(Resource, Default)]
+pub struct ConsoleState {
+    /// Whether the console is currently open.
+    pub open: bool,
+    /// The line being typed.
+    pub input: String,
+    /// Printed lines, oldest first.
+    pub output: Vec<String>,
+    /// Previously run commands, oldest first.
+    pub history: Vec<String>,
+    /// Position in `history` while browsing it with Up/Down.
+    history_index: Option<usize>,
+    /// Commands entered this frame, run by `run_console_commands`.
+    pending: Vec<String>,
+}
+
+impl ConsoleState {
+    /// Append a (possibly multi-line) message to the output.
+    pub fn print(&mut self, text: &str) {
+        self.output.extend(text.lines().map(str::to_string));
+        let excess = self.output.len().saturating_sub(MAX_OUTPUT_LINES);
+        self.output.drain(..excess);
+    }
+}
+
+/// Run condition: true while the console is closed.
+#[allow(clippy::needless_pass_by_value)]
+#[must_use]
+pub fn console_closed(state: Res<ConsoleState>) -> bool {
+    !state.open
+}
+
+/// Resources available to console commands.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct ConsoleCtx<'w, 's> {
+    pub world: ResMut<'w, World>,
+    pub blocks: Res<'w, BlockRegistry>,
+    pub biomes: Res<'w, BiomeRegistry>,
+    pub world_time: ResMut<'w, WorldTime>,
+    pub day_phases: EventWriter<'w, DayPhaseEvent>,
+    pub weather: ResMut<'w, WorldWeather>,
+    pub explosions: EventWriter<'w, ExplosionEvent>,
+    pub mode: ResMut<'w, GameMode>,
+    pub dirty: ResMut<'w, DirtyChunks>,
+    pub editor: ResMut<'w, RegionEditor>,
+    pub block_watcher: Option<Res<'w, BlockWatcher>>,
+    pub biome_watcher: Option<Res<'w, BiomeWatcher>>,
+    pub settings_watcher: Option<Res<'w, SettingsWatcher>>,
+    pub profiler: ResMut<'w, ChunkProfiler>,
+    pub replay: ResMut<'w, ReplayState>,
+    pub player: Query<'w, 's, (&'static mut Transform, &'static mut Player)>,
+}
+
+impl ConsoleCtx<'_, '_> {
+    /// The player's eye position.
+    fn player_position(&self) -> Result<Vec3, String> {
+        self.player.get_single().map(|(tf, _)| tf.translation).map_err(|_| "no player".to_string())
+    }
+}
+
+/// A console command: gets the arguments after the command name and returns
+/// the text to print or an error message.
+pub type ConsoleHandler = fn(&mut ConsoleCtx, &[&str]) -> Result<String, String>;
+
+/// How Tab completes a command's arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgCompletion {
+    /// No completion.
+    None,
+    /// Complete block names.
+    Blocks,
+    /// Complete from a fixed list.
+    Words(&'static [&'static str]),
+}
+
+/// A registered console command.
+#[derive(Clone, Copy)]
+pub struct ConsoleCommand {
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub handler: ConsoleHandler,
+    /// Completion per argument position; the last entry repeats.
+    pub completion: &'static [ArgCompletion],
+}
+
+/// Registered console commands by name.
+#[derive(Resource, Default)]
+pub struct ConsoleCommands {
+    pub commands: BTreeMap<String, ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    /// Register (or replace) the command `name`.
+    pub fn register(&mut self, name: &str, command: ConsoleCommand) {
+        self.commands.insert(name.to_string(), command);
+    }
+
+    /// Run a command line and return the text to print.
+    pub fn run(&self, ctx: &mut ConsoleCtx, line: &str) -> Result<String, String> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else { return Ok(String::new()) };
+        let args: Vec<&str> = words.collect();
+        if name == "help" {
+            return Ok(self.commands.values().map(|c| format!("{} - {}", c.usage, c.help)).collect::<Vec<_>>().join("\n"));
+        }
+        let command = self.commands.get(name).ok_or_else(|| format!("unknown command '{name}' (try 'help')"))?;
+        (command.handler)(ctx, &args).map_err(|e| format!("{e}\nusage: {}", command.usage))
+    }
+
+    /// Tab completion for `input`.
+    ///
+    /// # Return
+    /// * `(String, Vec<String>)` - the completed input (unchanged when there is
+    ///   nothing to add) and every candidate for the word being completed
+    #[must_use]
+    pub fn complete(&self, input: &str, blocks: &BlockRegistry) -> (String, Vec<String>) {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let typing_new_word = input.is_empty() || input.ends_with(' ');
+        let (done, partial) = match words.split_last() {
+            Some((last, rest)) if !typing_new_word => (rest, *last),
+            _ => (&words[..], ""),
+        };
+
+        let pool: Vec<String> = match done.first() {
+            None => self.commands.keys().cloned().chain(std::iter::once("help".to_string())).collect(),
+            Some(name) => {
+                let Some(command) = self.commands.get(*name) else { return (input.to_string(), Vec::new()) };
+                let index = done.len() - 1;
+                match command.completion.get(index).or(command.completion.last()) {
+                    Some(ArgCompletion::Blocks) => blocks.blocks.keys().cloned().collect(),
+                    Some(ArgCompletion::Words(words)) => words.iter().map(|w| (*w).to_string()).collect(),
+                    Some(ArgCompletion::None) | None => Vec::new(),
+                }
+            }
+        };
+        let mut candidates: Vec<String> = pool.into_iter().filter(|c| c.starts_with(partial)).collect();
+        candidates.sort();
+        candidates.dedup();
+
+        let Some(first) = candidates.first() else { return (input.to_string(), candidates) };
+        let common = candidates.iter().fold(first.clone(), |prefix, c| {
+            prefix.chars().zip(c.chars()).take_while(|(a, b)| a == b).map(|(a, _)| a).collect()
+        });
+        let mut completed: String = done.iter().map(|w| format!("{w} ")).collect();
+        completed.push_str(&common);
+        if candidates.len() == 1 {
+            completed.push(' ');
+        }
+        (completed, candidates)
+    }
+}
+
+/// Root node of the console.
+#[derive(Component)]
+pub struct ConsolePanel;
+
+/// Text showing the console output.
+#[derive(Component)]
+pub struct ConsoleOutputText;
+
+/// Text showing the input line.
+#[derive(Component)]
+pub struct ConsoleInputText;
+
+/// Spawn the (hidden) console panel.
+///
+/// # Arguments
+/// * `commands` - `Commands` to spawn the panel nodes
+/// * `asset_server` - asset server for loading the console font
+#[allow(clippy::needless_pass_by_value)]
+pub fn spawn_console(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font: Handle<Font> = asset_server.load("fonts/OpenSans.ttf");
+    let style = TextStyle { font, font_size: 16.0, color: Color::srgb(0.9, 0.9, 0.9) };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(40.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::FlexEnd,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    display: Display::None,
+                    ..default()
+                },
+                background_color: Color::srgba(0.05, 0.05, 0.08, 0.85).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            ConsolePanel,
+        ))
+        .with_children(|p| {
+            p.spawn((TextBundle::from_section("", style.clone()), ConsoleOutputText));
+            p.spawn((
+                TextBundle::from_section("> ", TextStyle { color: Color::srgb(1.0, 1.0, 0.6), ..style }),
+                ConsoleInputText,
+            ));
+        });
+}
+
+/// Open or close the console, releasing the cursor while it is open and
+/// grabbing and hiding it again when it closes.
+fn set_console_open(state: &mut ConsoleState, open: bool, windows: &mut Query<&mut Window, With<PrimaryWindow>>) {
+    state.open = open;
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.cursor.grab_mode = if open { CursorGrabMode::None } else { CursorGrabMode::Locked };
+        window.cursor.visible = open;
+    }
+}
+
+/// Open/close the console and feed it keyboard input while open. Runs in
+/// `PreUpdate` after Bevy's input systems; while the console is open the
+/// keyboard state is cleared afterwards so gameplay systems ignore the keys.
+///
+/// # Arguments
+/// * `keys` - keyboard state (toggle key, cleared while open)
+/// * `typed` - keyboard events with the typed characters
+/// * `map` - input map providing the `console` keys
+/// * `state` - console state
+/// * `commands` - registered commands (for tab completion)
+/// * `blocks` - block registry (for tab completion)
+/// * `windows` - primary window, for cursor grab state
+#[allow(clippy::needless_pass_by_value)]
+pub fn console_input(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut typed: EventReader<KeyboardInput>,
+    map: Res<InputMap>,
+    mut state: ResMut<ConsoleState>,
+    commands: Res<ConsoleCommands>,
+    blocks: Res<BlockRegistry>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if map.key_just_pressed("console", &keys) {
+        let open = !state.open;
+        set_console_open(&mut state, open, &mut windows);
+        typed.clear();
+        keys.reset_all();
+        return;
+    }
+    if !state.open {
+        typed.clear();
+        return;
+    }
+
+    for event in typed.read() {
+        if event.state != ButtonState::Pressed || map.binds_key("console", event.key_code) {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Escape => {
+                set_console_open(&mut state, false, &mut windows);
+                break; // The rest is dropped next frame while closed
+            }
+            Key::Character(text) => state.input.push_str(text),
+            Key::Space => state.input.push(' '),
+            Key::Backspace => {
+                state.input.pop();
+            }
+            Key::Enter => {
+                let line = std::mem::take(&mut state.input).trim().to_string();
+                state.history_index = None;
+                if !line.is_empty() {
+                    if state.history.last() != Some(&line) {
+                        state.history.push(line.clone());
+                    }
+                    state.pending.push(line);
+                }
+            }
+            Key::ArrowUp if !state.history.is_empty() => {
+                let index = state.history_index.map_or(state.history.len() - 1, |i| i.saturating_sub(1));
+                state.history_index = Some(index);
+                state.input = state.history[index].clone();
+            }
+            Key::ArrowDown => {
+                if let Some(index) = state.history_index {
+                    let next = index + 1;
+                    state.history_index = (next < state.history.len()).then_some(next);
+                    state.input = state.history.get(next).cloned().unwrap_or_default();
+                }
+            }
+            Key::Tab => {
+                let (completed, candidates) = commands.complete(&state.input, &blocks);
+                if candidates.len() > 1 {
+                    let list = candidates.join("  ");
+                    state.print(&list);
+                }
+                state.input = completed;
+            }
+            _ => {}
+        }
+    }
+    keys.reset_all();
+}
+
+/// Run the commands entered this frame and print their results.
+///
+/// # Arguments
+/// * `state` - console state holding the entered commands
+/// * `commands` - registered commands
+/// * `ctx` - resources available to commands
+#[allow(clippy::needless_pass_by_value)]
+pub fn run_console_commands(mut state: ResMut<ConsoleState>, commands: Res<ConsoleCommands>, mut ctx: ConsoleCtx) {
+    if state.pending.is_empty() {
+        return;
+    }
+    for line in std::mem::take(&mut state.pending) {
+        state.print(&format!("> {line}"));
+        match commands.run(&mut ctx, &line) {
+            Ok(text) => state.print(&text),
+            Err(e) => state.print(&format!("error: {e}")),
+        }
+    }
+}
+
+/// Show/hide the console and refresh its text.
+///
+/// # Arguments
+/// * `state` - console state
+/// * `panel` - style of the console root node
+/// * `output` - output text
+/// * `input` - input line text
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+pub fn update_console_ui(
+    state: Res<ConsoleState>,
+    mut panel: Query<&mut Style, With<ConsolePanel>>,
+    mut output: Query<&mut Text, (With<ConsoleOutputText>, Without<ConsoleInputText>)>,
+    mut input: Query<&mut Text, (With<ConsoleInputText>, Without<ConsoleOutputText>)>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    if let Ok(mut style) = panel.get_single_mut() {
+        style.display = if state.open { Display::Flex } else { Display::None };
+    }
+    if let Ok(mut text) = output.get_single_mut() {
+        let start = state.output.len().saturating_sub(VISIBLE_OUTPUT_LINES);
+        text.sections[0].value = state.output[start..].join("\n");
+    }
+    if let Ok(mut text) = input.get_single_mut() {
+        text.sections[0].value = format!("> {}_", state.input);
+    }
+}
+
+/// Parse a coordinate; `~` is `current`, `~n` is `current + n`.
+fn parse_coord(arg: &str, current: f32) -> Result<f32, String> {
+    let (base, rest) = match arg.strip_prefix('~') {
+        Some(rest) => (current, rest),
+        None => (0.0, arg),
+    };
+    if rest.is_empty() {
+        return Ok(base);
+    }
+    rest.parse::<f32>().map(|v| base + v).map_err(|_| format!("'{arg}' is not a number"))
+}
+
+/// Parse a block coordinate (see `parse_coord`).
+#[allow(clippy::cast_possible_truncation)]
+fn parse_block_coord(arg: &str, current: f32) -> Result<i32, String> {
+    parse_coord(arg, current).map(|v| v.floor() as i32)
+}
+
+fn tp(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let [x, y, z] = args else { return Err("expected 3 coordinates".to_string()) };
+    let current = ctx.player_position()?;
+    let target = Vec3::new(parse_coord(x, current.x)?, parse_coord(y, current.y)?, parse_coord(z, current.z)?);
+    for (mut tf, mut player) in &mut ctx.player {
+        tf.translation = target;
+        player.velocity = Vec3::ZERO;
+        player.fall_distance = 0.0;
+    }
+    Ok(format!("Teleported to ({:.1}, {:.1}, {:.1})", target.x, target.y, target.z))
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn time(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let clock = &mut ctx.world_time;
+    match args {
+        [] => {
+            let (hours, minutes) = clock.clock();
+            let frozen = if clock.frozen { ", frozen" } else { "" };
+            Ok(format!(
+                "Day {}, {hours:02}:{minutes:02} (tick {}/{TICKS_PER_DAY}), scale x{}{frozen}",
+                clock.day,
+                clock.tick_of_day(),
+                clock.scale
+            ))
+        }
+        ["set", value] => {
+            // The cycle starts at sunrise: a quarter day later is noon
+            let target = match *value {
+                "day" => DayPhase::Dawn.time_of_day(),
+                "noon" => DayPhase::Noon.time_of_day(),
+                "night" => DayPhase::Dusk.time_of_day(),
+                "midnight" => DayPhase::Midnight.time_of_day(),
+                v => {
+                    let ticks = v.parse::<u64>().map_err(|_| format!("'{v}' is not a time"))?;
+                    (ticks % TICKS_PER_DAY) as f32 / TICKS_PER_DAY as f32
+                }
+            };
+            let passed = clock.set_time_of_day(target);
+            clock.send_phases(passed, &mut ctx.day_phases);
+            Ok(format!("Time set to tick {} of day {}", clock.tick_of_day(), clock.day))
+        }
+        ["rate", value] => {
+            let scale = value.parse::<f32>().ok().filter(|s| s.is_finite() && *s >= 0.0);
+            clock.scale = scale.ok_or_else(|| format!("'{value}' is not a time scale"))?;
+            Ok(format!("Time scale set to x{}", clock.scale))
+        }
+        ["freeze"] => {
+            clock.frozen = true;
+            Ok("Time frozen".to_string())
+        }
+        ["resume"] => {
+            clock.frozen = false;
+            Ok("Time resumed".to_string())
+        }
+        _ => Err("expected 'time', 'time set <value>', 'time rate <x>', 'time freeze' or 'time resume'".to_string()),
+    }
+}
+
+fn weather(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let position = ctx.player_position()?;
+    let region = region_of(position);
+    let state = ctx.weather.region_mut(region, column_of(position), &ctx.biomes, ctx.world_time.day);
+    match args {
+        [] => Ok(format!(
+            "Weather in region ({}, {}): {}, changing in {:.0} s",
+            region.x,
+            region.y,
+            state.weather.name(),
+            state.remaining.max(0.0)
+        )),
+        [name] => {
+            let weather = Weather::from_name(name).ok_or_else(|| format!("unknown weather '{name}'"))?;
+            state.change_to(weather, MAX_SPELL_SECONDS);
+            Ok(format!("Weather set to {name}"))
+        }
+        _ => Err("expected 'weather' or 'weather clear|rain|snow|thunder'".to_string()),
+    }
+}
+
+fn explode(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let power = match args.first() {
+        Some(arg) => arg.parse::<f32>().map_err(|_| format!("invalid power '{arg}'"))?,
+        None => DEFAULT_EXPLODE_POWER,
+    };
+    if !(power > 0.0 && power <= MAX_EXPLODE_POWER) {
+        return Err(format!("power must be above 0 and at most {MAX_EXPLODE_POWER}"));
+    }
+    let center = match args {
+        [] | [_] => targeted_block(ctx)?.0.as_vec3() + Vec3::splat(0.5),
+        [_, x, y, z] => {
+            let current = ctx.player_position()?;
+            Vec3::new(parse_coord(x, current.x)?, parse_coord(y, current.y)?, parse_coord(z, current.z)?)
+        }
+        _ => return Err("expected a power and optionally 3 coordinates".to_string()),
+    };
+    ctx.explosions.send(ExplosionEvent { center, power });
+    Ok(format!("Explosion of power {power} at ({:.1}, {:.1}, {:.1})", center.x, center.y, center.z))
+}
+
+fn seed(_ctx: &mut ConsoleCtx, _args: &[&str]) -> Result<String, String> {
+    Ok(format!("Seed: {WORLD_SEED}"))
+}
+
+fn fill(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let [x1, y1, z1, x2, y2, z2, block] = args else { return Err("expected 2 corners and a block".to_string()) };
+    let current = ctx.player_position()?;
+    let a = IVec3::new(
+        parse_block_coord(x1, current.x)?,
+        parse_block_coord(y1, current.y)?,
+        parse_block_coord(z1, current.z)?,
+    );
+    let b = IVec3::new(
+        parse_block_coord(x2, current.x)?,
+        parse_block_coord(y2, current.y)?,
+        parse_block_coord(z2, current.z)?,
+    );
+    let id = block_id(ctx, block)?;
+    let region = Region::new(a, b);
+    region.check_volume()?;
+    let edits = edit::fill(region, id);
+    Ok(format!("Filled {} blocks with {block}", apply(ctx, &edits)))
+}
+
+/// Resolve a block name (`air` included) to its id.
+fn block_id(ctx: &ConsoleCtx, name: &str) -> Result<BlockId, String> {
+    if name == "air" {
+        return Ok(blocks::AIR);
+    }
+    ctx.blocks.get(name).map(|b| b.id).ok_or_else(|| format!("unknown block '{name}'"))
+}
+
+/// Apply `edits` as one undoable step and mark the touched chunks dirty.
+fn apply(ctx: &mut ConsoleCtx, edits: &[BlockEdit]) -> usize {
+    let (changed, chunks) = ctx.editor.apply(&mut ctx.world, &ctx.blocks, edits);
+    ctx.dirty.0.extend(chunks);
+    changed
+}
+
+/// The selected region, checked against `MAX_EDIT_VOLUME`.
+fn selection(ctx: &ConsoleCtx) -> Result<Region, String> {
+    let region = ctx.editor.selection().ok_or("select two corners first (pos1/pos2)")?;
+    region.check_volume()?;
+    Ok(region)
+}
+
+/// The block the player is looking at and the air block in front of it.
+fn targeted_block(ctx: &ConsoleCtx) -> Result<(IVec3, IVec3), String> {
+    let (tf, _) = ctx.player.get_single().map_err(|_| "no player".to_string())?;
+    raycast_block(&ctx.world, tf.translation, *tf.forward(), SELECT_REACH).ok_or_else(|| "no block in sight".to_string())
+}
+
+fn set_corner(ctx: &mut ConsoleCtx, corner: usize, args: &[&str]) -> Result<String, String> {
+    let pos = match args {
+        [] => targeted_block(ctx)?.0,
+        [x, y, z] => {
+            let current = ctx.player_position()?;
+            IVec3::new(
+                parse_block_coord(x, current.x)?,
+                parse_block_coord(y, current.y)?,
+                parse_block_coord(z, current.z)?,
+            )
+        }
+        _ => return Err("expected no arguments or 3 coordinates".to_string()),
+    };
+    ctx.editor.corners[corner] = Some(pos);
+    let size = ctx.editor.selection().map(|r| format!(" ({} blocks selected)", r.volume())).unwrap_or_default();
+    Ok(format!("Corner {} set to ({}, {}, {}){size}", corner + 1, pos.x, pos.y, pos.z))
+}
+
+fn pos1(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    set_corner(ctx, 0, args)
+}
+
+fn pos2(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    set_corner(ctx, 1, args)
+}
+
+fn set(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let [block] = args else { return Err("expected a block".to_string()) };
+    let edits = edit::fill(selection(ctx)?, block_id(ctx, block)?);
+    Ok(format!("Set {} blocks to {block}", apply(ctx, &edits)))
+}
+
+fn replace(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let [from, to] = args else { return Err("expected two blocks".to_string()) };
+    let edits = edit::replace(&ctx.world, selection(ctx)?, block_id(ctx, from)?, block_id(ctx, to)?);
+    Ok(format!("Replaced {} blocks", apply(ctx, &edits)))
+}
+
+fn hollow(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let [block] = args else { return Err("expected a block".to_string()) };
+    let edits = edit::hollow(selection(ctx)?, block_id(ctx, block)?);
+    Ok(format!("Changed {} blocks", apply(ctx, &edits)))
+}
+
+fn copy(ctx: &mut ConsoleCtx, _args: &[&str]) -> Result<String, String> {
+    let region = selection(ctx)?;
+    ctx.editor.clipboard = Some(Clipboard::copy(&ctx.world, region));
+    Ok(format!("Copied {} blocks", region.volume()))
+}
+
+fn paste(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let degrees = match args {
+        [] => 0,
+        [d] => d.parse::<i32>().ok().filter(|d| d % 90 == 0).ok_or_else(|| format!("'{d}' is not a multiple of 90"))?,
+        _ => return Err("expected an optional rotation".to_string()),
+    };
+    let clipboard = ctx.editor.clipboard.as_ref().ok_or("the clipboard is empty (copy first)")?.rotated(degrees / 90);
+    let (_, origin) = targeted_block(ctx)?;
+    let edits = clipboard.paste(origin, false);
+    Ok(format!("Pasted {} blocks", apply(ctx, &edits)))
+}
+
+fn undo(ctx: &mut ConsoleCtx, _args: &[&str]) -> Result<String, String> {
+    let (changed, chunks) = ctx.editor.undo(&mut ctx.world, &ctx.blocks).ok_or("nothing to undo")?;
+    ctx.dirty.0.extend(chunks);
+    Ok(format!("Undid {changed} blocks"))
+}
+
+fn redo(ctx: &mut ConsoleCtx, _args: &[&str]) -> Result<String, String> {
+    let (changed, chunks) = ctx.editor.redo(&mut ctx.world, &ctx.blocks).ok_or("nothing to redo")?;
+    ctx.dirty.0.extend(chunks);
+    Ok(format!("Redid {changed} blocks"))
+}
+
+fn export(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let [name] = args else { return Err("expected a structure name".to_string()) };
+    let clipboard = ctx.editor.clipboard.as_ref().ok_or("the clipboard is empty (copy first)")?;
+    let path = clipboard.to_template(name, &ctx.blocks)?.save(STRUCTURE_DIR)?;
+    Ok(format!("Exported the clipboard to {}", path.display()))
+}
+
+fn biome(ctx: &mut ConsoleCtx, _args: &[&str]) -> Result<String, String> {
+    let pos = ctx.player_position()?.floor().as_ivec3();
+    let climate = ctx.biomes.climate.sample(pos.x, pos.z);
+    let weights = ctx.biomes.biome_weights(pos.x, pos.z);
+    let name = weights.first().map_or("unknown", |(b, _)| b.name.as_str());
+    let blend: Vec<String> = weights.iter().map(|(b, w)| format!("{} {:.0}%", b.name, w * 100.0)).collect();
+    Ok(format!(
+        "Biome: {name} ({:.1}°C, humidity {:.2}, continentalness {:.2}; blend: {})",
+        climate.temperature,
+        climate.humidity,
+        climate.continentalness,
+        blend.join(", ")
+    ))
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn regen(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let (cx, cz) = match args {
+        ["chunk"] => {
+            let pos = ctx.player_position()?.floor().as_ivec3();
+            (pos.x.div_euclid(CHUNK_SIZE_I32), pos.z.div_euclid(CHUNK_SIZE_I32))
+        }
+        ["chunk", x, z] => (
+            x.parse::<i32>().map_err(|_| format!("'{x}' is not a chunk coordinate"))?,
+            z.parse::<i32>().map_err(|_| format!("'{z}' is not a chunk coordinate"))?,
+        ),
+        _ => return Err("expected 'regen chunk [cx cz]'".to_string()),
+    };
+    if !ctx.world.chunks.contains_key(&(cx, cz)) {
+        return Err(format!("chunk ({cx}, {cz}) is not loaded"));
+    }
+    let mut chunk = Chunk::new();
+    chunk.generate(cx, cz, &ctx.blocks);
+    ctx.world.chunks.insert((cx, cz), chunk);
+    // Corner blocks reach both neighbours of each axis
+    ctx.dirty.0.extend(affected_chunks(cx, cz, IVec3::new(cx * CHUNK_SIZE_I32, 0, cz * CHUNK_SIZE_I32)));
+    ctx.dirty.0.extend(affected_chunks(
+        cx,
+        cz,
+        IVec3::new((cx + 1) * CHUNK_SIZE_I32 - 1, 0, (cz + 1) * CHUNK_SIZE_I32 - 1),
+    ));
+    Ok(format!("Regenerated chunk ({cx}, {cz})"))
+}
+
+/// Flag a RON watcher as changed so its hot-reload system reloads the files.
+fn trigger_reload(watcher: &crate::ron::RonWatcher) {
+    match watcher.changed.lock() {
+        Ok(mut flag) => *flag = true,
+        Err(poisoned) => *poisoned.into_inner() = true,
+    }
+}
+
+fn reload(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    let watcher = match args {
+        ["blocks"] => ctx.block_watcher.as_ref().map(|w| &w.0),
+        ["biomes"] => ctx.biome_watcher.as_ref().map(|w| &w.0),
+        ["settings"] => ctx.settings_watcher.as_ref().map(|w| &w.0),
+        _ => return Err("expected blocks, biomes or settings".to_string()),
+    };
+    let watcher = watcher.ok_or_else(|| format!("{} cannot be reloaded", args[0]))?;
+    trigger_reload(watcher);
+    Ok(format!("Reloading {}", args[0]))
+}
+
+fn gamemode(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    match args {
+        [] => Ok(format!("Game mode: {}", ctx.mode.name())),
+        [name] => {
+            *ctx.mode = GameMode::from_name(name).ok_or_else(|| format!("unknown game mode '{name}'"))?;
+            Ok(format!("Game mode set to {}", ctx.mode.name()))
+        }
+        _ => Err("expected one game mode".to_string()),
+    }
+}
+
+fn profile(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    match args {
+        [] | ["summary"] => Ok(ctx.profiler.summary_text()),
+        ["export", format] => {
+            let format = match *format {
+                "json" => ProfileFormat::Json,
+                "trace" => ProfileFormat::ChromeTrace,
+                other => return Err(format!("unknown format '{other}' (json or trace)")),
+            };
+            let path = ctx.profiler.export(PROFILE_DIR, format)?;
+            Ok(format!("Profile written to {}", path.display()))
+        }
+        ["clear"] => {
+            ctx.profiler.clear();
+            Ok("Profile cleared".to_string())
+        }
+        ["on" | "off"] => {
+            ctx.profiler.enabled = args[0] == "on";
+            Ok(format!("Chunk profiling {}", args[0]))
+        }
+        ["note", note @ ..] if !note.is_empty() => {
+            ctx.profiler.note = note.join(" ");
+            Ok(format!("Profile note: {}", ctx.profiler.note))
+        }
+        ["compare", base, new] => Ok(compare_profiles(&load_profile(base)?, &load_profile(new)?)),
+        _ => Err("expected summary, export json|trace, clear, on, off, note <text> or compare <a.json> <b.json>".to_string()),
+    }
+}
+
+fn replay(ctx: &mut ConsoleCtx, args: &[&str]) -> Result<String, String> {
+    match args {
+        [] | ["status"] => Ok(ctx.replay.status()),
+        ["record"] => {
+            ctx.replay.record();
+            Ok("Recording a replay (replay stop to save it)".to_string())
+        }
+        ["play", file] => {
+            let path = if std::path::Path::new(file).exists() { (*file).to_string() } else { format!("{REPLAY_DIR}/{file}") };
+            let replay = Replay::load(&path)?;
+            let ticks = replay.ticks.len();
+            ctx.replay.play(replay);
+            Ok(format!("Playing {path} ({ticks} ticks)"))
+        }
+        ["stop"] => match ctx.replay.stop() {
+            Some(recorded) => {
+                let path = recorded.save(REPLAY_DIR)?;
+                Ok(format!("Replay of {} ticks written to {}", recorded.ticks.len(), path.display()))
+            }
+            None => Ok("Replay stopped".to_string()),
+        },
+        _ => Err("expected status, record, play <file> or stop".to_string()),
+    }
+}
+
+/// Register every built-in command.
+pub fn register_builtin_commands(commands: &mut ConsoleCommands) {
+    use ArgCompletion::{Blocks, None as NoArg, Words};
+
+    let mut add = |name: &str, usage, help, handler, completion| {
+        commands.register(name, ConsoleCommand { usage, help, handler, completion });
+    };
+    add("tp", "tp x y z", "Teleport to a position (~ = current coordinate)", tp, &[NoArg]);
+    add(
+        "time",
+        "time [set day|noon|night|midnight|<ticks> | rate x | freeze | resume]",
+        "Show, set, speed up or freeze the time of day",
+        time,
+        &[Words(&["set", "rate", "freeze", "resume"]), Words(&["day", "noon", "night", "midnight"])],
+    );
+    add(
+        "weather",
+        "weather [clear|rain|snow|thunder]",
+        "Show or set the weather of your region",
+        weather,
+        &[Words(&["clear", "rain", "snow", "thunder"]), NoArg],
+    );
+    add(
+        "explode",
+        "explode [power] [x y z]",
+        "Cause an explosion at the targeted block or a position",
+        explode,
+        &[NoArg],
+    );
+    add("seed", "seed", "Show the world seed", seed, &[NoArg]);
+    add(
+        "fill",
+        "fill x1 y1 z1 x2 y2 z2 block",
+        "Fill a box with a block (or air)",
+        fill,
+        &[NoArg, NoArg, NoArg, NoArg, NoArg, NoArg, Blocks, NoArg],
+    );
+    add("biome", "biome", "Show the biome at your position", biome, &[NoArg]);
+    add("regen", "regen chunk [cx cz]", "Regenerate a chunk from the generator", regen, &[Words(&["chunk"]), NoArg]);
+    add(
+        "reload",
+        "reload blocks|biomes|settings",
+        "Reload RON data files",
+        reload,
+        &[Words(&["blocks", "biomes", "settings"]), NoArg],
+    );
+    add(
+        "gamemode",
+        "gamemode [survival|creative|spectator]",
+        "Show or set the game mode",
+        gamemode,
+        &[Words(&["survival", "creative", "spectator"]), NoArg],
+    );
+    add("pos1", "pos1 [x y z]", "Set the first selection corner (default: targeted block)", pos1, &[NoArg]);
+    add("pos2", "pos2 [x y z]", "Set the second selection corner (default: targeted block)", pos2, &[NoArg]);
+    add("set", "set block", "Fill the selection with a block", set, &[Blocks, NoArg]);
+    add("replace", "replace from to", "Replace one block with another in the selection", replace, &[Blocks, Blocks, NoArg]);
+    add("hollow", "hollow block", "Turn the selection into a hollow box of a block", hollow, &[Blocks, NoArg]);
+    add("copy", "copy", "Copy the selection to the clipboard", copy, &[NoArg]);
+    add(
+        "paste",
+        "paste [0|90|180|270]",
+        "Paste the clipboard at the targeted block, rotated clockwise",
+        paste,
+        &[Words(&["0", "90", "180", "270"]), NoArg],
+    );
+    add("undo", "undo", "Undo the last region edit", undo, &[NoArg]);
+    add("redo", "redo", "Redo the last undone region edit", redo, &[NoArg]);
+    add("export", "export name", "Save the clipboard as data/structures/<name>.ron", export, &[NoArg]);
+    add(
+        "profile",
+        "profile [summary|export json|trace|clear|on|off|note text|compare a b]",
+        "Show, export or compare chunk pipeline timings",
+        profile,
+        &[Words(&["summary", "export", "clear", "on", "off", "note", "compare"]), Words(&["json", "trace"]), NoArg],
+    );
+    add(
+        "replay",
+        "replay [status|record|play file|stop]",
+        "Record player input or play a recording back, checking for divergence",
+        replay,
+        &[Words(&["status", "record", "play", "stop"]), NoArg],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+
+    #[test]
+    fn tab_completes_commands_and_block_names() {
+        let mut commands = ConsoleCommands::default();
+        register_builtin_commands(&mut commands);
+        let mut blocks = BlockRegistry::default();
+        blocks.register(Block { id: 1, name: "planks".to_string(), ..Block::default() });
+        blocks.register(Block { id: 2, name: "plains_grass".to_string(), ..Block::default() });
+
+        assert_eq!(commands.complete("ti", &blocks).0, "time ");
+        assert_eq!(commands.complete("expl", &blocks).0, "explode ");
+        assert_eq!(commands.complete("time s", &blocks).0, "time set ");
+        assert_eq!(commands.complete("fill 0 0 0 1 1 1 planks", &blocks).0, "fill 0 0 0 1 1 1 planks ");
+
+        let (completed, candidates) = commands.complete("fill 0 0 0 1 1 1 pl", &blocks);
+        assert_eq!(completed, "fill 0 0 0 1 1 1 pla");
+        assert_eq!(candidates, vec!["plains_grass".to_string(), "planks".to_string()]);
+        assert!(commands.complete("tp 1 ", &blocks).1.is_empty());
+    }
+
+    #[test]
+    fn coordinates_can_be_relative() {
+        assert_eq!(parse_coord("~", 4.0), Ok(4.0));
+        assert_eq!(parse_coord("~-1.5", 4.0), Ok(2.5));
+        assert_eq!(parse_coord("7", 4.0), Ok(7.0));
+        assert!(parse_coord("x", 4.0).is_err());
+    }
+}
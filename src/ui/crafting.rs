@@ -0,0 +1,369 @@
+//! Crafting panel.
+//!
+//! A panel toggled with the `crafting` keybind (default `C`) holding the 3x3
+//! `CraftingGrid`, its output and a recipe book. Clicking an empty grid cell
+//! puts in one item from the selected hotbar slot, clicking a filled cell
+//! takes it back, and clicking the output crafts the matching recipe. The
+//! recipe book lists every recipe in the `RecipeBook` with its ingredients;
+//! recipes the player can afford are highlighted and clicking one lays it
+//! out in the grid from the inventory. Items left in the grid return to the
+//! inventory when the panel closes. The cursor is released while the panel
+//! is open so the buttons can be clicked.
+
+use crate::crafting::{CraftingGrid, RecipeBook, GRID_CELLS, GRID_SIZE};
+use crate::item::{Inventory, ItemRegistry};
+use crate::input::ActionState;
+use bevy::prelude::*;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+
+const CRAFTABLE_COLOR: Color = Color::srgba(0.20, 0.45, 0.20, 0.9);
+const UNCRAFTABLE_COLOR: Color = Color::srgba(0.25, 0.25, 0.25, 0.9);
+const HOVERED_COLOR: Color = Color::srgba(0.30, 0.60, 0.30, 0.9);
+const CELL_SIZE: f32 = 72.0;
+
+/// Crafting panel visibility and the font used for its rows.
+#[derive(Resource, Default)]
+pub struct CraftingPanelState {
+    /// Whether the panel is currently open.
+    pub open: bool,
+    font: Handle<Font>,
+}
+
+/// Root node of the crafting panel.
+#[derive(Component)]
+pub struct CraftingPanel;
+
+/// Container the recipe rows are spawned into.
+#[derive(Component)]
+pub struct CraftingRecipeList;
+
+/// Text listing the inventory contents at the top of the panel.
+#[derive(Component)]
+pub struct CraftingInventoryText;
+
+/// Container the grid cells and output button are spawned into.
+#[derive(Component)]
+pub struct CraftingGridNode;
+
+/// A clickable crafting grid cell; holds the cell index.
+#[derive(Component)]
+pub struct CraftingGridCell(pub usize);
+
+/// The clickable output next to the crafting grid.
+#[derive(Component)]
+pub struct CraftingOutputButton;
+
+/// A clickable recipe row; holds the recipe name.
+#[derive(Component)]
+pub struct CraftButton(pub String);
+
+/// Run condition: true while the crafting panel is closed. Used to keep
+/// cursor grabbing from stealing clicks meant for the panel.
+#[allow(clippy::needless_pass_by_value)]
+#[must_use]
+pub fn crafting_panel_closed(state: Res<CraftingPanelState>) -> bool {
+    !state.open
+}
+
+/// Spawn the (hidden) crafting panel.
+///
+/// # Arguments
+/// * `commands` - `Commands` to spawn the panel nodes and insert `CraftingPanelState`
+/// * `asset_server` - asset server for loading the panel font
+/// * `asset_paths` - registry for mapping asset handles to paths for debugging
+#[allow(clippy::needless_pass_by_value)]
+pub fn spawn_crafting_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut asset_paths: ResMut<crate::debug::AssetPathRegistry>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/OpenSans.ttf");
+    asset_paths.0.insert(format!("{:?}", font.clone()), "fonts/OpenSans.ttf".to_string());
+
+    let title_style = TextStyle { font: font.clone(), font_size: 24.0, color: Color::WHITE };
+    let body_style = TextStyle { font: font.clone(), font_size: 16.0, color: Color::srgb(0.8, 0.8, 0.8) };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(30.0),
+                    top: Val::Percent(15.0),
+                    width: Val::Percent(40.0),
+                    max_height: Val::Percent(70.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(12.0)),
+                    row_gap: Val::Px(6.0),
+                    overflow: Overflow::clip_y(),
+                    ..default()
+                },
+                background_color: Color::srgba(0.05, 0.05, 0.05, 0.85).into(),
+                ..default()
+            },
+            CraftingPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn(TextBundle::from_section("Crafting", title_style));
+            panel.spawn((TextBundle::from_section("", body_style), CraftingInventoryText));
+            panel.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(12.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                CraftingGridNode,
+            ));
+            panel.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                CraftingRecipeList,
+            ));
+        });
+
+    commands.insert_resource(CraftingPanelState { open: false, font });
+}
+
+/// Open/close the crafting panel with the `crafting` action, releasing the
+/// cursor while it is open. Closing it moves the grid items back into the
+/// inventory.
+///
+/// # Arguments
+/// * `actions` - action state
+/// * `state` - panel visibility state
+/// * `grid` - the crafting grid emptied on close
+/// * `inventory` - player inventory the grid items return to
+/// * `items` - item registry
+/// * `panel` - style of the panel root node
+/// * `windows` - primary window, for cursor grab state
+#[allow(clippy::needless_pass_by_value)]
+pub fn toggle_crafting_panel(
+    actions: Res<ActionState>,
+    mut state: ResMut<CraftingPanelState>,
+    mut grid: ResMut<CraftingGrid>,
+    mut inventory: ResMut<Inventory>,
+    items: Res<ItemRegistry>,
+    mut panel: Query<&mut Style, With<CraftingPanel>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !actions.just_pressed("crafting") {
+        return;
+    }
+
+    state.open = !state.open;
+    if !state.open && !grid.is_empty() {
+        grid.return_all(&mut inventory, &items);
+    }
+    if let Ok(mut style) = panel.get_single_mut() {
+        style.display = if state.open { Display::Flex } else { Display::None };
+    }
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.cursor.grab_mode = if state.open { CursorGrabMode::None } else { CursorGrabMode::Locked };
+        window.cursor.visible = state.open;
+    }
+}
+
+/// Rebuild the grid and the recipe rows while the panel is open and the
+/// recipes, items, inventory or grid changed.
+///
+/// # Arguments
+/// * `commands` - `Commands` used to respawn the grid and rows
+/// * `state` - panel visibility state (and font)
+/// * `book` - loaded recipes
+/// * `items` - item registry for labels and tag matching
+/// * `inventory` - player inventory checked for ingredients
+/// * `grid` - the crafting grid shown in the panel
+/// * `grid_node` - the grid container
+/// * `list` - the recipe row container
+/// * `inventory_text` - the inventory summary text
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
+pub fn refresh_crafting_panel(
+    mut commands: Commands,
+    state: Res<CraftingPanelState>,
+    book: Res<RecipeBook>,
+    items: Res<ItemRegistry>,
+    inventory: Res<Inventory>,
+    grid: Res<CraftingGrid>,
+    grid_node: Query<Entity, With<CraftingGridNode>>,
+    list: Query<Entity, With<CraftingRecipeList>>,
+    mut inventory_text: Query<&mut Text, With<CraftingInventoryText>>,
+) {
+    if !state.open {
+        return;
+    }
+    if !(state.is_changed() || book.is_changed() || items.is_changed() || inventory.is_changed() || grid.is_changed()) {
+        return;
+    }
+    let (Ok(grid_node), Ok(list)) = (grid_node.get_single(), list.get_single()) else { return };
+
+    if let Ok(mut text) = inventory_text.get_single_mut() {
+        let contents: Vec<String> = inventory
+            .slots
+            .iter()
+            .flatten()
+            .map(|s| format!("{} x{}", items.get(&s.item).map_or(s.item.as_str(), |i| i.label()), s.count))
+            .collect();
+        text.sections[0].value = if contents.is_empty() {
+            "Inventory: empty".to_string()
+        } else {
+            format!("Inventory: {}", contents.join(", "))
+        };
+    }
+
+    let style = TextStyle { font: state.font.clone(), font_size: 16.0, color: Color::WHITE };
+    let label = |name: &str| items.get(name).map_or(name, |i| i.label()).to_string();
+    let cell = |background: Color| ButtonBundle {
+        style: Style {
+            width: Val::Px(CELL_SIZE),
+            height: Val::Px(CELL_SIZE),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: background.into(),
+        ..default()
+    };
+    let crafted = book.find_match(&grid, &items);
+
+    commands.entity(grid_node).despawn_descendants();
+    commands.entity(grid_node).with_children(|node| {
+        node.spawn(NodeBundle {
+            style: Style {
+                display: Display::Grid,
+                grid_template_columns: RepeatedGridTrack::px(GRID_SIZE as u16, CELL_SIZE),
+                row_gap: Val::Px(4.0),
+                column_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|cells| {
+            for index in 0..GRID_CELLS {
+                let name = grid.cells[index].as_deref().map(&label).unwrap_or_default();
+                cells.spawn((cell(UNCRAFTABLE_COLOR), CraftingGridCell(index))).with_children(|c| {
+                    c.spawn(TextBundle::from_section(name, style.clone()));
+                });
+            }
+        });
+
+        let (output, color) = match crafted {
+            Some(recipe) => (format!("{} x{}", label(&recipe.output.item), recipe.output.count), CRAFTABLE_COLOR),
+            None => (String::new(), UNCRAFTABLE_COLOR),
+        };
+        node.spawn(TextBundle::from_section("->", style.clone()));
+        node.spawn((cell(color), CraftingOutputButton)).with_children(|c| {
+            c.spawn(TextBundle::from_section(output, style.clone()));
+        });
+    });
+
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|list| {
+        for recipe in book.sorted() {
+            let output = label(&recipe.output.item);
+            let needs: Vec<String> = recipe
+                .requirements()
+                .iter()
+                .map(|(ingredient, count)| format!("{count} {}", ingredient.label()))
+                .collect();
+            let color = if recipe.can_fill_grid(&inventory, &items) { CRAFTABLE_COLOR } else { UNCRAFTABLE_COLOR };
+
+            list.spawn((
+                ButtonBundle {
+                    style: Style { padding: UiRect::all(Val::Px(4.0)), ..default() },
+                    background_color: color.into(),
+                    ..default()
+                },
+                CraftButton(recipe.name.clone()),
+            ))
+            .with_children(|row| {
+                row.spawn(TextBundle::from_section(
+                    format!("{output} x{}  <-  {}", recipe.output.count, needs.join(", ")),
+                    style.clone(),
+                ));
+            });
+        }
+    });
+}
+
+/// Lay the clicked recipe out in the grid and highlight hovered rows. Items
+/// already in the grid go back to the inventory first.
+///
+/// # Arguments
+/// * `buttons` - recipe rows whose interaction changed this frame
+/// * `book` - loaded recipes
+/// * `items` - item registry
+/// * `grid` - the crafting grid the recipe is laid out in
+/// * `inventory` - player inventory ingredients are taken from
+#[allow(clippy::needless_pass_by_value)]
+pub fn handle_craft_buttons(
+    mut buttons: Query<(&Interaction, &CraftButton, &mut BackgroundColor), Changed<Interaction>>,
+    book: Res<RecipeBook>,
+    items: Res<ItemRegistry>,
+    mut grid: ResMut<CraftingGrid>,
+    mut inventory: ResMut<Inventory>,
+) {
+    for (interaction, button, mut background) in &mut buttons {
+        let Some(recipe) = book.get(&button.0) else { continue };
+        match interaction {
+            Interaction::Pressed => {
+                grid.return_all(&mut inventory, &items);
+                recipe.fill_grid(&mut grid, &mut inventory, &items);
+            }
+            Interaction::Hovered if recipe.can_fill_grid(&inventory, &items) => {
+                *background = HOVERED_COLOR.into();
+            }
+            Interaction::Hovered | Interaction::None => {
+                let craftable = recipe.can_fill_grid(&inventory, &items);
+                *background = if craftable { CRAFTABLE_COLOR } else { UNCRAFTABLE_COLOR }.into();
+            }
+        }
+    }
+}
+
+/// Move items between the inventory and the clicked grid cell, and craft
+/// the matching recipe when the output is clicked.
+///
+/// # Arguments
+/// * `cells` - grid cells whose interaction changed this frame
+/// * `output` - the output button, if its interaction changed this frame
+/// * `book` - loaded recipes
+/// * `items` - item registry
+/// * `grid` - the crafting grid
+/// * `inventory` - player inventory items move from and to
+#[allow(clippy::needless_pass_by_value)]
+pub fn handle_grid_buttons(
+    cells: Query<(&Interaction, &CraftingGridCell), Changed<Interaction>>,
+    output: Query<&Interaction, (Changed<Interaction>, With<CraftingOutputButton>)>,
+    book: Res<RecipeBook>,
+    items: Res<ItemRegistry>,
+    mut grid: ResMut<CraftingGrid>,
+    mut inventory: ResMut<Inventory>,
+) {
+    for (interaction, cell) in &cells {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if grid.cells[cell.0].is_some() {
+            grid.take(cell.0, &mut inventory, &items);
+        } else {
+            grid.put_selected(cell.0, &mut inventory);
+        }
+    }
+
+    if output.iter().any(|interaction| *interaction == Interaction::Pressed)
+        && let Some(recipe) = book.find_match(&grid, &items)
+    {
+        recipe.craft(&mut grid, &mut inventory, &items);
+    }
+}
@@ -0,0 +1,128 @@
+//! Heads-up display: the player's health bar and the death message.
+//!
+//! The health bar sits at the bottom centre of the screen and is hidden in
+//! game modes without damage.
+
+use crate::player::{GameMode, Health, Player};
+use bevy::prelude::*;
+
+/// Width of the health bar in pixels.
+pub const HEALTH_BAR_WIDTH: f32 = 200.0;
+
+/// Root node of the health bar.
+#[derive(Component)]
+pub struct HealthBar;
+
+/// Filled part of the health bar; its width follows the player's health.
+#[derive(Component)]
+pub struct HealthBarFill;
+
+/// "You died" message shown until the player respawns.
+#[derive(Component)]
+pub struct DeathMessage;
+
+/// Spawn the (initially full) health bar and the hidden death message.
+///
+/// # Arguments
+/// * `commands` - `Commands` used to spawn UI nodes
+/// * `asset_server` - asset server for the message font
+#[allow(clippy::needless_pass_by_value)]
+pub fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(24.0),
+                    left: Val::Percent(50.0),
+                    margin: UiRect::left(Val::Px(-HEALTH_BAR_WIDTH * 0.5)),
+                    width: Val::Px(HEALTH_BAR_WIDTH),
+                    height: Val::Px(12.0),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.5).into(),
+                border_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+            HealthBar,
+        ))
+        .with_children(|p| {
+            p.spawn((
+                NodeBundle {
+                    style: Style { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+                    background_color: Color::srgb(0.8, 0.1, 0.1).into(),
+                    ..default()
+                },
+                HealthBarFill,
+            ));
+        });
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|p| {
+            p.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "You died",
+                        TextStyle {
+                            font: asset_server.load("fonts/OpenSans.ttf"),
+                            font_size: 48.0,
+                            color: Color::srgb(0.9, 0.1, 0.1),
+                        },
+                    ),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                DeathMessage,
+            ));
+        });
+}
+
+/// Keep the health bar and death message in sync with the player's health.
+///
+/// # Arguments
+/// * `mode` - current game mode; the bar is hidden when damage is off
+/// * `player` - player health
+/// * `bar` - health bar root style
+/// * `fill` - health bar fill style
+/// * `message` - death message visibility
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+pub fn update_hud(
+    mode: Res<GameMode>,
+    player: Query<&Health, With<Player>>,
+    mut bar: Query<&mut Style, (With<HealthBar>, Without<HealthBarFill>)>,
+    mut fill: Query<&mut Style, (With<HealthBarFill>, Without<HealthBar>)>,
+    mut message: Query<&mut Visibility, With<DeathMessage>>,
+) {
+    let Ok(health) = player.get_single() else { return };
+
+    if let Ok(mut style) = bar.get_single_mut() {
+        let display = if mode.takes_damage() { Display::Flex } else { Display::None };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    if let Ok(mut style) = fill.get_single_mut() {
+        let width = Val::Percent(health.fraction() * 100.0);
+        if style.width != width {
+            style.width = width;
+        }
+    }
+    if let Ok(mut visibility) = message.get_single_mut() {
+        let shown = if health.dead { Visibility::Visible } else { Visibility::Hidden };
+        if *visibility != shown {
+            *visibility = shown;
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Minimap and full-screen map.
+//!
+//! Both draw the cached chunk overview tiles (see `chunk::overview`). The
+//! minimap sits in the top-right corner and turns with the player so the
+//! view direction is always up. The `map` keybind (default `M`) opens a
+//! north-up full-screen map with the player's position and heading; the
+//! `map_biomes` keybind (default `B`) toggles its biome overlay. Images are
+//! redrawn when the player moves to another block or a tile changes.
+
+use crate::biome::BiomeRegistry;
+use crate::chunk::overview::{ChunkOverview, ChunkTint, UNEXPLORED_COLOUR};
+use crate::player::{Player, PlayerLook};
+use crate::input::ActionState;
+use crate::world::generate::biome_colour;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+/// Side of the minimap window in pixels.
+pub const MINIMAP_SIZE: f32 = 160.0;
+
+/// Blocks from the player to the edge of the minimap image. The image is
+/// larger than the window so its corners stay covered while it rotates.
+pub const MINIMAP_RADIUS: i32 = 114;
+
+/// Blocks from the player to the edge of the full-screen map.
+pub const FULL_MAP_RADIUS: i32 = 384;
+
+/// Seconds between redraws of the full-screen map.
+pub const FULL_MAP_REFRESH_SECONDS: f32 = 0.5;
+
+/// Map visibility and the images the maps draw into.
+#[derive(Resource)]
+pub struct MapState {
+    /// Whether the full-screen map is open.
+    pub full_open: bool,
+    /// Whether the full-screen map shows the biome overlay.
+    pub biomes: bool,
+    minimap: Handle<Image>,
+    full: Handle<Image>,
+    /// Centre and overview revision the minimap was last drawn with.
+    minimap_drawn: Option<(IVec2, u64)>,
+    /// Centre, overview revision and overlay flag of the last full map.
+    full_drawn: Option<(IVec2, u64, bool)>,
+    full_timer: Timer,
+}
+
+/// Root node of the minimap.
+#[derive(Component)]
+pub struct Minimap;
+
+/// The rotating minimap image.
+#[derive(Component)]
+pub struct MinimapImage;
+
+/// Root node of the full-screen map.
+#[derive(Component)]
+pub struct FullMap;
+
+/// Player marker on the full-screen map.
+#[derive(Component)]
+pub struct FullMapMarker;
+
+/// Biome legend of the full-screen map.
+#[derive(Component)]
+pub struct FullMapLegend;
+
+/// A square RGBA image filled with the unexplored colour.
+fn map_image(radius: i32) -> Image {
+    let size = u32::try_from(radius * 2).unwrap_or(0);
+    Image::new_fill(
+        Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &UNEXPLORED_COLOUR,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// A player marker: a dot with a nose pointing up (forward).
+fn spawn_marker(parent: &mut ChildBuilder, style: Style, marker: impl Bundle) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style { width: Val::Px(10.0), height: Val::Px(10.0), margin: UiRect::all(Val::Px(-5.0)), ..style },
+                background_color: Color::srgb(0.9, 0.15, 0.15).into(),
+                border_radius: BorderRadius::MAX,
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|p| {
+            p.spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(3.0),
+                    top: Val::Px(-6.0),
+                    width: Val::Px(4.0),
+                    height: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            });
+        });
+}
+
+/// Create the map images, insert `MapState` and spawn the minimap and the
+/// (hidden) full-screen map.
+///
+/// # Arguments
+/// * `commands` - `Commands` to spawn UI nodes and insert `MapState`
+/// * `images` - image assets the map images are added to
+/// * `asset_server` - asset server for the legend font
+#[allow(clippy::needless_pass_by_value, clippy::cast_precision_loss)]
+pub fn spawn_map_ui(mut commands: Commands, mut images: ResMut<Assets<Image>>, asset_server: Res<AssetServer>) {
+    let minimap = images.add(map_image(MINIMAP_RADIUS));
+    let full = images.add(map_image(FULL_MAP_RADIUS));
+    let image_size = (MINIMAP_RADIUS * 2) as f32;
+    let centred = Style { position_type: PositionType::Absolute, left: Val::Percent(50.0), top: Val::Percent(50.0), ..default() };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    width: Val::Px(MINIMAP_SIZE),
+                    height: Val::Px(MINIMAP_SIZE),
+                    border: UiRect::all(Val::Px(2.0)),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                border_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+            Minimap,
+        ))
+        .with_children(|p| {
+            let offset = (MINIMAP_SIZE - 4.0 - image_size) * 0.5;
+            p.spawn((
+                ImageBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(offset),
+                        top: Val::Px(offset),
+                        width: Val::Px(image_size),
+                        height: Val::Px(image_size),
+                        ..default()
+                    },
+                    image: UiImage::new(minimap.clone()),
+                    ..default()
+                },
+                MinimapImage,
+            ));
+            spawn_marker(p, centred.clone(), ());
+        });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.85).into(),
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            FullMap,
+        ))
+        .with_children(|p| {
+            p.spawn(ImageBundle {
+                style: Style { width: Val::Vh(85.0), height: Val::Vh(85.0), ..default() },
+                image: UiImage::new(full.clone()),
+                ..default()
+            })
+            .with_children(|p| spawn_marker(p, centred, FullMapMarker));
+            p.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle { font: asset_server.load("fonts/OpenSans.ttf"), font_size: 16.0, color: Color::WHITE },
+                ),
+                FullMapLegend,
+            ));
+        });
+
+    commands.insert_resource(MapState {
+        full_open: false,
+        biomes: true,
+        minimap,
+        full,
+        minimap_drawn: None,
+        full_drawn: None,
+        full_timer: Timer::from_seconds(FULL_MAP_REFRESH_SECONDS, TimerMode::Repeating),
+    });
+}
+
+/// Open/close the full-screen map and toggle its biome overlay.
+///
+/// # Arguments
+/// * `actions` - action state for the `map` and `map_biomes` actions
+/// * `state` - map state
+#[allow(clippy::needless_pass_by_value)]
+pub fn toggle_map(actions: Res<ActionState>, mut state: ResMut<MapState>) {
+    if actions.just_pressed("map") {
+        state.full_open = !state.full_open;
+        state.full_drawn = None;
+    }
+    if state.full_open && actions.just_pressed("map_biomes") {
+        state.biomes = !state.biomes;
+    }
+}
+
+/// Redraw the map images when needed and keep the map nodes in sync with
+/// the player.
+///
+/// # Arguments
+/// * `time` - time resource for the full map refresh timer
+/// * `state` - map state
+/// * `overview` - chunk overview tiles
+/// * `biomes` - biome registry for the overlay and legend
+/// * `images` - image assets holding the map images
+/// * `player` - player position and look angles
+/// * `minimap` - minimap root and image nodes
+/// * `full_map` - full map root and marker nodes
+/// * `legend` - full map legend text
+#[derive(bevy::ecs::system::SystemParam)]
+#[allow(clippy::type_complexity)]
+pub struct MapUiCtx<'w, 's> {
+    pub time: Res<'w, Time>,
+    pub state: ResMut<'w, MapState>,
+    pub overview: Res<'w, ChunkOverview>,
+    pub biomes: Res<'w, BiomeRegistry>,
+    pub images: ResMut<'w, Assets<Image>>,
+    pub player: Query<'w, 's, (&'static Transform, &'static PlayerLook), With<Player>>,
+    pub minimap: Query<'w, 's, &'static mut Style, (With<Minimap>, Without<FullMap>)>,
+    pub minimap_image: Query<'w, 's, &'static mut Transform, (With<MinimapImage>, Without<Player>, Without<FullMapMarker>)>,
+    pub full_map: Query<'w, 's, &'static mut Style, (With<FullMap>, Without<Minimap>)>,
+    pub marker: Query<'w, 's, &'static mut Transform, (With<FullMapMarker>, Without<Player>, Without<MinimapImage>)>,
+    pub legend: Query<'w, 's, &'static mut Text, With<FullMapLegend>>,
+}
+
+/// See `MapUiCtx`.
+#[allow(clippy::cast_possible_truncation)]
+pub fn update_map_ui(mut ctx: MapUiCtx) {
+    let Ok((tf, look)) = ctx.player.get_single() else { return };
+    let center = IVec2::new(tf.translation.x.floor() as i32, tf.translation.z.floor() as i32);
+    let revision = ctx.overview.revision;
+    let full_open = ctx.state.full_open;
+
+    for (mut style, shown) in [(ctx.minimap.get_single_mut(), !full_open), (ctx.full_map.get_single_mut(), full_open)] {
+        let display = if shown { Display::Flex } else { Display::None };
+        if let Ok(style) = style.as_mut()
+            && style.display != display
+        {
+            style.display = display;
+        }
+    }
+
+    if full_open {
+        // The map is north-up, so the marker turns with the player
+        if let Ok(mut marker) = ctx.marker.get_single_mut() {
+            marker.rotation = Quat::from_rotation_z(-look.yaw);
+        }
+        let ticked = ctx.state.full_timer.tick(ctx.time.delta()).just_finished();
+        let key = (center, revision, ctx.state.biomes);
+        if ctx.state.full_drawn != Some(key) && (ticked || ctx.state.full_drawn.is_none()) {
+            let biomes = &ctx.biomes;
+            let tint = |(cx, cz): (i32, i32)| biomes.get_biome_at(cx, cz).map(|b| biome_colour(b.temperature, b.humidity).0);
+            let pixels = ctx.overview.render(center, FULL_MAP_RADIUS, ctx.state.biomes.then_some(&tint as ChunkTint));
+            if let Some(image) = ctx.images.get_mut(&ctx.state.full) {
+                image.data = pixels;
+            }
+            ctx.state.full_drawn = Some(key);
+
+            if let Ok(mut text) = ctx.legend.get_single_mut() {
+                text.sections.truncate(1);
+                let style = text.sections[0].style.clone();
+                text.sections[0].value = format!(
+                    "X {} Z {}   [M] close   [B] biomes {}",
+                    center.x,
+                    center.y,
+                    if ctx.state.biomes { "on" } else { "off" }
+                );
+                if ctx.state.biomes {
+                    let mut names: Vec<_> = ctx.biomes.biomes.values().collect();
+                    names.sort_by(|a, b| a.name.cmp(&b.name));
+                    for b in names {
+                        let [r, g, bl] = biome_colour(b.temperature, b.humidity).0;
+                        let color = Color::srgb_u8(r, g, bl);
+                        text.sections.push(TextSection::new(format!("   {}", b.name), TextStyle { color, ..style.clone() }));
+                    }
+                }
+            }
+        }
+        return;
+    }
+
+    // The minimap turns so the view direction points up
+    if let Ok(mut image) = ctx.minimap_image.get_single_mut() {
+        image.rotation = Quat::from_rotation_z(look.yaw);
+    }
+    if ctx.state.minimap_drawn != Some((center, revision)) {
+        let pixels = ctx.overview.render(center, MINIMAP_RADIUS, None);
+        if let Some(image) = ctx.images.get_mut(&ctx.state.minimap) {
+            image.data = pixels;
+        }
+        ctx.state.minimap_drawn = Some((center, revision));
+    }
+}
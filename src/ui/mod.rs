@@ -2,9 +2,19 @@
 //!
 //! This module implements a simple debug overlay, an optional chunk grid
 //! renderer for debugging, and spawning of a crosshair UI element. The
-//! overlay periodically displays FPS, triangle counts, player position and
-//! biome information.
-
+//! overlay periodically displays FPS, the chunk streaming budget, triangle
+//! counts, player position, biome information and the held item. The crafting panel lives in
+//! `crafting`, the health bar in `hud`, the developer console in
+//! `console`, the minimap and full-screen map in `map` and the pause and
+//! settings menu in `settings_menu`.
+
+pub mod console;
+pub mod crafting;
+pub mod hud;
+pub mod map;
+pub mod settings_menu;
+
+use crate::input::ActionState;
 use crate::player::Player;
 use crate::world::World;
 use bevy::diagnostic::{Diagnostic, DiagnosticsStore};
@@ -37,24 +47,24 @@ pub fn setup_debug_overlay(mut commands: Commands) {
     commands.insert_resource(DebugGridVisible::default());
 }
 
-/// Toggle the debug overlay visibility when F1 is pressed.
+/// Toggle the debug overlay visibility on the `toggle_debug` action (F1).
 ///
 /// # Arguments
 /// * `state` - mutable `DebugOverlayState` resource
-/// * `input` - keyboard input resource
+/// * `actions` - action state
 #[allow(clippy::needless_pass_by_value)]
 pub fn toggle_debug_overlay(
     mut state: ResMut<DebugOverlayState>,
-    input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
 ) {
-    if input.just_pressed(KeyCode::F1) {
+    if actions.just_pressed("toggle_debug") {
         state.visible = !state.visible;
     }
 }
 
 #[allow(clippy::needless_pass_by_value)]
-pub fn toggle_debug_grid(mut grid: ResMut<DebugGridVisible>, input: Res<ButtonInput<KeyCode>>) {
-    if input.just_pressed(KeyCode::F2) {
+pub fn toggle_debug_grid(mut grid: ResMut<DebugGridVisible>, actions: Res<ActionState>) {
+    if actions.just_pressed("toggle_grid") {
         grid.0 = !grid.0;
     }
 }
@@ -72,6 +82,12 @@ pub fn toggle_debug_grid(mut grid: ResMut<DebugGridVisible>, input: Res<ButtonIn
 /// * `query` - text query identifying the debug overlay UI text element
 /// * `player_query` - query for player position and facing
 /// * `mesh_stats` - optional mesh stats for triangle counts
+/// * `inventory` - player inventory for the held item line
+/// * `mode` - game mode; creative shows the `CreativeBlock` as held
+/// * `creative` - the block creative mode places
+/// * `block_registry` - block names for the creative held line
+/// * `budget` - optional streaming budget for the budget line
+/// * `governor` - optional frame-time governor for its scale and target
 #[derive(bevy::ecs::system::SystemParam)]
 pub struct DebugOverlayCtx<'w, 's> {
     pub diagnostics: Res<'w, DiagnosticsStore>,
@@ -83,6 +99,12 @@ pub struct DebugOverlayCtx<'w, 's> {
     pub query: Query<'w, 's, &'static mut Text, With<DebugOverlayText>>,
     pub player_query: Query<'w, 's, (&'static GlobalTransform, &'static Transform), With<Player>>,
     pub mesh_stats: Option<Res<'w, crate::chunk::MeshGenerationStats>>,
+    pub inventory: Res<'w, crate::item::Inventory>,
+    pub mode: Res<'w, crate::player::GameMode>,
+    pub creative: Res<'w, crate::block::CreativeBlock>,
+    pub block_registry: Res<'w, crate::block::BlockRegistry>,
+    pub budget: Option<Res<'w, crate::chunk::lod::StreamingBudget>>,
+    pub governor: Option<Res<'w, crate::chunk::governor::StreamingGovernor>>,
 }
 
 /// Constantly update the debug overlay text with debug information.
@@ -146,11 +168,9 @@ pub fn update_debug_overlay(mut ctx: DebugOverlayCtx<'_, '_>) {
         };
 
         // Get biome at player position
-        let chunk_x = (pos.x / 32.0).floor() as i32;
-        let chunk_z = (pos.z / 32.0).floor() as i32;
         let biome_name = ctx
             .biome_registry
-            .get_biome_at(chunk_x, chunk_z)
+            .biome_at_block(pos.x.floor() as i32, pos.z.floor() as i32)
             .map_or("unknown", |b| b.name.as_str());
 
         (
@@ -164,15 +184,35 @@ pub fn update_debug_overlay(mut ctx: DebugOverlayCtx<'_, '_>) {
     let mesh_triangles = ctx.mesh_stats.as_ref().map_or(0, |s| s.total_triangles);
     let mesh_quads = mesh_triangles / 2;
 
+    let held = if ctx.mode.infinite_blocks() {
+        let block = ctx.creative.current(&ctx.block_registry).and_then(|id| ctx.block_registry.get_by_id(id));
+        block.map_or_else(|| "empty".to_string(), |b| format!("{} (creative)", b.name))
+    } else {
+        ctx.inventory
+            .selected_stack()
+            .map_or_else(|| "empty".to_string(), |s| format!("{} x{}", s.item, s.count))
+    };
+
+    let budget = ctx.budget.as_ref().map_or_else(String::new, |b| {
+        let governor = match &ctx.governor {
+            Some(g) if g.enabled => format!(" (x{:.2}, target {:.1} ms)", g.scale, g.target_ms),
+            _ => String::new(),
+        };
+        format!("\nBudget: gen {} mesh {} lod {}{}", b.generation_tasks, b.mesh_builds_per_frame, b.lod_builds_per_frame, governor)
+    });
+
     text.sections[0].value = format!(
-        "FPS: {:.1}\nFrame Time: {:.2} ms\nChunks: {}\nTriangles: {} (Quads: {})\n{}\n{}",
+        "FPS: {:.1}\nFrame Time: {:.2} ms{}\nChunks: {}\nTriangles: {} (Quads: {})\n{}\n{}\nHeld [{}]: {}",
         fps,
-        frame_time * 1000.0,
+        frame_time,
+        budget,
         chunk_count,
         mesh_triangles,
         mesh_quads,
         pos_str,
-        direction
+        direction,
+        ctx.inventory.selected + 1,
+        held
     );
 }
 
@@ -0,0 +1,779 @@
+//! Pause menu with the settings and key rebinding screens.
+//!
+//! The `pause` action (default `Esc`) opens the pause menu and releases the
+//! cursor. From there the settings screen shows one tab per section of
+//! `Settings` with a slider, toggle or dropdown for every field in
+//! `settings::fields::menu_fields`; hovering a row shows its description
+//! from `Settings::field_descriptions`. The controls screen lists the
+//! keyboard and mouse bindings of every action: click one and press a key to
+//! rebind it (`Esc` cancels; gamepad bindings are kept). Actions sharing a
+//! binding are marked with `(!)`.
+//!
+//! While the menu is open the virtual clock is paused (see
+//! `pause_while_menu_open`), so the `FixedUpdate` simulation (physics, block
+//! ticks, world time, weather) and anything else driven by `Time` stands
+//! still.
+//!
+//! Changes apply immediately. They are written back to the settings file
+//! (keeping its comments, see `settings::writer`) when leaving a screen or
+//! closing the menu.
+
+use crate::input::{rebind_keyboard, ActionState, InputMap};
+use crate::settings::fields::{menu_fields, FieldKind, FieldValue, SettingField, SECTIONS};
+use crate::settings::writer::{save_settings, SETTINGS_FILE};
+use crate::settings::Settings;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+
+const PANEL_COLOR: Color = Color::srgba(0.05, 0.05, 0.05, 0.9);
+const BUTTON_COLOR: Color = Color::srgba(0.25, 0.25, 0.25, 0.9);
+const HOVERED_COLOR: Color = Color::srgba(0.35, 0.35, 0.35, 0.9);
+const ACTIVE_COLOR: Color = Color::srgba(0.20, 0.45, 0.20, 0.9);
+const TRACK_COLOR: Color = Color::srgba(0.15, 0.15, 0.15, 0.9);
+const FILL_COLOR: Color = Color::srgb(0.30, 0.60, 0.30);
+const TEXT_COLOR: Color = Color::WHITE;
+const HINT_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+
+/// Width of the value widgets in pixels.
+const WIDGET_WIDTH: f32 = 160.0;
+
+/// Which screen of the menu is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MenuScreen {
+    #[default]
+    Pause,
+    Settings,
+    Controls,
+}
+
+/// Menu visibility, navigation and pending changes.
+///
+/// # Fields
+/// * `open` - whether the menu is shown
+/// * `screen` - the current screen
+/// * `section` - index into `SECTIONS` of the settings tab shown
+/// * `open_dropdown` - field index of the dropdown whose options are listed
+/// * `capturing` - keybind action waiting for a key press
+/// * `dirty` - settings changed since they were last written to the file
+/// * `status` - message shown at the bottom of the menu
+#[derive(Resource, Default)]
+pub struct SettingsMenuState {
+    pub open: bool,
+    pub screen: MenuScreen,
+    pub section: usize,
+    pub open_dropdown: Option<usize>,
+    pub capturing: Option<String>,
+    pub dirty: bool,
+    pub status: String,
+    fields: Vec<SettingField>,
+    font: Handle<Font>,
+}
+
+impl SettingsMenuState {
+    /// Write the settings to the settings file if anything changed.
+    fn save(&mut self, settings: &Settings) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        self.status = match save_settings(SETTINGS_FILE, settings) {
+            Ok(()) => format!("Saved to {SETTINGS_FILE}"),
+            Err(e) => {
+                eprintln!("warning: could not save settings: {e}");
+                format!("Could not save settings: {e}")
+            }
+        };
+    }
+
+    /// Leave the current screen: back to the pause screen, or close the menu.
+    fn back(&mut self, settings: &Settings) {
+        self.save(settings);
+        self.open_dropdown = None;
+        self.capturing = None;
+        if self.screen == MenuScreen::Pause {
+            self.open = false;
+        } else {
+            self.screen = MenuScreen::Pause;
+        }
+    }
+}
+
+/// Run condition: true while the settings menu is closed.
+#[allow(clippy::needless_pass_by_value)]
+#[must_use]
+pub fn settings_menu_closed(state: Res<SettingsMenuState>) -> bool {
+    !state.open
+}
+
+/// Pause the virtual clock while the menu is open and resume it when the
+/// menu closes, however it was closed (key or button).
+///
+/// # Arguments
+/// * `state` - menu state
+/// * `time` - the virtual clock that drives `Time` and `FixedUpdate`
+#[allow(clippy::needless_pass_by_value)]
+pub fn pause_while_menu_open(state: Res<SettingsMenuState>, mut time: ResMut<Time<Virtual>>) {
+    if state.open == time.is_paused() {
+        return;
+    }
+    if state.open {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+}
+
+/// What a menu button does when clicked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuAction {
+    Resume,
+    OpenSettings,
+    OpenControls,
+    Quit,
+    Back,
+    Section(usize),
+    Toggle(usize),
+    Dropdown(usize),
+    Choose(usize, &'static str),
+    Rebind(String),
+    ResetKeybinds,
+}
+
+/// Root node of the menu.
+#[derive(Component)]
+pub struct SettingsMenu;
+
+/// Title text of the menu.
+#[derive(Component)]
+pub struct SettingsMenuTitle;
+
+/// Container the current screen is spawned into.
+#[derive(Component)]
+pub struct SettingsMenuContent;
+
+/// Text at the bottom of the menu: field descriptions and save status.
+#[derive(Component)]
+pub struct SettingsMenuHint;
+
+/// A clickable menu button.
+#[derive(Component)]
+pub struct MenuButton(pub MenuAction);
+
+/// Marks the tab of the section being shown.
+#[derive(Component)]
+pub struct ActiveTab;
+
+/// A settings row; holds the field index, for hover descriptions.
+#[derive(Component)]
+pub struct FieldRow(pub usize);
+
+/// Text showing a field's value.
+#[derive(Component)]
+pub struct FieldValueText(pub usize);
+
+/// Slider track of a field.
+#[derive(Component)]
+pub struct SliderTrack(pub usize);
+
+/// Filled part of a slider track.
+#[derive(Component)]
+pub struct SliderFill(pub usize);
+
+/// Text showing the key bound to an action.
+#[derive(Component)]
+pub struct KeybindText(pub String);
+
+/// "render_distance" -> "Render distance"
+fn label(name: &str) -> String {
+    let mut label = name.replace('_', " ");
+    if let Some(first) = label.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    label
+}
+
+/// How far along its range a slider value is, in percent.
+fn slider_percent(kind: FieldKind, value: &FieldValue) -> f32 {
+    match (kind, value) {
+        (FieldKind::Slider { min, max, .. }, FieldValue::Number(n)) if max > min => ((n - min) / (max - min) * 100.0).clamp(0.0, 100.0),
+        _ => 0.0,
+    }
+}
+
+/// Text on a keybind button: the action's keyboard and mouse bindings.
+fn keybind_label(state: &SettingsMenuState, map: &InputMap, action: &str) -> String {
+    if state.capturing.as_deref() == Some(action) {
+        return "Press a key...".to_string();
+    }
+    let bindings: Vec<String> = map.bindings(action).iter().filter(|b| !b.is_gamepad()).map(ToString::to_string).collect();
+    let text = if bindings.is_empty() { "-".to_string() } else { bindings.join(" / ") };
+    if map.in_conflict(action) { format!("{text} (!)") } else { text }
+}
+
+/// Spawn the (hidden) menu and insert `SettingsMenuState`.
+///
+/// # Arguments
+/// * `commands` - `Commands` to spawn the menu nodes and insert the state
+/// * `asset_server` - asset server for loading the menu font
+#[allow(clippy::needless_pass_by_value)]
+pub fn spawn_settings_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font: Handle<Font> = asset_server.load("fonts/OpenSans.ttf");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.5).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            SettingsMenu,
+        ))
+        .with_children(|root| {
+            root.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(620.0),
+                    max_height: Val::Percent(90.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(16.0)),
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..default()
+            })
+            .with_children(|panel| {
+                panel.spawn((
+                    TextBundle::from_section("Paused", TextStyle { font: font.clone(), font_size: 26.0, color: TEXT_COLOR }),
+                    SettingsMenuTitle,
+                ));
+                panel.spawn((
+                    NodeBundle {
+                        style: Style { flex_direction: FlexDirection::Column, row_gap: Val::Px(6.0), ..default() },
+                        ..default()
+                    },
+                    SettingsMenuContent,
+                ));
+                panel.spawn((
+                    TextBundle::from_section("", TextStyle { font: font.clone(), font_size: 14.0, color: HINT_COLOR }),
+                    SettingsMenuHint,
+                ));
+            });
+        });
+
+    commands.insert_resource(SettingsMenuState { fields: menu_fields(), font, ..default() });
+}
+
+/// Open the menu with the `pause` keybind, or go back a screen (closing it
+/// from the pause screen). Releases the cursor while the menu is open.
+///
+/// # Arguments
+/// * `actions` - action state for the `pause` action
+/// * `settings` - settings, saved when leaving a screen
+/// * `state` - menu state
+/// * `windows` - primary window, for cursor grab state
+#[allow(clippy::needless_pass_by_value)]
+pub fn toggle_settings_menu(
+    actions: Res<ActionState>,
+    settings: Res<Settings>,
+    mut state: ResMut<SettingsMenuState>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !actions.just_pressed("pause") || state.capturing.is_some() {
+        return;
+    }
+
+    if state.open {
+        state.back(&settings);
+    } else {
+        state.open = true;
+        state.screen = MenuScreen::Pause;
+        state.status.clear();
+    }
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.cursor.grab_mode = if state.open { CursorGrabMode::None } else { CursorGrabMode::Locked };
+        window.cursor.visible = state.open;
+    }
+}
+
+/// Spawn a button with a text label.
+fn spawn_button(parent: &mut ChildBuilder, text: String, style: &TextStyle, width: Val, bundle: impl Bundle) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width,
+                    padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            bundle,
+        ))
+        .with_children(|b| {
+            b.spawn(TextBundle::from_section(text, style.clone()));
+        });
+}
+
+/// Spawn the value widget of field `index`.
+fn spawn_field_widget(parent: &mut ChildBuilder, index: usize, field: &SettingField, value: &FieldValue, state: &SettingsMenuState, text: &TextStyle) {
+    match field.kind {
+        FieldKind::Toggle => {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(WIDGET_WIDTH),
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    },
+                    MenuButton(MenuAction::Toggle(index)),
+                ))
+                .with_children(|b| {
+                    b.spawn((TextBundle::from_section(value.display(field.kind), text.clone()), FieldValueText(index)));
+                });
+        }
+        FieldKind::Slider { .. } => {
+            parent
+                .spawn(NodeBundle {
+                    style: Style { width: Val::Px(WIDGET_WIDTH), column_gap: Val::Px(8.0), align_items: AlignItems::Center, ..default() },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        ButtonBundle {
+                            style: Style { flex_grow: 1.0, height: Val::Px(14.0), ..default() },
+                            background_color: TRACK_COLOR.into(),
+                            ..default()
+                        },
+                        RelativeCursorPosition::default(),
+                        SliderTrack(index),
+                    ))
+                    .with_children(|track| {
+                        track.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(slider_percent(field.kind, value)),
+                                    height: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                background_color: FILL_COLOR.into(),
+                                ..default()
+                            },
+                            SliderFill(index),
+                        ));
+                    });
+                    row.spawn((
+                        TextBundle::from_section(value.display(field.kind), text.clone())
+                            .with_style(Style { width: Val::Px(44.0), ..default() }),
+                        FieldValueText(index),
+                    ));
+                });
+        }
+        FieldKind::Dropdown(options) => {
+            parent
+                .spawn(NodeBundle { style: Style { width: Val::Px(WIDGET_WIDTH), ..default() }, ..default() })
+                .with_children(|anchor| {
+                    anchor
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Percent(100.0),
+                                    padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                                    justify_content: JustifyContent::SpaceBetween,
+                                    ..default()
+                                },
+                                background_color: BUTTON_COLOR.into(),
+                                ..default()
+                            },
+                            MenuButton(MenuAction::Dropdown(index)),
+                        ))
+                        .with_children(|b| {
+                            b.spawn((TextBundle::from_section(value.display(field.kind), text.clone()), FieldValueText(index)));
+                            b.spawn(TextBundle::from_section("v", text.clone()));
+                        });
+                    if state.open_dropdown != Some(index) {
+                        return;
+                    }
+                    anchor
+                        .spawn(NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                top: Val::Percent(100.0),
+                                width: Val::Percent(100.0),
+                                flex_direction: FlexDirection::Column,
+                                ..default()
+                            },
+                            background_color: PANEL_COLOR.into(),
+                            z_index: ZIndex::Local(1),
+                            ..default()
+                        })
+                        .with_children(|list| {
+                            for option in options {
+                                spawn_button(list, option.to_string(), text, Val::Percent(100.0), MenuButton(MenuAction::Choose(index, option)));
+                            }
+                        });
+                });
+        }
+    }
+}
+
+/// What the menu content was built for: screen, settings tab, open dropdown
+/// and keybind count.
+type MenuLayout = (MenuScreen, usize, Option<usize>, usize);
+
+/// Respawn the menu's content when the screen, the settings tab or the open
+/// dropdown changes.
+///
+/// # Arguments
+/// * `commands` - `Commands` used to respawn the content
+/// * `state` - menu state
+/// * `settings` - current settings, for the initial widget values
+/// * `root` - style of the menu root node
+/// * `content` - the content container
+/// * `title` - the title text
+/// * `built` - layout the content was last built for
+#[allow(clippy::needless_pass_by_value, clippy::too_many_lines)]
+pub fn rebuild_settings_menu(
+    mut commands: Commands,
+    state: Res<SettingsMenuState>,
+    settings: Res<Settings>,
+    mut root: Query<&mut Style, With<SettingsMenu>>,
+    content: Query<Entity, With<SettingsMenuContent>>,
+    mut title: Query<&mut Text, With<SettingsMenuTitle>>,
+    mut built: Local<Option<MenuLayout>>,
+) {
+    if let Ok(mut style) = root.get_single_mut() {
+        let display = if state.open { Display::Flex } else { Display::None };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    let layout = (state.screen, state.section, state.open_dropdown, settings.controls.keybinds.len());
+    if !state.open {
+        *built = None;
+        return;
+    }
+    if *built == Some(layout) {
+        return;
+    }
+    *built = Some(layout);
+    let Ok(content) = content.get_single() else { return };
+
+    if let Ok(mut title) = title.get_single_mut() {
+        title.sections[0].value = match state.screen {
+            MenuScreen::Pause => "Paused",
+            MenuScreen::Settings => "Settings",
+            MenuScreen::Controls => "Controls",
+        }
+        .to_string();
+    }
+
+    let text = TextStyle { font: state.font.clone(), font_size: 16.0, color: TEXT_COLOR };
+    commands.entity(content).despawn_descendants();
+    commands.entity(content).with_children(|content| match state.screen {
+        MenuScreen::Pause => {
+            for (name, action) in [
+                ("Resume", MenuAction::Resume),
+                ("Settings", MenuAction::OpenSettings),
+                ("Controls", MenuAction::OpenControls),
+                ("Quit", MenuAction::Quit),
+            ] {
+                spawn_button(content, name.to_string(), &text, Val::Percent(100.0), MenuButton(action));
+            }
+        }
+        MenuScreen::Settings => {
+            content
+                .spawn(NodeBundle { style: Style { column_gap: Val::Px(4.0), flex_wrap: FlexWrap::Wrap, ..default() }, ..default() })
+                .with_children(|tabs| {
+                    for (i, section) in SECTIONS.iter().enumerate() {
+                        if i == state.section {
+                            spawn_button(tabs, label(section), &text, Val::Auto, (MenuButton(MenuAction::Section(i)), ActiveTab));
+                        } else {
+                            spawn_button(tabs, label(section), &text, Val::Auto, MenuButton(MenuAction::Section(i)));
+                        }
+                    }
+                });
+            let section = SECTIONS.get(state.section).copied().unwrap_or_default();
+            for (index, field) in state.fields.iter().enumerate().filter(|(_, f)| f.section == section) {
+                content
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                justify_content: JustifyContent::SpaceBetween,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::vertical(Val::Px(2.0)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        Interaction::default(),
+                        FieldRow(index),
+                    ))
+                    .with_children(|row| {
+                        row.spawn(TextBundle::from_section(label(field.name), text.clone()));
+                        spawn_field_widget(row, index, field, &(field.get)(&settings), &state, &text);
+                    });
+            }
+            spawn_button(content, "Back".to_string(), &text, Val::Percent(100.0), MenuButton(MenuAction::Back));
+        }
+        MenuScreen::Controls => {
+            let map = InputMap::from_settings(&settings.controls);
+            content
+                .spawn(NodeBundle {
+                    style: Style { flex_wrap: FlexWrap::Wrap, column_gap: Val::Px(12.0), row_gap: Val::Px(4.0), ..default() },
+                    ..default()
+                })
+                .with_children(|grid| {
+                    for action in map.actions() {
+                        grid.spawn(NodeBundle {
+                            style: Style { width: Val::Px(286.0), justify_content: JustifyContent::SpaceBetween, align_items: AlignItems::Center, ..default() },
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn(TextBundle::from_section(label(action), text.clone()));
+                            row.spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(120.0),
+                                        padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                                        justify_content: JustifyContent::Center,
+                                        ..default()
+                                    },
+                                    background_color: BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                MenuButton(MenuAction::Rebind(action.to_string())),
+                            ))
+                            .with_children(|b| {
+                                b.spawn((
+                                    TextBundle::from_section(keybind_label(&state, &map, action), text.clone()),
+                                    KeybindText(action.to_string()),
+                                ));
+                            });
+                        });
+                    }
+                });
+            spawn_button(content, "Reset to defaults".to_string(), &text, Val::Percent(100.0), MenuButton(MenuAction::ResetKeybinds));
+            spawn_button(content, "Back".to_string(), &text, Val::Percent(100.0), MenuButton(MenuAction::Back));
+        }
+    });
+}
+
+/// Run the clicked button's action and highlight hovered buttons.
+///
+/// # Arguments
+/// * `buttons` - menu buttons whose interaction changed this frame
+/// * `state` - menu state
+/// * `settings` - settings changed by toggles, dropdowns and resets
+/// * `windows` - primary window, to grab the cursor again on resume
+/// * `exit` - `AppExit` events, sent by the quit button
+#[allow(clippy::needless_pass_by_value)]
+pub fn handle_menu_buttons(
+    mut buttons: Query<(&Interaction, &MenuButton, &mut BackgroundColor, Has<ActiveTab>), Changed<Interaction>>,
+    mut state: ResMut<SettingsMenuState>,
+    mut settings: ResMut<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let was_open = state.open;
+    for (interaction, button, mut background, active) in &mut buttons {
+        *background = match interaction {
+            Interaction::Pressed | Interaction::Hovered => HOVERED_COLOR,
+            Interaction::None if active => ACTIVE_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        }
+        .into();
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match &button.0 {
+            MenuAction::Resume => {
+                state.screen = MenuScreen::Pause;
+                state.back(&settings);
+            }
+            MenuAction::OpenSettings => state.screen = MenuScreen::Settings,
+            MenuAction::OpenControls => state.screen = MenuScreen::Controls,
+            MenuAction::Quit => {
+                state.save(&settings);
+                exit.send(AppExit::Success);
+            }
+            MenuAction::Back => state.back(&settings),
+            MenuAction::Section(i) => {
+                state.section = *i;
+                state.open_dropdown = None;
+            }
+            MenuAction::Toggle(i) => {
+                let Some(field) = state.fields.get(*i).copied() else { continue };
+                if let FieldValue::Bool(b) = (field.get)(&settings) {
+                    (field.set)(&mut settings, &FieldValue::Bool(!b));
+                    state.dirty = true;
+                }
+            }
+            MenuAction::Dropdown(i) => {
+                state.open_dropdown = if state.open_dropdown == Some(*i) { None } else { Some(*i) };
+            }
+            MenuAction::Choose(i, option) => {
+                let Some(field) = state.fields.get(*i).copied() else { continue };
+                (field.set)(&mut settings, &FieldValue::Choice((*option).to_string()));
+                state.open_dropdown = None;
+                state.dirty = true;
+            }
+            MenuAction::Rebind(action) => state.capturing = Some(action.clone()),
+            MenuAction::ResetKeybinds => {
+                settings.controls.keybinds = crate::settings::ControlsSettings::default().keybinds;
+                state.capturing = None;
+                state.dirty = true;
+            }
+        }
+    }
+
+    if was_open
+        && !state.open
+        && let Ok(mut window) = windows.get_single_mut()
+    {
+        window.cursor.grab_mode = CursorGrabMode::Locked;
+        window.cursor.visible = false;
+    }
+}
+
+/// Drag sliders: while a track is pressed, set its field from the cursor
+/// position along it.
+///
+/// # Arguments
+/// * `tracks` - slider tracks with their interaction and relative cursor position
+/// * `state` - menu state (field table, marked dirty on change)
+/// * `settings` - settings the value is written to
+#[allow(clippy::needless_pass_by_value)]
+pub fn drag_settings_sliders(
+    tracks: Query<(&Interaction, &RelativeCursorPosition, &SliderTrack)>,
+    mut state: ResMut<SettingsMenuState>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, cursor, track) in &tracks {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let (Some(cursor), Some(field)) = (cursor.normalized, state.fields.get(track.0).copied()) else { continue };
+        let FieldKind::Slider { min, max, .. } = field.kind else { continue };
+        let value = FieldValue::Number(field.kind.snap(min + cursor.x.clamp(0.0, 1.0) * (max - min)));
+        if (field.get)(&settings) != value {
+            (field.set)(&mut settings, &value);
+            state.dirty = true;
+        }
+    }
+}
+
+/// While a keybind is being rebound, bind the next key pressed to it
+/// (`Esc` cancels), replacing the action's keyboard and mouse bindings but
+/// keeping its gamepad ones. The key press is consumed so it does not also
+/// trigger its old action; a binding shared with another action is reported
+/// in the status line.
+///
+/// # Arguments
+/// * `input` - keyboard input resource
+/// * `state` - menu state with the action being rebound
+/// * `settings` - settings the keybind is written to
+pub fn capture_keybind(mut input: ResMut<ButtonInput<KeyCode>>, mut state: ResMut<SettingsMenuState>, mut settings: ResMut<Settings>) {
+    let Some(action) = state.capturing.clone() else { return };
+    let Some(key) = input.get_just_pressed().next().copied() else { return };
+    input.clear_just_pressed(key);
+    state.capturing = None;
+    if key == KeyCode::Escape {
+        return;
+    }
+    match Settings::key_name(key) {
+        Some(name) => {
+            let defaults = crate::settings::ControlsSettings::default_keybinds();
+            let current = settings.controls.keybinds.get(&action).or_else(|| defaults.get(&action)).cloned().unwrap_or_default();
+            settings.controls.keybinds.insert(action.clone(), rebind_keyboard(&current, &name));
+            state.dirty = true;
+
+            let map = InputMap::from_settings(&settings.controls);
+            let shared: Vec<&str> = map
+                .conflicts
+                .iter()
+                .filter(|c| c.actions.contains(&action))
+                .flat_map(|c| c.actions.iter().map(String::as_str))
+                .filter(|a| *a != action)
+                .collect();
+            state.status = if shared.is_empty() {
+                format!("{} bound to {name}", label(&action))
+            } else {
+                format!("{} bound to {name}, also used by {}", label(&action), shared.join(", "))
+            };
+        }
+        None => state.status = format!("{key:?} cannot be bound"),
+    }
+}
+
+/// Keep value texts, slider fills, keybind labels and the hint line in step
+/// with the settings (which may also change through hot reload).
+///
+/// # Arguments
+/// * `state` - menu state
+/// * `settings` - current settings
+/// * `values` - field value texts
+/// * `fills` - slider fills
+/// * `keybinds` - keybind labels
+/// * `rows` - settings rows, for the hovered field's description
+/// * `hint` - the hint text
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+pub fn update_settings_menu(
+    state: Res<SettingsMenuState>,
+    settings: Res<Settings>,
+    mut values: Query<(&mut Text, &FieldValueText), (Without<KeybindText>, Without<SettingsMenuHint>)>,
+    mut fills: Query<(&mut Style, &SliderFill)>,
+    mut keybinds: Query<(&mut Text, &KeybindText), (Without<FieldValueText>, Without<SettingsMenuHint>)>,
+    rows: Query<(&Interaction, &FieldRow)>,
+    mut hint: Query<&mut Text, With<SettingsMenuHint>>,
+) {
+    if !state.open {
+        return;
+    }
+    if settings.is_changed() || state.is_changed() {
+        for (mut text, value) in &mut values {
+            if let Some(field) = state.fields.get(value.0) {
+                text.sections[0].value = (field.get)(&settings).display(field.kind);
+            }
+        }
+        for (mut style, fill) in &mut fills {
+            if let Some(field) = state.fields.get(fill.0) {
+                style.width = Val::Percent(slider_percent(field.kind, &(field.get)(&settings)));
+            }
+        }
+        let map = InputMap::from_settings(&settings.controls);
+        for (mut text, keybind) in &mut keybinds {
+            text.sections[0].value = keybind_label(&state, &map, &keybind.0);
+        }
+    }
+
+    let Ok(mut hint) = hint.get_single_mut() else { return };
+    let hovered = rows.iter().find(|(i, _)| **i != Interaction::None).and_then(|(_, row)| state.fields.get(row.0));
+    let description = hovered.and_then(|f| Settings::field_descriptions().get(f.section).and_then(|m| m.get(f.name)).copied());
+    let value = match (description, state.screen) {
+        (Some(description), _) => description.to_string(),
+        (None, MenuScreen::Controls) if state.status.is_empty() => "Click a keybind, then press the new key (Esc cancels).".to_string(),
+        _ => state.status.clone(),
+    };
+    if hint.sections[0].value != value {
+        hint.sections[0].value = value;
+    }
+}
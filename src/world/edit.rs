@@ -0,0 +1,394 @@
+//! Region editing.
+//!
+//! World-edit style tools for large builds. Two corners (picked with the
+//! `select_corner_1` / `select_corner_2` keybinds on the targeted block, or
+//! typed in the console) define a `Region`. Operations turn a region into a
+//! list of `BlockEdit`s which `RegionEditor::apply` writes in one batch
+//! through `World::set_blocks_with_state`, so every touched chunk is
+//! remeshed once. Each applied batch keeps the blocks it overwrote for
+//! undo/redo.
+//!
+//! The clipboard holds a copy of a region, can be rotated in quarter turns
+//! when pasting and can be exported as a structure template (see
+//! `world::structure`).
+
+use super::structure::{StructureBlock, StructureTemplate};
+use super::{World, MAX_HEIGHT};
+use crate::block::{blocks, raycast_block, BlockId, BlockRegistry};
+use crate::input::ActionState;
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Largest number of blocks one operation may touch.
+pub const MAX_EDIT_VOLUME: i64 = 64 * 64 * 64;
+
+/// Number of edit batches kept for undo.
+pub const MAX_UNDO: usize = 32;
+
+/// How far corner selection reaches, in blocks.
+pub const SELECT_REACH: f32 = 64.0;
+
+/// A box of blocks, both corners inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub min: IVec3,
+    pub max: IVec3,
+}
+
+impl Region {
+    /// The box spanned by two opposite corners in any order.
+    #[must_use]
+    pub fn new(a: IVec3, b: IVec3) -> Self {
+        Self { min: a.min(b), max: a.max(b) }
+    }
+
+    /// Size in blocks along each axis.
+    #[must_use]
+    pub fn size(&self) -> IVec3 {
+        self.max - self.min + IVec3::ONE
+    }
+
+    /// Number of blocks in the region.
+    #[must_use]
+    pub fn volume(&self) -> i64 {
+        let size = self.size().as_i64vec3();
+        size.x * size.y * size.z
+    }
+
+    /// Every position in the region.
+    pub fn positions(self) -> impl Iterator<Item = IVec3> {
+        let Region { min, max } = self;
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| IVec3::new(x, y, z))))
+    }
+
+    /// Whether `pos` lies on the region's outer faces.
+    #[must_use]
+    pub fn on_shell(&self, pos: IVec3) -> bool {
+        pos.cmpeq(self.min).any() || pos.cmpeq(self.max).any()
+    }
+
+    /// Refuse regions larger than `MAX_EDIT_VOLUME`.
+    ///
+    /// # Errors
+    /// Returns a message if the region is too large.
+    pub fn check_volume(&self) -> Result<(), String> {
+        if self.volume() > MAX_EDIT_VOLUME {
+            return Err(format!("region too large ({} blocks, max {MAX_EDIT_VOLUME})", self.volume()));
+        }
+        Ok(())
+    }
+}
+
+/// A single block write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockEdit {
+    pub pos: IVec3,
+    pub block: BlockId,
+    pub state: u8,
+}
+
+/// Set every block in `region` to `block`.
+#[must_use]
+pub fn fill(region: Region, block: BlockId) -> Vec<BlockEdit> {
+    region.positions().map(|pos| BlockEdit { pos, block, state: 0 }).collect()
+}
+
+/// Replace every `from` block in `region` with `to`.
+#[must_use]
+pub fn replace(world: &World, region: Region, from: BlockId, to: BlockId) -> Vec<BlockEdit> {
+    region
+        .positions()
+        .filter(|p| world.get_block(p.x, p.y, p.z) == from)
+        .map(|pos| BlockEdit { pos, block: to, state: 0 })
+        .collect()
+}
+
+/// Make `region` a hollow box: `block` on the outer faces, air inside.
+#[must_use]
+pub fn hollow(region: Region, block: BlockId) -> Vec<BlockEdit> {
+    region
+        .positions()
+        .map(|pos| BlockEdit { pos, block: if region.on_shell(pos) { block } else { blocks::AIR }, state: 0 })
+        .collect()
+}
+
+/// Write `edits` to the loaded chunks of `world` in one batch.
+///
+/// # Panics
+/// Only if `MAX_HEIGHT` does not fit in an `i32`.
+///
+/// # Return
+/// * `(Vec<BlockEdit>, HashSet<(i32, i32)>)` - the edits that undo the
+///   change (only blocks that actually changed) and the chunks to remesh
+pub fn apply_edits(world: &mut World, registry: &BlockRegistry, edits: &[BlockEdit]) -> (Vec<BlockEdit>, HashSet<(i32, i32)>) {
+    let mut undo = Vec::new();
+    let mut seen = HashSet::new();
+    let mut writes = Vec::new();
+    let max_h = i32::try_from(MAX_HEIGHT).expect("MAX_HEIGHT fits in i32");
+    for edit in edits {
+        let BlockEdit { pos, block, state } = *edit;
+        if !world.is_loaded(pos.x, pos.z) || !(0..max_h).contains(&pos.y) {
+            continue;
+        }
+        let old = BlockEdit { pos, block: world.get_block(pos.x, pos.y, pos.z), state: world.get_block_state(pos.x, pos.y, pos.z) };
+        if old.block == block && old.state == state {
+            continue;
+        }
+        // Only the first write to a position knows what was there before
+        if seen.insert(pos) {
+            undo.push(old);
+        }
+        writes.push((pos, block, state));
+    }
+    let chunks = world.set_blocks_with_state(writes, registry);
+    (undo, chunks)
+}
+
+/// A copied region.
+///
+/// # Fields
+/// * `size` - size of the copied box
+/// * `blocks` - `(block, state)` of every position, indexed by `index`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Clipboard {
+    pub size: IVec3,
+    pub blocks: Vec<(BlockId, u8)>,
+}
+
+impl Clipboard {
+    /// Copy the blocks of `region`.
+    #[must_use]
+    pub fn copy(world: &World, region: Region) -> Self {
+        let blocks = region
+            .positions()
+            .map(|p| (world.get_block(p.x, p.y, p.z), world.get_block_state(p.x, p.y, p.z)))
+            .collect();
+        Self { size: region.size(), blocks }
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn index(&self, pos: IVec3) -> usize {
+        ((pos.x * self.size.y + pos.y) * self.size.z + pos.z) as usize
+    }
+
+    /// Block and state at a position relative to the minimum corner.
+    #[must_use]
+    pub fn get(&self, pos: IVec3) -> (BlockId, u8) {
+        self.blocks[self.index(pos)]
+    }
+
+    /// Every relative position of the clipboard.
+    fn positions(&self) -> impl Iterator<Item = IVec3> {
+        Region::new(IVec3::ZERO, self.size - IVec3::ONE).positions()
+    }
+
+    /// The clipboard turned clockwise (seen from above) around the Y axis
+    /// by `quarter_turns` quarter turns (negative turns go anticlockwise).
+    #[must_use]
+    pub fn rotated(&self, quarter_turns: i32) -> Self {
+        let mut rotated = self.clone();
+        for _ in 0..quarter_turns.rem_euclid(4) {
+            let from = rotated.clone();
+            rotated.size = IVec3::new(from.size.z, from.size.y, from.size.x);
+            for pos in from.positions() {
+                // (x, z) -> (depth - 1 - z, x)
+                let to = IVec3::new(from.size.z - 1 - pos.z, pos.y, pos.x);
+                let index = rotated.index(to);
+                rotated.blocks[index] = from.get(pos);
+            }
+        }
+        rotated
+    }
+
+    /// Edits placing the clipboard with its minimum corner at `origin`.
+    /// With `skip_air` the air of the clipboard leaves the world untouched.
+    #[must_use]
+    pub fn paste(&self, origin: IVec3, skip_air: bool) -> Vec<BlockEdit> {
+        self.positions()
+            .map(|pos| (pos, self.get(pos)))
+            .filter(|(_, (block, _))| !skip_air || *block != blocks::AIR)
+            .map(|(pos, (block, state))| BlockEdit { pos: origin + pos, block, state })
+            .collect()
+    }
+
+    /// Convert to the structure template format.
+    ///
+    /// # Errors
+    /// Returns a message if a block id is missing from the registry.
+    #[allow(clippy::cast_sign_loss)]
+    pub fn to_template(&self, name: &str, registry: &BlockRegistry) -> Result<StructureTemplate, String> {
+        let mut template = StructureTemplate {
+            name: name.to_string(),
+            size: (self.size.x as u32, self.size.y as u32, self.size.z as u32),
+            ..StructureTemplate::default()
+        };
+        let mut palette: HashMap<BlockId, u16> = HashMap::new();
+        for pos in self.positions() {
+            let (block, state) = self.get(pos);
+            if block == blocks::AIR {
+                continue;
+            }
+            let index = match palette.get(&block) {
+                Some(index) => *index,
+                None => {
+                    let entry = registry.get_by_id(block).ok_or_else(|| format!("unknown block id {block}"))?;
+                    let index = u16::try_from(template.palette.len()).map_err(|_| "too many block types".to_string())?;
+                    template.palette.push(entry.name.clone());
+                    palette.insert(block, index);
+                    index
+                }
+            };
+            template.blocks.push(StructureBlock { pos: (pos.x as u32, pos.y as u32, pos.z as u32), block: index, state });
+        }
+        Ok(template)
+    }
+}
+
+/// Selection, clipboard and undo history of the region tools.
+#[derive(Resource, Default)]
+pub struct RegionEditor {
+    /// The two selection corners.
+    pub corners: [Option<IVec3>; 2],
+    /// The last copied region.
+    pub clipboard: Option<Clipboard>,
+    undo: Vec<Vec<BlockEdit>>,
+    redo: Vec<Vec<BlockEdit>>,
+}
+
+impl RegionEditor {
+    /// The selected region, once both corners are set.
+    #[must_use]
+    pub fn selection(&self) -> Option<Region> {
+        match self.corners {
+            [Some(a), Some(b)] => Some(Region::new(a, b)),
+            _ => None,
+        }
+    }
+
+    /// Apply `edits` as one undoable step.
+    ///
+    /// # Return
+    /// * `(usize, HashSet<(i32, i32)>)` - number of blocks changed and the chunks to remesh
+    pub fn apply(&mut self, world: &mut World, registry: &BlockRegistry, edits: &[BlockEdit]) -> (usize, HashSet<(i32, i32)>) {
+        let (undo, chunks) = apply_edits(world, registry, edits);
+        let changed = undo.len();
+        if changed > 0 {
+            self.undo.push(undo);
+            if self.undo.len() > MAX_UNDO {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+        }
+        (changed, chunks)
+    }
+
+    /// Revert the last applied step.
+    ///
+    /// # Return
+    /// * `Option<(usize, HashSet<(i32, i32)>)>` - blocks changed and chunks to
+    ///   remesh, or `None` if there is nothing to undo
+    pub fn undo(&mut self, world: &mut World, registry: &BlockRegistry) -> Option<(usize, HashSet<(i32, i32)>)> {
+        let edits = self.undo.pop()?;
+        let (redo, chunks) = apply_edits(world, registry, &edits);
+        let changed = redo.len();
+        self.redo.push(redo);
+        Some((changed, chunks))
+    }
+
+    /// Re-apply the last undone step.
+    ///
+    /// # Return
+    /// * `Option<(usize, HashSet<(i32, i32)>)>` - blocks changed and chunks to
+    ///   remesh, or `None` if there is nothing to redo
+    pub fn redo(&mut self, world: &mut World, registry: &BlockRegistry) -> Option<(usize, HashSet<(i32, i32)>)> {
+        let edits = self.redo.pop()?;
+        let (undo, chunks) = apply_edits(world, registry, &edits);
+        let changed = undo.len();
+        self.undo.push(undo);
+        Some((changed, chunks))
+    }
+}
+
+/// Set a selection corner to the targeted block when the `select_corner_1`
+/// or `select_corner_2` action is pressed.
+///
+/// # Arguments
+/// * `actions` - action state for the `select_corner_*` actions
+/// * `camera` - player camera transform for the ray
+/// * `world` - world to ray cast against
+/// * `editor` - region editor holding the corners
+#[allow(clippy::needless_pass_by_value)]
+pub fn select_region_corners(
+    actions: Res<ActionState>,
+    camera: Query<&Transform, With<Camera3d>>,
+    world: Res<World>,
+    mut editor: ResMut<RegionEditor>,
+) {
+    let Some(corner) = ["select_corner_1", "select_corner_2"].iter().position(|a| actions.just_pressed(a)) else { return };
+    let Ok(camera) = camera.get_single() else { return };
+
+    if let Some((hit, _)) = raycast_block(&world, camera.translation, *camera.forward(), SELECT_REACH) {
+        editor.corners[corner] = Some(hit);
+        info!("Corner {} set to ({}, {}, {})", corner + 1, hit.x, hit.y, hit.z);
+    }
+}
+
+/// Outline the selected region (or the single corner picked so far).
+///
+/// # Arguments
+/// * `editor` - region editor holding the corners
+/// * `gizmos` - gizmo drawing context
+#[allow(clippy::needless_pass_by_value)]
+pub fn draw_region_selection(editor: Res<RegionEditor>, mut gizmos: Gizmos) {
+    let region = match editor.corners {
+        [Some(a), Some(b)] => Region::new(a, b),
+        [Some(a), None] | [None, Some(a)] => Region::new(a, a),
+        [None, None] => return,
+    };
+    let size = region.size().as_vec3();
+    let center = region.min.as_vec3() + size * 0.5;
+    gizmos.cuboid(Transform::from_translation(center).with_scale(size + Vec3::splat(0.02)), Color::srgb(1.0, 0.6, 0.0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::chunk::Chunk;
+
+    #[test]
+    fn paste_rotates_and_undo_restores() {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: 1, name: "planks".to_string(), ..Block::default() });
+        let mut world = World::new();
+        world.chunks.insert((0, 0), Chunk::new());
+        let mut editor = RegionEditor::default();
+
+        // An L of planks: (0,0,0), (1,0,0) and (0,0,1)
+        let shape = [IVec3::new(0, 0, 0), IVec3::new(1, 0, 0), IVec3::new(0, 0, 1)];
+        let edits: Vec<BlockEdit> = shape.iter().map(|&pos| BlockEdit { pos, block: 1, state: 0 }).collect();
+        let (changed, chunks) = editor.apply(&mut world, &registry, &edits);
+        assert_eq!(changed, 3);
+        assert!(chunks.contains(&(0, 0)));
+
+        let clipboard = Clipboard::copy(&world, Region::new(IVec3::ZERO, IVec3::new(1, 0, 1)));
+        let rotated = clipboard.rotated(1);
+        assert_eq!(rotated.get(IVec3::new(0, 0, 0)), (1, 0));
+        assert_eq!(rotated.get(IVec3::new(1, 0, 0)), (1, 0));
+        assert_eq!(rotated.get(IVec3::new(1, 0, 1)), (1, 0));
+        assert_eq!(rotated.get(IVec3::new(0, 0, 1)), (blocks::AIR, 0));
+        assert_eq!(clipboard.rotated(4), clipboard);
+
+        let origin = IVec3::new(10, 5, 10);
+        editor.apply(&mut world, &registry, &rotated.paste(origin, true));
+        assert_eq!(world.get_block(11, 5, 11), 1);
+        editor.undo(&mut world, &registry);
+        assert_eq!(world.get_block(11, 5, 11), blocks::AIR);
+        editor.redo(&mut world, &registry);
+        assert_eq!(world.get_block(11, 5, 11), 1);
+
+        let template = clipboard.to_template("corner", &registry).expect("known blocks");
+        assert_eq!(template.palette, vec!["planks".to_string()]);
+        assert_eq!(template.blocks.len(), 3);
+    }
+}
@@ -0,0 +1,289 @@
+//! Explosions.
+//!
+//! An explosion casts `RAYS_PER_EDGE`² rays through each face of a cube
+//! around its centre. Every ray starts with the explosion's power (jittered
+//! by ±30%) and steps `RAY_STEP` blocks at a time. Each step loses some power
+//! to the air, and each block the ray passes through absorbs power according
+//! to its `blast_resistance`. A breakable block is destroyed when a ray
+//! still has power left after passing through it.
+//!
+//! `World::explode` removes all destroyed blocks in one `World::set_blocks`
+//! batch and returns the chunks to remesh, each once. `handle_explosions`
+//! turns `ExplosionEvent`s into explosions, spawns drops, pushes the
+//! player, dropped items and falling blocks away from the centre and hurts
+//! the player.
+
+use super::tick::BlockTickScheduler;
+use super::{World, MAX_HEIGHT};
+use crate::atlas_builder::AtlasUVMap;
+use crate::block::falling::{BlockMeshCache, FallingBlock};
+use crate::block::{blocks, BlockId, BlockRegistry, DirtyChunks};
+use crate::chunk::VoxelMaterialHandle;
+use crate::item::{spawn_item_drop, DroppedItem, ItemRegistry, ItemStack};
+use crate::player::{DamageEvent, DamageSource, Player};
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+/// Rays cast along each edge of a cube face (16 → 1352 rays in total).
+pub const RAYS_PER_EDGE: u32 = 16;
+
+/// Distance (in blocks) a ray travels per step.
+pub const RAY_STEP: f32 = 0.3;
+
+/// Power a ray loses per step through air.
+pub const AIR_ABSORPTION: f32 = 0.225;
+
+/// Knockback radius per unit of explosion power.
+pub const KNOCKBACK_RADIUS_PER_POWER: f32 = 2.0;
+
+/// Speed (blocks per second) given to entities right at the centre; it
+/// falls off linearly to zero at the knockback radius.
+pub const KNOCKBACK_SPEED: f32 = 20.0;
+
+/// Player damage per unit of explosion power at the centre; it falls off
+/// like the knockback.
+pub const DAMAGE_PER_POWER: f32 = 4.0;
+
+/// Request an explosion; handled by `handle_explosions`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ExplosionEvent {
+    pub center: Vec3,
+    pub power: f32,
+}
+
+/// Result of `World::explode`.
+///
+/// # Fields
+/// * `destroyed` - removed blocks and the block they were before the explosion
+/// * `chunks` - chunks whose mesh must be rebuilt
+#[derive(Debug, Default)]
+pub struct Explosion {
+    pub destroyed: Vec<(IVec3, BlockId)>,
+    pub chunks: HashSet<(i32, i32)>,
+}
+
+/// Deterministic jitter in `0.0..1.0` for ray `index` of an explosion
+/// (splitmix64 of the centre and the index).
+#[allow(clippy::cast_precision_loss)]
+fn ray_jitter(center: Vec3, index: u32) -> f32 {
+    let seed = (u64::from(center.x.to_bits()) << 32) ^ (u64::from(center.y.to_bits()) << 16) ^ u64::from(center.z.to_bits());
+    let mut z = seed.wrapping_add(u64::from(index).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    ((z ^ (z >> 31)) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Unit directions from the centre through the surface of a cube.
+#[allow(clippy::cast_precision_loss)]
+fn ray_directions() -> impl Iterator<Item = Vec3> {
+    let last = RAYS_PER_EDGE - 1;
+    (0..RAYS_PER_EDGE).flat_map(move |i| {
+        (0..RAYS_PER_EDGE).flat_map(move |j| {
+            (0..RAYS_PER_EDGE).filter_map(move |k| {
+                let on_surface = i == 0 || i == last || j == 0 || j == last || k == 0 || k == last;
+                let cell = Vec3::new(i as f32, j as f32, k as f32) / last as f32 * 2.0 - Vec3::ONE;
+                on_surface.then(|| cell.normalize())
+            })
+        })
+    })
+}
+
+impl World {
+    /// Blocks an explosion at `center` with `power` would destroy. Rays stop
+    /// at unloaded chunks and at the bottom and top of the world.
+    ///
+    /// # Arguments
+    /// * `center` - explosion centre in world coordinates
+    /// * `power` - explosion power (roughly its radius in blocks through air)
+    /// * `block_registry` - block registry providing `blast_resistance` and `breakable`
+    ///
+    /// # Return
+    /// * `Vec<(IVec3, BlockId)>` - each destroyed position once, with its current block
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn explosion_targets(&self, center: Vec3, power: f32, block_registry: &BlockRegistry) -> Vec<(IVec3, BlockId)> {
+        let mut seen = HashSet::new();
+        let mut targets = Vec::new();
+
+        for (index, dir) in (0u32..).zip(ray_directions()) {
+            let mut intensity = power * (0.7 + ray_jitter(center, index) * 0.6);
+            let mut pos = center;
+            while intensity > 0.0 {
+                let cell = pos.floor().as_ivec3();
+                if cell.y < 0 || cell.y >= MAX_HEIGHT as i32 || !self.is_loaded(cell.x, cell.z) {
+                    break;
+                }
+                let id = self.get_block(cell.x, cell.y, cell.z);
+                if id != blocks::AIR {
+                    let def = block_registry.get_by_id(id);
+                    let resistance = def.map_or(0.0, |b| b.blast_resistance.max(0.0));
+                    intensity -= (resistance + RAY_STEP) * RAY_STEP;
+                    if intensity > 0.0 && def.is_some_and(|b| b.breakable) && seen.insert(cell) {
+                        targets.push((cell, id));
+                    }
+                }
+                pos += dir * RAY_STEP;
+                intensity -= AIR_ABSORPTION;
+            }
+        }
+        targets
+    }
+
+    /// Explode at `center`: remove every block `explosion_targets` reports in
+    /// a single batched edit.
+    ///
+    /// # Arguments
+    /// * `center` - explosion centre in world coordinates
+    /// * `power` - explosion power
+    /// * `block_registry` - block registry
+    ///
+    /// # Return
+    /// * `Explosion` - destroyed blocks and the chunks to remesh
+    pub fn explode(&mut self, center: Vec3, power: f32, block_registry: &BlockRegistry) -> Explosion {
+        let destroyed = self.explosion_targets(center, power, block_registry);
+        let chunks = self.set_blocks(destroyed.iter().map(|(pos, _)| (*pos, blocks::AIR)), block_registry);
+        Explosion { destroyed, chunks }
+    }
+}
+
+/// Velocity change for something at `pos` caught in an explosion, or
+/// `None` if it is outside the knockback radius.
+#[must_use]
+pub fn knockback(center: Vec3, power: f32, pos: Vec3) -> Option<Vec3> {
+    let radius = power * KNOCKBACK_RADIUS_PER_POWER;
+    let offset = pos - center;
+    let distance = offset.length();
+    if distance >= radius {
+        return None;
+    }
+    // Straight above/below the centre (or at it) push upwards
+    let dir = offset.try_normalize().unwrap_or(Vec3::Y);
+    Some(dir * KNOCKBACK_SPEED * (1.0 - distance / radius))
+}
+
+/// System parameters used by `handle_explosions`.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct ExplosionCtx<'w, 's> {
+    pub commands: Commands<'w, 's>,
+    pub world: ResMut<'w, World>,
+    pub block_registry: Res<'w, BlockRegistry>,
+    pub item_registry: Res<'w, ItemRegistry>,
+    pub dirty: ResMut<'w, DirtyChunks>,
+    pub scheduler: ResMut<'w, BlockTickScheduler>,
+    pub mesh_cache: ResMut<'w, BlockMeshCache>,
+    pub meshes: ResMut<'w, Assets<Mesh>>,
+    pub layer_map: Option<Res<'w, AtlasUVMap>>,
+    pub material_handle: Option<Res<'w, VoxelMaterialHandle>>,
+}
+
+/// Carry out requested explosions: destroy blocks, queue each touched chunk
+/// for one remesh, spawn drops (each destroyed block drops with a chance of
+/// `1 / power`), knock back the player, dropped items and falling blocks,
+/// and hurt the player.
+///
+/// # Arguments
+/// * `ctx` - An `ExplosionCtx` with the world, registries and render handles.
+/// * `events` - requested explosions
+/// * `damage` - damage event writer for hurting the player
+/// * `player` - the player, knocked back when close
+/// * `drops` - dropped items, knocked back when close
+/// * `falling` - falling blocks, knocked up or down when close
+#[allow(clippy::type_complexity)]
+pub fn handle_explosions(
+    mut ctx: ExplosionCtx,
+    mut events: EventReader<ExplosionEvent>,
+    mut damage: EventWriter<DamageEvent>,
+    mut player: Query<(&Transform, &mut Player)>,
+    mut drops: Query<(&Transform, &mut DroppedItem), Without<Player>>,
+    mut falling: Query<(&Transform, &mut FallingBlock), (Without<Player>, Without<DroppedItem>)>,
+) {
+    for event in events.read() {
+        if event.power <= 0.0 {
+            continue;
+        }
+        let explosion = ctx.world.explode(event.center, event.power, &ctx.block_registry);
+        ctx.dirty.0.extend(explosion.chunks.iter().copied());
+
+        if let (Some(layer_map), Some(material)) = (ctx.layer_map.as_ref(), ctx.material_handle.as_ref()) {
+            for (pos, id) in &explosion.destroyed {
+                let Some(def) = ctx.block_registry.get_by_id(*id) else { continue };
+                if def.drop_count == 0 || ctx.item_registry.get(&def.drop_item).is_none() {
+                    continue;
+                }
+                if ctx.scheduler.random_f32() * event.power >= 1.0 {
+                    continue;
+                }
+                let mesh = ctx.mesh_cache.get_or_build(*id, &ctx.block_registry, layer_map, &mut ctx.meshes);
+                spawn_item_drop(
+                    &mut ctx.commands,
+                    pos.as_vec3() + Vec3::splat(0.5),
+                    ItemStack { item: def.drop_item.clone(), count: def.drop_count },
+                    mesh,
+                    material,
+                );
+            }
+        }
+
+        for (tf, mut player) in &mut player {
+            // Body centre: the player transform sits at eye height
+            if let Some(push) = knockback(event.center, event.power, tf.translation - Vec3::Y * 0.85) {
+                player.velocity += push;
+                player.on_ground = false;
+                let amount = event.power * DAMAGE_PER_POWER * push.length() / KNOCKBACK_SPEED;
+                damage.send(DamageEvent { amount, source: DamageSource::Explosion });
+            }
+        }
+        for (tf, mut drop) in &mut drops {
+            if let Some(push) = knockback(event.center, event.power, tf.translation) {
+                drop.velocity += push;
+            }
+        }
+        for (tf, mut block) in &mut falling {
+            if let Some(push) = knockback(event.center, event.power, tf.translation + Vec3::splat(0.5)) {
+                block.velocity += push.y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::chunk::Chunk;
+
+    fn registry() -> BlockRegistry {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: 1, name: "stone".to_string(), blast_resistance: 1.0, ..Block::default() });
+        registry.register(Block { id: 2, name: "bedrock".to_string(), breakable: false, blast_resistance: 1000.0, ..Block::default() });
+        registry
+    }
+
+    #[test]
+    fn explosion_stops_at_unbreakable_blocks_and_remeshes_each_chunk_once() {
+        let registry = registry();
+        let mut world = World::new();
+        for cx in -1..=0 {
+            let mut chunk = Chunk::new();
+            for x in 0..32 {
+                for z in 0..32 {
+                    for y in 0..16 {
+                        chunk.set(x, y, z, 1);
+                    }
+                    chunk.set(x, 8, z, 2);
+                }
+            }
+            world.chunks.insert((cx, 0), chunk);
+        }
+
+        let explosion = world.explode(Vec3::new(0.5, 12.5, 16.5), 4.0, &registry);
+
+        assert!(!explosion.destroyed.is_empty());
+        assert_eq!(world.get_block(0, 12, 16), blocks::AIR);
+        assert!((0..32).all(|x| world.get_block(x, 8, 16) == 2 && world.get_block(x, 7, 16) == 1));
+        assert_eq!(world.take_block_updates().len(), explosion.destroyed.len());
+        let mut chunks: Vec<_> = explosion.chunks.into_iter().collect();
+        chunks.sort_unstable();
+        assert_eq!(chunks, vec![(-1, 0), (0, 0)]);
+    }
+}
@@ -0,0 +1,180 @@
+//! Headless terrain generation.
+//!
+//! Generates a rectangle of chunks for a seed without any renderer and
+//! draws top-down maps of the result. Used by the `stratum-gen` tool to
+//! compare generator changes from the command line.
+
+use crate::biome::BiomeRegistry;
+use crate::block::{blocks, BlockRegistry};
+use crate::chunk::{Chunk, CHUNK_SIZE};
+use crate::world::MAX_HEIGHT;
+use image::{Rgb, RgbImage};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const CHUNK_SIZE_I32: i32 = CHUNK_SIZE as i32;
+
+/// A rectangle of chunks, both corners inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkArea {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+impl ChunkArea {
+    /// The square of chunks within `radius` of `center`.
+    #[must_use]
+    pub fn around(center: (i32, i32), radius: i32) -> Self {
+        Self { min: (center.0 - radius, center.1 - radius), max: (center.0 + radius, center.1 + radius) }
+    }
+
+    /// Every chunk coordinate in the area, row by row.
+    pub fn chunks(self) -> impl Iterator<Item = (i32, i32)> {
+        (self.min.1..=self.max.1).flat_map(move |z| (self.min.0..=self.max.0).map(move |x| (x, z)))
+    }
+
+    /// Size of the area in blocks (x, z).
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn block_size(self) -> (u32, u32) {
+        let width = (self.max.0 - self.min.0 + 1) * CHUNK_SIZE_I32;
+        let depth = (self.max.1 - self.min.1 + 1) * CHUNK_SIZE_I32;
+        (width.max(0) as u32, depth.max(0) as u32)
+    }
+
+    /// World block coordinates of image pixel `(px, pz)`.
+    #[allow(clippy::cast_possible_wrap)]
+    fn world_pos(self, px: u32, pz: u32) -> (i32, i32) {
+        (self.min.0 * CHUNK_SIZE_I32 + px as i32, self.min.1 * CHUNK_SIZE_I32 + pz as i32)
+    }
+}
+
+/// Generate every chunk of `area` with `seed`, in parallel.
+#[must_use]
+pub fn generate_area(area: ChunkArea, seed: u32, registry: &BlockRegistry) -> HashMap<(i32, i32), Chunk> {
+    let coords: Vec<(i32, i32)> = area.chunks().collect();
+    coords
+        .into_par_iter()
+        .map(|(cx, cz)| {
+            let mut chunk = Chunk::new();
+            chunk.generate_with_seed(cx, cz, seed, registry);
+            ((cx, cz), chunk)
+        })
+        .collect()
+}
+
+/// Height of the highest non-air block of the column at world `(x, z)`, or
+/// `None` for an empty or missing column.
+#[must_use]
+pub fn surface_height(chunks: &HashMap<(i32, i32), Chunk>, x: i32, z: i32) -> Option<usize> {
+    let chunk = chunks.get(&(x.div_euclid(CHUNK_SIZE_I32), z.div_euclid(CHUNK_SIZE_I32)))?;
+    let lx = usize::try_from(x.rem_euclid(CHUNK_SIZE_I32)).ok()?;
+    let lz = usize::try_from(z.rem_euclid(CHUNK_SIZE_I32)).ok()?;
+    (0..MAX_HEIGHT).rev().find(|&y| chunk.get(lx, y, lz) != blocks::AIR)
+}
+
+/// Greyscale top-down height map of `area`: black is the lowest surface in
+/// the area and white the highest. Water columns are tinted blue.
+///
+/// # Return
+/// * `(RgbImage, (usize, usize))` - the map and the lowest/highest surface
+///   heights it was scaled to
+#[must_use]
+pub fn height_map(chunks: &HashMap<(i32, i32), Chunk>, area: ChunkArea, registry: &BlockRegistry) -> (RgbImage, (usize, usize)) {
+    let (width, depth) = area.block_size();
+    let water = registry.id_for_name("water");
+    let columns: Vec<Option<(usize, bool)>> = (0..depth)
+        .flat_map(|pz| (0..width).map(move |px| (px, pz)))
+        .map(|(px, pz)| {
+            let (x, z) = area.world_pos(px, pz);
+            let y = surface_height(chunks, x, z)?;
+            let chunk = &chunks[&(x.div_euclid(CHUNK_SIZE_I32), z.div_euclid(CHUNK_SIZE_I32))];
+            let lx = usize::try_from(x.rem_euclid(CHUNK_SIZE_I32)).ok()?;
+            let lz = usize::try_from(z.rem_euclid(CHUNK_SIZE_I32)).ok()?;
+            Some((y, water.is_some_and(|w| chunk.get(lx, y, lz) == w)))
+        })
+        .collect();
+
+    let heights = columns.iter().flatten().map(|(y, _)| *y);
+    let low = heights.clone().min().unwrap_or(0);
+    let high = heights.max().unwrap_or(0);
+    let range = (high - low).max(1);
+
+    let mut image = RgbImage::new(width, depth);
+    for (pixel, column) in image.pixels_mut().zip(&columns) {
+        *pixel = match column {
+            None => Rgb([0, 0, 0]),
+            Some((y, is_water)) => {
+                let v = u8::try_from((y - low) * 255 / range).unwrap_or(u8::MAX);
+                if *is_water { Rgb([v / 3, v / 2, 160 + v / 3]) } else { Rgb([v, v, v]) }
+            }
+        };
+    }
+    (image, (low, high))
+}
+
+/// Colour of a biome on the biome map, from its climate: warm biomes are
+/// red, cold ones blue, and humidity adds green.
+#[must_use]
+pub fn biome_colour(temperature: f32, humidity: f32) -> Rgb<u8> {
+    let warmth = ((temperature + 20.0) / 60.0).clamp(0.0, 1.0);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgb([channel(warmth), channel(humidity), channel(1.0 - warmth)])
+}
+
+/// Top-down biome map of `area`, one pixel per block, coloured with
+/// `biome_colour`. Unknown biomes are black. Biomes come from the
+/// registry's climate fields, so seed them to match the terrain.
+///
+/// # Return
+/// * `(RgbImage, Vec<String>)` - the map and the names of the biomes on it
+#[must_use]
+pub fn biome_map(biomes: &BiomeRegistry, area: ChunkArea) -> (RgbImage, Vec<String>) {
+    let (width, depth) = area.block_size();
+    let mut image = RgbImage::new(width, depth);
+    let mut names = Vec::new();
+    for (px, pz, pixel) in image.enumerate_pixels_mut() {
+        let (x, z) = area.world_pos(px, pz);
+        let biome = biomes.biome_at_block(x, z);
+        *pixel = biome.map_or(Rgb([0, 0, 0]), |b| biome_colour(b.temperature, b.humidity));
+        if let Some(b) = biome
+            && !names.contains(&b.name)
+        {
+            names.push(b.name.clone());
+        }
+    }
+    names.sort();
+    (image, names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::world::save::{decode_region, encode_region};
+
+    #[test]
+    fn generation_is_seeded_and_survives_a_region_round_trip() {
+        let mut registry = BlockRegistry::default();
+        registry.register(Block { id: 1, name: "grass".to_string(), ..Block::default() });
+        registry.register(Block { id: 2, name: "dirt".to_string(), ..Block::default() });
+        let area = ChunkArea::around((0, 0), 0);
+
+        let a = generate_area(area, 7, &registry);
+        let b = generate_area(area, 7, &registry);
+        let c = generate_area(area, 8, &registry);
+        assert_eq!(a[&(0, 0)].blocks, b[&(0, 0)].blocks);
+        assert_ne!(a[&(0, 0)].blocks, c[&(0, 0)].blocks);
+
+        let (map, (low, high)) = height_map(&a, area, &registry);
+        assert_eq!(map.dimensions(), (32, 32));
+        assert!(low <= high);
+
+        let chunks: Vec<_> = a.iter().collect();
+        let decoded = decode_region(&encode_region(&chunks)).expect("valid region");
+        assert_eq!(decoded[&(0, 0)].blocks, a[&(0, 0)].blocks);
+        assert!(decode_region(b"not a region").is_err());
+    }
+}
@@ -5,6 +5,11 @@
 //! querying and setting blocks in world coordinates and will generate a
 //! deterministic chunk when a write occurs to an unloaded chunk.
 //!
+//! Every successful `set_block` is recorded as a block update.
+//! `dispatch_block_updates` turns them into `BlockChanged` events once per
+//! frame so any number of systems (falling blocks, block ticks, ...) can
+//! react to changes.
+//!
 //! # Example:
 //!
 //! ```
@@ -16,8 +21,33 @@
 
 use crate::block::{blocks, BlockId};
 use crate::chunk::{Chunk, CHUNK_SIZE};
+use crate::settings::{GameplaySettings, Settings};
 use bevy::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Game ticks and block tick scheduling.
+pub mod tick;
+
+/// World time: day count, time of day and day phase events.
+pub mod time;
+
+/// Weather per region, sampled from the biomes, and the precipitation heightmap.
+pub mod weather;
+
+/// Explosions (ray cast blast, batched block removal, knockback).
+pub mod explosion;
+
+/// World save data (game mode, world time, ...).
+pub mod save;
+
+/// Region editing: selection, fill/replace/hollow, clipboard and undo.
+pub mod edit;
+
+/// Structure templates (saved builds).
+pub mod structure;
+
+/// Headless generation of chunk areas and top-down maps (`stratum-gen`).
+pub mod generate;
 
 /// Maximum world build height (exclusive upper bound).
 pub const MAX_HEIGHT: usize = 256;
@@ -25,10 +55,14 @@ pub const MAX_HEIGHT: usize = 256;
 /// The `World` resource holds loaded chunks keyed by `(chunk_x, chunk_z)`.
 ///
 /// # Fields
-/// * `chunks` - mapping from chunk coordinates to `Chunk` data
+/// * `chunks` - mapping from chunk coordinates to `Chunk` data (block ids and block states)
+/// * `block_updates` - world positions written by `set_block` since the last
+///   `take_block_updates`; only reachable through `push_block_update` and
+///   `take_block_updates` so every writer goes through the same queue
 #[derive(Resource)]
 pub struct World {
     pub chunks: HashMap<(i32, i32), Chunk>,
+    block_updates: Vec<IVec3>,
 }
 
 impl World {
@@ -40,9 +74,30 @@ impl World {
     pub fn new() -> Self {
         World {
             chunks: HashMap::new(),
+            block_updates: Vec::new(),
         }
     }
 
+    /// Drain the block positions changed through `set_block`.
+    ///
+    /// # Return
+    /// * `Vec<IVec3>` - changed positions in the order they were written
+    pub fn take_block_updates(&mut self) -> Vec<IVec3> {
+        std::mem::take(&mut self.block_updates)
+    }
+
+    /// Record a block update at `pos` without writing a block, e.g. when
+    /// only a block's neighbours should react.
+    pub fn push_block_update(&mut self, pos: IVec3) {
+        self.block_updates.push(pos);
+    }
+
+    /// Whether any block update is waiting for `take_block_updates`.
+    #[must_use]
+    pub fn has_block_updates(&self) -> bool {
+        !self.block_updates.is_empty()
+    }
+
     /// Get the block ID at world coordinates (x, y, z).
     ///
     /// # Arguments
@@ -59,24 +114,33 @@ impl World {
     /// constants, but documented for completeness).
     #[must_use]
     pub fn get_block(&self, x: i32, y: i32, z: i32) -> BlockId {
-        let max_h = i32::try_from(MAX_HEIGHT).expect("MAX_HEIGHT fits in i32");
-        if y < 0 || y >= max_h {
-            return blocks::AIR;
-        }
-
-        let chunk_size_i32 = i32::try_from(CHUNK_SIZE).expect("CHUNK_SIZE fits in i32");
-        let cx = x.div_euclid(chunk_size_i32);
-        let cz = z.div_euclid(chunk_size_i32);
-        let lx = usize::try_from(x.rem_euclid(chunk_size_i32)).expect("local x non-negative");
-        let ly = usize::try_from(y).expect("local y non-negative");
-        let lz = usize::try_from(z.rem_euclid(chunk_size_i32)).expect("local z non-negative");
+        let Some(((cx, cz), (lx, ly, lz))) = to_chunk_local(x, y, z) else { return blocks::AIR };
 
         self.chunks
             .get(&(cx, cz))
             .map_or(blocks::AIR, |c| c.get(lx, ly, lz))
     }
 
+    /// Get the block state at world coordinates (x, y, z).
+    ///
+    /// # Arguments
+    /// * `x`, `y`, `z` - world coordinates for the requested block
+    ///
+    /// # Return
+    /// * `u8` - block state (e.g. a fluid's flow level), `0` if none is stored or out of bounds
+    ///
+    /// # Panics
+    ///
+    /// Same constant conversions as `get_block`.
+    #[must_use]
+    pub fn get_block_state(&self, x: i32, y: i32, z: i32) -> u8 {
+        let Some(((cx, cz), (lx, ly, lz))) = to_chunk_local(x, y, z) else { return 0 };
+
+        self.chunks.get(&(cx, cz)).map_or(0, |c| c.get_state(lx, ly, lz))
+    }
+
     /// Set a block at world coordinates, generating the chunk if necessary.
+    /// Any block state stored at the position is cleared.
     ///
     /// # Arguments
     /// * `x`, `y`, `z` - world coordinates where the block will be placed
@@ -86,24 +150,38 @@ impl World {
     /// # Return
     /// * `Option<(i32, i32)>` - `(chunk_x, chunk_z)` of the chunk modified, or
     ///   `None` if the coordinates were out-of-bounds (e.g., y outside valid range)
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockId, block_registry: &crate::block::BlockRegistry) -> Option<(i32, i32)> {
+        self.set_block_with_state(x, y, z, block, 0, block_registry)
+    }
+
+    /// Set a block and its state at world coordinates, generating the chunk
+    /// if necessary.
+    ///
+    /// # Arguments
+    /// * `x`, `y`, `z` - world coordinates where the block will be placed
+    /// * `block` - the `BlockId` to place
+    /// * `state` - the block state to store (`0` = default)
+    /// * `block_registry` - used when generating the chunk deterministically
+    ///
+    /// # Return
+    /// * `Option<(i32, i32)>` - `(chunk_x, chunk_z)` of the chunk modified, or
+    ///   `None` if the coordinates were out-of-bounds (e.g., y outside valid range)
     ///
     /// # Panics
     ///
     /// Uses `i32::try_from` / `usize::try_from` for constant and index
     /// conversions and will panic if those conversions fail (not expected
     /// for configured constants).
-    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockId, block_registry: &crate::block::BlockRegistry) -> Option<(i32, i32)> {
-        let max_h = i32::try_from(MAX_HEIGHT).expect("MAX_HEIGHT fits in i32");
-        if y < 0 || y >= max_h {
-            return None;
-        }
-
-        let chunk_size_i32 = i32::try_from(CHUNK_SIZE).expect("CHUNK_SIZE fits in i32");
-        let cx = x.div_euclid(chunk_size_i32);
-        let cz = z.div_euclid(chunk_size_i32);
-        let lx = usize::try_from(x.rem_euclid(chunk_size_i32)).expect("local x non-negative");
-        let ly = usize::try_from(y).expect("local y non-negative");
-        let lz = usize::try_from(z.rem_euclid(chunk_size_i32)).expect("local z non-negative");
+    pub fn set_block_with_state(
+        &mut self,
+        x: i32,
+        y: i32,
+        z: i32,
+        block: BlockId,
+        state: u8,
+        block_registry: &crate::block::BlockRegistry,
+    ) -> Option<(i32, i32)> {
+        let ((cx, cz), (lx, ly, lz)) = to_chunk_local(x, y, z)?;
 
         // If chunk not present, generate it deterministically and insert so changes succeed
         self.chunks.entry((cx, cz)).or_insert_with(|| {
@@ -111,11 +189,168 @@ impl World {
             c.generate(cx, cz, block_registry);
             c
         });
-        self.chunks.get_mut(&(cx, cz)).map(|c| {
+        let changed = self.chunks.get_mut(&(cx, cz)).map(|c| {
             c.set(lx, ly, lz, block);
+            c.set_state(lx, ly, lz, state);
             (cx, cz)
-        })
+        });
+        if changed.is_some() {
+            self.push_block_update(IVec3::new(x, y, z));
+        }
+        changed
+    }
+
+    /// Write many blocks in one batch. Edits in unloaded chunks are skipped
+    /// (nothing is generated). Every written block is still recorded as a
+    /// block update.
+    ///
+    /// # Arguments
+    /// * `edits` - `(position, block)` pairs to write
+    /// * `block_registry` - block registry
+    ///
+    /// # Return
+    /// * `HashSet<(i32, i32)>` - every chunk whose mesh must be rebuilt (see
+    ///   `block::affected_chunks`), each listed once however many of its
+    ///   blocks changed
+    pub fn set_blocks(
+        &mut self,
+        edits: impl IntoIterator<Item = (IVec3, BlockId)>,
+        block_registry: &crate::block::BlockRegistry,
+    ) -> HashSet<(i32, i32)> {
+        self.set_blocks_with_state(edits.into_iter().map(|(pos, block)| (pos, block, 0)), block_registry)
+    }
+
+    /// Like `set_blocks`, but also writes each block's state.
+    ///
+    /// # Arguments
+    /// * `edits` - `(position, block, state)` triples to write
+    /// * `block_registry` - block registry
+    ///
+    /// # Return
+    /// * `HashSet<(i32, i32)>` - every chunk whose mesh must be rebuilt
+    pub fn set_blocks_with_state(
+        &mut self,
+        edits: impl IntoIterator<Item = (IVec3, BlockId, u8)>,
+        block_registry: &crate::block::BlockRegistry,
+    ) -> HashSet<(i32, i32)> {
+        let mut chunks = HashSet::new();
+        for (pos, block, state) in edits {
+            if !self.is_loaded(pos.x, pos.z) {
+                continue;
+            }
+            if let Some((cx, cz)) = self.set_block_with_state(pos.x, pos.y, pos.z, block, state, block_registry) {
+                chunks.extend(crate::block::affected_chunks(cx, cz, pos));
+            }
+        }
+        chunks
+    }
+
+    /// Change only the block state at world coordinates. Unlike `set_block`
+    /// this does not record a block update and does not generate missing
+    /// chunks; use it for state that neither changes the mesh nor concerns
+    /// neighbouring blocks (e.g. a fire's age).
+    ///
+    /// # Return
+    /// * `bool` - `true` if the chunk was loaded and the state was written
+    ///
+    /// # Panics
+    ///
+    /// Same constant conversions as `get_block`.
+    pub fn set_block_state(&mut self, x: i32, y: i32, z: i32, state: u8) -> bool {
+        let Some(((cx, cz), (lx, ly, lz))) = to_chunk_local(x, y, z) else { return false };
+
+        self.chunks.get_mut(&(cx, cz)).map(|c| c.set_state(lx, ly, lz, state)).is_some()
+    }
+
+    /// Whether the chunk column containing world position (x, z) is loaded.
+    ///
+    /// # Panics
+    ///
+    /// Same constant conversions as `get_block`.
+    #[must_use]
+    pub fn is_loaded(&self, x: i32, z: i32) -> bool {
+        let chunk_size_i32 = i32::try_from(CHUNK_SIZE).expect("CHUNK_SIZE fits in i32");
+        self.chunks.contains_key(&(x.div_euclid(chunk_size_i32), z.div_euclid(chunk_size_i32)))
+    }
+}
+
+/// Block position inside a chunk, `(x, y, z)`.
+type LocalPos = (usize, usize, usize);
+
+/// Split world coordinates into a chunk key and the position inside it.
+///
+/// # Return
+/// * `Option<((i32, i32), LocalPos)>` - `(chunk_x, chunk_z)` and
+///   the chunk-local `(x, y, z)`, or `None` if `y` is outside `0..MAX_HEIGHT`
+///
+/// # Panics
+///
+/// Uses `i32::try_from` / `usize::try_from` for constant and index
+/// conversions and will panic if those conversions fail (not expected for
+/// configured constants).
+fn to_chunk_local(x: i32, y: i32, z: i32) -> Option<((i32, i32), LocalPos)> {
+    let max_h = i32::try_from(MAX_HEIGHT).expect("MAX_HEIGHT fits in i32");
+    if y < 0 || y >= max_h {
+        return None;
+    }
+
+    let chunk_size_i32 = i32::try_from(CHUNK_SIZE).expect("CHUNK_SIZE fits in i32");
+    let cx = x.div_euclid(chunk_size_i32);
+    let cz = z.div_euclid(chunk_size_i32);
+    let lx = usize::try_from(x.rem_euclid(chunk_size_i32)).expect("local x non-negative");
+    let ly = usize::try_from(y).expect("local y non-negative");
+    let lz = usize::try_from(z.rem_euclid(chunk_size_i32)).expect("local z non-negative");
+    Some(((cx, cz), (lx, ly, lz)))
+}
+
+/// Gameplay rules the world simulation reads, copied from
+/// `Settings.gameplay` by `sync_world_rules`.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorldRules {
+    pub fire_spread: bool, // Fire spreads to and burns away flammable blocks
+}
+
+impl WorldRules {
+    /// Rules taken from the gameplay settings.
+    #[must_use]
+    pub fn from_settings(gameplay: &GameplaySettings) -> Self {
+        Self { fire_spread: gameplay.fire_spread }
+    }
+}
+
+impl Default for WorldRules {
+    fn default() -> Self {
+        Self::from_settings(&GameplaySettings::default())
+    }
+}
+
+/// Copy `Settings.gameplay` into `WorldRules` whenever the settings change
+/// (e.g. the settings file is edited at runtime).
+pub fn sync_world_rules(settings: Res<Settings>, mut rules: ResMut<WorldRules>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let desired = WorldRules::from_settings(&settings.gameplay);
+    if *rules != desired {
+        *rules = desired;
+    }
+}
+
+/// Event sent for every block position written through `World::set_block`.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockChanged(pub IVec3);
+
+/// Send a `BlockChanged` event for every block update recorded by `World::set_block`.
+///
+/// # Arguments
+/// * `world` - world whose pending block updates are drained
+/// * `events` - writer for `BlockChanged` events
+pub fn dispatch_block_updates(mut world: ResMut<World>, mut events: EventWriter<BlockChanged>) {
+    if world.block_updates.is_empty() {
+        return;
     }
+    events.send_batch(world.take_block_updates().into_iter().map(BlockChanged));
 }
 
 impl Default for World {
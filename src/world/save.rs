@@ -0,0 +1,270 @@
+//! World save data.
+//!
+//! Terrain is regenerated from noise, so a save only holds the world state
+//! that cannot be regenerated. `WorldMeta` is stored as RON in
+//! `<save dir>/world.ron` and written whenever the game mode changes, the
+//! world time is changed or has run on by `TIME_SAVE_STEP`, and on exit.
+//!
+//! Chunks can be stored in region files (`<save dir>/region/r.<x>.<z>.region`,
+//! `REGION_CHUNKS` x `REGION_CHUNKS` chunks each). `stratum-gen` writes them
+//! for a generated area. A region file is little-endian binary:
+//!
+//! * the magic `REGION_MAGIC` and a `u32` chunk count;
+//! * per chunk: `i32` chunk x and z, the blocks run-length encoded as a `u32`
+//!   run count followed by `(u16 length, u8 block)` runs, and a `u32` state
+//!   count followed by `(u32 index, u8 state)` pairs.
+
+use crate::chunk::{Chunk, CHUNK_SIZE};
+use crate::player::GameMode;
+use crate::world::time::WorldTime;
+use crate::world::MAX_HEIGHT;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Directory of the world the game loads and saves.
+pub const SAVE_DIR: &str = "saves/world";
+
+/// File name of the world metadata inside a save directory.
+pub const WORLD_META_FILE: &str = "world.ron";
+
+/// Fraction of a day the world time runs on before it is saved again.
+pub const TIME_SAVE_STEP: f32 = 1.0 / 48.0;
+
+/// Per-world state saved alongside the world.
+///
+/// # Fields
+/// * `game_mode` - the game mode the world was last played in
+/// * `time` - the world clock
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorldMeta {
+    #[serde(default)]
+    pub game_mode: GameMode,
+    #[serde(default)]
+    pub time: WorldTime,
+}
+
+impl WorldMeta {
+    /// Load the metadata of the world saved in `dir`. A missing or
+    /// unreadable file gives the defaults (a new world).
+    ///
+    /// # Arguments
+    /// * `dir` - save directory (e.g. `SAVE_DIR`)
+    #[must_use]
+    pub fn load(dir: &str) -> Self {
+        let path = Path::new(dir).join(WORLD_META_FILE);
+        let Ok(text) = std::fs::read_to_string(&path) else { return Self::default() };
+        ron::from_str(&text).unwrap_or_else(|e| {
+            eprintln!("warning: could not parse {}: {e}", path.display());
+            Self::default()
+        })
+    }
+
+    /// Write the metadata into `dir`, creating the directory if needed.
+    ///
+    /// # Arguments
+    /// * `dir` - save directory (e.g. `SAVE_DIR`)
+    ///
+    /// # Errors
+    /// Returns a message if the directory cannot be created or the file written.
+    pub fn save(&self, dir: &str) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {dir}: {e}"))?;
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+        let path = Path::new(dir).join(WORLD_META_FILE);
+        std::fs::write(&path, text).map_err(|e| format!("cannot write {}: {e}", path.display()))
+    }
+}
+
+/// Whether `time` differs enough from the saved `saved` time to save again:
+/// a different day, scale or frozen state, or `TIME_SAVE_STEP` of the day.
+#[must_use]
+pub fn time_needs_save(saved: &WorldTime, time: &WorldTime) -> bool {
+    saved.day != time.day
+        || saved.scale != time.scale
+        || saved.frozen != time.frozen
+        || (saved.time_of_day - time.time_of_day).abs() >= TIME_SAVE_STEP
+}
+
+/// Copy the current game mode and world time into `WorldMeta` and save it
+/// when either changes (see the module docs). Runs in `Last` so the exit
+/// frame is saved too.
+///
+/// # Arguments
+/// * `mode` - current game mode
+/// * `time` - world clock
+/// * `exit` - app exit events
+/// * `meta` - saved world metadata
+#[allow(clippy::needless_pass_by_value)]
+pub fn save_world_meta(mode: Res<GameMode>, time: Res<WorldTime>, mut exit: EventReader<AppExit>, mut meta: ResMut<WorldMeta>) {
+    let exiting = exit.read().count() > 0;
+    if meta.game_mode == *mode && !time_needs_save(&meta.time, &time) && !exiting {
+        return;
+    }
+    meta.game_mode = *mode;
+    meta.time = *time;
+    if let Err(e) = meta.save(SAVE_DIR) {
+        eprintln!("warning: could not save world: {e}");
+    }
+}
+
+/// Chunks per region file along x and z.
+pub const REGION_CHUNKS: i32 = 16;
+
+/// Magic bytes (format name and version) at the start of a region file.
+pub const REGION_MAGIC: &[u8; 8] = b"STRGREG1";
+
+/// Chunks stored in one region file.
+pub type RegionChunks<'a> = Vec<(&'a (i32, i32), &'a Chunk)>;
+
+/// Path of the region file holding chunk `(chunk_x, chunk_z)` in save `dir`.
+#[must_use]
+pub fn region_path(dir: &str, chunk_x: i32, chunk_z: i32) -> PathBuf {
+    let (rx, rz) = (chunk_x.div_euclid(REGION_CHUNKS), chunk_z.div_euclid(REGION_CHUNKS));
+    Path::new(dir).join("region").join(format!("r.{rx}.{rz}.region"))
+}
+
+/// Encode chunks in the region file format.
+///
+/// # Panics
+/// If a chunk has more than `u32::MAX` runs or states (it cannot).
+#[must_use]
+pub fn encode_region(chunks: &[(&(i32, i32), &Chunk)]) -> Vec<u8> {
+    let count = |n: usize| u32::try_from(n).expect("count fits in u32").to_le_bytes();
+    let mut out = REGION_MAGIC.to_vec();
+    out.extend(count(chunks.len()));
+    for ((cx, cz), chunk) in chunks {
+        out.extend(cx.to_le_bytes());
+        out.extend(cz.to_le_bytes());
+
+        let mut runs: Vec<(u16, u8)> = Vec::new();
+        for &block in &chunk.blocks {
+            match runs.last_mut() {
+                Some((len, id)) if *id == block && *len < u16::MAX => *len += 1,
+                _ => runs.push((1, block)),
+            }
+        }
+        out.extend(count(runs.len()));
+        for (len, id) in runs {
+            out.extend(len.to_le_bytes());
+            out.push(id);
+        }
+
+        let mut states: Vec<(&u32, &u8)> = chunk.states.iter().collect();
+        states.sort_unstable();
+        out.extend(count(states.len()));
+        for (index, state) in states {
+            out.extend(index.to_le_bytes());
+            out.push(*state);
+        }
+    }
+    out
+}
+
+/// Decode a region file.
+///
+/// # Errors
+/// Returns a message if the data is not a valid region file.
+pub fn decode_region(data: &[u8]) -> Result<HashMap<(i32, i32), Chunk>, String> {
+    struct Reader<'a>(&'a [u8]);
+    impl Reader<'_> {
+        fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+            let (head, rest) = self.0.split_at_checked(N).ok_or("region file is truncated")?;
+            self.0 = rest;
+            Ok(head.try_into().expect("split at N"))
+        }
+        fn u32(&mut self) -> Result<u32, String> {
+            self.take().map(u32::from_le_bytes)
+        }
+        fn i32(&mut self) -> Result<i32, String> {
+            self.take().map(i32::from_le_bytes)
+        }
+    }
+
+    let mut reader = Reader(data);
+    if &reader.take::<8>()? != REGION_MAGIC {
+        return Err("not a region file".to_string());
+    }
+    let volume = CHUNK_SIZE * MAX_HEIGHT * CHUNK_SIZE;
+    let mut chunks = HashMap::new();
+    for _ in 0..reader.u32()? {
+        let (cx, cz) = (reader.i32()?, reader.i32()?);
+        let mut chunk = Chunk::new();
+        chunk.blocks.clear();
+        for _ in 0..reader.u32()? {
+            let len = usize::from(u16::from_le_bytes(reader.take()?));
+            let [id] = reader.take()?;
+            if chunk.blocks.len() + len > volume {
+                return Err(format!("chunk ({cx}, {cz}) has more than {volume} blocks"));
+            }
+            chunk.blocks.extend(std::iter::repeat_n(id, len));
+        }
+        if chunk.blocks.len() != volume {
+            return Err(format!("chunk ({cx}, {cz}) has {} blocks instead of {volume}", chunk.blocks.len()));
+        }
+        for _ in 0..reader.u32()? {
+            let index = reader.u32()?;
+            let [state] = reader.take()?;
+            if usize::try_from(index).map_or(true, |i| i >= volume) {
+                return Err(format!("chunk ({cx}, {cz}) has a state outside the chunk"));
+            }
+            chunk.states.insert(index, state);
+        }
+        chunks.insert((cx, cz), chunk);
+    }
+    Ok(chunks)
+}
+
+/// Write chunks into the region files of save `dir`, replacing those files.
+///
+/// # Return
+/// * `Vec<PathBuf>` - the region files written
+///
+/// # Errors
+/// Returns a message if a directory or file cannot be written.
+pub fn write_regions(dir: &str, chunks: &HashMap<(i32, i32), Chunk>) -> Result<Vec<PathBuf>, String> {
+    let mut regions: HashMap<PathBuf, RegionChunks> = HashMap::new();
+    for (coords, chunk) in chunks {
+        regions.entry(region_path(dir, coords.0, coords.1)).or_default().push((coords, chunk));
+    }
+    let mut written = Vec::new();
+    for (path, mut chunks) in regions {
+        chunks.sort_by_key(|(coords, _)| **coords);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("cannot create {}: {e}", parent.display()))?;
+        }
+        std::fs::write(&path, encode_region(&chunks)).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+        written.push(path);
+    }
+    written.sort();
+    Ok(written)
+}
+
+/// Read every chunk stored in a region file.
+///
+/// # Errors
+/// Returns a message if the file cannot be read or is not a valid region file.
+pub fn read_region(path: &Path) -> Result<HashMap<(i32, i32), Chunk>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    decode_region(&data).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_rejects_runs_past_the_chunk_volume() {
+        let mut data = REGION_MAGIC.to_vec();
+        data.extend(1u32.to_le_bytes());
+        data.extend([0i32.to_le_bytes(), 0i32.to_le_bytes()].concat());
+        data.extend(u32::MAX.to_le_bytes());
+        for _ in 0..8 {
+            data.extend(u16::MAX.to_le_bytes());
+            data.push(1);
+        }
+        let Err(err) = decode_region(&data) else { panic!("too many blocks were accepted") };
+        assert!(err.contains("more than"), "{err}");
+    }
+}
@@ -0,0 +1,64 @@
+//! Structure templates.
+//!
+//! A structure template is a block-for-block copy of a build, written as RON
+//! to `data/structures/<name>.ron` by the console `export` command (see
+//! `data/structure_template.ron`). Blocks are stored by name through a
+//! palette so templates survive block id changes, and air is left out.
+//! Templates are only exported for now; nothing loads them back and world
+//! generation does not place them.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Directory structure templates are exported to.
+pub const STRUCTURE_DIR: &str = "data/structures";
+
+/// One non-air block of a structure.
+///
+/// # Fields
+/// * `pos` - position relative to the structure's minimum corner
+/// * `block` - index into the template's `palette`
+/// * `state` - block state (`0` = default)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructureBlock {
+    pub pos: (u32, u32, u32),
+    pub block: u16,
+    #[serde(default)]
+    pub state: u8,
+}
+
+/// A saved structure.
+///
+/// # Fields
+/// * `name` - structure name (also the file name)
+/// * `size` - bounding box size in blocks (x, y, z)
+/// * `palette` - block names used by the structure
+/// * `blocks` - the structure's non-air blocks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructureTemplate {
+    pub name: String,
+    pub size: (u32, u32, u32),
+    pub palette: Vec<String>,
+    pub blocks: Vec<StructureBlock>,
+}
+
+impl StructureTemplate {
+    /// Write the template to `<dir>/<name>.ron`, creating the directory if needed.
+    ///
+    /// # Return
+    /// * `PathBuf` - the file written
+    ///
+    /// # Errors
+    /// Returns a message if the name is not a plain file name or the file
+    /// cannot be written.
+    pub fn save(&self, dir: &str) -> Result<PathBuf, String> {
+        if self.name.is_empty() || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(format!("'{}' is not a valid structure name", self.name));
+        }
+        std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {dir}: {e}"))?;
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+        let path = Path::new(dir).join(format!("{}.ron", self.name));
+        std::fs::write(&path, text).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+}
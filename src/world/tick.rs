@@ -0,0 +1,386 @@
+//! World ticks: the fixed 20 Hz game tick counter, scheduled block ticks and
+//! random block ticks.
+//!
+//! `game_tick_system` and `run_block_ticks` run in `FixedUpdate` with the
+//! rest of the gameplay simulation, so game ticks follow simulated time
+//! rather than the frame rate.
+//!
+//! * **Scheduled ticks** are kept in a priority queue of `(due tick, position)`.
+//!   Blocks with `ticks: true` are scheduled `GAME_TICK_RATE / tick_rate` game
+//!   ticks after they are placed and keep rescheduling themselves while they
+//!   stay in place. Handlers can also schedule ticks explicitly. A position
+//!   has at most one pending tick; scheduling it again before it runs is a
+//!   no-op, so repeated block updates cannot grow the queue.
+//! * **Random ticks** pick `RANDOM_TICKS_PER_SECTION` random positions in every
+//!   loaded 32x32x32 chunk section each game tick; blocks with
+//!   `random_tick: true` at those positions get a random tick.
+//!
+//! Both kinds are dispatched to Rust handlers registered by block name in the
+//! `BlockTickHandlers` resource (see `crate::block::tick_handlers` for the
+//! built-in ones). Block edits made by handlers are remeshed through
+//! `DirtyChunks`.
+
+use super::{BlockChanged, World, WorldRules};
+use crate::block::{BlockId, BlockRegistry, DirtyChunks};
+use crate::chunk::{CHUNK_DIM, CHUNK_LAYERS_Y, CHUNK_SIZE};
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Game ticks per second.
+pub const GAME_TICK_RATE: f32 = 20.0;
+
+/// Length of a day/night cycle in seconds.
+pub const FULL_DAY_SECONDS: f32 = 48.0 * 60.0;
+
+/// Random ticks given to every loaded chunk section per game tick.
+pub const RANDOM_TICKS_PER_SECTION: u32 = 3;
+
+/// Most game ticks run in one `run_block_ticks` call after a jump in the
+/// tick count; the rest are skipped.
+pub const MAX_TICKS_PER_FRAME: u64 = 4;
+
+/// Number of game ticks elapsed since startup.
+#[derive(Resource, Default)]
+pub struct GameTicks {
+    pub count: u64,
+}
+
+/// Timer driving `GameTicks` at `GAME_TICK_RATE`.
+#[derive(Resource)]
+pub struct TickTimer(pub Timer);
+
+impl Default for TickTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(1.0 / GAME_TICK_RATE, TimerMode::Repeating))
+    }
+}
+
+/// Advance `GameTicks` once per elapsed tick interval of simulated time.
+///
+/// # Arguments
+/// * `ticks` - game tick counter
+/// * `timer` - tick timer
+/// * `time` - fixed time resource for delta timing
+#[allow(clippy::needless_pass_by_value)]
+pub fn game_tick_system(mut ticks: ResMut<GameTicks>, mut timer: ResMut<TickTimer>, time: Res<Time>) {
+    let elapsed = timer.0.tick(time.delta()).times_finished_this_tick();
+    if elapsed > 0 {
+        ticks.count = ticks.count.wrapping_add(u64::from(elapsed));
+    }
+}
+
+/// Why a handler is being called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickKind {
+    Scheduled,
+    Random,
+}
+
+/// Everything a tick handler may read or change.
+pub struct TickCtx<'a> {
+    pub world: &'a mut World,
+    pub blocks: &'a BlockRegistry,
+    pub scheduler: &'a mut BlockTickScheduler,
+    pub dirty: &'a mut DirtyChunks,
+    pub rules: &'a WorldRules, // Gameplay rules from the settings (e.g. `fire_spread`)
+    pub tick: u64, // Current game tick
+}
+
+impl TickCtx<'_> {
+    /// Set a block and queue the affected chunks for remeshing.
+    ///
+    /// # Return
+    /// `true` if the block was written.
+    pub fn set_block(&mut self, pos: IVec3, block: BlockId) -> bool {
+        self.set_block_state(pos, block, 0)
+    }
+
+    /// Set a block together with its state and queue the affected chunks
+    /// for remeshing.
+    ///
+    /// # Return
+    /// `true` if the block was written.
+    pub fn set_block_state(&mut self, pos: IVec3, block: BlockId, state: u8) -> bool {
+        let written = self.world.set_block_with_state(pos.x, pos.y, pos.z, block, state, self.blocks).is_some();
+        if written {
+            self.dirty.mark_block(pos);
+        }
+        written
+    }
+
+    /// Block id at `pos`.
+    #[must_use]
+    pub fn get_block(&self, pos: IVec3) -> BlockId {
+        self.world.get_block(pos.x, pos.y, pos.z)
+    }
+
+    /// Change only the block state at `pos`, without a block update or
+    /// remesh (see `World::set_block_state`).
+    pub fn set_state(&mut self, pos: IVec3, state: u8) -> bool {
+        self.world.set_block_state(pos.x, pos.y, pos.z, state)
+    }
+
+    /// Block state at `pos`.
+    #[must_use]
+    pub fn get_state(&self, pos: IVec3) -> u8 {
+        self.world.get_block_state(pos.x, pos.y, pos.z)
+    }
+}
+
+/// A block tick handler. Called with the ticked position and the tick kind.
+pub type TickHandler = fn(&mut TickCtx, IVec3, TickKind);
+
+/// Tick handlers keyed by block name.
+#[derive(Resource, Default, Clone)]
+pub struct BlockTickHandlers {
+    pub handlers: HashMap<String, TickHandler>,
+}
+
+impl BlockTickHandlers {
+    pub fn register(&mut self, block: &str, handler: TickHandler) {
+        self.handlers.insert(block.to_string(), handler);
+    }
+
+    #[must_use]
+    pub fn get(&self, block: &str) -> Option<TickHandler> {
+        self.handlers.get(block).copied()
+    }
+}
+
+/// Priority queue of scheduled block ticks plus the random tick RNG.
+#[derive(Resource)]
+pub struct BlockTickScheduler {
+    queue: BinaryHeap<Reverse<(u64, i32, i32, i32)>>,
+    pending: HashSet<IVec3>, // Positions in `queue`
+    rng: u64,
+    last_tick: u64, // Last game tick processed by `run_block_ticks`
+}
+
+impl Default for BlockTickScheduler {
+    fn default() -> Self {
+        Self::with_seed(0x9E37_79B9_7F4A_7C15)
+    }
+}
+
+impl BlockTickScheduler {
+    /// Create an empty scheduler whose random ticks are seeded with `seed`.
+    #[must_use]
+    pub fn with_seed(seed: u64) -> Self {
+        Self { queue: BinaryHeap::new(), pending: HashSet::new(), rng: seed, last_tick: 0 }
+    }
+
+    /// Schedule a tick for `pos` at game tick `due`, unless `pos` already
+    /// has a pending tick.
+    pub fn schedule(&mut self, pos: IVec3, due: u64) {
+        if self.pending.insert(pos) {
+            self.queue.push(Reverse((due, pos.x, pos.y, pos.z)));
+        }
+    }
+
+    /// Number of pending scheduled ticks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Pop the earliest scheduled tick due at or before `now`.
+    pub fn pop_due(&mut self, now: u64) -> Option<IVec3> {
+        match self.queue.peek() {
+            Some(Reverse((due, ..))) if *due <= now => {
+                let pos = self.queue.pop().map(|Reverse((_, x, y, z))| IVec3::new(x, y, z))?;
+                self.pending.remove(&pos);
+                Some(pos)
+            }
+            _ => None,
+        }
+    }
+
+    /// Next pseudo random number (splitmix64).
+    pub fn next_random(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Pseudo random number in `0..bound`.
+    pub fn random_below(&mut self, bound: u32) -> u32 {
+        #[allow(clippy::cast_possible_truncation)]
+        let r = (self.next_random() >> 32) as u32;
+        r % bound.max(1)
+    }
+
+    /// Pseudo random float in `0.0..1.0`.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn random_f32(&mut self) -> f32 {
+        (self.next_random() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Game ticks between scheduled ticks of a block with the given `tick_rate` (ticks per second).
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn tick_delay(tick_rate: f32) -> u64 {
+    if tick_rate <= 0.0 {
+        return u64::from(GAME_TICK_RATE as u32);
+    }
+    ((GAME_TICK_RATE / tick_rate).round() as u64).max(1)
+}
+
+/// Schedule ticks for changed blocks that have `ticks: true` and a handler.
+///
+/// # Arguments
+/// * `changes` - block changes made through `World::set_block`
+/// * `world` - world used to look up the changed block
+/// * `blocks` - block registry
+/// * `handlers` - registered tick handlers
+/// * `scheduler` - scheduled tick queue
+/// * `ticks` - current game tick
+#[allow(clippy::needless_pass_by_value)]
+pub fn schedule_block_ticks(
+    mut changes: EventReader<BlockChanged>,
+    world: Res<World>,
+    blocks: Res<BlockRegistry>,
+    handlers: Res<BlockTickHandlers>,
+    mut scheduler: ResMut<BlockTickScheduler>,
+    ticks: Res<GameTicks>,
+) {
+    for BlockChanged(pos) in changes.read() {
+        let Some(block) = blocks.get_by_id(world.get_block(pos.x, pos.y, pos.z)) else { continue };
+        if block.ticks && handlers.get(&block.name).is_some() {
+            scheduler.schedule(*pos, ticks.count + tick_delay(block.tick_rate));
+        }
+    }
+}
+
+/// System parameters used by `run_block_ticks`.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct RunTicksCtx<'w> {
+    pub world: ResMut<'w, World>,
+    pub blocks: Res<'w, BlockRegistry>,
+    pub handlers: Res<'w, BlockTickHandlers>,
+    pub scheduler: ResMut<'w, BlockTickScheduler>,
+    pub dirty: ResMut<'w, DirtyChunks>,
+    pub rules: Res<'w, WorldRules>,
+    pub ticks: Res<'w, GameTicks>,
+}
+
+/// Run scheduled and random block ticks for every game tick elapsed since
+/// the last run (at most `MAX_TICKS_PER_FRAME`). Runs each simulation step
+/// after `game_tick_system`.
+///
+/// # Arguments
+/// * `ctx` - A `RunTicksCtx` with the world, registries and scheduler.
+pub fn run_block_ticks(mut ctx: RunTicksCtx) {
+    let now = ctx.ticks.count;
+    let first = ctx.scheduler.last_tick.max(now.saturating_sub(MAX_TICKS_PER_FRAME)) + 1;
+    if first > now {
+        return;
+    }
+    ctx.scheduler.last_tick = now;
+
+    let RunTicksCtx { world, blocks, handlers, scheduler, dirty, rules, .. } = &mut ctx;
+    let mut tick_ctx = TickCtx {
+        world,
+        blocks,
+        scheduler,
+        dirty,
+        rules,
+        tick: first,
+    };
+
+    for tick in first..=now {
+        tick_ctx.tick = tick;
+        run_scheduled_ticks(&mut tick_ctx, handlers);
+        run_random_ticks(&mut tick_ctx, handlers);
+    }
+}
+
+/// Call handlers for every scheduled tick due at `ctx.tick`, rescheduling
+/// ticking blocks that are still in place.
+fn run_scheduled_ticks(ctx: &mut TickCtx, handlers: &BlockTickHandlers) {
+    while let Some(pos) = ctx.scheduler.pop_due(ctx.tick) {
+        let id = ctx.get_block(pos);
+        let Some(block) = ctx.blocks.get_by_id(id) else { continue };
+        let Some(handler) = handlers.get(&block.name) else { continue };
+        let (ticks, delay) = (block.ticks, tick_delay(block.tick_rate));
+
+        handler(ctx, pos, TickKind::Scheduled);
+
+        if ticks && ctx.get_block(pos) == id {
+            let due = ctx.tick + delay;
+            ctx.scheduler.schedule(pos, due);
+        }
+    }
+}
+
+/// Give `RANDOM_TICKS_PER_SECTION` random ticks to every loaded chunk section.
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+fn run_random_ticks(ctx: &mut TickCtx, handlers: &BlockTickHandlers) {
+    // Resolve handlers once per tick instead of per sampled block
+    let random: HashMap<BlockId, TickHandler> = ctx
+        .blocks
+        .blocks
+        .values()
+        .filter(|b| b.random_tick)
+        .filter_map(|b| handlers.get(&b.name).map(|h| (b.id, h)))
+        .collect();
+    if random.is_empty() {
+        return;
+    }
+
+    let mut chunks: Vec<(i32, i32)> = ctx.world.chunks.keys().copied().collect();
+    chunks.sort_unstable(); // Deterministic order for a given seed
+
+    let size = CHUNK_SIZE as i32;
+    let dim = CHUNK_DIM as u32;
+    for (cx, cz) in chunks {
+        for section in 0..CHUNK_LAYERS_Y as i32 {
+            for _ in 0..RANDOM_TICKS_PER_SECTION {
+                let pos = IVec3::new(
+                    cx * size + ctx.scheduler.random_below(dim) as i32,
+                    section * CHUNK_DIM as i32 + ctx.scheduler.random_below(dim) as i32,
+                    cz * size + ctx.scheduler.random_below(dim) as i32,
+                );
+                if let Some(handler) = random.get(&ctx.get_block(pos)) {
+                    handler(ctx, pos, TickKind::Random);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduled_ticks_pop_in_due_order() {
+        let mut scheduler = BlockTickScheduler::default();
+        scheduler.schedule(IVec3::new(3, 0, 0), 30);
+        scheduler.schedule(IVec3::new(1, 0, 0), 10);
+        scheduler.schedule(IVec3::new(2, 0, 0), 20);
+        scheduler.schedule(IVec3::new(2, 0, 0), 15);
+        assert_eq!(scheduler.len(), 3, "a pending position is not queued twice");
+
+        assert_eq!(scheduler.pop_due(5), None);
+        assert_eq!(scheduler.pop_due(25), Some(IVec3::new(1, 0, 0)));
+        assert_eq!(scheduler.pop_due(25), Some(IVec3::new(2, 0, 0)));
+        assert_eq!(scheduler.pop_due(25), None);
+        assert_eq!(scheduler.len(), 1);
+    }
+
+    #[test]
+    fn tick_delay_follows_tick_rate() {
+        assert_eq!(tick_delay(20.0), 1);
+        assert_eq!(tick_delay(4.0), 5);
+        assert_eq!(tick_delay(0.0), 20);
+    }
+}
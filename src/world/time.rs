@@ -0,0 +1,234 @@
+//! World time: the day count and the time of day.
+//!
+//! `WorldTime` drives the day/night cycle (see `app::lighting`). A day lasts
+//! `FULL_DAY_SECONDS` of simulated time at scale 1 and is split into four
+//! quarters starting at dawn (sunrise), noon, dusk (sunset) and midnight.
+//! `advance_world_time` moves the clock on each simulation step unless it is
+//! frozen and sends a `DayPhaseEvent` whenever one of those moments passes,
+//! so systems such as mob spawning or lighting can react to nightfall
+//! without polling the clock. Setting the time (`WorldTime::set_time_of_day`)
+//! reports the phases it skips over the same way.
+//!
+//! The time is saved with the world in `WorldMeta` (see `world::save`) and
+//! can be changed with the console `time` command.
+
+use super::tick::{FULL_DAY_SECONDS, GAME_TICK_RATE};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Game ticks in one day at scale 1.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub const TICKS_PER_DAY: u64 = (FULL_DAY_SECONDS * GAME_TICK_RATE) as u64;
+
+/// The moments of a day that send a `DayPhaseEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DayPhase {
+    Dawn,
+    Noon,
+    Dusk,
+    Midnight,
+}
+
+impl DayPhase {
+    /// Every phase, in the order they pass during a day.
+    pub const ALL: [DayPhase; 4] = [DayPhase::Dawn, DayPhase::Noon, DayPhase::Dusk, DayPhase::Midnight];
+
+    /// Time of day (fraction of a day) at which the phase begins.
+    #[must_use]
+    pub fn time_of_day(self) -> f32 {
+        match self {
+            DayPhase::Dawn => 0.0,
+            DayPhase::Noon => 0.25,
+            DayPhase::Dusk => 0.5,
+            DayPhase::Midnight => 0.75,
+        }
+    }
+
+    /// Lowercase name, as used by the console.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            DayPhase::Dawn => "dawn",
+            DayPhase::Noon => "noon",
+            DayPhase::Dusk => "dusk",
+            DayPhase::Midnight => "midnight",
+        }
+    }
+}
+
+/// Sent when the world time passes the start of a `DayPhase`.
+///
+/// # Fields
+/// * `phase` - the phase that began
+/// * `day` - the day it began on
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayPhaseEvent {
+    pub phase: DayPhase,
+    pub day: u64,
+}
+
+/// The world's clock.
+///
+/// # Fields
+/// * `day` - days passed since the world was created
+/// * `time_of_day` - fraction of the current day (0 = dawn, 0.25 = noon,
+///   0.5 = dusk, 0.75 = midnight)
+/// * `scale` - speed of the clock relative to `FULL_DAY_SECONDS` per day
+/// * `frozen` - whether the clock is stopped
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WorldTime {
+    #[serde(default)]
+    pub day: u64,
+    #[serde(default)]
+    pub time_of_day: f32,
+    #[serde(default = "WorldTime::default_scale")]
+    pub scale: f32,
+    #[serde(default)]
+    pub frozen: bool,
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self { day: 0, time_of_day: 0.0, scale: Self::default_scale(), frozen: false }
+    }
+}
+
+impl WorldTime {
+    fn default_scale() -> f32 { 1.0 }
+
+    /// Move the clock on by `seconds` of simulated time (times `scale`),
+    /// unless it is frozen. At most one day passes per call.
+    ///
+    /// # Returns
+    /// The phases that began, in order.
+    pub fn advance(&mut self, seconds: f32) -> Vec<DayPhase> {
+        if self.frozen {
+            return Vec::new();
+        }
+        self.advance_fraction(seconds * self.scale / FULL_DAY_SECONDS)
+    }
+
+    /// Move the clock on by `fraction` of a day (at most one), ignoring
+    /// `scale` and `frozen`.
+    fn advance_fraction(&mut self, fraction: f32) -> Vec<DayPhase> {
+        let mut passed = Vec::new();
+        let mut remaining = fraction.clamp(0.0, 1.0);
+        while remaining > 0.0 {
+            // The next phase boundary; dawn of the next day is at 1.0
+            let (phase, at) = DayPhase::ALL[1..]
+                .iter()
+                .map(|p| (*p, p.time_of_day()))
+                .find(|(_, at)| *at > self.time_of_day)
+                .unwrap_or((DayPhase::Dawn, 1.0));
+            let step = at - self.time_of_day;
+            if remaining < step {
+                self.time_of_day += remaining;
+                break;
+            }
+            remaining -= step;
+            passed.push(phase);
+            if phase == DayPhase::Dawn {
+                self.day += 1;
+                self.time_of_day = 0.0;
+            } else {
+                self.time_of_day = at;
+            }
+        }
+        passed
+    }
+
+    /// Set the time of day, moving forward to the next day if `time_of_day`
+    /// is earlier than the current time. Works while frozen.
+    ///
+    /// # Returns
+    /// The phases passed on the way (including the one at `time_of_day`), in
+    /// order, as `advance` reports them.
+    pub fn set_time_of_day(&mut self, time_of_day: f32) -> Vec<DayPhase> {
+        let target = time_of_day.rem_euclid(1.0);
+        let passed = self.advance_fraction((target - self.time_of_day).rem_euclid(1.0));
+        // Snap to the exact target; stepping through the phases can leave rounding error
+        self.time_of_day = target;
+        passed
+    }
+
+    /// Send a `DayPhaseEvent` for each of `phases` (as returned by `advance`
+    /// or `set_time_of_day`), dated with the current day.
+    pub fn send_phases(&self, phases: Vec<DayPhase>, events: &mut EventWriter<DayPhaseEvent>) {
+        for phase in phases {
+            debug!("Day {}: {}", self.day, phase.name());
+            events.send(DayPhaseEvent { phase, day: self.day });
+        }
+    }
+
+    /// Angle (radians) of the sun around the world: 0 at dawn, pi/2 at noon.
+    #[must_use]
+    pub fn sun_angle(&self) -> f32 {
+        self.time_of_day * std::f32::consts::TAU
+    }
+
+    /// Whether the sun is up.
+    #[must_use]
+    pub fn is_day(&self) -> bool {
+        self.time_of_day < DayPhase::Dusk.time_of_day()
+    }
+
+    /// Ticks since the start of the current day (0..`TICKS_PER_DAY`).
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    pub fn tick_of_day(&self) -> u64 {
+        ((self.time_of_day * TICKS_PER_DAY as f32) as u64).min(TICKS_PER_DAY - 1)
+    }
+
+    /// Wall-clock style time `(hours, minutes)`, with dawn at 06:00.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn clock(&self) -> (u32, u32) {
+        let minutes = ((self.time_of_day * 24.0 * 60.0) as u32 + 6 * 60) % (24 * 60);
+        (minutes / 60, minutes % 60)
+    }
+}
+
+/// Advance `WorldTime` by the simulation step and report the phases that
+/// began. Runs in `FixedUpdate`.
+///
+/// # Arguments
+/// * `time` - fixed time resource for delta timing
+/// * `world_time` - the world clock
+/// * `events` - day phase event writer
+#[allow(clippy::needless_pass_by_value)]
+pub fn advance_world_time(time: Res<Time>, mut world_time: ResMut<WorldTime>, mut events: EventWriter<DayPhaseEvent>) {
+    if world_time.frozen {
+        return;
+    }
+    let passed = world_time.advance(time.delta_seconds());
+    world_time.send_phases(passed, &mut events);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advancing_passes_phases_and_days() {
+        let mut time = WorldTime { time_of_day: 0.45, ..WorldTime::default() };
+        // A tenth of a day crosses dusk
+        assert_eq!(time.advance(FULL_DAY_SECONDS * 0.1), vec![DayPhase::Dusk]);
+        assert!(!time.is_day());
+
+        // Through midnight into the next day's dawn
+        assert_eq!(time.advance(FULL_DAY_SECONDS * 0.5), vec![DayPhase::Midnight, DayPhase::Dawn]);
+        assert_eq!(time.day, 1);
+        assert!((time.time_of_day - 0.05).abs() < 1e-4);
+
+        time.frozen = true;
+        assert!(time.advance(FULL_DAY_SECONDS).is_empty());
+
+        // Setting an earlier time moves forward to the next day, even while
+        // frozen, and passes the phases in between
+        assert_eq!(time.set_time_of_day(0.0), vec![DayPhase::Noon, DayPhase::Dusk, DayPhase::Midnight, DayPhase::Dawn]);
+        assert_eq!((time.day, time.clock()), (2, (6, 0)));
+        assert_eq!(time.set_time_of_day(0.6), vec![DayPhase::Noon, DayPhase::Dusk]);
+        assert!(time.set_time_of_day(0.6).is_empty());
+        assert_eq!((time.day, time.time_of_day), (2, 0.6));
+    }
+}
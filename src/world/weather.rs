@@ -0,0 +1,562 @@
+//! Weather: a state machine per region, sampled from the biomes.
+//!
+//! The world is split into square regions of `REGION_SIZE` blocks. Every
+//! region within `ACTIVE_REGION_RADIUS` of the player has a `RegionWeather`:
+//! the weather it is in, the weather it is changing from and how far that
+//! change has got. When a spell of weather runs out the next one is picked
+//! with `BiomeRegistry::sample_weather_by_value` from the biome at the
+//! region's centre (or, in the player's own region, the biome under the
+//! player; see `weather_column`), seeded by the world seed, the region, the
+//! day and the spell count. Rain and thunder fall as snow in biomes at or below
+//! `FREEZING_POINT`; weather names this module does not know (such as the
+//! desert's "sandstorm") count as clear. A change takes
+//! `Weather::transition_seconds` of the new weather.
+//!
+//! `WeatherEffects` is what the weather does to the scene: how much
+//! sunlight gets through, how overcast the sky is, the fog and how hard it
+//! rains or snows. `update_weather` advances the regions each simulation
+//! step and eases `ActiveWeather` towards the effects of the player's
+//! region, so crossing into a region with other weather fades too.
+//! `app::weather` applies the active effects to the sky, lights, fog and
+//! precipitation.
+//!
+//! `PrecipitationHeightmap` keeps the highest block of each column around
+//! the player so rain and snow stop at roofs and overhangs. `app::weather`
+//! uploads it to the GPU, where the precipitation shader tests against it.
+
+use super::generate::surface_height;
+use super::time::WorldTime;
+use super::{BlockChanged, World};
+use crate::biome::{Biome, BiomeRegistry};
+use crate::chunk::{CHUNK_SIZE, WORLD_SEED};
+use crate::lighting::{smoothstep, DaylightInfo};
+use crate::player::Player;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Edge length of a weather region in blocks.
+pub const REGION_SIZE: i32 = 256;
+
+/// Regions around the player's (in each direction) whose weather is kept.
+pub const ACTIVE_REGION_RADIUS: i32 = 1;
+
+/// Shortest and longest spell of weather (seconds of simulated time).
+pub const MIN_SPELL_SECONDS: f32 = 120.0;
+pub const MAX_SPELL_SECONDS: f32 = 600.0;
+
+/// Temperature (°C) at or below which rain falls as snow.
+pub const FREEZING_POINT: f32 = 0.0;
+
+/// Seconds for the player's view to follow a change of region weather.
+pub const REGION_BLEND_SECONDS: f32 = 6.0;
+
+/// Columns kept around the player (in each direction) by
+/// `PrecipitationHeightmap`.
+pub const HEIGHTMAP_RADIUS: i32 = 24;
+
+/// Blocks the player moves from the heightmap's centre before it is rebuilt.
+const HEIGHTMAP_REBUILD_DISTANCE: i32 = 4;
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const CHUNK_SIZE_I32: i32 = CHUNK_SIZE as i32;
+
+/// A kind of weather.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Snow,
+    Thunder,
+}
+
+impl Weather {
+    /// Every kind of weather.
+    pub const ALL: [Weather; 4] = [Weather::Clear, Weather::Rain, Weather::Snow, Weather::Thunder];
+
+    /// Lowercase name, as used in biome files and by the console.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Snow => "snow",
+            Weather::Thunder => "thunder",
+        }
+    }
+
+    /// The weather called `name`, if there is one.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Weather> {
+        Weather::ALL.into_iter().find(|w| w.name() == name)
+    }
+
+    /// The weather a `weather_chance` entry called `name` brings to
+    /// `biome`: unknown names are clear, rain and thunder freeze to snow.
+    #[must_use]
+    pub fn for_biome(name: &str, biome: &Biome) -> Weather {
+        match Weather::from_name(name) {
+            Some(Weather::Rain | Weather::Thunder) if biome.temperature <= FREEZING_POINT => Weather::Snow,
+            Some(weather) => weather,
+            None => Weather::Clear,
+        }
+    }
+
+    /// Seconds a change into this weather takes.
+    #[must_use]
+    pub fn transition_seconds(self) -> f32 {
+        match self {
+            Weather::Clear => 40.0,
+            Weather::Rain => 25.0,
+            Weather::Snow => 35.0,
+            Weather::Thunder => 15.0,
+        }
+    }
+
+    /// What this weather does to the scene once it has fully set in.
+    #[must_use]
+    pub fn effects(self) -> WeatherEffects {
+        match self {
+            Weather::Clear => WeatherEffects::default(),
+            Weather::Rain => WeatherEffects {
+                sunlight: 0.45,
+                cloud_cover: 0.7,
+                fog_density: 0.012,
+                fog_color: Vec3::new(0.55, 0.58, 0.62),
+                rain: 0.7,
+                snow: 0.0,
+            },
+            Weather::Snow => WeatherEffects {
+                sunlight: 0.55,
+                cloud_cover: 0.65,
+                fog_density: 0.02,
+                fog_color: Vec3::new(0.82, 0.84, 0.88),
+                rain: 0.0,
+                snow: 1.0,
+            },
+            Weather::Thunder => WeatherEffects {
+                sunlight: 0.2,
+                cloud_cover: 1.0,
+                fog_density: 0.016,
+                fog_color: Vec3::new(0.32, 0.34, 0.38),
+                rain: 1.0,
+                snow: 0.0,
+            },
+        }
+    }
+}
+
+/// What the weather does to the scene.
+///
+/// # Fields
+/// * `sunlight` - fraction of the sun's light that gets through (0..1)
+/// * `cloud_cover` - how overcast the sky is (0..1); dims the sky, stars and moon
+/// * `fog_density` - exponential fog density (0 = no fog)
+/// * `fog_color` - fog colour in daylight
+/// * `rain`, `snow` - precipitation intensity (0..1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeatherEffects {
+    pub sunlight: f32,
+    pub cloud_cover: f32,
+    pub fog_density: f32,
+    pub fog_color: Vec3,
+    pub rain: f32,
+    pub snow: f32,
+}
+
+impl Default for WeatherEffects {
+    fn default() -> Self {
+        Self {
+            sunlight: 1.0,
+            cloud_cover: 0.0,
+            fog_density: 0.0,
+            fog_color: Vec3::new(0.70, 0.78, 0.90),
+            rain: 0.0,
+            snow: 0.0,
+        }
+    }
+}
+
+impl WeatherEffects {
+    /// Effects `t` (0..1) of the way from `self` to `other`.
+    #[must_use]
+    pub fn lerp(&self, other: &WeatherEffects, t: f32) -> WeatherEffects {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        WeatherEffects {
+            sunlight: mix(self.sunlight, other.sunlight),
+            cloud_cover: mix(self.cloud_cover, other.cloud_cover),
+            fog_density: mix(self.fog_density, other.fog_density),
+            fog_color: self.fog_color.lerp(other.fog_color, t),
+            rain: mix(self.rain, other.rain),
+            snow: mix(self.snow, other.snow),
+        }
+    }
+
+    /// Dim the daylight for these effects: the sun by `sunlight`, the sky
+    /// light and ambient light by the cloud cover. Sun shadows fade out
+    /// under a heavy overcast.
+    pub fn apply(&self, info: &mut DaylightInfo) {
+        info.sun_illuminance *= self.sunlight;
+        info.skylight_illuminance *= 1.0 - self.cloud_cover * 0.4;
+        info.ambient_brightness *= 1.0 - self.cloud_cover * 0.3;
+        info.shadows_enabled &= self.cloud_cover < 0.8;
+    }
+}
+
+/// The weather of one region.
+///
+/// # Fields
+/// * `weather` - the weather the region is in or changing to
+/// * `from` - the weather it is changing from
+/// * `transition` - progress of the change (0..1, 1 = settled)
+/// * `remaining` - seconds until the next spell is picked
+/// * `spell` - spells picked so far, seeds the next pick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegionWeather {
+    pub weather: Weather,
+    pub from: Weather,
+    pub transition: f32,
+    pub remaining: f32,
+    pub spell: u32,
+}
+
+impl RegionWeather {
+    /// A region settled in `weather` for `seconds`.
+    #[must_use]
+    pub fn settled(weather: Weather, seconds: f32) -> Self {
+        Self { weather, from: weather, transition: 1.0, remaining: seconds, spell: 0 }
+    }
+
+    /// Change to `weather` over its transition time, for `seconds`.
+    pub fn change_to(&mut self, weather: Weather, seconds: f32) {
+        if weather != self.weather {
+            // Mid-change, carry on from whichever side is showing more
+            self.from = if self.transition < 0.5 { self.from } else { self.weather };
+            self.weather = weather;
+            self.transition = 0.0;
+        }
+        self.remaining = seconds;
+    }
+
+    /// Move on by `seconds`. When the spell runs out `next` is called with
+    /// the spell number and picks the next weather and how long it lasts.
+    pub fn advance(&mut self, seconds: f32, next: impl FnOnce(u32) -> (Weather, f32)) {
+        self.transition = (self.transition + seconds / self.weather.transition_seconds()).min(1.0);
+        self.remaining -= seconds;
+        if self.remaining <= 0.0 {
+            self.spell = self.spell.wrapping_add(1);
+            let (weather, duration) = next(self.spell);
+            self.change_to(weather, duration);
+        }
+    }
+
+    /// Current effects, blended across a change.
+    #[must_use]
+    pub fn effects(&self) -> WeatherEffects {
+        self.from.effects().lerp(&self.weather.effects(), smoothstep(self.transition))
+    }
+}
+
+/// Deterministic value in 0..1 for `keys` (splitmix64 over the world seed).
+#[allow(clippy::cast_precision_loss, clippy::cast_sign_loss)]
+fn unit_random(keys: [i64; 4]) -> f32 {
+    let mut state = u64::from(WORLD_SEED) ^ 0x3EA7_4E00_0000_0000;
+    for key in keys {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15).wrapping_add(key as u64);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        state ^= state >> 31;
+    }
+    (state >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// The block column `(x, z)` whose biome picks the weather of `region`: the
+/// player's column `player` when they are in `region`, else its centre.
+#[must_use]
+pub fn weather_column(region: IVec2, player: IVec2) -> IVec2 {
+    if player.div_euclid(IVec2::splat(REGION_SIZE)) == region {
+        player
+    } else {
+        region * REGION_SIZE + IVec2::splat(REGION_SIZE / 2)
+    }
+}
+
+/// Pick spell `spell` of `region` on `day`: the weather from the biome at
+/// block column `column` (see `weather_column`) and how long it lasts.
+#[must_use]
+pub fn sample_spell(biomes: &BiomeRegistry, region: IVec2, column: IVec2, day: u64, spell: u32) -> (Weather, f32) {
+    #[allow(clippy::cast_possible_wrap)]
+    let keys = |salt: i64| [i64::from(region.x), i64::from(region.y), day as i64 ^ (i64::from(spell) << 32), salt];
+    let duration = MIN_SPELL_SECONDS + (MAX_SPELL_SECONDS - MIN_SPELL_SECONDS) * unit_random(keys(1));
+    let weather = biomes.biome_at_block(column.x, column.y).map_or(Weather::Clear, |biome| {
+        biomes
+            .sample_weather_by_value(biome, unit_random(keys(0)))
+            .map_or(Weather::Clear, |name| Weather::for_biome(name, biome))
+    });
+    (weather, duration)
+}
+
+/// The block column `(x, z)` containing world position `position`.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn column_of(position: Vec3) -> IVec2 {
+    IVec2::new(position.x.floor() as i32, position.z.floor() as i32)
+}
+
+/// The region containing world position `position`.
+#[must_use]
+pub fn region_of(position: Vec3) -> IVec2 {
+    column_of(position).div_euclid(IVec2::splat(REGION_SIZE))
+}
+
+/// Weather of the regions around the player.
+#[derive(Resource, Debug, Default)]
+pub struct WorldWeather {
+    pub regions: HashMap<IVec2, RegionWeather>,
+}
+
+impl WorldWeather {
+    /// The weather of `region`, picking its first spell from the biome at
+    /// `column` if it has none.
+    pub fn region_mut(&mut self, region: IVec2, column: IVec2, biomes: &BiomeRegistry, day: u64) -> &mut RegionWeather {
+        self.regions.entry(region).or_insert_with(|| {
+            let (weather, duration) = sample_spell(biomes, region, column, day, 0);
+            RegionWeather::settled(weather, duration)
+        })
+    }
+}
+
+/// What the player currently sees of the weather.
+///
+/// # Fields
+/// * `weather` - the weather of the player's region
+/// * `effects` - effects eased towards those of the player's region
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ActiveWeather {
+    pub weather: Weather,
+    pub effects: WeatherEffects,
+}
+
+/// Advance the weather of the regions around the player, forget the others
+/// and ease `ActiveWeather` towards the player's region. Runs in
+/// `FixedUpdate`.
+///
+/// # Arguments
+/// * `time` - fixed time resource for delta timing
+/// * `world_time` - the world clock, seeds new spells by day
+/// * `biomes` - biome registry weather is sampled from
+/// * `weather` - per-region weather
+/// * `active` - the weather the player sees
+/// * `player` - the player's transform
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_weather(
+    time: Res<Time>,
+    world_time: Res<WorldTime>,
+    biomes: Res<BiomeRegistry>,
+    mut weather: ResMut<WorldWeather>,
+    mut active: ResMut<ActiveWeather>,
+    player: Query<&Transform, With<Player>>,
+) {
+    let Ok(tf) = player.get_single() else { return };
+    let centre = region_of(tf.translation);
+    let column = column_of(tf.translation);
+    let seconds = time.delta_seconds();
+    let day = world_time.day;
+
+    weather.regions.retain(|region, _| (*region - centre).abs().max_element() <= ACTIVE_REGION_RADIUS);
+    for x in -ACTIVE_REGION_RADIUS..=ACTIVE_REGION_RADIUS {
+        for z in -ACTIVE_REGION_RADIUS..=ACTIVE_REGION_RADIUS {
+            let region = centre + IVec2::new(x, z);
+            let column = weather_column(region, column);
+            weather
+                .region_mut(region, column, &biomes, day)
+                .advance(seconds, |spell| sample_spell(&biomes, region, column, day, spell));
+        }
+    }
+
+    let here = weather.region_mut(centre, column, &biomes, day);
+    let target = here.effects();
+    if active.weather != here.weather {
+        debug!("Weather in region ({}, {}): {}", centre.x, centre.y, here.weather.name());
+        active.weather = here.weather;
+    }
+    active.effects = active.effects.lerp(&target, seconds / REGION_BLEND_SECONDS);
+}
+
+/// Highest block of each column around the player, so precipitation stops
+/// at the first block above it.
+#[derive(Resource, Debug, Default)]
+pub struct PrecipitationHeightmap {
+    centre: Option<IVec2>,
+    heights: Vec<i32>,
+    loaded_chunks: Vec<(i32, i32)>,
+}
+
+impl PrecipitationHeightmap {
+    /// Columns along each side of the map.
+    pub const WIDTH: i32 = HEIGHTMAP_RADIUS * 2 + 1;
+
+    /// Column of the map's first height (lowest x and z), once built.
+    #[must_use]
+    pub fn origin(&self) -> Option<IVec2> {
+        self.centre.map(|centre| centre - IVec2::splat(HEIGHTMAP_RADIUS))
+    }
+
+    /// Highest block of each column, row by row from `origin` (-1 for an
+    /// empty column).
+    #[must_use]
+    pub fn heights(&self) -> &[i32] {
+        &self.heights
+    }
+
+    /// Index of column `(x, z)` in `heights`, if it is on the map.
+    #[allow(clippy::cast_sign_loss)]
+    fn index(&self, x: i32, z: i32) -> Option<usize> {
+        let centre = self.centre?;
+        let (dx, dz) = (x - centre.x + HEIGHTMAP_RADIUS, z - centre.y + HEIGHTMAP_RADIUS);
+        ((0..Self::WIDTH).contains(&dx) && (0..Self::WIDTH).contains(&dz)).then(|| (dx + dz * Self::WIDTH) as usize)
+    }
+
+    /// Loaded chunks under a map centred on `centre`.
+    fn chunks_under(world: &World, centre: IVec2) -> Vec<(i32, i32)> {
+        let min = (centre - IVec2::splat(HEIGHTMAP_RADIUS)).div_euclid(IVec2::splat(CHUNK_SIZE_I32));
+        let max = (centre + IVec2::splat(HEIGHTMAP_RADIUS)).div_euclid(IVec2::splat(CHUNK_SIZE_I32));
+        (min.x..=max.x)
+            .flat_map(|cx| (min.y..=max.y).map(move |cz| (cx, cz)))
+            .filter(|key| world.chunks.contains_key(key))
+            .collect()
+    }
+
+    /// Whether the map must be rebuilt for a player above column `centre`:
+    /// the player moved away from its centre or chunks under it were
+    /// loaded or unloaded.
+    #[must_use]
+    pub fn needs_rebuild(&self, world: &World, centre: IVec2) -> bool {
+        match self.centre {
+            Some(old) => {
+                (centre - old).abs().max_element() > HEIGHTMAP_REBUILD_DISTANCE
+                    || Self::chunks_under(world, old) != self.loaded_chunks
+            }
+            None => true,
+        }
+    }
+
+    /// Read every column within `HEIGHTMAP_RADIUS` of `centre`.
+    pub fn rebuild(&mut self, world: &World, centre: IVec2) {
+        self.centre = Some(centre);
+        self.loaded_chunks = Self::chunks_under(world, centre);
+        self.heights = (0..Self::WIDTH * Self::WIDTH)
+            .map(|i| {
+                let x = centre.x - HEIGHTMAP_RADIUS + i % Self::WIDTH;
+                let z = centre.y - HEIGHTMAP_RADIUS + i / Self::WIDTH;
+                Self::column_height(world, x, z)
+            })
+            .collect();
+    }
+
+    /// Re-read column `(x, z)` after one of its blocks changed.
+    pub fn update_column(&mut self, world: &World, x: i32, z: i32) {
+        if let Some(i) = self.index(x, z) {
+            self.heights[i] = Self::column_height(world, x, z);
+        }
+    }
+
+    fn column_height(world: &World, x: i32, z: i32) -> i32 {
+        surface_height(&world.chunks, x, z).and_then(|y| i32::try_from(y).ok()).unwrap_or(-1)
+    }
+
+    /// Whether a block above `position` keeps precipitation off it. Columns
+    /// off the map are open.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn is_sheltered(&self, position: Vec3) -> bool {
+        self.index(position.x.floor() as i32, position.z.floor() as i32)
+            .is_some_and(|i| self.heights[i] as f32 + 1.0 > position.y)
+    }
+}
+
+/// Keep `PrecipitationHeightmap` around the player: rebuild it when the
+/// player moves away or chunks under it change, and re-read columns whose
+/// blocks changed.
+///
+/// # Arguments
+/// * `world` - the world blocks are read from
+/// * `heightmap` - the heightmap
+/// * `changes` - block change events
+/// * `player` - the player's transform
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_precipitation_heightmap(
+    world: Res<World>,
+    mut heightmap: ResMut<PrecipitationHeightmap>,
+    mut changes: EventReader<BlockChanged>,
+    player: Query<&Transform, With<Player>>,
+) {
+    let Ok(tf) = player.get_single() else { return };
+    let centre = column_of(tf.translation);
+    if heightmap.needs_rebuild(&world, centre) {
+        changes.clear();
+        heightmap.rebuild(&world, centre);
+        return;
+    }
+    for BlockChanged(pos) in changes.read() {
+        heightmap.update_column(&world, pos.x, pos.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+
+    #[test]
+    fn weather_is_sampled_blended_and_sheltered() {
+        let registry = BiomeRegistry::default();
+        let mut biome = Biome {
+            weather_chance: HashMap::from([("clear".to_string(), 1.0), ("rain".to_string(), 3.0)]),
+            ..Biome::default()
+        };
+        // Name order: clear takes the first quarter, rain the rest
+        assert_eq!(registry.sample_weather_by_value(&biome, 0.2), Some("clear"));
+        assert_eq!(registry.sample_weather_by_value(&biome, 0.3), Some("rain"));
+        assert_eq!(registry.sample_weather_by_value(&biome, 1.0), Some("rain"));
+
+        biome.temperature = -5.0;
+        assert_eq!(Weather::for_biome("rain", &biome), Weather::Snow);
+        assert_eq!(Weather::for_biome("sandstorm", &biome), Weather::Clear);
+
+        // A change fades in over the new weather's transition time
+        let mut region = RegionWeather::settled(Weather::Clear, 1.0);
+        region.advance(2.0, |spell| {
+            assert_eq!(spell, 1);
+            (Weather::Thunder, 100.0)
+        });
+        assert_eq!((region.from, region.weather, region.remaining), (Weather::Clear, Weather::Thunder, 100.0));
+        assert_eq!(region.effects(), Weather::Clear.effects());
+        region.advance(Weather::Thunder.transition_seconds() / 2.0, |_| unreachable!());
+        let halfway = region.effects();
+        assert!(halfway.sunlight < 1.0 && halfway.sunlight > Weather::Thunder.effects().sunlight);
+        region.advance(Weather::Thunder.transition_seconds(), |_| unreachable!());
+        assert!((region.effects().sunlight - Weather::Thunder.effects().sunlight).abs() < 1e-5);
+
+        // The player's region follows the biome under the player, the others
+        // their centre
+        let player = column_of(Vec3::new(-10.5, 64.0, 300.2));
+        assert_eq!(player, IVec2::new(-11, 300));
+        assert_eq!(weather_column(IVec2::new(-1, 1), player), player);
+        assert_eq!(weather_column(IVec2::new(0, 1), player), IVec2::new(128, 384));
+
+        // A roof over (3, 3) at y = 70 keeps the rain off below it
+        let mut world = World::new();
+        let mut chunk = Chunk::new();
+        chunk.set(3, 70, 3, 1);
+        world.chunks.insert((0, 0), chunk);
+        let mut heightmap = PrecipitationHeightmap::default();
+        assert!(heightmap.needs_rebuild(&world, IVec2::ZERO));
+        heightmap.rebuild(&world, IVec2::ZERO);
+        assert!(heightmap.is_sheltered(Vec3::new(3.5, 65.0, 3.5)));
+        assert!(!heightmap.is_sheltered(Vec3::new(3.5, 72.0, 3.5)));
+        assert!(!heightmap.is_sheltered(Vec3::new(4.5, 65.0, 3.5)));
+        assert!(!heightmap.needs_rebuild(&world, IVec2::new(2, 2)));
+    }
+}